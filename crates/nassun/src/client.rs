@@ -1,7 +1,11 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use std::time::Duration;
+
 use async_std::sync::Arc;
+use chrono::{DateTime, Utc};
+use oro_client::search::{SearchOptions, SearchResults};
 use oro_client::{OroClient, OroClientBuilder};
 use oro_common::{CorgiManifest, CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata};
 use url::Url;
@@ -14,7 +18,7 @@ use crate::error::Result;
 use crate::fetch::DirFetcher;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::fetch::GitFetcher;
-use crate::fetch::{DummyFetcher, NpmFetcher, PackageFetcher};
+use crate::fetch::{pick_registry, DummyFetcher, NpmFetcher, PackageFetcher};
 use crate::package::Package;
 use crate::resolver::{PackageResolution, PackageResolver};
 use crate::tarball::Tarball;
@@ -29,7 +33,10 @@ pub struct NassunOpts {
     base_dir: Option<PathBuf>,
     default_tag: Option<String>,
     registries: HashMap<Option<String>, Url>,
+    mirrors: HashMap<Option<String>, Vec<Url>>,
     memoize_metadata: bool,
+    before: Option<DateTime<Utc>>,
+    minimum_release_age: Option<Duration>,
 }
 
 impl NassunOpts {
@@ -69,6 +76,27 @@ impl NassunOpts {
         self
     }
 
+    /// Adds a mirror for the default registry. Mirrors are tried, in the
+    /// order they were added, whenever the registry before them is
+    /// unavailable (5xx responses, timeouts, or connection failures).
+    /// Tarball URLs in packuments are rewritten to point at whichever
+    /// registry is currently in use.
+    pub fn mirror(mut self, mirror: Url) -> Self {
+        self.mirrors.entry(None).or_default().push(mirror);
+        self
+    }
+
+    /// Adds a mirror for the registry used by a specific scope. See
+    /// [`NassunOpts::mirror`].
+    pub fn scope_mirror(mut self, scope: impl AsRef<str>, mirror: Url) -> Self {
+        let scope = scope.as_ref();
+        self.mirrors
+            .entry(Some(scope.strip_prefix('@').unwrap_or(scope).to_string()))
+            .or_default()
+            .push(mirror);
+        self
+    }
+
     /// Sets basic auth credentials for a registry.
     pub fn basic_auth(
         mut self,
@@ -112,6 +140,30 @@ impl NassunOpts {
         self
     }
 
+    /// Only resolve to package versions published at or before this time,
+    /// as if resolving at that point in the past. Falls back to older
+    /// versions that satisfy the requested spec, and errors if there are
+    /// none.
+    ///
+    /// This requires publish times, so full packuments will be fetched
+    /// instead of the smaller "Corgi" packuments.
+    pub fn before(mut self, before: DateTime<Utc>) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    /// Skip package versions that were published less than `age` ago,
+    /// falling back to older versions that satisfy the requested spec. Can
+    /// be combined with [`NassunOpts::before`], in which case the earlier
+    /// of the two cutoffs is used.
+    ///
+    /// This requires publish times, so full packuments will be fetched
+    /// instead of the smaller "Corgi" packuments.
+    pub fn minimum_release_age(mut self, age: Duration) -> Self {
+        self.minimum_release_age = Some(age);
+        self
+    }
+
     /// Whether to memoize package metadata. This will keep any processed
     /// packuments in memory for the lifetime of this `Nassun` instance.
     /// Setting this to `true` may increase performance when fetching many
@@ -160,6 +212,14 @@ impl NassunOpts {
             Arc::new(None)
         };
         let client = self.client.unwrap_or_else(|| self.client_builder.build());
+        let min_age_cutoff = self
+            .minimum_release_age
+            .and_then(|age| chrono::Duration::from_std(age).ok())
+            .and_then(|age| Utc::now().checked_sub_signed(age));
+        let before = match (self.before, min_age_cutoff) {
+            (Some(before), Some(min_age)) => Some(before.min(min_age)),
+            (before, min_age) => before.or(min_age),
+        };
         Nassun {
             #[cfg(not(target_arch = "wasm32"))]
             cache,
@@ -173,12 +233,17 @@ impl NassunOpts {
                     .base_dir
                     .unwrap_or_else(|| std::env::current_dir().expect("failed to get cwd.")),
                 default_tag: self.default_tag.unwrap_or_else(|| "latest".into()),
+                before,
             },
+            client: client.clone(),
+            registries: Arc::new(self.registries.clone()),
             npm_fetcher: Arc::new(NpmFetcher::new(
                 #[allow(clippy::redundant_clone)]
                 client.clone(),
                 self.registries,
+                self.mirrors,
                 self.memoize_metadata,
+                before.is_some(),
             )),
             #[cfg(not(target_arch = "wasm32"))]
             dir_fetcher: Arc::new(DirFetcher::new()),
@@ -193,6 +258,8 @@ impl NassunOpts {
 pub struct Nassun {
     cache: Arc<Option<PathBuf>>,
     resolver: PackageResolver,
+    client: OroClient,
+    registries: Arc<HashMap<Option<String>, Url>>,
     npm_fetcher: Arc<dyn PackageFetcher>,
     #[cfg(not(target_arch = "wasm32"))]
     dir_fetcher: Arc<dyn PackageFetcher>,
@@ -283,6 +350,30 @@ impl Nassun {
         Self::new().resolve(spec.as_ref()).await?.entries().await
     }
 
+    /// The registry that packages in `scope` are fetched from, ignoring any
+    /// mirrors. Always ends in `/`, so registry-relative paths (like
+    /// `foo/-/foo-1.0.0.tgz`) can be joined onto it.
+    pub fn registry(&self, scope: Option<&str>) -> Url {
+        pick_registry(&self.registries, &scope.map(|s| s.to_string()))
+    }
+
+    /// Path of the tarball at `url` relative to the configured registry (or
+    /// mirror) that hosts it, like `foo/-/foo-1.0.0.tgz`. Tarballs hosted
+    /// anywhere else don't have one.
+    pub fn registry_path(&self, url: &Url) -> Option<String> {
+        self.npm_fetcher.registry_path(url)
+    }
+
+    /// Searches for packages. If the search text mentions a scope (like
+    /// `@myorg/utils`), the search is sent to that scope's registry.
+    pub async fn search(&self, options: &SearchOptions) -> Result<SearchResults> {
+        Ok(self
+            .client
+            .with_registry(self.registry(options.scope()))
+            .search(options)
+            .await?)
+    }
+
     /// Resolve a string spec (e.g. `foo@^1.2.3`, `github:foo/bar`, etc), to a
     /// [`Package`] that can be used for further operations.
     pub async fn resolve(&self, spec: impl AsRef<str>) -> Result<Package> {
@@ -356,3 +447,43 @@ impl Nassun {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use mockito::Matcher;
+
+    use super::*;
+
+    #[async_std::test]
+    async fn search_uses_scoped_registry() -> miette::Result<()> {
+        let mut default = mockito::Server::new();
+        let mut scoped = mockito::Server::new();
+        let results = serde_json::json!({ "objects": [], "total": 0 }).to_string();
+        let default_search = default
+            .mock("GET", "/-/v1/search")
+            .match_query(Matcher::UrlEncoded("text".into(), "parser".into()))
+            .with_body(&results)
+            .expect(1)
+            .create_async()
+            .await;
+        let scoped_search = scoped
+            .mock("GET", "/-/v1/search")
+            .match_query(Matcher::UrlEncoded("text".into(), "@myorg/parser".into()))
+            .with_body(&results)
+            .expect(1)
+            .create_async()
+            .await;
+
+        let nassun = NassunOpts::new()
+            .registry(default.url().parse().unwrap())
+            .scope_registry("@myorg", scoped.url().parse().unwrap())
+            .retries(0)
+            .build();
+        nassun.search(&SearchOptions::new("parser")).await?;
+        nassun.search(&SearchOptions::new("@myorg/parser")).await?;
+
+        default_search.assert_async().await;
+        scoped_search.assert_async().await;
+        Ok(())
+    }
+}
@@ -123,6 +123,58 @@ pub enum NassunError {
         versions: Vec<String>,
     },
 
+    /// Versions matching the requested spec exist, but all of them were
+    /// published after the configured release cutoff (`before` or
+    /// `minimum-release-age`).
+    #[error("No `{name}` version matching `{spec}` was published before {cutoff}. Excluded versions: {}", excluded.join(", "))]
+    #[diagnostic(
+        code(resolver::no_version_before_cutoff),
+        url(docsrs),
+        help("Wait for these versions to age past the cutoff, relax `minimum-release-age`/`before`, or request an older version explicitly.")
+    )]
+    NoVersionBeforeCutoff {
+        name: String,
+        spec: PackageSpec,
+        cutoff: String,
+        excluded: Vec<String>,
+    },
+
+    /// A package's registry signature does not match its metadata. This
+    /// means the package's metadata may have been tampered with, either at
+    /// the registry, at a mirror, or in transit.
+    #[error("Registry signature for {0} (key `{1}`) is invalid.")]
+    #[diagnostic(
+        code(nassun::signature::invalid),
+        url(docsrs),
+        help(
+            "Do not install this package until you've confirmed its integrity with the registry."
+        )
+    )]
+    InvalidSignature(String, String),
+
+    /// A package was signed with a key that the registry does not list in
+    /// its public keys.
+    #[error("{0} was signed with key `{1}`, which is not one of the registry's public keys.")]
+    #[diagnostic(code(nassun::signature::unknown_key), url(docsrs))]
+    UnknownSignatureKey(String, String),
+
+    /// A package was signed with a registry key that had already expired at
+    /// the time the package was published.
+    #[error("{0} was signed with key `{1}`, which had expired by the time it was published.")]
+    #[diagnostic(code(nassun::signature::expired_key), url(docsrs))]
+    ExpiredSignatureKey(String, String),
+
+    /// One of the registry's public keys could not be parsed.
+    #[error("Registry public key `{0}` is malformed.")]
+    #[diagnostic(code(nassun::signature::invalid_key), url(docsrs))]
+    InvalidRegistryKey(String),
+
+    /// A package has registry signatures, but no integrity information for
+    /// them to sign.
+    #[error("{0} has registry signatures, but no integrity to check them against.")]
+    #[diagnostic(code(nassun::signature::missing_integrity), url(docsrs))]
+    MissingSignedIntegrity(String),
+
     /// Generic serde-wasm-bindgen error.
     #[cfg(target_arch = "wasm32")]
     #[error(transparent)]
@@ -125,7 +125,7 @@ impl PackageFetcher for DirFetcher {
     }
 
     async fn packument(&self, spec: &PackageSpec, base_dir: &Path) -> Result<Arc<Packument>> {
-        let path = match spec {
+        let path = match spec.target() {
             PackageSpec::Dir { path } => base_dir.join(path),
             _ => panic!("There shouldn't be anything but Dirs here"),
         };
@@ -137,7 +137,7 @@ impl PackageFetcher for DirFetcher {
         spec: &PackageSpec,
         base_dir: &Path,
     ) -> Result<Arc<CorgiPackument>> {
-        let path = match spec {
+        let path = match spec.target() {
             PackageSpec::Dir { path } => base_dir.join(path),
             _ => panic!("There shouldn't be anything but Dirs here"),
         };
@@ -205,6 +205,7 @@ impl Manifest {
         let mut packument = CorgiPackument {
             versions: HashMap::new(),
             tags: HashMap::new(),
+            time: HashMap::new(),
         };
         let version = metadata
             .manifest
@@ -303,4 +304,18 @@ mod test {
         );
         Ok(())
     }
+
+    #[async_std::test]
+    async fn read_aliased_packument() -> miette::Result<()> {
+        let (fetcher, package_spec, _tmp, _package_path, cache_path) = setup_dirs()?;
+        let aliased = PackageSpec::Alias {
+            name: "renamed".into(),
+            spec: Box::new(package_spec),
+        };
+        let packument = fetcher.packument(&aliased, &cache_path).await?;
+        assert!(packument.versions.contains_key(&"1.4.2".parse()?));
+        let packument = fetcher.corgi_packument(&aliased, &cache_path).await?;
+        assert!(packument.versions.contains_key(&"1.4.2".parse()?));
+        Ok(())
+    }
 }
@@ -48,6 +48,7 @@ impl PackageFetcher for DummyFetcher {
         let mut packument = CorgiPackument {
             versions: HashMap::new(),
             tags: HashMap::new(),
+            time: HashMap::new(),
         };
         let version = corgi_meta
             .manifest
@@ -4,16 +4,19 @@ use async_std::sync::Arc;
 use async_trait::async_trait;
 use oro_common::{CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata};
 use oro_package_spec::PackageSpec;
+use url::Url;
 
 use crate::error::Result;
 use crate::package::Package;
+use crate::SignatureStatus;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) use dir::DirFetcher;
 pub(crate) use dummy::DummyFetcher;
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) use git::GitFetcher;
-pub(crate) use npm::NpmFetcher;
+pub use npm::is_tarball_path;
+pub(crate) use npm::{pick_registry, NpmFetcher};
 
 #[cfg(not(target_arch = "wasm32"))]
 mod dir;
@@ -35,4 +38,13 @@ pub trait PackageFetcher: std::fmt::Debug + Send + Sync {
         base_dir: &Path,
     ) -> Result<Arc<CorgiPackument>>;
     async fn tarball(&self, pkg: &Package) -> Result<crate::TarballStream>;
+    async fn verify_signature(&self, _pkg: &Package) -> Result<SignatureStatus> {
+        Ok(SignatureStatus::Unsupported)
+    }
+    /// Path of the tarball at `url` relative to the configured registry
+    /// that hosts it, like `foo/-/foo-1.0.0.tgz`. Tarballs hosted anywhere
+    /// else don't have one.
+    fn registry_path(&self, _url: &Url) -> Option<String> {
+        None
+    }
 }
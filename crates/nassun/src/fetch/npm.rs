@@ -1,10 +1,13 @@
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::Path;
 
 use async_std::sync::Arc;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use dashmap::DashMap;
-use oro_client::{self, OroClient};
+use oro_client::keys::RegistryKey;
+use oro_client::{self, OroClient, OroClientError};
 use oro_common::{CorgiPackument, CorgiVersionMetadata, Packument, VersionMetadata};
 use oro_package_spec::PackageSpec;
 use url::Url;
@@ -13,12 +16,23 @@ use crate::error::{NassunError, Result};
 use crate::fetch::PackageFetcher;
 use crate::package::Package;
 use crate::resolver::PackageResolution;
+use crate::signature::verify_signatures;
+use crate::SignatureStatus;
 
 #[derive(Debug)]
 pub(crate) struct NpmFetcher {
     client: OroClient,
     registries: HashMap<Option<String>, Url>,
+    mirrors: HashMap<Option<String>, Vec<Url>>,
+    /// Index into the registry chain for each scope of the last registry
+    /// that successfully served a request.
+    active_registries: DashMap<Option<String>, usize>,
+    registry_keys: DashMap<Option<String>, Arc<Vec<RegistryKey>>>,
     cache_packuments: bool,
+    /// Whether resolution needs publish times. If so, full packuments are
+    /// fetched even when only a Corgi packument was asked for, since Corgi
+    /// responses don't include them.
+    publish_times: bool,
     packuments: DashMap<String, Arc<Packument>>,
     corgi_packuments: DashMap<String, Arc<CorgiPackument>>,
 }
@@ -27,25 +41,173 @@ impl NpmFetcher {
     pub(crate) fn new(
         client: OroClient,
         registries: HashMap<Option<String>, Url>,
+        mirrors: HashMap<Option<String>, Vec<Url>>,
         cache_packuments: bool,
+        publish_times: bool,
     ) -> Self {
         Self {
             client,
             registries,
+            mirrors,
+            active_registries: DashMap::new(),
+            registry_keys: DashMap::new(),
             packuments: DashMap::new(),
             corgi_packuments: DashMap::new(),
             cache_packuments,
+            publish_times,
         }
     }
 }
 
-impl NpmFetcher {
-    fn pick_registry(&self, scope: &Option<String>) -> Url {
-        self.registries
+/// Picks the registry configured for `scope`, falling back to the default
+/// registry, and finally to the public npm registry.
+pub(crate) fn pick_registry(
+    registries: &HashMap<Option<String>, Url>,
+    scope: &Option<String>,
+) -> Url {
+    registry_base(
+        registries
             .get(scope)
-            .or_else(|| self.registries.get(&None))
-            .cloned()
-            .unwrap_or_else(|| "https://registry.npmjs.org/".parse().unwrap())
+            .or_else(|| registries.get(&None))
+            .unwrap_or(&"https://registry.npmjs.org/".parse().unwrap()),
+    )
+}
+
+/// Whether `path` follows the registry tarball layout: `foo/-/foo-1.0.0.tgz`,
+/// or `@scope/foo/-/foo-1.0.0.tgz`.
+pub fn is_tarball_path(path: &str) -> bool {
+    let segment = |s: &str| !s.is_empty() && !s.starts_with('.') && !s.contains(['/', '\\', ':']);
+    let Some((name, file)) = path.split_once("/-/") else {
+        return false;
+    };
+    let name = match name.split_once('/') {
+        Some((scope, name)) => scope.starts_with('@') && segment(&scope[1..]) && segment(name),
+        None => segment(name),
+    };
+    name && segment(file) && file.ends_with(".tgz")
+}
+
+/// Makes sure a registry URL ends in `/`, so that joining package paths onto
+/// it doesn't drop its last path segment.
+pub(crate) fn registry_base(registry: &Url) -> Url {
+    let mut registry = registry.clone();
+    if !registry.path().ends_with('/') {
+        registry.set_path(&format!("{}/", registry.path()));
+    }
+    registry
+}
+
+impl NpmFetcher {
+    /// Key used to look up registries and mirrors for `scope`. Scopes
+    /// without any registry configuration of their own share the default
+    /// chain.
+    fn chain_key(&self, scope: &Option<String>) -> Option<String> {
+        if self.registries.contains_key(scope) || self.mirrors.contains_key(scope) {
+            scope.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Ordered list of registries to try for `scope`: the configured
+    /// registry first, followed by any mirrors, in the order they were
+    /// added.
+    fn registry_chain(&self, scope: &Option<String>) -> Vec<Url> {
+        let key = self.chain_key(scope);
+        let mut chain = vec![pick_registry(&self.registries, &key)];
+        for mirror in self.mirrors.get(&key).into_iter().flatten() {
+            let mirror = registry_base(mirror);
+            if !chain.contains(&mirror) {
+                chain.push(mirror);
+            }
+        }
+        chain
+    }
+
+    /// Every registry and mirror that's been configured, for any scope.
+    fn all_registries(&self) -> Vec<Url> {
+        let mut all = Vec::new();
+        let scopes = std::iter::once(None)
+            .chain(self.registries.keys().cloned())
+            .chain(self.mirrors.keys().cloned());
+        for scope in scopes {
+            for registry in self.registry_chain(&scope) {
+                if !all.contains(&registry) {
+                    all.push(registry);
+                }
+            }
+        }
+        all
+    }
+
+    /// Runs `request` against each registry in the chain for `scope`,
+    /// starting with the one that last worked, until one of them succeeds.
+    /// Only failures that mean the registry itself is unavailable (5xx
+    /// responses, timeouts, connection errors) fall through to the next
+    /// one. Returns the result along with the registry that produced it.
+    async fn with_failover<T, F, Fut>(&self, scope: &Option<String>, request: F) -> Result<(T, Url)>
+    where
+        F: Fn(Url) -> Fut,
+        Fut: Future<Output = std::result::Result<T, OroClientError>>,
+    {
+        let key = self.chain_key(scope);
+        let chain = self.registry_chain(scope);
+        let start = self
+            .active_registries
+            .get(&key)
+            .map(|idx| *idx)
+            .unwrap_or(0);
+        let mut order = (start..chain.len()).chain(0..start).peekable();
+        while let Some(idx) = order.next() {
+            let registry = &chain[idx];
+            match request(registry.clone()).await {
+                Ok(res) => {
+                    if idx != start {
+                        self.active_registries.insert(key, idx);
+                    }
+                    return Ok((res, registry.clone()));
+                }
+                Err(err) if err.is_registry_unavailable() && order.peek().is_some() => {
+                    tracing::warn!(
+                        "Registry {registry} is unavailable ({err}). Falling back to {}.",
+                        chain[*order.peek().unwrap()]
+                    );
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        unreachable!("registry chains always have at least one registry")
+    }
+
+    /// Public signing keys for the registry chain used by `scope`.
+    async fn registry_keys(&self, scope: &Option<String>) -> Result<Arc<Vec<RegistryKey>>> {
+        let key = self.chain_key(scope);
+        if let Some(keys) = self.registry_keys.get(&key) {
+            return Ok(keys.value().clone());
+        }
+        let (keys, _) = self
+            .with_failover(scope, |registry| {
+                let client = self.client.with_registry(registry);
+                async move { client.registry_keys().await }
+            })
+            .await?;
+        let keys = Arc::new(keys);
+        self.registry_keys.insert(key, keys.clone());
+        Ok(keys)
+    }
+
+    /// Points a tarball URL that lives under any registry in `chain` at the
+    /// `active` registry instead.
+    fn rewrite_tarball(chain: &[Url], active: &Url, tarball: &mut Option<Url>) {
+        if let Some(url) = tarball {
+            let rewritten = chain
+                .iter()
+                .find_map(|registry| url.as_str().strip_prefix(registry.as_str()))
+                .and_then(|path| active.join(path).ok());
+            if let Some(rewritten) = rewritten {
+                *url = rewritten;
+            }
+        }
     }
 }
 
@@ -65,6 +227,17 @@ impl PackageFetcher for NpmFetcher {
         Ok(self._name(spec).to_string())
     }
 
+    fn registry_path(&self, url: &Url) -> Option<String> {
+        if url.query().is_some() || url.fragment().is_some() {
+            return None;
+        }
+        let path = self
+            .all_registries()
+            .iter()
+            .find_map(|registry| url.as_str().strip_prefix(registry.as_str()))?;
+        is_tarball_path(path).then(|| path.to_string())
+    }
+
     async fn corgi_metadata(&self, pkg: &Package) -> Result<CorgiVersionMetadata> {
         let wanted = match pkg.resolved() {
             PackageResolution::Npm { ref version, .. } => version,
@@ -109,8 +282,27 @@ impl PackageFetcher for NpmFetcher {
                     return Ok(packument.value().clone());
                 }
             }
-            let client = self.client.with_registry(self.pick_registry(scope));
-            let packument = Arc::new(client.corgi_packument(&name).await?);
+            if self.publish_times {
+                let packument: CorgiPackument =
+                    (*self.packument(spec, _base_dir).await?).clone().into();
+                let packument = Arc::new(packument);
+                if self.cache_packuments {
+                    self.corgi_packuments
+                        .insert(name.clone(), packument.clone());
+                }
+                return Ok(packument);
+            }
+            let (mut packument, active) = self
+                .with_failover(scope, |registry| {
+                    let client = self.client.with_registry(registry);
+                    async move { client.corgi_packument(&name).await }
+                })
+                .await?;
+            let chain = self.registry_chain(scope);
+            for version in packument.versions.values_mut() {
+                Self::rewrite_tarball(&chain, &active, &mut version.dist.tarball);
+            }
+            let packument = Arc::new(packument);
             if self.cache_packuments {
                 self.corgi_packuments
                     .insert(name.clone(), packument.clone());
@@ -140,8 +332,17 @@ impl PackageFetcher for NpmFetcher {
                     return Ok(packument.value().clone());
                 }
             }
-            let client = self.client.with_registry(self.pick_registry(scope));
-            let packument = Arc::new(client.packument(&name).await?);
+            let (mut packument, active) = self
+                .with_failover(scope, |registry| {
+                    let client = self.client.with_registry(registry);
+                    async move { client.packument(&name).await }
+                })
+                .await?;
+            let chain = self.registry_chain(scope);
+            for version in packument.versions.values_mut() {
+                Self::rewrite_tarball(&chain, &active, &mut version.dist.tarball);
+            }
+            let packument = Arc::new(packument);
             if self.cache_packuments {
                 self.packuments.insert(name.clone(), packument.clone());
             }
@@ -156,7 +357,83 @@ impl PackageFetcher for NpmFetcher {
             PackageResolution::Npm { ref tarball, .. } => tarball,
             _ => panic!("How did a non-Npm resolution get here?"),
         };
-        Ok(self.client.stream_external(url).await?)
+        let scope = match pkg.from().target() {
+            PackageSpec::Npm { scope, .. } => scope.clone(),
+            _ => None,
+        };
+        let chain = self.registry_chain(&scope);
+        let path = chain
+            .iter()
+            .find_map(|registry| url.as_str().strip_prefix(registry.as_str()));
+        if let Some(path) = path {
+            // Tarballs hosted on one of our registries can be fetched from
+            // any of its mirrors, too.
+            let (tarball, _) = self
+                .with_failover(&scope, |registry| {
+                    let client = &self.client;
+                    let url = registry.join(path);
+                    async move { client.stream_external(&url?).await }
+                })
+                .await?;
+            Ok(tarball)
+        } else {
+            Ok(self.client.stream_external(url).await?)
+        }
+    }
+
+    async fn verify_signature(&self, pkg: &Package) -> Result<SignatureStatus> {
+        let (name, version, integrity) = match pkg.resolved() {
+            PackageResolution::Npm {
+                ref name,
+                ref version,
+                ref integrity,
+                ..
+            } => (name, version, integrity),
+            _ => panic!("How did a non-Npm resolution get here?"),
+        };
+        let scope = match pkg.from().target() {
+            PackageSpec::Npm { scope, .. } => scope.clone(),
+            _ => None,
+        };
+        let keys = self.registry_keys(&scope).await?;
+        if keys.is_empty() {
+            return Ok(SignatureStatus::Unsupported);
+        }
+        let packument = self.corgi_packument(pkg.from(), Path::new("")).await?;
+        let dist = &packument
+            .versions
+            .get(version)
+            .ok_or_else(|| NassunError::MissingVersion(pkg.from().clone(), version.clone()))?
+            .dist;
+        let published = packument
+            .time
+            .get(&version.to_string())
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| time.with_timezone(&Utc));
+        let status = verify_signatures(
+            &keys,
+            name,
+            version,
+            dist.integrity.as_deref(),
+            &dist.signatures,
+            published,
+        )?;
+        // The signature vouches for the registry's integrity. Make sure
+        // that's also the integrity we're going to check the tarball against.
+        if let (SignatureStatus::Verified { keyid }, Some(expected)) = (&status, integrity) {
+            let signed: Option<ssri::Integrity> =
+                dist.integrity.as_deref().and_then(|i| i.parse().ok());
+            if signed
+                .and_then(|signed| expected.matches(&signed))
+                .is_none()
+            {
+                return Err(NassunError::InvalidSignature(
+                    format!("{name}@{version}"),
+                    keyid.clone(),
+                ));
+            }
+        }
+        Ok(status)
     }
 }
 
@@ -169,7 +446,13 @@ mod test {
 
     #[async_std::test]
     async fn read_name() -> miette::Result<()> {
-        let fetcher = NpmFetcher::new(oro_client::OroClient::default(), HashMap::default(), false);
+        let fetcher = NpmFetcher::new(
+            oro_client::OroClient::default(),
+            HashMap::default(),
+            HashMap::default(),
+            false,
+            false,
+        );
         let spec = PackageSpec::Npm {
             scope: None,
             name: "npm".to_string(),
@@ -260,7 +543,13 @@ mod test {
         let mut registries = HashMap::new();
         registries.insert(None, Url::parse(mock_server.url().as_ref()).unwrap());
 
-        let fetcher = NpmFetcher::new(oro_client::OroClient::default(), registries, false);
+        let fetcher = NpmFetcher::new(
+            oro_client::OroClient::default(),
+            registries,
+            HashMap::default(),
+            false,
+            false,
+        );
         let spec = PackageSpec::Npm {
             scope: None,
             name: "oro-test-example".to_string(),
@@ -292,4 +581,248 @@ mod test {
         );
         Ok(())
     }
+
+    fn mirror_packument(tarball_registry: &str) -> String {
+        format!(
+            r#"{{
+            "name": "oro-test-example",
+            "dist-tags": {{ "latest": "1.0.0" }},
+            "versions": {{
+                "1.0.0": {{
+                    "name": "oro-test-example",
+                    "version": "1.0.0",
+                    "dist": {{
+                        "tarball": "{tarball_registry}/oro-test-example/-/oro-test-example-1.0.0.tgz"
+                    }}
+                }}
+            }}
+        }}"#
+        )
+    }
+
+    #[async_std::test]
+    async fn mirror_failover() -> miette::Result<()> {
+        let mut primary = mockito::Server::new();
+        let mut mirror = mockito::Server::new();
+        let primary_packument = primary
+            .mock("GET", "/oro-test-example")
+            .with_status(503)
+            .expect(1)
+            .create_async()
+            .await;
+        mirror
+            .mock("GET", "/oro-test-example")
+            .with_body(mirror_packument(&primary.url()))
+            .create_async()
+            .await;
+        let mirror_tarball = mirror
+            .mock("GET", "/oro-test-example/-/oro-test-example-1.0.0.tgz")
+            .with_body("tarball")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let mut registries = HashMap::new();
+        registries.insert(None, Url::parse(&primary.url()).unwrap());
+        let mut mirrors = HashMap::new();
+        mirrors.insert(None, vec![Url::parse(&mirror.url()).unwrap()]);
+        let fetcher = NpmFetcher::new(
+            oro_client::OroClientBuilder::new().retries(0).build(),
+            registries,
+            mirrors,
+            false,
+            false,
+        );
+        let spec: PackageSpec = "oro-test-example@1.0.0".parse()?;
+        let packument = fetcher.corgi_packument(&spec, Path::new("")).await?;
+        let tarball = packument
+            .versions
+            .get(&"1.0.0".parse()?)
+            .unwrap()
+            .dist
+            .tarball
+            .clone()
+            .unwrap();
+        assert_eq!(
+            tarball,
+            Url::parse(&format!(
+                "{}/oro-test-example/-/oro-test-example-1.0.0.tgz",
+                mirror.url()
+            ))
+            .unwrap(),
+            "tarball URLs are rewritten to point at the active mirror"
+        );
+
+        // Once a mirror has taken over, we stick with it instead of hitting
+        // the broken registry again.
+        fetcher.corgi_packument(&spec, Path::new("")).await?;
+        primary_packument.assert_async().await;
+
+        // Tarballs locked against the primary registry are also fetched from
+        // the mirror.
+        let pkg = crate::Nassun::new().resolve_from(
+            "oro-test-example".into(),
+            spec,
+            PackageResolution::Npm {
+                name: "oro-test-example".into(),
+                version: "1.0.0".parse()?,
+                tarball: Url::parse(&format!(
+                    "{}/oro-test-example/-/oro-test-example-1.0.0.tgz",
+                    primary.url()
+                ))
+                .unwrap(),
+                integrity: None,
+            },
+        );
+        let mut data = String::new();
+        futures::AsyncReadExt::read_to_string(&mut fetcher.tarball(&pkg).await?, &mut data)
+            .await
+            .unwrap();
+        assert_eq!(data, "tarball");
+        mirror_tarball.assert_async().await;
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn mirror_not_used_for_missing_packages() -> miette::Result<()> {
+        let mut primary = mockito::Server::new();
+        let mut mirror = mockito::Server::new();
+        primary
+            .mock("GET", "/oro-test-example")
+            .with_status(404)
+            .create_async()
+            .await;
+        let mirror_packument = mirror
+            .mock("GET", "/oro-test-example")
+            .expect(0)
+            .create_async()
+            .await;
+
+        let mut registries = HashMap::new();
+        registries.insert(None, Url::parse(&primary.url()).unwrap());
+        let mut mirrors = HashMap::new();
+        mirrors.insert(None, vec![Url::parse(&mirror.url()).unwrap()]);
+        let fetcher = NpmFetcher::new(
+            oro_client::OroClientBuilder::new().retries(0).build(),
+            registries,
+            mirrors,
+            false,
+            false,
+        );
+        let spec: PackageSpec = "oro-test-example@1.0.0".parse()?;
+        let res = fetcher.corgi_packument(&spec, Path::new("")).await;
+        assert!(matches!(
+            res,
+            Err(NassunError::OroClientError(
+                oro_client::OroClientError::PackageNotFound(..)
+            ))
+        ));
+        mirror_packument.assert_async().await;
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn verify_registry_signatures() -> miette::Result<()> {
+        use crate::signature::test::{sign, test_key};
+
+        let mut registry = mockito::Server::new();
+        let (signing, key) = test_key("SHA256:test", None);
+        registry
+            .mock("GET", "/-/npm/v1/keys")
+            .with_body(serde_json::json!({ "keys": [key] }).to_string())
+            .create_async()
+            .await;
+        let integrity = "sha512-deadbeef";
+        let good = sign(&signing, "SHA256:test", &format!("good@1.0.0:{integrity}"));
+        // Signed for a different version than the one being published.
+        let bad = sign(&signing, "SHA256:test", &format!("bad@0.0.1:{integrity}"));
+        for (name, signatures) in [
+            ("good", vec![good]),
+            ("bad", vec![bad]),
+            ("unsigned", vec![]),
+        ] {
+            registry
+                .mock("GET", format!("/{name}").as_str())
+                .with_body(
+                    serde_json::json!({
+                        "name": name,
+                        "dist-tags": { "latest": "1.0.0" },
+                        "versions": {
+                            "1.0.0": {
+                                "name": name,
+                                "version": "1.0.0",
+                                "dist": {
+                                    "tarball": format!("{}/{name}/-/{name}-1.0.0.tgz", registry.url()),
+                                    "integrity": integrity,
+                                    "signatures": signatures,
+                                }
+                            }
+                        }
+                    })
+                    .to_string(),
+                )
+                .create_async()
+                .await;
+        }
+
+        let nassun = crate::NassunOpts::new()
+            .registry(Url::parse(&registry.url()).unwrap())
+            .client(oro_client::OroClientBuilder::new().retries(0).build())
+            .build();
+        assert_eq!(
+            nassun.resolve("good@1").await?.verify_signature().await?,
+            SignatureStatus::Verified {
+                keyid: "SHA256:test".into()
+            }
+        );
+        assert!(matches!(
+            nassun.resolve("bad@1").await?.verify_signature().await,
+            Err(NassunError::InvalidSignature(..))
+        ));
+        assert_eq!(
+            nassun
+                .resolve("unsigned@1")
+                .await?
+                .verify_signature()
+                .await?,
+            SignatureStatus::Missing
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn registry_path() {
+        let fetcher = NpmFetcher::new(
+            oro_client::OroClient::default(),
+            HashMap::from([
+                (None, "https://mirror.example.com/npm".parse().unwrap()),
+                (
+                    Some("@scope".into()),
+                    "https://registry.npmjs.org/".parse().unwrap(),
+                ),
+            ]),
+            HashMap::default(),
+            false,
+            false,
+        );
+        let path = |url: &str| fetcher.registry_path(&url.parse().unwrap());
+        assert_eq!(
+            path("https://mirror.example.com/npm/a/-/a-1.0.0.tgz").as_deref(),
+            Some("a/-/a-1.0.0.tgz")
+        );
+        assert_eq!(
+            path("https://registry.npmjs.org/@scope/b/-/b-2.0.0.tgz").as_deref(),
+            Some("@scope/b/-/b-2.0.0.tgz")
+        );
+        assert_eq!(path("https://example.com/c/-/c-3.0.0.tgz"), None);
+        assert_eq!(path("https://mirror.example.com/npm/c.tgz"), None);
+        assert_eq!(
+            path("https://mirror.example.com/npm/a/-/a-1.0.0.tgz?x=y"),
+            None
+        );
+        assert_eq!(
+            path("https://mirror.example.com/npm/../a/-/a-1.0.0.tgz"),
+            None
+        );
+    }
 }
@@ -13,6 +13,7 @@ pub mod error;
 pub mod fetch;
 pub mod package;
 pub mod resolver;
+mod signature;
 pub mod tarball;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
@@ -26,6 +27,7 @@ pub use error::NassunError;
 #[cfg(not(target_arch = "wasm32"))]
 pub use package::*;
 pub use resolver::*;
+pub use signature::SignatureStatus;
 #[cfg(not(target_arch = "wasm32"))]
 pub use tarball::*;
 #[cfg(target_arch = "wasm32")]
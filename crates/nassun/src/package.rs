@@ -14,6 +14,7 @@ use crate::error::NassunError;
 use crate::error::Result;
 use crate::fetch::PackageFetcher;
 use crate::resolver::PackageResolution;
+use crate::signature::SignatureStatus;
 use crate::tarball::Tarball;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::tarball::TarballIndex;
@@ -73,6 +74,16 @@ impl Package {
         &self.resolved
     }
 
+    /// Path of this package's tarball relative to the configured registry
+    /// (or mirror) that hosts it, like `foo/-/foo-1.0.0.tgz`. Only registry
+    /// packages hosted on one of those have one.
+    pub fn registry_path(&self) -> Option<String> {
+        match &self.resolved {
+            PackageResolution::Npm { tarball, .. } => self.fetcher.registry_path(tarball),
+            _ => None,
+        }
+    }
+
     /// The full [`Packument`] that this `Package` was resolved from.
     pub async fn packument(&self) -> Result<Arc<Packument>> {
         self.fetcher.packument(&self.from, &self.base_dir).await
@@ -98,6 +109,16 @@ impl Package {
         self.fetcher.corgi_metadata(self).await
     }
 
+    /// Checks this package's registry signatures against its registry's
+    /// public keys. Returns an error if the package is signed, but the
+    /// signature doesn't check out.
+    ///
+    /// Only packages resolved from a registry can be verified. All others
+    /// will return [`SignatureStatus::Unsupported`].
+    pub async fn verify_signature(&self) -> Result<SignatureStatus> {
+        self.fetcher.verify_signature(self).await
+    }
+
     /// `AsyncRead` of the raw tarball data for this package. The data will
     /// not be checked for integrity based on the current `Package`'s
     /// [`Integrity`]. That is, bad or incomplete data may be returned.
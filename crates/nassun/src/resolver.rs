@@ -1,4 +1,6 @@
-use std::{fmt::Display, path::PathBuf, sync::Arc};
+use std::{collections::HashMap, fmt::Display, path::PathBuf, sync::Arc};
+
+use chrono::{DateTime, Utc};
 
 use node_semver::{Range as SemVerRange, Version as SemVerVersion};
 use oro_common::CorgiPackument;
@@ -99,6 +101,8 @@ impl PackageResolution {
 pub(crate) struct PackageResolver {
     pub(crate) default_tag: String,
     pub(crate) base_dir: PathBuf,
+    /// Only resolve to versions published at or before this time.
+    pub(crate) before: Option<DateTime<Utc>>,
 }
 
 impl PackageResolver {
@@ -173,15 +177,79 @@ impl PackageResolver {
             });
         }
 
+        let too_new = self.published_after_cutoff(name, packument);
+        let target = self.pick_version(spec, packument, |v| !too_new.contains_key(v))?;
+        if !too_new.is_empty() {
+            self.check_excluded(name, spec, packument, &too_new, target)?;
+        }
+
+        target
+            .and_then(|v| packument.versions.get(v))
+            .ok_or_else(|| NassunError::NoVersion {
+                name: name.into(),
+                spec: spec.clone(),
+                versions: packument.versions.keys().map(|k| k.to_string()).collect(),
+            })
+            .and_then(|v| {
+                Ok(PackageResolution::Npm {
+                    name: name.into(),
+                    version: v
+                        .manifest
+                        .version
+                        .clone()
+                        .unwrap_or_else(|| "0.0.0".parse().unwrap()),
+                    tarball: if let Some(tarball) = &v.dist.tarball {
+                        tarball.clone()
+                    } else {
+                        return Err(NassunError::NoTarball(
+                            name.into(),
+                            wanted.clone(),
+                            Box::new(v.clone()),
+                        ));
+                    },
+                    integrity: v
+                        .dist
+                        .integrity
+                        .as_ref()
+                        .map(|i| i.parse())
+                        .or_else(|| {
+                            v.dist
+                                .shasum
+                                .as_ref()
+                                .map(|s| format!("sha1-{}", s).parse())
+                        })
+                        .transpose()?,
+                })
+            })
+    }
+
+    /// Picks the version of `packument` that best matches `spec`, only
+    /// considering versions for which `allowed` returns true.
+    fn pick_version<'a>(
+        &self,
+        spec: &'a PackageSpec,
+        packument: &'a CorgiPackument,
+        allowed: impl Fn(&SemVerVersion) -> bool,
+    ) -> Result<Option<&'a SemVerVersion>, NassunError> {
+        use PackageSpec::*;
         let mut target: Option<&SemVerVersion> = match spec {
             Npm {
                 requested: Some(VersionSpec::Version(ref version)),
                 ..
-            } => Some(version),
+            } => Some(version).filter(|v| allowed(v)),
             Npm {
                 requested: Some(VersionSpec::Tag(tag)),
                 ..
-            } => packument.tags.get(tag.as_str()),
+            } => match packument.tags.get(tag.as_str()) {
+                // If the tagged version is too new, fall back to the newest
+                // allowed version that came before it.
+                Some(tagged) if !allowed(tagged) => SemVerRange::parse(format!("<={tagged}"))
+                    .ok()
+                    .and_then(|range| {
+                        max_satisfying(packument.versions.keys().filter(|v| allowed(v)), &range)
+                    }),
+                tagged => tagged,
+            },
             Npm {
                 requested: Some(VersionSpec::Range(_)),
                 ..
@@ -192,7 +260,7 @@ impl PackageResolver {
             _ => return Err(NassunError::InvalidPackageSpec(spec.clone())),
         };
 
-        let tag_version = packument.tags.get(&self.default_tag);
+        let tag_version = packument.tags.get(&self.default_tag).filter(|v| allowed(v));
 
         if target.is_none()
             && tag_version.is_some()
@@ -220,7 +288,7 @@ impl PackageResolver {
                 ..
             } = spec
             {
-                target = max_satisfying(packument.versions.keys(), range);
+                target = max_satisfying(packument.versions.keys().filter(|v| allowed(v)), range);
             }
         }
 
@@ -236,44 +304,95 @@ impl PackageResolver {
             }
         }
 
-        target
-            .and_then(|v| packument.versions.get(v))
-            .ok_or_else(|| NassunError::NoVersion {
+        Ok(target)
+    }
+
+    /// Versions in `packument` that were published after the configured
+    /// cutoff, along with their publish times. Versions with no recorded
+    /// publish time are never excluded.
+    fn published_after_cutoff<'a>(
+        &self,
+        name: &str,
+        packument: &'a CorgiPackument,
+    ) -> HashMap<&'a SemVerVersion, DateTime<Utc>> {
+        let Some(cutoff) = self.before else {
+            return HashMap::new();
+        };
+        if packument.time.is_empty() {
+            tracing::warn!(
+                "Registry did not provide publish times for {name}. Versions published after {} can't be excluded.",
+                cutoff.to_rfc3339()
+            );
+            return HashMap::new();
+        }
+        packument
+            .versions
+            .keys()
+            .filter_map(|version| {
+                let published = packument.time.get(&version.to_string())?;
+                let published = DateTime::parse_from_rfc3339(published).ok()?;
+                let published = published.with_timezone(&Utc);
+                (published > cutoff).then_some((version, published))
+            })
+            .collect()
+    }
+
+    /// Explains which versions the release cutoff kept us from resolving
+    /// `spec` to: as a warning if an older version was picked instead, or
+    /// as an error if nothing else matched.
+    fn check_excluded(
+        &self,
+        name: &str,
+        spec: &PackageSpec,
+        packument: &CorgiPackument,
+        too_new: &HashMap<&SemVerVersion, DateTime<Utc>>,
+        target: Option<&SemVerVersion>,
+    ) -> Result<(), NassunError> {
+        let Some(newest) = self.pick_version(spec, packument, |_| true)? else {
+            return Ok(());
+        };
+        if Some(newest) == target {
+            return Ok(());
+        }
+        let range = match spec {
+            PackageSpec::Npm {
+                requested: Some(VersionSpec::Range(range)),
+                ..
+            } => Some(range),
+            _ => None,
+        };
+        let mut excluded = too_new
+            .iter()
+            .filter(|(version, _)| {
+                **version <= newest
+                    && target.map(|target| *version > &target).unwrap_or(true)
+                    && range.map(|range| range.satisfies(version)).unwrap_or(true)
+            })
+            .collect::<Vec<_>>();
+        excluded.sort();
+        let excluded = excluded
+            .into_iter()
+            .map(|(version, published)| format!("{version} (published {})", published.to_rfc3339()))
+            .collect::<Vec<_>>();
+        let cutoff = self
+            .before
+            .expect("versions are only excluded when there's a cutoff");
+        if let Some(target) = target {
+            tracing::warn!(
+                "Resolved {name}@{} to {target}, skipping versions published after {}: {}",
+                spec.requested(),
+                cutoff.to_rfc3339(),
+                excluded.join(", ")
+            );
+            Ok(())
+        } else {
+            Err(NassunError::NoVersionBeforeCutoff {
                 name: name.into(),
                 spec: spec.clone(),
-                versions: packument.versions.keys().map(|k| k.to_string()).collect(),
-            })
-            .and_then(|v| {
-                Ok(PackageResolution::Npm {
-                    name: name.into(),
-                    version: v
-                        .manifest
-                        .version
-                        .clone()
-                        .unwrap_or_else(|| "0.0.0".parse().unwrap()),
-                    tarball: if let Some(tarball) = &v.dist.tarball {
-                        tarball.clone()
-                    } else {
-                        return Err(NassunError::NoTarball(
-                            name.into(),
-                            wanted.clone(),
-                            Box::new(v.clone()),
-                        ));
-                    },
-                    integrity: v
-                        .dist
-                        .integrity
-                        .as_ref()
-                        .map(|i| i.parse())
-                        .or_else(|| {
-                            v.dist
-                                .shasum
-                                .as_ref()
-                                .map(|s| format!("sha1-{}", s).parse())
-                        })
-                        .transpose()?,
-                })
+                cutoff: cutoff.to_rfc3339(),
+                excluded,
             })
+        }
     }
 }
 
@@ -287,6 +406,7 @@ fn max_satisfying<'a>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use oro_common::CorgiVersionMetadata;
     use test_case::test_case;
 
     #[test_case("1.4.2", "1.4.2", true; "exact version match")]
@@ -331,4 +451,73 @@ mod tests {
         };
         assert_eq!(resolution.satisfies(&package_spec).unwrap(), satifies);
     }
+
+    fn cutoff_packument() -> Arc<CorgiPackument> {
+        let mut packument = CorgiPackument::default();
+        for (version, published) in [
+            ("1.0.0", "2020-01-01T00:00:00.000Z"),
+            ("1.1.0", "2020-06-01T00:00:00.000Z"),
+            ("1.2.0", "2026-10-01T00:00:00.000Z"),
+        ] {
+            let version: SemVerVersion = version.parse().unwrap();
+            let mut metadata = CorgiVersionMetadata::default();
+            metadata.manifest.version = Some(version.clone());
+            metadata.dist.tarball = Some(
+                format!("https://example.com/oro-test-package/-/oro-test-package-{version}.tgz")
+                    .parse()
+                    .unwrap(),
+            );
+            packument
+                .time
+                .insert(version.to_string(), published.to_string());
+            packument.versions.insert(version, metadata);
+        }
+        packument
+            .tags
+            .insert("latest".into(), "1.2.0".parse().unwrap());
+        Arc::new(packument)
+    }
+
+    fn resolve_before(before: Option<&str>, spec: &str) -> Result<SemVerVersion, NassunError> {
+        let resolver = PackageResolver {
+            default_tag: "latest".into(),
+            base_dir: PathBuf::from("."),
+            before: before.map(|before| before.parse().unwrap()),
+        };
+        let spec: PackageSpec = format!("oro-test-package@{spec}").parse().unwrap();
+        resolver
+            .get_resolution("oro-test-package", &spec, &cutoff_packument())
+            .map(|resolution| resolution.npm_version().unwrap())
+    }
+
+    #[test_case(None, "^1.0.0", "1.2.0"; "no cutoff")]
+    #[test_case(Some("2021-01-01T00:00:00Z"), "^1.0.0", "1.1.0"; "range falls back")]
+    #[test_case(Some("2021-01-01T00:00:00Z"), "latest", "1.1.0"; "tag falls back")]
+    #[test_case(Some("2021-01-01T00:00:00Z"), "*", "1.1.0"; "any falls back")]
+    #[test_case(Some("2020-03-01T00:00:00Z"), "^1.0.0", "1.0.0"; "earlier cutoff")]
+    #[test_case(Some("2021-01-01T00:00:00Z"), "1.0.0", "1.0.0"; "exact version before cutoff")]
+    fn resolves_before_cutoff(before: Option<&str>, spec: &str, expected: &str) {
+        assert_eq!(
+            resolve_before(before, spec).unwrap(),
+            expected.parse::<SemVerVersion>().unwrap()
+        );
+    }
+
+    #[test]
+    fn errors_when_all_versions_are_too_new() {
+        let err = resolve_before(Some("2021-01-01T00:00:00Z"), "1.2.0").unwrap_err();
+        match err {
+            NassunError::NoVersionBeforeCutoff { excluded, .. } => {
+                assert_eq!(
+                    excluded,
+                    vec!["1.2.0 (published 2026-10-01T00:00:00+00:00)"]
+                )
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+        assert!(matches!(
+            resolve_before(Some("2019-01-01T00:00:00Z"), "^1.0.0"),
+            Err(NassunError::NoVersionBeforeCutoff { excluded, .. }) if excluded.len() == 3
+        ));
+    }
 }
@@ -0,0 +1,240 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use node_semver::Version;
+use oro_client::keys::RegistryKey;
+use oro_common::DistSignature;
+use p256::ecdsa::signature::Verifier;
+use p256::ecdsa::{Signature, VerifyingKey};
+use p256::pkcs8::DecodePublicKey;
+use serde::Serialize;
+
+use crate::error::{NassunError, Result};
+
+const SUPPORTED_KEYTYPE: &str = "ecdsa-sha2-nistp256";
+
+/// Outcome of checking a package's registry signatures.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// The package was signed by its registry, and the signature checks out.
+    Verified {
+        /// ID of the registry key the package was signed with.
+        keyid: String,
+    },
+    /// The registry publishes signing keys, but this package has no
+    /// signatures.
+    Missing,
+    /// Signatures can't be checked for this package, either because it
+    /// doesn't come from a registry, or because its registry doesn't
+    /// publish signing keys.
+    Unsupported,
+}
+
+impl SignatureStatus {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, SignatureStatus::Verified { .. })
+    }
+}
+
+/// Checks `signatures` for `name@version` against a registry's `keys`. The
+/// signed message is `<name>@<version>:<integrity>`.
+///
+/// Keys that have expired are only accepted for versions published before
+/// they expired. If the publish time isn't known, expired keys are accepted,
+/// since registries keep old signatures around after rotating keys.
+pub(crate) fn verify_signatures(
+    keys: &[RegistryKey],
+    name: &str,
+    version: &Version,
+    integrity: Option<&str>,
+    signatures: &[DistSignature],
+    published: Option<DateTime<Utc>>,
+) -> Result<SignatureStatus> {
+    if keys.is_empty() {
+        return Ok(SignatureStatus::Unsupported);
+    }
+    if signatures.is_empty() {
+        return Ok(SignatureStatus::Missing);
+    }
+    let pkg = format!("{name}@{version}");
+    let integrity = integrity.ok_or_else(|| NassunError::MissingSignedIntegrity(pkg.clone()))?;
+    let message = format!("{pkg}:{integrity}");
+    let mut last_err = None;
+    for signature in signatures {
+        let Some(key) = keys.iter().find(|key| key.keyid == signature.keyid) else {
+            last_err = Some(NassunError::UnknownSignatureKey(
+                pkg.clone(),
+                signature.keyid.clone(),
+            ));
+            continue;
+        };
+        if key.keytype != SUPPORTED_KEYTYPE {
+            tracing::debug!(
+                "Skipping signature for {pkg} made with unsupported key type {}",
+                key.keytype
+            );
+            continue;
+        }
+        if let (Some(expires), Some(published)) = (&key.expires, published) {
+            let expires = DateTime::parse_from_rfc3339(expires)
+                .map_err(|_| NassunError::InvalidRegistryKey(key.keyid.clone()))?;
+            if published >= expires {
+                last_err = Some(NassunError::ExpiredSignatureKey(
+                    pkg.clone(),
+                    key.keyid.clone(),
+                ));
+                continue;
+            }
+        }
+        let verifying_key = BASE64_STANDARD
+            .decode(&key.key)
+            .ok()
+            .and_then(|der| VerifyingKey::from_public_key_der(&der).ok())
+            .ok_or_else(|| NassunError::InvalidRegistryKey(key.keyid.clone()))?;
+        let valid = BASE64_STANDARD
+            .decode(&signature.sig)
+            .ok()
+            .and_then(|der| Signature::from_der(&der).ok())
+            .map(|sig| verifying_key.verify(message.as_bytes(), &sig).is_ok())
+            .unwrap_or(false);
+        if valid {
+            return Ok(SignatureStatus::Verified {
+                keyid: key.keyid.clone(),
+            });
+        }
+        last_err = Some(NassunError::InvalidSignature(
+            pkg.clone(),
+            key.keyid.clone(),
+        ));
+    }
+    Err(last_err.unwrap_or(NassunError::InvalidSignature(
+        pkg,
+        signatures[0].keyid.clone(),
+    )))
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use p256::ecdsa::signature::Signer;
+    use p256::ecdsa::SigningKey;
+    use p256::pkcs8::EncodePublicKey;
+
+    use super::*;
+
+    /// Deterministic key pair for tests.
+    pub(crate) fn test_key(keyid: &str, expires: Option<&str>) -> (SigningKey, RegistryKey) {
+        let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+        let public = signing_key
+            .verifying_key()
+            .to_public_key_der()
+            .unwrap()
+            .into_vec();
+        (
+            signing_key,
+            RegistryKey {
+                expires: expires.map(String::from),
+                keyid: keyid.into(),
+                keytype: SUPPORTED_KEYTYPE.into(),
+                scheme: SUPPORTED_KEYTYPE.into(),
+                key: BASE64_STANDARD.encode(public),
+            },
+        )
+    }
+
+    pub(crate) fn sign(key: &SigningKey, keyid: &str, message: &str) -> DistSignature {
+        let sig: Signature = key.sign(message.as_bytes());
+        DistSignature {
+            keyid: keyid.into(),
+            sig: BASE64_STANDARD.encode(sig.to_der().as_bytes()),
+        }
+    }
+
+    #[test]
+    fn verifies_signatures() -> miette::Result<()> {
+        let (signing, key) = test_key("SHA256:test", None);
+        let version = "1.0.0".parse()?;
+        let sig = sign(&signing, "SHA256:test", "foo@1.0.0:sha512-deadbeef");
+
+        assert_eq!(
+            verify_signatures(
+                &[key.clone()],
+                "foo",
+                &version,
+                Some("sha512-deadbeef"),
+                &[sig.clone()],
+                None
+            )?,
+            SignatureStatus::Verified {
+                keyid: "SHA256:test".into()
+            }
+        );
+
+        // Tampered integrity.
+        assert!(matches!(
+            verify_signatures(
+                &[key.clone()],
+                "foo",
+                &version,
+                Some("sha512-badc0ffee"),
+                &[sig.clone()],
+                None
+            ),
+            Err(NassunError::InvalidSignature(..))
+        ));
+
+        // Unknown key.
+        let (_, other) = test_key("SHA256:other", None);
+        assert!(matches!(
+            verify_signatures(
+                &[other],
+                "foo",
+                &version,
+                Some("sha512-deadbeef"),
+                &[sig.clone()],
+                None
+            ),
+            Err(NassunError::UnknownSignatureKey(..))
+        ));
+
+        assert_eq!(
+            verify_signatures(&[key], "foo", &version, Some("sha512-deadbeef"), &[], None)?,
+            SignatureStatus::Missing
+        );
+        assert_eq!(
+            verify_signatures(&[], "foo", &version, None, &[sig], None)?,
+            SignatureStatus::Unsupported
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn expired_keys() -> miette::Result<()> {
+        let (signing, key) = test_key("SHA256:test", Some("2020-01-01T00:00:00.000Z"));
+        let version = "1.0.0".parse()?;
+        let sig = sign(&signing, "SHA256:test", "foo@1.0.0:sha512-deadbeef");
+        let before = "2019-01-01T00:00:00Z".parse().unwrap();
+        let after = "2021-01-01T00:00:00Z".parse().unwrap();
+
+        assert!(verify_signatures(
+            &[key.clone()],
+            "foo",
+            &version,
+            Some("sha512-deadbeef"),
+            &[sig.clone()],
+            Some(before)
+        )?
+        .is_verified());
+        assert!(matches!(
+            verify_signatures(
+                &[key],
+                "foo",
+                &version,
+                Some("sha512-deadbeef"),
+                &[sig],
+                Some(after)
+            ),
+            Err(NassunError::ExpiredSignatureKey(..))
+        ));
+        Ok(())
+    }
+}
@@ -429,6 +429,41 @@ pub(crate) fn tarball_key(integrity: &Integrity) -> String {
     format!("nassun::package::{integrity}")
 }
 
+/// Reads a single file out of a package tarball that was previously
+/// extracted with `cache`, without going to the network. `path` is relative
+/// to the package root (for example, `"package.json"`).
+///
+/// Returns `None` if the tarball isn't in the cache, or if it doesn't
+/// contain a file at `path`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_cached_file(
+    cache: &Path,
+    integrity: &Integrity,
+    path: &str,
+) -> Result<Option<Vec<u8>>> {
+    let Some(entry) = cacache::index::find(cache, &tarball_key(integrity))
+        .map_err(|e| NassunError::ExtractCacheError(e, None))?
+    else {
+        return Ok(None);
+    };
+    let index = rkyv::check_archived_root::<TarballIndex>(
+        entry
+            .raw_metadata
+            .as_ref()
+            .ok_or_else(|| NassunError::CacheMissingIndexError(integrity.to_string()))?,
+    )
+    .map_err(|e| NassunError::DeserializeCacheError(e.to_string()))?;
+    let Some((sri, _)) = index.files.get(path) else {
+        return Ok(None);
+    };
+    let sri: Integrity = sri.as_str().parse()?;
+    match cacache::read_hash_sync(cache, &sri) {
+        Ok(data) => Ok(Some(data)),
+        Err(cacache::Error::IoError(e, _)) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(NassunError::ExtractCacheError(e, Some(PathBuf::from(path)))),
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) fn extract_from_cache(
     cache: &Path,
@@ -0,0 +1,212 @@
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+
+use indexmap::IndexMap;
+use nassun::PackageResolution;
+use node_semver::Version;
+use petgraph::stable_graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use unicase::UniCase;
+
+use crate::error::NodeMaintainerError;
+use crate::graph::Graph;
+
+impl Graph {
+    /// Collapses packages that show up more than once in the tree into a
+    /// single copy at the top of it, wherever a version that's already in
+    /// the tree satisfies everything that depends on that package. Returns
+    /// how many nodes were removed from the graph.
+    ///
+    /// The resolver places packages as it discovers them, so the order they
+    /// come in can leave duplicates behind that a different choice of
+    /// version would have avoided. No new versions are fetched here, and
+    /// collapses that would break any other part of the tree are skipped.
+    pub(crate) fn dedupe(&mut self) -> Result<usize, NodeMaintainerError> {
+        let before = self.inner.node_count();
+        // Collapsing one package can remove the conflicts that kept another
+        // one from being collapsed, so keep going until nothing changes.
+        loop {
+            let mut changed = false;
+            for name in self.duplicated_names() {
+                changed |= self.dedupe_name(&name)?;
+            }
+            if !changed {
+                break;
+            }
+        }
+        Ok(before - self.inner.node_count())
+    }
+
+    fn duplicated_names(&self) -> Vec<UniCase<String>> {
+        let mut counts = IndexMap::<&UniCase<String>, usize>::new();
+        for idx in self.inner.node_indices() {
+            if idx != self.root {
+                *counts.entry(&self[idx].name).or_default() += 1;
+            }
+        }
+        let mut names = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Tries to collapse every copy of `name` into one, returning whether
+    /// it managed to.
+    fn dedupe_name(&mut self, name: &UniCase<String>) -> Result<bool, NodeMaintainerError> {
+        let copies = self
+            .inner
+            .node_indices()
+            .filter(|idx| *idx != self.root && self[*idx].name == *name)
+            .collect::<Vec<_>>();
+        let requests = copies
+            .iter()
+            .flat_map(|idx| self.inner.edges_directed(*idx, Direction::Incoming))
+            .map(|edge| edge.weight().requested.clone())
+            .collect::<Vec<_>>();
+        let mut candidates = Vec::new();
+        'copies: for idx in &copies {
+            for requested in &requests {
+                if !self[*idx].package.resolved().satisfies(requested)? {
+                    continue 'copies;
+                }
+            }
+            candidates.push(*idx);
+        }
+        // Newer versions win, and then copies that are already closer to
+        // the top of the tree, since moving them is less likely to break
+        // their own dependencies.
+        candidates.sort_by_cached_key(|idx| {
+            (Reverse(self.version(*idx).cloned()), self[*idx].depth(self))
+        });
+        for keep in candidates {
+            let mut deduped = self.clone();
+            deduped.collapse(keep, &copies);
+            if deduped.is_consistent()? {
+                *self = deduped;
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn version(&self, idx: NodeIndex) -> Option<&Version> {
+        match self[idx].package.resolved() {
+            PackageResolution::Npm { version, .. } => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Moves `keep` to the top of the tree, points everything that depended
+    /// on any of `copies` at it, and removes the rest of the copies, along
+    /// with anything nothing depends on anymore.
+    fn collapse(&mut self, keep: NodeIndex, copies: &[NodeIndex]) {
+        let root = self.root;
+        let name = self[keep].name.clone();
+        if let Some(parent) = self[keep].parent {
+            self[parent].children.shift_remove(&name);
+        }
+        self[keep].parent = Some(root);
+        self[root].children.insert(name.clone(), keep);
+
+        let removed = copies
+            .iter()
+            .copied()
+            .filter(|idx| *idx != keep)
+            .collect::<Vec<_>>();
+        // All the new edges go in before anything gets removed, so none of
+        // them can end up reusing the index of an edge something still
+        // points to.
+        for copy in &removed {
+            let dependents = self
+                .inner
+                .edges_directed(*copy, Direction::Incoming)
+                .map(|edge| (edge.source(), edge.weight().clone()))
+                .collect::<Vec<_>>();
+            for (dependent, edge) in dependents {
+                let edge_idx = self.inner.add_edge(dependent, keep, edge);
+                self[dependent].dependencies.insert(name.clone(), edge_idx);
+            }
+        }
+        for copy in removed {
+            self.remove_subtree(copy);
+        }
+
+        let mut reachable = HashSet::from([root]);
+        let mut q = VecDeque::from([root]);
+        while let Some(idx) = q.pop_front() {
+            for dep in self.inner.neighbors_directed(idx, Direction::Outgoing) {
+                if reachable.insert(dep) {
+                    q.push_back(dep);
+                }
+            }
+        }
+        let unreachable = self
+            .inner
+            .node_indices()
+            .filter(|idx| !reachable.contains(idx))
+            .collect::<Vec<_>>();
+        for idx in unreachable {
+            self.remove_subtree(idx);
+        }
+    }
+
+    /// Removes `idx` and everything nested under it in the filesystem
+    /// hierarchy.
+    fn remove_subtree(&mut self, idx: NodeIndex) {
+        let Some(node) = self.inner.node_weight(idx) else {
+            // Already went away with an ancestor.
+            return;
+        };
+        let name = node.name.clone();
+        if let Some(parent) = node
+            .parent
+            .and_then(|parent| self.inner.node_weight_mut(parent))
+        {
+            if parent.children.get(&name) == Some(&idx) {
+                parent.children.shift_remove(&name);
+            }
+        }
+        let mut q = VecDeque::from([idx]);
+        while let Some(idx) = q.pop_front() {
+            if let Some(node) = self.inner.remove_node(idx) {
+                q.extend(node.children.into_values());
+            }
+        }
+    }
+
+    /// Whether every node is where its parent thinks it is, and every
+    /// dependency resolves, through the filesystem hierarchy, to a package
+    /// that satisfies it.
+    fn is_consistent(&self) -> Result<bool, NodeMaintainerError> {
+        for idx in self.inner.node_indices() {
+            let node = &self[idx];
+            if idx != self.root {
+                let parent = node
+                    .parent
+                    .and_then(|parent| self.inner.node_weight(parent));
+                if parent.and_then(|parent| parent.children.get(&node.name)) != Some(&idx) {
+                    return Ok(false);
+                }
+            }
+            for (dep_name, edge_idx) in &node.dependencies {
+                let Some((from, to)) = self.inner.edge_endpoints(*edge_idx) else {
+                    return Ok(false);
+                };
+                if from != idx
+                    || self.resolve_dep(idx, dep_name) != Some(to)
+                    || !self[to]
+                        .package
+                        .resolved()
+                        .satisfies(&self[*edge_idx].requested)?
+                {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::{HashMap, VecDeque};
+
+use nassun::PackageResolution;
+use node_semver::Version;
+use oro_common::DeprecationInfo;
+use petgraph::stable_graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::Serialize;
+
+use crate::graph::Graph;
+
+/// A deprecated package in a resolved dependency tree, as returned by
+/// [`crate::NodeMaintainer::deprecations`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeprecatedPackage {
+    pub name: String,
+    pub version: Option<Version>,
+    /// `node_modules` path of the package, relative to the project root.
+    pub path: String,
+    /// Deprecation message. `None` if the package was deprecated without
+    /// giving a reason.
+    pub reason: Option<String>,
+    /// Chain of dependencies that pulled this package in, starting with a
+    /// direct dependency of the project and ending with this package. If
+    /// the package is depended on from multiple places, this is the
+    /// shortest such chain.
+    pub dependency_path: Vec<String>,
+}
+
+impl DeprecatedPackage {
+    /// Whether this package is a direct dependency of the project.
+    pub fn is_direct(&self) -> bool {
+        self.dependency_path.len() == 1
+    }
+}
+
+impl Graph {
+    /// Collects every deprecated package in the graph, sorted by
+    /// `node_modules` path.
+    pub(crate) fn deprecations(&self) -> Vec<DeprecatedPackage> {
+        // Breadth-first walk along dependency edges, so each node is
+        // reached through its shortest dependency chain.
+        let mut reached_from: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut q = VecDeque::from([self.root]);
+        while let Some(idx) = q.pop_front() {
+            for edge in self.inner.edges_directed(idx, Direction::Outgoing) {
+                let target = edge.target();
+                if target != self.root && !reached_from.contains_key(&target) {
+                    reached_from.insert(target, idx);
+                    q.push_back(target);
+                }
+            }
+        }
+
+        let mut deprecations = self
+            .inner
+            .node_indices()
+            .filter_map(|idx| {
+                let node = &self.inner[idx];
+                let reason = match node.deprecated.as_ref()? {
+                    DeprecationInfo::Reason(reason) => Some(reason.clone()),
+                    DeprecationInfo::UnknownReason => None,
+                };
+                let mut dependency_path = Vec::new();
+                let mut current = Some(idx);
+                while let Some(curr_idx) = current.filter(|curr_idx| *curr_idx != self.root) {
+                    dependency_path.push(self.inner[curr_idx].name.to_string());
+                    current = reached_from.get(&curr_idx).copied();
+                }
+                dependency_path.reverse();
+                Some(DeprecatedPackage {
+                    name: node.package.name().to_string(),
+                    version: match node.package.resolved() {
+                        PackageResolution::Npm { version, .. } => Some(version.clone()),
+                        _ => None,
+                    },
+                    path: self.node_path_string(idx),
+                    reason,
+                    dependency_path,
+                })
+            })
+            .collect::<Vec<_>>();
+        deprecations.sort_by(|a, b| a.path.cmp(&b.path));
+        deprecations
+    }
+}
@@ -174,6 +174,13 @@ pub enum NodeMaintainerError {
     #[diagnostic(code(node_maintainer::build_manifest_read_error), url(docsrs))]
     BuildManifestReadError(std::path::PathBuf, #[source] std::io::Error),
 
+    /// Failed to read or write a package's install script side effects in
+    /// the cache. Refer to the error message for more details.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error(transparent)]
+    #[diagnostic(code(node_maintainer::side_effects_cache_error), url(docsrs))]
+    SideEffectsCacheError(#[from] cacache::Error),
+
     /// Some error occurred while running a script. Refer to the error message
     /// for more details.
     #[cfg(not(target_arch = "wasm32"))]
@@ -197,6 +204,100 @@ pub enum NodeMaintainerError {
         help("Did you modify package.json by hand?")
     )]
     LockfileMismatch,
+
+    /// There's no valid `node_modules/.orogene-meta.kdl` in the project, so
+    /// there's no installed dependency tree to work with.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("No installed dependency tree found at {}.", .0.display())]
+    #[diagnostic(
+        code(node_maintainer::not_installed),
+        url(docsrs),
+        help("Run `oro apply` to install the project's dependencies first.")
+    )]
+    NotInstalled(std::path::PathBuf),
+
+    /// The project's `package.json` no longer matches the dependency tree
+    /// installed in `node_modules`.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("The installed dependency tree is out of date with package.json.")]
+    #[diagnostic(
+        code(node_maintainer::installed_tree_outdated),
+        url(docsrs),
+        help("Run `oro apply` to bring node_modules up to date first.")
+    )]
+    InstalledTreeOutdated,
+
+    /// A package was requested that isn't in the installed dependency tree.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("{0} is not installed.")]
+    #[diagnostic(
+        code(node_maintainer::package_not_installed),
+        url(docsrs),
+        help("Packages can be given by name (`foo`) or name and version (`foo@1.2.3`).")
+    )]
+    PackageNotInstalled(String),
+
+    /// A `patchedDependencies` key couldn't be parsed. Keys should be a
+    /// package name, optionally followed by `@` and an exact version.
+    #[error("Invalid patched dependency `{0}`.")]
+    #[diagnostic(
+        code(node_maintainer::invalid_patched_dependency),
+        url(docsrs),
+        help("Use `name` or `name@<version>`, for example `left-pad` or `@scope/pkg@1.2.3`.")
+    )]
+    InvalidPatchedDependency(String),
+
+    /// A patch file isn't a valid unified diff.
+    #[error("Failed to parse patch file {} at line {1}: {2}", .0.display())]
+    #[diagnostic(
+        code(node_maintainer::patch_parse_error),
+        url(docsrs),
+        help("Patch files should be unified diffs, like the ones `oro patch-commit` and `git diff` create.")
+    )]
+    PatchParseError(std::path::PathBuf, usize, String),
+
+    /// A patch no longer applies to the package it's configured for.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Failed to apply patch {} to {package}: {reason}", .patch.display())]
+    #[diagnostic(
+        code(node_maintainer::patch_apply_error),
+        url(docsrs),
+        help("The patch may have been made for a different version of the package. Use `oro patch` to recreate it.")
+    )]
+    PatchApplyError {
+        package: String,
+        patch: std::path::PathBuf,
+        reason: String,
+    },
+
+    /// Only text files can be included in patches.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("Can't create a patch for binary file {}.", .0.display())]
+    #[diagnostic(
+        code(node_maintainer::binary_patch_file),
+        url(docsrs),
+        help("Revert your changes to this file, or move them into a text file.")
+    )]
+    BinaryPatchFile(std::path::PathBuf),
+
+    /// An entry in `node_modules/.oro-links.kdl` isn't a valid link.
+    #[error("Invalid linked package entry:\n{0}")]
+    #[diagnostic(
+        code(node_maintainer::invalid_link),
+        url(docsrs),
+        help("Entries should look like `link \"name\" \"/path/to/package\"`. Use `oro unlink` to remove all links and start over.")
+    )]
+    InvalidLink(KdlNode),
+
+    /// A package was linked to a directory that no longer exists.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[error("{0} is linked to {}, which doesn't exist anymore.", .1.display())]
+    #[diagnostic(
+        code(node_maintainer::missing_linked_package),
+        url(docsrs),
+        help("Run `oro unlink {0}` to go back to the package from package.json, or `oro link` it again from its new location.")
+    )]
+    MissingLinkedPackage(String, std::path::PathBuf),
 }
 
 impl<T> From<mpsc::TrySendError<T>> for NodeMaintainerError {
@@ -3,18 +3,21 @@ use std::{
     ffi::OsStr,
     ops::{Index, IndexMut},
     path::Path,
+    sync::Arc,
 };
 
 use indexmap::IndexMap;
 use kdl::KdlDocument;
 use nassun::{package::Package, PackageResolution, PackageSpec};
-use oro_common::CorgiManifest;
+use oro_common::{CorgiManifest, DeprecationInfo};
 use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableGraph};
 #[cfg(not(target_arch = "wasm32"))]
 use petgraph::Direction;
 use unicase::UniCase;
 
-use crate::{error::NodeMaintainerError, Lockfile, LockfileNode};
+use crate::{
+    error::NodeMaintainerError, links::Link, patches::PackagePatch, Lockfile, LockfileNode,
+};
 
 #[cfg(debug_assertions)]
 use NodeMaintainerError::GraphValidationError;
@@ -51,6 +54,14 @@ pub struct Node {
     /// not necessarily dependencies, and this Node's dependencies may not all
     /// be in this HashMap.
     pub(crate) children: IndexMap<UniCase<String>, NodeIndex>,
+    /// ID of the registry key this Node's package signature was verified
+    /// against, if it was verified.
+    pub(crate) signature_keyid: Option<String>,
+    /// Deprecation notice for this Node's package, if it's deprecated.
+    pub(crate) deprecated: Option<DeprecationInfo>,
+    /// Patch from `patchedDependencies` to apply to this Node's package
+    /// after it's extracted.
+    pub(crate) patch: Option<Arc<PackagePatch>>,
 }
 
 impl Node {
@@ -99,6 +110,9 @@ impl Node {
             children: IndexMap::new(),
             dependencies: IndexMap::new(),
             dependency_reqs,
+            signature_keyid: None,
+            deprecated: None,
+            patch: None,
         })
     }
 
@@ -131,10 +145,12 @@ impl Edge {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct Graph {
     pub(crate) root: NodeIndex,
     pub(crate) inner: StableGraph<Node, Edge>,
+    /// The root package's dependencies that were replaced with `oro link`.
+    pub(crate) links: IndexMap<UniCase<String>, Link>,
 }
 
 impl Index<NodeIndex> for Graph {
@@ -371,7 +387,14 @@ impl Graph {
         let path = self.node_path(node);
         let node = &self.inner[node];
         let resolved = match node.package.resolved() {
-            PackageResolution::Npm { tarball, .. } => tarball.to_string(),
+            PackageResolution::Npm { tarball, .. } => {
+                // Tarballs from the configured registries are recorded
+                // relative to them, so the lockfile works against any of
+                // them.
+                node.package
+                    .registry_path()
+                    .unwrap_or_else(|| tarball.to_string())
+            }
             PackageResolution::Dir { path, .. } => path.to_string_lossy().into(),
             PackageResolution::Git { info, .. } => info.to_string(),
         };
@@ -413,6 +436,9 @@ impl Graph {
                 PackageResolution::Npm { ref integrity, .. } => integrity.clone(),
                 _ => None,
             },
+            signature_keyid: node.signature_keyid.clone(),
+            deprecated: node.deprecated.clone(),
+            patch: node.patch.as_ref().map(|patch| patch.integrity.clone()),
         })
     }
 }
@@ -5,20 +5,46 @@ pub use nassun::Nassun;
 #[cfg(not(target_arch = "wasm32"))]
 pub use nassun::{NassunError, NassunOpts};
 
+pub use deprecations::DeprecatedPackage;
 pub use error::*;
 pub use into_kdl::IntoKdl;
+pub use license_expr::LicenseExpr;
+#[cfg(not(target_arch = "wasm32"))]
+pub use linkers::{link_package_bins, unlink_package_bins};
+pub use links::LinkedDependencies;
 pub use lockfile::*;
+pub use lockfile_diff::*;
 #[cfg(not(target_arch = "wasm32"))]
 pub use maintainer::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use patches::{apply_patch_file, create_patch};
+pub use sbom::{Sbom, SbomComponent, SbomOptions};
+#[cfg(not(target_arch = "wasm32"))]
+pub use script_logs::{
+    script_log_file_name, script_log_package_name, ScriptFailure, SCRIPT_FAILURE_LINES,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use store::PackageStore;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
 
+mod dedupe;
+mod deprecations;
 mod error;
 mod graph;
 mod into_kdl;
+mod license_expr;
 mod linkers;
+mod links;
 mod lockfile;
+mod lockfile_diff;
 mod maintainer;
+mod patches;
 mod resolver;
+mod sbom;
+#[cfg(not(target_arch = "wasm32"))]
+mod script_logs;
+#[cfg(not(target_arch = "wasm32"))]
+mod store;
 #[cfg(target_arch = "wasm32")]
 pub use wasm::*;
@@ -0,0 +1,144 @@
+/// A parsed SPDX license expression, like `(MIT OR Apache-2.0)`.
+///
+/// SBOMs and license policies both use this to tell whether a package's
+/// `license` field is an SPDX expression at all. npm's `UNLICENSED` isn't
+/// an SPDX license ID, and neither is anything like `SEE LICENSE IN
+/// LICENSE.md`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+    /// A single license ID, including any `WITH <exception>`.
+    License(String),
+    And(Box<LicenseExpr>, Box<LicenseExpr>),
+    Or(Box<LicenseExpr>, Box<LicenseExpr>),
+}
+
+impl LicenseExpr {
+    /// Parses an SPDX license expression, returning `None` if `input` isn't
+    /// one. `AND` binds tighter than `OR`, and operators are
+    /// case-insensitive.
+    pub fn parse(input: &str) -> Option<Self> {
+        let spaced = input.replace('(', " ( ").replace(')', " ) ");
+        let tokens = spaced.split_whitespace().collect::<Vec<_>>();
+        let mut pos = 0;
+        let expr = Self::parse_or(&tokens, &mut pos)?;
+        (pos == tokens.len()).then_some(expr)
+    }
+
+    fn parse_or(tokens: &[&str], pos: &mut usize) -> Option<Self> {
+        let mut expr = Self::parse_and(tokens, pos)?;
+        while tokens
+            .get(*pos)
+            .filter(|t| t.eq_ignore_ascii_case("OR"))
+            .is_some()
+        {
+            *pos += 1;
+            expr = Self::Or(Box::new(expr), Box::new(Self::parse_and(tokens, pos)?));
+        }
+        Some(expr)
+    }
+
+    fn parse_and(tokens: &[&str], pos: &mut usize) -> Option<Self> {
+        let mut expr = Self::parse_atom(tokens, pos)?;
+        while tokens
+            .get(*pos)
+            .filter(|t| t.eq_ignore_ascii_case("AND"))
+            .is_some()
+        {
+            *pos += 1;
+            expr = Self::And(Box::new(expr), Box::new(Self::parse_atom(tokens, pos)?));
+        }
+        Some(expr)
+    }
+
+    fn parse_atom(tokens: &[&str], pos: &mut usize) -> Option<Self> {
+        let token = *tokens.get(*pos)?;
+        *pos += 1;
+        if token == "(" {
+            let expr = Self::parse_or(tokens, pos)?;
+            if tokens.get(*pos) != Some(&")") {
+                return None;
+            }
+            *pos += 1;
+            return Some(expr);
+        }
+        let is_id = |t: &str| {
+            t.chars()
+                .all(|c| c.is_ascii_alphanumeric() || ".-+:".contains(c))
+                && !["AND", "OR", "WITH", ")", "UNLICENSED"]
+                    .contains(&t.to_ascii_uppercase().as_str())
+        };
+        if !is_id(token) {
+            return None;
+        }
+        if tokens
+            .get(*pos)
+            .filter(|t| t.eq_ignore_ascii_case("WITH"))
+            .is_some()
+        {
+            let exception = *tokens.get(*pos + 1).filter(|t| is_id(t))?;
+            *pos += 2;
+            return Some(Self::License(format!("{token} WITH {exception}")));
+        }
+        Some(Self::License(token.into()))
+    }
+
+    /// Collects every license ID in this expression, in order, including any
+    /// `WITH <exception>`.
+    pub fn collect_ids(&self, ids: &mut Vec<String>) {
+        match self {
+            Self::License(id) => ids.push(id.clone()),
+            Self::And(a, b) | Self::Or(a, b) => {
+                a.collect_ids(ids);
+                b.collect_ids(ids);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_expressions() {
+        use LicenseExpr::*;
+        let lic = |id: &str| Box::new(License(id.into()));
+        assert_eq!(LicenseExpr::parse("MIT"), Some(License("MIT".into())));
+        assert_eq!(
+            LicenseExpr::parse("(MIT OR Apache-2.0) AND BSD-3-Clause"),
+            Some(And(
+                Box::new(Or(lic("MIT"), lic("Apache-2.0"))),
+                lic("BSD-3-Clause")
+            ))
+        );
+        assert_eq!(
+            LicenseExpr::parse("MIT OR Apache-2.0 AND ISC"),
+            Some(Or(lic("MIT"), Box::new(And(lic("Apache-2.0"), lic("ISC"))))),
+            "AND binds tighter than OR"
+        );
+        assert_eq!(
+            LicenseExpr::parse("GPL-2.0-only WITH Classpath-exception-2.0"),
+            Some(License("GPL-2.0-only WITH Classpath-exception-2.0".into()))
+        );
+        assert_eq!(LicenseExpr::parse("SEE LICENSE IN LICENSE.md"), None);
+        assert_eq!(LicenseExpr::parse("UNLICENSED"), None);
+        assert_eq!(LicenseExpr::parse("(MIT OR"), None);
+        assert_eq!(LicenseExpr::parse("MIT)"), None);
+    }
+
+    #[test]
+    fn collects_ids() {
+        let mut ids = Vec::new();
+        LicenseExpr::parse("(MIT OR Apache-2.0) AND GPL-2.0-only WITH Classpath-exception-2.0")
+            .unwrap()
+            .collect_ids(&mut ids);
+        assert_eq!(
+            ids,
+            vec![
+                "MIT",
+                "Apache-2.0",
+                "GPL-2.0-only WITH Classpath-exception-2.0"
+            ]
+        );
+    }
+}
@@ -15,12 +15,13 @@ use walkdir::WalkDir;
 
 use crate::error::{IoContext, NodeMaintainerError};
 use crate::graph::Graph;
-use crate::{META_FILE_NAME, STORE_DIR_NAME};
+use crate::{ScriptFailure, LINKS_FILE_NAME, META_FILE_NAME, STORE_DIR_NAME};
 
 use super::LinkerOptions;
 
 pub(crate) struct HoistedLinker {
     pub(crate) pending_rebuild: Arc<Mutex<HashSet<NodeIndex>>>,
+    pub(crate) script_failures: Arc<Mutex<Vec<ScriptFailure>>>,
     pub(crate) mkdir_cache: Arc<DashSet<PathBuf>>,
     pub(crate) opts: LinkerOptions,
 }
@@ -29,6 +30,7 @@ impl HoistedLinker {
     pub fn new(opts: LinkerOptions) -> Self {
         Self {
             pending_rebuild: Arc::new(Mutex::new(HashSet::new())),
+            script_failures: Arc::new(Mutex::new(Vec::new())),
             mkdir_cache: Arc::new(DashSet::new()),
             opts,
         }
@@ -68,6 +70,10 @@ impl HoistedLinker {
                         prefix.display()
                     )
                 })?;
+                if entry.file_name() == LINKS_FILE_NAME {
+                    // Links outlive the tree they were installed into.
+                    continue;
+                }
                 let ty = entry.file_type().await.io_context(|| {
                     format!(
                         "Failed to get file type from entry at {}.",
@@ -107,6 +113,7 @@ impl HoistedLinker {
         let nm_osstr = Some(std::ffi::OsStr::new("node_modules"));
         let bin_osstr = Some(std::ffi::OsStr::new(".bin"));
         let meta = prefix.join(META_FILE_NAME);
+        let links = prefix.join(LINKS_FILE_NAME);
         let mut extraneous_packages = 0;
         let extraneous = &mut extraneous_packages;
 
@@ -115,8 +122,8 @@ impl HoistedLinker {
             .filter_entry(move |entry| {
                 let entry_path = entry.path();
 
-                if entry_path == meta {
-                    // Skip the meta file
+                if entry_path == meta || entry_path == links {
+                    // Skip the meta and links files
                     return false;
                 }
 
@@ -284,7 +291,7 @@ impl HoistedLinker {
                     actually_extracted,
                     pending_rebuild,
                 )| async move {
-                    if child_idx == graph.root {
+                    if child_idx == graph.root || graph.is_inside_link(child_idx) {
                         return Ok(());
                     }
 
@@ -300,27 +307,35 @@ impl HoistedLinker {
                     let start = std::time::Instant::now();
 
                     if !target_dir.exists() {
-                        graph[child_idx]
-                            .package
-                            .extract_to_dir(&target_dir, extract_mode)
-                            .await?;
+                        if let Some(dir) = graph.linked_dir(child_idx) {
+                            super::link_dir(dir, &target_dir).await?;
+                        } else {
+                            graph[child_idx]
+                                .package
+                                .extract_to_dir(&target_dir, extract_mode)
+                                .await?;
+                            super::apply_patch(graph, child_idx, &target_dir).await?;
+                        }
                         actually_extracted.fetch_add(1, atomic::Ordering::SeqCst);
                         let target_dir = target_dir.clone();
-                        let build_mani = async_std::task::spawn_blocking(move || {
-                            BuildManifest::from_path(target_dir.join("package.json")).map_err(|e| {
+                        let package = graph[child_idx].package.clone();
+                        let needs_rebuild = async_std::task::spawn_blocking(move || {
+                            let build_mani = BuildManifest::from_path(
+                                target_dir.join("package.json"),
+                            )
+                            .map_err(|e| {
                                 NodeMaintainerError::BuildManifestReadError(
                                     target_dir.join("package.json"),
                                     e,
                                 )
-                            })
+                            })?;
+                            Ok::<_, NodeMaintainerError>(
+                                super::has_lifecycle_scripts(&build_mani, &package, &target_dir)
+                                    || !build_mani.bin.is_empty(),
+                            )
                         })
                         .await?;
-                        if build_mani.scripts.contains_key("preinstall")
-                            || build_mani.scripts.contains_key("install")
-                            || build_mani.scripts.contains_key("postinstall")
-                            || build_mani.scripts.contains_key("prepare")
-                            || !build_mani.bin.is_empty()
-                        {
+                        if needs_rebuild {
                             pending_rebuild.lock().await.insert(child_idx);
                         }
                     }
@@ -371,6 +386,8 @@ impl HoistedLinker {
             let entry = entry?;
             if entry.path().file_name() == bin_file_name {
                 async_std::fs::remove_dir_all(entry.path()).await.io_context(|| format!("Failed to remove directory at {} while clearing out existing node_modules/.bin directories.", entry.path().display()))?;
+                // It has to be created all over again if anything links into it.
+                self.mkdir_cache.remove(entry.path());
             }
         }
         futures::stream::iter(self.pending_rebuild.lock().await.iter().copied())
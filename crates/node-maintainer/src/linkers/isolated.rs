@@ -1,6 +1,6 @@
 use std::{
-    collections::{BinaryHeap, HashMap, HashSet},
-    path::{Path, PathBuf},
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    path::{Component, Path, PathBuf},
     sync::{
         atomic::{self, AtomicUsize},
         Arc,
@@ -9,32 +9,119 @@ use std::{
 
 use dashmap::DashSet;
 use futures::{lock::Mutex, StreamExt, TryStreamExt};
-use nassun::ExtractMode;
+use indexmap::IndexMap;
+use nassun::{ExtractMode, PackageResolution};
+use once_cell::sync::OnceCell;
 use oro_common::BuildManifest;
-use petgraph::{stable_graph::NodeIndex, visit::EdgeRef, Direction};
-use ssri::Integrity;
+use petgraph::{stable_graph::NodeIndex, Direction};
 
-use crate::{error::IoContext, graph::Graph, NodeMaintainerError, META_FILE_NAME, STORE_DIR_NAME};
+use crate::{
+    error::IoContext,
+    graph::Graph,
+    store::{self, FileLock, PackageStore, ENTRY_COMPLETE_NAME, ENTRY_LOCK_NAME, ENTRY_TMP_NAME},
+    NodeMaintainerError, ScriptFailure, LINKS_FILE_NAME, META_FILE_NAME, STORE_DIR_NAME,
+};
 
-use super::LinkerOptions;
+use super::{closure_hashes, LinkerOptions};
 
 pub(crate) struct IsolatedLinker {
     pub(crate) pending_rebuild: Arc<Mutex<HashSet<NodeIndex>>>,
+    pub(crate) script_failures: Arc<Mutex<Vec<ScriptFailure>>>,
     pub(crate) pending_bin_link: Arc<Mutex<BinaryHeap<NodeIndex>>>,
     pub(crate) mkdir_cache: Arc<DashSet<PathBuf>>,
     pub(crate) opts: LinkerOptions,
+    store_dir_names: OnceCell<HashMap<NodeIndex, String>>,
 }
 
 impl IsolatedLinker {
     pub fn new(opts: LinkerOptions) -> Self {
+        if let Some(store) = opts
+            .store
+            .as_ref()
+            .filter(|_| !opts.hoist_patterns.is_empty())
+        {
+            tracing::warn!(
+                "Ignoring hoist patterns ({}), since packages aren't privately hoisted when using a shared store ({}).",
+                opts.hoist_patterns.join(", "),
+                store.display()
+            );
+        }
         Self {
             pending_rebuild: Arc::new(Mutex::new(HashSet::new())),
+            script_failures: Arc::new(Mutex::new(Vec::new())),
             pending_bin_link: Arc::new(Mutex::new(BinaryHeap::new())),
             mkdir_cache: Arc::new(DashSet::new()),
             opts,
+            store_dir_names: OnceCell::new(),
         }
     }
 
+    /// Names of every package's directory in the store. They only depend on
+    /// the graph, which doesn't change over the linker's lifetime, so
+    /// they're only worked out once.
+    fn store_dir_names(&self, graph: &Graph) -> &HashMap<NodeIndex, String> {
+        self.store_dir_names.get_or_init(|| store_dir_names(graph))
+    }
+
+    /// The package store packages get extracted to: either a shared one, or
+    /// one inside the project's `node_modules`.
+    fn store_dir(&self) -> PathBuf {
+        self.opts
+            .store
+            .clone()
+            .unwrap_or_else(|| self.opts.root.join("node_modules").join(STORE_DIR_NAME))
+    }
+
+    /// Picks the packages that get hoisted because they match a hoist
+    /// pattern. When there's more than one package with the same name, the
+    /// one closest to the root wins, and nothing gets hoisted over the
+    /// project's own direct dependencies.
+    fn hoisted(&self, graph: &Graph, names: &HashMap<NodeIndex, String>) -> Hoisted {
+        let mut hoisted = Hoisted::default();
+        if self.opts.public_hoist_patterns.is_empty() && self.opts.hoist_patterns.is_empty() {
+            return hoisted;
+        }
+        // Private hoisting would leak packages between projects sharing a
+        // store, so it only happens with a project-local one.
+        let hoist_patterns: &[String] = if self.opts.store.is_none() {
+            &self.opts.hoist_patterns
+        } else {
+            &[]
+        };
+        let mut taken = graph
+            .inner
+            .neighbors_directed(graph.root, Direction::Outgoing)
+            .map(|idx| graph[idx].package.name())
+            .collect::<HashSet<_>>();
+        let mut visited = HashSet::from([graph.root]);
+        let mut queue = VecDeque::from([graph.root]);
+        while let Some(idx) = queue.pop_front() {
+            let mut deps = graph
+                .inner
+                .neighbors_directed(idx, Direction::Outgoing)
+                .filter(|dep| visited.insert(*dep))
+                .collect::<Vec<_>>();
+            // Keep which of several same-depth packages wins stable.
+            deps.sort_by(|a, b| names[a].cmp(&names[b]));
+            for dep in deps {
+                queue.push_back(dep);
+                let name = graph[dep].package.name();
+                if taken.contains(name) {
+                    continue;
+                }
+                if matches_patterns(&self.opts.public_hoist_patterns, name) {
+                    hoisted.public.push(dep);
+                } else if matches_patterns(hoist_patterns, name) {
+                    hoisted.private.push(dep);
+                } else {
+                    continue;
+                }
+                taken.insert(name);
+            }
+        }
+        hoisted
+    }
+
     pub async fn prune(&self, graph: &Graph) -> Result<usize, NodeMaintainerError> {
         let start = std::time::Instant::now();
 
@@ -48,12 +135,18 @@ impl IsolatedLinker {
             return Ok(0);
         }
 
-        let store = prefix.join(STORE_DIR_NAME);
+        let store = self.store_dir();
 
-        if self.opts.actual_tree.is_none() || !async_std::path::Path::new(&store).exists().await {
-            // If there's no actual tree previously calculated, we can't trust
-            // *anything* inside node_modules, so everything is immediately
-            // extraneous and we wipe it all. Sorry.
+        if self.opts.actual_tree.is_none()
+            || !async_std::path::Path::new(&store).exists().await
+            || !store::read_meta(&prefix).map_or(false, |meta| {
+                store::store_layout_matches(&meta, self.opts.store.as_deref())
+            })
+        {
+            // If there's no actual tree previously calculated, or the store
+            // was laid out by an older version or lives somewhere else now,
+            // we can't trust *anything* inside node_modules, so everything
+            // is immediately extraneous and we wipe it all. Sorry.
             let mut entries = async_std::fs::read_dir(&prefix).await.io_context(|| {
                 format!(
                     "Failed to read contents of node_modules at {}.",
@@ -67,6 +160,10 @@ impl IsolatedLinker {
                         prefix.display()
                     )
                 })?;
+                if entry.file_name() == LINKS_FILE_NAME {
+                    // Links outlive the tree they were installed into.
+                    continue;
+                }
                 let path = entry.path();
                 let ty = entry.file_type().await.io_context(|| {
                     format!(
@@ -100,19 +197,34 @@ impl IsolatedLinker {
             return Ok(0);
         }
 
-        let mut expected = HashSet::new();
-
-        let expected_mut = &mut expected;
+        let names = self.store_dir_names(graph);
+        let hoisted = self.hoisted(graph, names);
+        let hoisted_ref = &hoisted;
         let store_ref = &store;
-        // Clean out individual node_modules within
-        let indices = graph.inner.node_indices().map(move |idx| {
-            if idx != graph.root {
-                let pkg_store_dir = store_ref.join(package_dir_name(graph, idx));
+        let hoist_dir = store.join("node_modules");
+        let mut expected = names
+            .values()
+            .map(|name| store_ref.join(name))
+            .collect::<HashSet<_>>();
+        if !hoisted.private.is_empty() {
+            expected.insert(hoist_dir.clone());
+        }
 
-                expected_mut.insert(pkg_store_dir);
-            }
-            idx
-        });
+        // Clean out individual node_modules within. Nodes that share a store
+        // directory have identical dependencies, so we only need to look at
+        // one of them. Shared stores are only ever written to under lock, and
+        // get pruned separately, so we leave their entries alone.
+        let mut seen = HashSet::new();
+        let indices = graph
+            .inner
+            .node_indices()
+            .filter(|idx| {
+                *idx == graph.root
+                    || (self.opts.store.is_none()
+                        && !graph.is_link(*idx)
+                        && seen.insert(&names[idx]))
+            })
+            .collect::<Vec<_>>();
 
         let prefix_ref = &prefix;
         futures::stream::iter(indices)
@@ -124,7 +236,7 @@ impl IsolatedLinker {
                     prefix_ref.to_owned()
                 } else {
                     store_ref
-                        .join(package_dir_name(graph, idx))
+                        .join(&names[&idx])
                         .join("node_modules")
                         .join(pkg.name())
                         .join("node_modules")
@@ -133,11 +245,18 @@ impl IsolatedLinker {
 
                 let mut expected_deps = HashMap::new();
 
-                for edge in graph.inner.edges_directed(idx, Direction::Outgoing) {
-                    let dep_pkg = &graph[edge.target()].package;
+                let mut deps = graph
+                    .inner
+                    .neighbors_directed(idx, Direction::Outgoing)
+                    .collect::<Vec<_>>();
+                if idx == graph.root {
+                    deps.extend(&hoisted_ref.public);
+                }
+                for dep in deps {
+                    let dep_pkg = &graph[dep].package;
                     let dep_store_dir = async_std::path::PathBuf::from(
                         store_ref
-                            .join(package_dir_name(graph, edge.target()))
+                            .join(&names[&dep])
                             .join("node_modules")
                             .join(dep_pkg.name()),
                     );
@@ -232,6 +351,51 @@ impl IsolatedLinker {
             })
             .await?;
 
+        // Links into the store that nothing expects anymore, like packages
+        // that no longer match a hoist pattern, get removed too, along with
+        // any bins they leave dangling.
+        let mut stale_links = vec![(
+            prefix.clone(),
+            expected_links(
+                graph,
+                names,
+                graph
+                    .inner
+                    .neighbors_directed(graph.root, Direction::Outgoing)
+                    .chain(hoisted.public.iter().copied()),
+                &prefix,
+                &store,
+            ),
+        )];
+        if self.opts.store.is_none() {
+            stale_links.push((
+                hoist_dir.clone(),
+                expected_links(
+                    graph,
+                    names,
+                    hoisted.private.iter().copied(),
+                    &hoist_dir,
+                    &store,
+                ),
+            ));
+        }
+        let stale_store = store.clone();
+        async_std::task::spawn_blocking(move || {
+            for (nm, expected) in stale_links {
+                remove_stale_links(&nm, &expected, &stale_store)?;
+            }
+            Ok::<_, NodeMaintainerError>(())
+        })
+        .await?;
+
+        if self.opts.store.is_some() {
+            tracing::debug!(
+                "Pruned project links in {}ms. Shared store left as-is.",
+                start.elapsed().as_micros() / 1000
+            );
+            return Ok(0);
+        }
+
         let expected_ref = &expected;
 
         let pruned = Arc::new(AtomicUsize::new(0));
@@ -340,9 +504,32 @@ impl IsolatedLinker {
         let start = std::time::Instant::now();
 
         let root = &self.opts.root;
-        let store = root.join("node_modules").join(STORE_DIR_NAME);
+        let store = self.store_dir();
         let store_ref = &store;
-        let stream = futures::stream::iter(graph.inner.node_indices());
+        // Installs share the lock on a shared store, so that it can't be
+        // pruned out from under them.
+        let shared_store = self.opts.store.as_ref().map(PackageStore::new);
+        let _store_lock = match &shared_store {
+            Some(shared) => {
+                let lock = shared.lock(false).await?;
+                shared.register_project(root)?;
+                Some(lock)
+            }
+            None => None,
+        };
+        let names = self.store_dir_names(graph);
+        let hoisted = self.hoisted(graph, names);
+        let hoisted_ref = &hoisted;
+        // Nodes with the same store directory get extracted and linked
+        // together, so we never have two tasks writing to the same place.
+        let mut groups = IndexMap::<&str, Vec<NodeIndex>>::new();
+        for idx in graph.inner.node_indices() {
+            if idx != graph.root {
+                groups.entry(&names[&idx]).or_default().push(idx);
+            }
+        }
+        let stream =
+            futures::stream::iter(std::iter::once(vec![graph.root]).chain(groups.into_values()));
         let concurrent_count = Arc::new(AtomicUsize::new(0));
         let pending_rebuild = self.pending_rebuild.clone();
         let pending_bin_link = self.pending_bin_link.clone();
@@ -351,12 +538,16 @@ impl IsolatedLinker {
         let total_completed = Arc::new(AtomicUsize::new(0));
         let node_modules = root.join("node_modules");
         super::mkdirp(&node_modules, &self.mkdir_cache)?;
+        std::fs::create_dir_all(&store)
+            .io_context(|| format!("Failed to create package store at {}.", store.display()))?;
+        // The bin links for the root's dependencies always get refreshed.
+        pending_bin_link.lock().await.push(graph.root);
         let extract_mode = if let Some(cache) = self.opts.cache.as_deref() {
-            if super::supports_reflink(cache, &node_modules) {
+            if super::supports_reflink(cache, &store) {
                 ExtractMode::Reflink
             } else if self.opts.prefer_copy {
                 ExtractMode::Copy
-            } else if super::supports_hardlink(cache, &node_modules) {
+            } else if super::supports_hardlink(cache, &store) {
                 ExtractMode::Hardlink
             } else {
                 ExtractMode::Copy
@@ -365,9 +556,9 @@ impl IsolatedLinker {
             ExtractMode::AutoHardlink
         };
         stream
-            .map(|idx| {
+            .map(|nodes| {
                 Ok((
-                    idx,
+                    nodes,
                     concurrent_count.clone(),
                     total_completed.clone(),
                     actually_extracted.clone(),
@@ -378,16 +569,34 @@ impl IsolatedLinker {
             .try_for_each_concurrent(
                 self.opts.concurrency,
                 move |(
-                    child_idx,
+                    nodes,
                     concurrent_count,
                     total_completed,
                     actually_extracted,
                     pending_rebuild,
                     pending_bin_link,
                 )| async move {
+                    let child_idx = nodes[0];
                     if child_idx == graph.root {
-                        self.link_deps(graph, child_idx, store_ref, &root.join("node_modules"))
-                            .await?;
+                        self.link_deps(
+                            graph,
+                            names,
+                            graph
+                                .inner
+                                .neighbors_directed(child_idx, Direction::Outgoing)
+                                .chain(hoisted_ref.public.iter().copied()),
+                            store_ref,
+                            &root.join("node_modules"),
+                        )
+                        .await?;
+                        self.link_deps(
+                            graph,
+                            names,
+                            hoisted_ref.private.iter().copied(),
+                            store_ref,
+                            &store_ref.join("node_modules"),
+                        )
+                        .await?;
                         return Ok(());
                     }
 
@@ -396,54 +605,102 @@ impl IsolatedLinker {
                     let pkg = &graph[child_idx].package;
 
                     // Actual package contents are extracted to
-                    // `node_modules/.oro-store/<package-name>-<hash>/node_modules/<package-name>`
+                    // `node_modules/.oro-store/<package-name>@<version>-<hash>/node_modules/<package-name>`
                     let target_dir = store_ref
-                        .join(package_dir_name(graph, child_idx))
+                        .join(&names[&child_idx])
                         .join("node_modules")
                         .join(pkg.name());
 
                     let start = std::time::Instant::now();
 
-                    if !target_dir.exists() {
-                        graph[child_idx]
-                            .package
-                            .extract_to_dir(&target_dir, extract_mode)
-                            .await?;
+                    let entry_dir = store_ref.join(&names[&child_idx]);
+                    std::fs::create_dir_all(&entry_dir).io_context(|| {
+                        format!("Failed to create store entry at {}.", entry_dir.display())
+                    })?;
+                    let _entry_lock =
+                        FileLock::acquire(&entry_dir.join(ENTRY_LOCK_NAME), true).await?;
+
+                    if !entry_dir.join(ENTRY_COMPLETE_NAME).exists() {
+                        // Whatever's there was left behind by an install
+                        // that didn't finish, so start over.
+                        remove_incomplete(&target_dir)?;
+                        if let Some(dir) = graph.linked_dir(child_idx) {
+                            super::link_dir(dir, &target_dir).await?;
+                        } else {
+                            // Packages get extracted off to the side and
+                            // then moved into place, so nothing ever sees a
+                            // half-extracted one.
+                            let tmp_dir = entry_dir.join(ENTRY_TMP_NAME);
+                            remove_incomplete(&tmp_dir)?;
+                            graph[child_idx]
+                                .package
+                                .extract_to_dir(&tmp_dir, extract_mode)
+                                .await?;
+                            super::apply_patch(graph, child_idx, &tmp_dir).await?;
+                            if let Some(parent) = target_dir.parent() {
+                                std::fs::create_dir_all(parent).io_context(|| {
+                                    format!("Failed to create directory at {}.", parent.display())
+                                })?;
+                            }
+                            std::fs::rename(&tmp_dir, &target_dir).io_context(|| {
+                                format!(
+                                    "Failed to move extracted package from {} to {}.",
+                                    tmp_dir.display(),
+                                    target_dir.display()
+                                )
+                            })?;
+                        }
                         actually_extracted.fetch_add(1, atomic::Ordering::SeqCst);
                         let target_dir = target_dir.clone();
-                        let build_mani = async_std::task::spawn_blocking(move || {
-                            BuildManifest::from_path(target_dir.join("package.json")).map_err(|e| {
+                        let package = graph[child_idx].package.clone();
+                        let needs_rebuild = async_std::task::spawn_blocking(move || {
+                            let build_mani = BuildManifest::from_path(
+                                target_dir.join("package.json"),
+                            )
+                            .map_err(|e| {
                                 NodeMaintainerError::BuildManifestReadError(
                                     target_dir.join("package.json"),
                                     e,
                                 )
-                            })
+                            })?;
+                            Ok::<_, NodeMaintainerError>(super::has_lifecycle_scripts(
+                                &build_mani,
+                                &package,
+                                &target_dir,
+                            ))
                         })
                         .await?;
-                        if build_mani.scripts.contains_key("preinstall")
-                            || build_mani.scripts.contains_key("install")
-                            || build_mani.scripts.contains_key("postinstall")
-                            || build_mani.scripts.contains_key("prepare")
-                        {
+                        // Entries with scripts to run aren't done until
+                        // those have run.
+                        if needs_rebuild {
                             pending_rebuild.lock().await.insert(child_idx);
+                        } else {
+                            mark_complete(&entry_dir)?;
                         }
-                        if !build_mani.bin.is_empty() {
-                            pending_bin_link.lock().await.push(child_idx);
-                        }
+                        // New entries need their dependencies' bins linked.
+                        pending_bin_link.lock().await.push(child_idx);
                     }
 
-                    self.link_deps(
-                        graph,
-                        child_idx,
-                        store_ref,
-                        &target_dir.join("node_modules"),
-                    )
-                    .await?;
+                    // Linked packages use their own `node_modules`.
+                    if !graph.is_link(child_idx) {
+                        self.link_deps(
+                            graph,
+                            names,
+                            graph
+                                .inner
+                                .neighbors_directed(child_idx, Direction::Outgoing),
+                            store_ref,
+                            &target_dir.join("node_modules"),
+                        )
+                        .await?;
+                    }
 
                     let elapsed = start.elapsed();
 
                     if let Some(on_extract) = &self.opts.on_extract_progress {
-                        on_extract(&graph[child_idx].package, elapsed);
+                        for idx in &nodes {
+                            on_extract(&graph[*idx].package, elapsed);
+                        }
                     }
 
                     tracing::trace!(
@@ -452,7 +709,8 @@ impl IsolatedLinker {
                         graph[child_idx].package.name(),
                         target_dir.display(),
                         elapsed.as_micros() / 1000,
-                        total_completed.fetch_add(1, atomic::Ordering::SeqCst) + 1,
+                        total_completed.fetch_add(nodes.len(), atomic::Ordering::SeqCst)
+                            + nodes.len(),
                     );
 
                     Ok::<_, NodeMaintainerError>(())
@@ -460,7 +718,13 @@ impl IsolatedLinker {
             )
             .await?;
         let meta = node_modules.join(META_FILE_NAME);
-        std::fs::write(&meta, graph.to_kdl()?.to_string()).io_context(|| {
+        let mut meta_doc = graph.to_kdl()?;
+        meta_doc.nodes_mut().extend(store::store_meta_nodes(
+            self.opts.store.as_deref(),
+            names.values().map(|name| name.as_str()),
+        ));
+        meta_doc.fmt();
+        std::fs::write(&meta, meta_doc.to_string()).io_context(|| {
             format!(
                 "Failed to write Orogene meta file into node_modules, at {}.",
                 meta.display()
@@ -478,26 +742,92 @@ impl IsolatedLinker {
 
     pub async fn link_bins(&self, graph: &Graph) -> Result<usize, NodeMaintainerError> {
         let root = &self.opts.root;
-        let store = root.join("node_modules").join(STORE_DIR_NAME);
+        let store = self.store_dir();
         let store_ref = &store;
+        let names = self.store_dir_names(graph);
+        let hoisted = self.hoisted(graph, names);
+        let mut bins = HashMap::new();
         let mut linked = 0;
 
         let mut pending = self.pending_bin_link.lock().await;
         while let Some(idx) = pending.pop() {
-            let added = self.link_dep_bins(graph, idx, root, store_ref).await?;
-            linked += added;
+            if idx == graph.root {
+                // Hoisted packages go first, so that the bins of the
+                // project's direct dependencies win any conflicts.
+                let deps = hoisted
+                    .public
+                    .iter()
+                    .copied()
+                    .chain(graph.inner.neighbors_directed(idx, Direction::Outgoing))
+                    .collect();
+                linked += self
+                    .link_dep_bins(graph, names, &mut bins, deps, root, store_ref)
+                    .await?;
+                linked += self
+                    .link_dep_bins(
+                        graph,
+                        names,
+                        &mut bins,
+                        hoisted.private.clone(),
+                        store_ref,
+                        store_ref,
+                    )
+                    .await?;
+                continue;
+            }
+            if graph.is_link(idx) {
+                continue;
+            }
+            // Bins get linked into the entry itself, which other installs
+            // using the same store might be writing to as well.
+            let entry_dir = store_ref.join(&names[&idx]);
+            let _entry_lock = FileLock::acquire(&entry_dir.join(ENTRY_LOCK_NAME), true).await?;
+            let node_dir = entry_dir
+                .join("node_modules")
+                .join(graph[idx].package.name());
+            let deps = graph
+                .inner
+                .neighbors_directed(idx, Direction::Outgoing)
+                .collect();
+            linked += self
+                .link_dep_bins(graph, names, &mut bins, deps, &node_dir, store_ref)
+                .await?;
         }
         Ok(linked)
     }
 
+    /// Holds the lock on the store entry `idx` is installed in, so its
+    /// scripts don't run while another install is writing to it.
+    pub(crate) async fn lock_entry(
+        &self,
+        graph: &Graph,
+        idx: NodeIndex,
+    ) -> Result<FileLock, NodeMaintainerError> {
+        let entry_dir = self.store_dir().join(&self.store_dir_names(graph)[&idx]);
+        FileLock::acquire(&entry_dir.join(ENTRY_LOCK_NAME), true).await
+    }
+
+    /// Marks the store entries of packages whose scripts have run as
+    /// complete.
+    pub(crate) async fn mark_built(&self, graph: &Graph) -> Result<(), NodeMaintainerError> {
+        let store = self.store_dir();
+        let names = self.store_dir_names(graph);
+        for idx in self.pending_rebuild.lock().await.iter() {
+            if *idx == graph.root || graph.is_link(*idx) {
+                continue;
+            }
+            let entry_dir = store.join(&names[idx]);
+            let _entry_lock = FileLock::acquire(&entry_dir.join(ENTRY_LOCK_NAME), true).await?;
+            mark_complete(&entry_dir)?;
+        }
+        Ok(())
+    }
+
     pub fn package_dir(&self, graph: &Graph, idx: NodeIndex) -> (PathBuf, PathBuf) {
         let pkg = &graph[idx].package;
         let dir = self
-            .opts
-            .root
-            .join("node_modules")
-            .join(STORE_DIR_NAME)
-            .join(package_dir_name(graph, idx))
+            .store_dir()
+            .join(&self.store_dir_names(graph)[&idx])
             .join("node_modules")
             .join(pkg.name());
         (dir.clone(), dir)
@@ -506,15 +836,16 @@ impl IsolatedLinker {
     async fn link_deps(
         &self,
         graph: &Graph,
-        node: NodeIndex,
+        names: &HashMap<NodeIndex, String>,
+        deps: impl IntoIterator<Item = NodeIndex>,
         store_ref: &Path,
         target_nm: &Path,
     ) -> Result<(), NodeMaintainerError> {
         // Then we symlink/junction all of the package's dependencies into its `node_modules` dir.
-        for edge in graph.inner.edges_directed(node, Direction::Outgoing) {
-            let dep_pkg = &graph[edge.target()].package;
+        for dep in deps {
+            let dep_pkg = &graph[dep].package;
             let dep_store_dir = store_ref
-                .join(package_dir_name(graph, edge.target()))
+                .join(&names[&dep])
                 .join("node_modules")
                 .join(dep_pkg.name());
             let dep_nm_entry = target_nm.join(dep_pkg.name());
@@ -558,38 +889,38 @@ impl IsolatedLinker {
         Ok(())
     }
 
+    /// Links the bins of `deps`, which are linked into
+    /// `node_dir/node_modules`, into `node_dir/node_modules/.bin`. `bins`
+    /// caches each store entry's bins, since lots of packages share
+    /// dependencies.
     async fn link_dep_bins(
         &self,
         graph: &Graph,
-        node: NodeIndex,
-        root_path: &Path,
+        names: &HashMap<NodeIndex, String>,
+        bins: &mut HashMap<String, HashMap<String, PathBuf>>,
+        deps: Vec<NodeIndex>,
+        node_dir: &Path,
         store_ref: &Path,
     ) -> Result<usize, NodeMaintainerError> {
-        if node == graph.root {
-            return Ok(0);
-        }
         let mut linked = 0;
-        let node_path = store_ref
-            .join(package_dir_name(graph, node))
-            .join("node_modules")
-            .join(&graph[node].name.to_string());
-        let build_mani = BuildManifest::from_path(node_path.join("package.json")).map_err(|e| {
-            NodeMaintainerError::BuildManifestReadError(node_path.join("package.json"), e)
-        })?;
-        for edge in graph.inner.edges_directed(node, Direction::Incoming) {
-            let dep_node = &graph[edge.source()];
-            let dep_store_dir = if dep_node.idx == graph.root {
-                root_path.to_owned()
-            } else {
-                store_ref
-                    .join(package_dir_name(graph, edge.source()))
-                    .join("node_modules")
-                    .join(&dep_node.name.to_string())
-            };
-            let dep_bin_dir = dep_store_dir.join("node_modules").join(".bin");
-            for (name, path) in &build_mani.bin {
-                let to = dep_bin_dir.join(name);
-                let from = dep_store_dir.join("node_modules").join(name).join(path);
+        let bin_dir = node_dir.join("node_modules").join(".bin");
+        for dep in deps {
+            let dep_name = graph[dep].package.name();
+            let entry = &names[&dep];
+            if !bins.contains_key(entry) {
+                let dep_path = store_ref.join(entry).join("node_modules").join(dep_name);
+                let build_mani =
+                    BuildManifest::from_path(dep_path.join("package.json")).map_err(|e| {
+                        NodeMaintainerError::BuildManifestReadError(
+                            dep_path.join("package.json"),
+                            e,
+                        )
+                    })?;
+                bins.insert(entry.clone(), build_mani.bin);
+            }
+            for (name, path) in &bins[entry] {
+                let to = bin_dir.join(name);
+                let from = node_dir.join("node_modules").join(dep_name).join(path);
                 let name = name.clone();
                 let mkdir_cache = self.mkdir_cache.clone();
                 async_std::task::spawn_blocking(move || {
@@ -632,14 +963,224 @@ impl IsolatedLinker {
     }
 }
 
-fn package_dir_name(graph: &Graph, idx: NodeIndex) -> String {
-    let node = &graph[idx];
-    let subdir = graph.node_path_string(idx);
+/// Packages linked somewhere other than their dependents' `node_modules`,
+/// because they match a hoist pattern.
+#[derive(Debug, Default)]
+struct Hoisted {
+    /// Linked into the root `node_modules`, where the whole project can see
+    /// them.
+    public: Vec<NodeIndex>,
+    /// Linked into `node_modules` inside the store, where only packages in
+    /// the store can see them.
+    private: Vec<NodeIndex>,
+}
+
+/// Whether `name` matches any of `patterns`, without also matching one of
+/// the patterns negated with a leading `!`.
+fn matches_patterns(patterns: &[String], name: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if glob_matches(negated, name) {
+                return false;
+            }
+        } else if glob_matches(pattern, name) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Matches `name` against `pattern`, where `*` matches any number of
+/// characters (including `/`, so `@types/*` works).
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = (pattern.as_bytes(), name.as_bytes());
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was, and how much of `name` it's swallowed so far.
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
 
-    let mut name = node.name.to_string();
-    name.push('@');
-    let (_, mut hex) = Integrity::from(subdir).to_hex();
-    hex.truncate(8);
-    name.push_str(&hex);
-    name
+/// Where each of `deps` should be linked inside `nm`, mapped to the store
+/// directory it should point to.
+fn expected_links(
+    graph: &Graph,
+    names: &HashMap<NodeIndex, String>,
+    deps: impl IntoIterator<Item = NodeIndex>,
+    nm: &Path,
+    store: &Path,
+) -> HashMap<PathBuf, PathBuf> {
+    deps.into_iter()
+        .map(|dep| {
+            let name = graph[dep].package.name();
+            (
+                nm.join(name),
+                store.join(&names[&dep]).join("node_modules").join(name),
+            )
+        })
+        .collect()
+}
+
+/// Removes links in `nm` that point into `store`, but not where `expected`
+/// says they should, and then any bins in `nm/.bin` left dangling. Links
+/// pointing anywhere else aren't ours, so they're left alone.
+fn remove_stale_links(
+    nm: &Path,
+    expected: &HashMap<PathBuf, PathBuf>,
+    store: &Path,
+) -> Result<(), NodeMaintainerError> {
+    if !nm.exists() {
+        return Ok(());
+    }
+    let store = normalize(&store::absolute(store));
+    let expected = expected
+        .iter()
+        .map(|(link, target)| (link, normalize(&store::absolute(target))))
+        .collect::<HashMap<_, _>>();
+    let mut links = Vec::new();
+    for entry in read_dir(nm)? {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('@') && path.is_dir() {
+            links.extend(read_dir(&path)?.into_iter().map(|entry| entry.path()));
+        } else {
+            links.push(path);
+        }
+    }
+    for link in links {
+        if !link
+            .symlink_metadata()
+            .map_or(false, |meta| meta.is_symlink())
+        {
+            continue;
+        }
+        let target = link.read_link().io_context(|| {
+            format!(
+                "Failed to read symlink at {} while pruning node_modules.",
+                link.display()
+            )
+        })?;
+        let parent = link.parent().expect("must have a parent");
+        let target = normalize(&store::absolute(&parent.join(target)));
+        if target.starts_with(&store) && expected.get(&link) != Some(&target) {
+            remove_link(&link)?;
+        }
+    }
+    let bin_dir = nm.join(".bin");
+    if bin_dir.exists() {
+        for entry in read_dir(&bin_dir)? {
+            let path = entry.path();
+            if path
+                .symlink_metadata()
+                .map_or(false, |meta| meta.is_symlink())
+                && !path.exists()
+            {
+                remove_link(&path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lexically resolves any `..` in `path`, without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir => {}
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<std::fs::DirEntry>, NodeMaintainerError> {
+    std::fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .io_context(|| {
+            format!(
+                "Failed to read contents of node_modules at {} while pruning node_modules.",
+                dir.display()
+            )
+        })
+}
+
+fn remove_link(link: &Path) -> Result<(), NodeMaintainerError> {
+    std::fs::remove_file(link)
+        .or_else(|_| std::fs::remove_dir(link))
+        .io_context(|| {
+            format!(
+                "Failed to delete stale link at {} while pruning node_modules.",
+                link.display()
+            )
+        })
+}
+
+/// Removes whatever an interrupted install left at `path`, if anything.
+fn remove_incomplete(path: &Path) -> Result<(), NodeMaintainerError> {
+    let Ok(meta) = path.symlink_metadata() else {
+        return Ok(());
+    };
+    if meta.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+    .io_context(|| {
+        format!(
+            "Failed to remove incomplete store entry contents at {}.",
+            path.display()
+        )
+    })
+}
+
+/// Marks the store entry at `entry_dir` as fully installed.
+fn mark_complete(entry_dir: &Path) -> Result<(), NodeMaintainerError> {
+    let marker = entry_dir.join(ENTRY_COMPLETE_NAME);
+    std::fs::write(marker, "").io_context(|| {
+        format!(
+            "Failed to mark store entry at {} as complete.",
+            entry_dir.display()
+        )
+    })
+}
+
+/// Names every package's directory in the store. Directories are keyed by
+/// the package's identity plus a hash of its resolved dependency closure,
+/// so packages with identical dependency subgraphs share a directory no
+/// matter where they are in the tree. The root isn't in the store, so it
+/// doesn't get a name.
+fn store_dir_names(graph: &Graph) -> HashMap<NodeIndex, String> {
+    closure_hashes(graph)
+        .into_iter()
+        .filter(|(idx, _)| *idx != graph.root)
+        .map(|(idx, mut hex)| {
+            let node = &graph[idx];
+            hex.truncate(16);
+            let name = match node.package.resolved() {
+                PackageResolution::Npm { version, .. } => {
+                    format!("{}@{version}-{hex}", node.name)
+                }
+                _ => format!("{}@{hex}", node.name),
+            };
+            (idx, name)
+        })
+        .collect()
 }
@@ -15,22 +15,33 @@ use hoisted::HoistedLinker;
 #[cfg(not(target_arch = "wasm32"))]
 use isolated::IsolatedLinker;
 #[cfg(not(target_arch = "wasm32"))]
+use nassun::{package::Package, PackageResolution};
+#[cfg(not(target_arch = "wasm32"))]
 use oro_common::BuildManifest;
 #[cfg(not(target_arch = "wasm32"))]
 use oro_script::OroScript;
 #[cfg(not(target_arch = "wasm32"))]
-use petgraph::stable_graph::NodeIndex;
+use petgraph::{algo::tarjan_scc, stable_graph::NodeIndex, visit::EdgeRef, Direction};
+#[cfg(not(target_arch = "wasm32"))]
+use side_effects::SideEffectsCache;
+#[cfg(not(target_arch = "wasm32"))]
+use ssri::Integrity;
 
 #[cfg(not(target_arch = "wasm32"))]
 use crate::{
-    error::IoContext, graph::Graph, Lockfile, NodeMaintainerError, ProgressHandler, PruneProgress,
-    ScriptLineHandler, ScriptStartHandler,
+    error::IoContext,
+    graph::Graph,
+    script_logs::{self, ScriptLog},
+    Lockfile, NodeMaintainerError, ProgressHandler, PruneProgress, ScriptFailure,
+    ScriptOutputHandler, ScriptStartHandler,
 };
 
 #[cfg(not(target_arch = "wasm32"))]
 mod hoisted;
 #[cfg(not(target_arch = "wasm32"))]
 mod isolated;
+#[cfg(not(target_arch = "wasm32"))]
+mod side_effects;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) struct LinkerOptions {
@@ -39,11 +50,16 @@ pub(crate) struct LinkerOptions {
     pub(crate) script_concurrency: usize,
     pub(crate) cache: Option<PathBuf>,
     pub(crate) prefer_copy: bool,
+    pub(crate) side_effects_cache: bool,
+    pub(crate) store: Option<PathBuf>,
+    pub(crate) script_logs: Option<PathBuf>,
+    pub(crate) public_hoist_patterns: Vec<String>,
+    pub(crate) hoist_patterns: Vec<String>,
     pub(crate) root: PathBuf,
     pub(crate) on_prune_progress: Option<PruneProgress>,
     pub(crate) on_extract_progress: Option<ProgressHandler>,
     pub(crate) on_script_start: Option<ScriptStartHandler>,
-    pub(crate) on_script_line: Option<ScriptLineHandler>,
+    pub(crate) on_script_output: Option<ScriptOutputHandler>,
 }
 
 pub(crate) enum Linker {
@@ -71,6 +87,15 @@ impl Linker {
         Self::Null
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn opts(&self) -> Option<&LinkerOptions> {
+        match self {
+            Self::Isolated(isolated) => Some(&isolated.opts),
+            Self::Hoisted(hoisted) => Some(&hoisted.opts),
+            Self::Null => None,
+        }
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub async fn prune(
         &self,
@@ -107,13 +132,19 @@ impl Linker {
     ) -> Result<(), NodeMaintainerError> {
         tracing::debug!("Running lifecycle scripts...");
         let start = std::time::Instant::now();
+        let side_effects = if ignore_scripts {
+            None
+        } else {
+            self.side_effects_cache(graph).await
+        };
+        let restored = match &side_effects {
+            Some(side_effects) => self.restore_side_effects(graph, side_effects).await,
+            None => HashSet::new(),
+        };
+        self.run_lifecycle(graph, ignore_scripts, &restored, side_effects.as_ref())
+            .await?;
         if !ignore_scripts {
-            self.run_scripts(graph, "preinstall").await?;
-        }
-        self.link_bins(graph).await?;
-        if !ignore_scripts {
-            self.run_scripts(graph, "install").await?;
-            self.run_scripts(graph, "postinstall").await?;
+            self.run_root_scripts(graph).await?;
         }
         tracing::debug!(
             "Ran lifecycle scripts in {}ms.",
@@ -122,6 +153,306 @@ impl Linker {
         Ok(())
     }
 
+    /// Reruns install scripts for packages that are already installed, and
+    /// relinks all their bins. If `selected` is given, only those packages'
+    /// scripts run. The project's own scripts are left alone.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn rebuild_installed(
+        &self,
+        graph: &Graph,
+        selected: Option<&HashSet<NodeIndex>>,
+    ) -> Result<(), NodeMaintainerError> {
+        tracing::debug!("Rerunning lifecycle scripts...");
+        let start = std::time::Instant::now();
+        let Some(opts) = self.opts() else {
+            return Ok(());
+        };
+        // Nothing got extracted, so nothing's queued up yet. Everything
+        // installed has to be checked for scripts and bins instead.
+        // The hoisted linker leaves packages inside linked ones uninstalled.
+        let installed = graph.inner.node_indices().filter(|idx| {
+            *idx != graph.root && !(matches!(self, Self::Hoisted(_)) && graph.is_inside_link(*idx))
+        });
+        let manifests = futures::stream::iter(installed)
+            .map(|idx| {
+                let (package_dir, _) = self.package_dir(graph, idx);
+                let package = graph[idx].package.clone();
+                async move {
+                    let manifest = async_std::task::spawn_blocking(move || {
+                        let build_mani = BuildManifest::from_path(package_dir.join("package.json"))
+                            .map_err(|e| {
+                                NodeMaintainerError::BuildManifestReadError(
+                                    package_dir.join("package.json"),
+                                    e,
+                                )
+                            })?;
+                        let scripts = has_lifecycle_scripts(&build_mani, &package, &package_dir);
+                        Ok::<_, NodeMaintainerError>((scripts, !build_mani.bin.is_empty()))
+                    })
+                    .await?;
+                    Ok::<_, NodeMaintainerError>((idx, manifest))
+                }
+            })
+            .buffer_unordered(opts.concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut skip = HashSet::new();
+        match self {
+            Self::Isolated(isolated) => {
+                let mut pending_rebuild = isolated.pending_rebuild.lock().await;
+                let mut pending_bin_link = isolated.pending_bin_link.lock().await;
+                pending_bin_link.push(graph.root);
+                for (idx, (scripts, _)) in manifests {
+                    if scripts {
+                        pending_rebuild.insert(idx);
+                    }
+                    pending_bin_link.push(idx);
+                }
+            }
+            Self::Hoisted(hoisted) => {
+                let mut pending_rebuild = hoisted.pending_rebuild.lock().await;
+                for (idx, (scripts, bins)) in manifests {
+                    if scripts {
+                        pending_rebuild.insert(idx);
+                    } else if bins {
+                        // Hoisted bins get linked for everything waiting on
+                        // a rebuild, but their scripts shouldn't run.
+                        pending_rebuild.insert(idx);
+                        skip.insert(idx);
+                    }
+                }
+            }
+            Self::Null => {}
+        }
+        if let Some(selected) = selected {
+            if let Some((pending, _)) = self.pending_rebuild().await {
+                skip.extend(pending.into_iter().filter(|idx| !selected.contains(idx)));
+            }
+        }
+        let side_effects = self.side_effects_cache(graph).await;
+        self.run_lifecycle(graph, false, &skip, side_effects.as_ref())
+            .await?;
+        tracing::debug!(
+            "Reran lifecycle scripts in {}ms.",
+            start.elapsed().as_millis()
+        );
+        Ok(())
+    }
+
+    /// Runs every lifecycle event for the packages waiting on a rebuild,
+    /// except the ones in `skip`, linking bins along the way. Packages whose
+    /// scripts succeed get their side effects cached.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn run_lifecycle(
+        &self,
+        graph: &Graph,
+        ignore_scripts: bool,
+        skip: &HashSet<NodeIndex>,
+        side_effects: Option<&Arc<SideEffectsCache>>,
+    ) -> Result<(), NodeMaintainerError> {
+        if !ignore_scripts {
+            self.clear_script_logs(graph, skip).await;
+        }
+        let mut failed = HashSet::new();
+        if !ignore_scripts {
+            failed.extend(self.run_scripts(graph, "preinstall", skip).await?);
+        }
+        self.link_bins(graph).await?;
+        if !ignore_scripts {
+            failed.extend(self.run_scripts(graph, "install", skip).await?);
+            failed.extend(self.run_scripts(graph, "postinstall", skip).await?);
+            failed.extend(self.run_scripts(graph, "prepare", skip).await?);
+        }
+        if let Some(side_effects) = side_effects {
+            // Skipped packages are either already cached or weren't
+            // rebuilt, and failed ones have nothing worth caching.
+            let skip = skip.union(&failed).copied().collect();
+            self.save_side_effects(graph, side_effects, &skip).await;
+        }
+        if let Self::Isolated(isolated) = self {
+            isolated.mark_built(graph).await?;
+        }
+        Ok(())
+    }
+
+    /// Runs the project's own lifecycle scripts, once all of its
+    /// dependencies are built. Projects without a `package.json` of their own
+    /// have nothing to run.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn run_root_scripts(&self, graph: &Graph) -> Result<(), NodeMaintainerError> {
+        let Some(opts) = self.opts() else {
+            return Ok(());
+        };
+        if !opts.root.join("package.json").exists() {
+            return Ok(());
+        }
+        if let Some(dir) = &opts.script_logs {
+            script_logs::clear_log(dir, &graph[graph.root].package);
+        }
+        for event in LIFECYCLE_EVENTS {
+            self.run_dep_script(graph, graph.root, event, opts).await?;
+        }
+        Ok(())
+    }
+
+    /// Lifecycle scripts that failed during [`Linker::rebuild`], including
+    /// those of optional dependencies.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn script_failures(&self) -> Vec<ScriptFailure> {
+        match self {
+            Self::Isolated(isolated) => isolated.script_failures.lock().await.clone(),
+            Self::Hoisted(hoisted) => hoisted.script_failures.lock().await.clone(),
+            Self::Null => Vec::new(),
+        }
+    }
+
+    /// Removes the logs left behind by the last time scripts ran for the
+    /// packages about to run them again.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn clear_script_logs(&self, graph: &Graph, skip: &HashSet<NodeIndex>) {
+        let Some((pending, opts)) = self.pending_rebuild().await else {
+            return;
+        };
+        let Some(dir) = &opts.script_logs else {
+            return;
+        };
+        for idx in pending {
+            if !skip.contains(&idx) {
+                script_logs::clear_log(dir, &graph[idx].package);
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn side_effects_cache(&self, graph: &Graph) -> Option<Arc<SideEffectsCache>> {
+        let opts = self.opts()?;
+        if !opts.side_effects_cache {
+            return None;
+        }
+        let cache = opts.cache.as_deref()?;
+        let node_version = async_std::task::spawn_blocking(side_effects::node_version).await;
+        Some(Arc::new(SideEffectsCache::new(cache, graph, &node_version)))
+    }
+
+    /// Restores cached install script side effects for any packages that
+    /// have them, returning the packages that no longer need their scripts
+    /// run.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn restore_side_effects(
+        &self,
+        graph: &Graph,
+        side_effects: &Arc<SideEffectsCache>,
+    ) -> HashSet<NodeIndex> {
+        let Some((pending, opts)) = self.pending_rebuild().await else {
+            return HashSet::new();
+        };
+        futures::stream::iter(pending)
+            .filter(|idx| futures::future::ready(*idx != graph.root))
+            .map(|idx| {
+                let side_effects = side_effects.clone();
+                let (package_dir, _) = self.package_dir(graph, idx);
+                async move {
+                    let restored = async_std::task::spawn_blocking(move || {
+                        side_effects.restore(idx, &package_dir)
+                    })
+                    .await;
+                    match restored {
+                        Ok(true) => {
+                            tracing::debug!(
+                                "Restored cached install script side effects for {}.",
+                                graph[idx].package.resolved()
+                            );
+                            Some(idx)
+                        }
+                        Ok(false) => None,
+                        Err(e) => {
+                            // The scripts will just run as if nothing was
+                            // cached.
+                            tracing::debug!(
+                                "Failed to restore cached install script side effects for {}: {e}",
+                                graph[idx].package.resolved()
+                            );
+                            None
+                        }
+                    }
+                }
+            })
+            .buffer_unordered(opts.concurrency)
+            .filter_map(futures::future::ready)
+            .collect::<HashSet<_>>()
+            .await
+    }
+
+    /// Caches the side effects of every package whose install scripts ran
+    /// successfully, skipping the ones in `skip`.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn save_side_effects(
+        &self,
+        graph: &Graph,
+        side_effects: &Arc<SideEffectsCache>,
+        skip: &HashSet<NodeIndex>,
+    ) {
+        let Some((pending, opts)) = self.pending_rebuild().await else {
+            return;
+        };
+        futures::stream::iter(pending)
+            .filter(|idx| futures::future::ready(*idx != graph.root && !skip.contains(idx)))
+            .map(|idx| {
+                let side_effects = side_effects.clone();
+                let (package_dir, _) = self.package_dir(graph, idx);
+                let package = graph[idx].package.clone();
+                async move {
+                    let saved = async_std::task::spawn_blocking(move || {
+                        side_effects.save(idx, &package, &package_dir)
+                    })
+                    .await;
+                    // Failing to cache something is no reason to fail the
+                    // install.
+                    if let Err(e) = saved {
+                        tracing::debug!(
+                            "Failed to cache install script side effects for {}: {e}",
+                            graph[idx].package.resolved()
+                        );
+                    }
+                }
+            })
+            .buffer_unordered(opts.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn pending_rebuild(&self) -> Option<(Vec<NodeIndex>, &LinkerOptions)> {
+        let (pending_rebuild, opts) = match self {
+            Self::Isolated(isolated) => (&isolated.pending_rebuild, &isolated.opts),
+            Self::Hoisted(hoisted) => (&hoisted.pending_rebuild, &hoisted.opts),
+            Self::Null => return None,
+        };
+        let pending = pending_rebuild
+            .lock()
+            .await
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+        Some((pending, opts))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn package_dir(&self, graph: &Graph, idx: NodeIndex) -> (PathBuf, PathBuf) {
+        if idx == graph.root {
+            let root = self
+                .opts()
+                .map(|opts| opts.root.clone())
+                .unwrap_or_default();
+            return (root.clone(), root);
+        }
+        match self {
+            Self::Isolated(isolated) => isolated.package_dir(graph, idx),
+            Self::Hoisted(hoisted) => hoisted.package_dir(graph, idx),
+            Self::Null => unreachable!("Null linker has no package directories."),
+        }
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     async fn link_bins(
         &self,
@@ -143,19 +474,21 @@ impl Linker {
         Ok(linked)
     }
 
+    /// Runs `event` for every package waiting on a rebuild, except the ones
+    /// in `skip`. Returns the optional packages whose scripts failed.
     #[cfg(not(target_arch = "wasm32"))]
-    pub async fn run_scripts(&self, graph: &Graph, event: &str) -> Result<(), NodeMaintainerError> {
-        let (pending_rebuild, opts) = match self {
-            Self::Isolated(isolated) => (&isolated.pending_rebuild, &isolated.opts),
-            Self::Hoisted(hoisted) => (&hoisted.pending_rebuild, &hoisted.opts),
-            Self::Null => return Ok(()),
+    pub async fn run_scripts(
+        &self,
+        graph: &Graph,
+        event: &str,
+        skip: &HashSet<NodeIndex>,
+    ) -> Result<HashSet<NodeIndex>, NodeMaintainerError> {
+        let Some((mut pending, opts)) = self.pending_rebuild().await else {
+            return Ok(HashSet::new());
         };
-        let pending = pending_rebuild
-            .lock()
-            .await
-            .iter()
-            .copied()
-            .collect::<Vec<_>>();
+        // Linked packages are the user's own working copies, so running
+        // their scripts is up to them.
+        pending.retain(|idx| !skip.contains(idx) && !graph.is_link(*idx));
         // Map of package to the set of packages that need to run before it can run.
         let dependencies = pending
             .iter()
@@ -204,13 +537,22 @@ impl Linker {
         }
 
         let sender_ref = &sender;
+        let failed = Mutex::new(HashSet::new());
+        let failed_ref = &failed;
 
         receiver
             .map(Ok)
             .try_for_each_concurrent(
                 opts.script_concurrency,
                 move |(idx, remaining_arc, dependents)| async move {
-                    let ret = self.run_dep_script(graph, idx, event, opts).await;
+                    let ret = match self.run_dep_script(graph, idx, event, opts).await {
+                        Ok(true) => Ok(()),
+                        Ok(false) => {
+                            failed_ref.lock().await.insert(idx);
+                            Ok(())
+                        }
+                        Err(e) => Err(e),
+                    };
 
                     let mut remaining = remaining_arc.lock().await;
 
@@ -239,9 +581,11 @@ impl Linker {
             )
             .await?;
 
-        Ok(())
+        Ok(failed.into_inner())
     }
 
+    /// Runs `event` for a single package. Returns `false` if the package is
+    /// optional and its script failed.
     #[cfg(not(target_arch = "wasm32"))]
     async fn run_dep_script(
         &self,
@@ -249,17 +593,9 @@ impl Linker {
         idx: NodeIndex,
         event: &str,
         opts: &LinkerOptions,
-    ) -> Result<(), NodeMaintainerError> {
+    ) -> Result<bool, NodeMaintainerError> {
         let root = &opts.root;
-        let (package_dir, workspace_path) = if idx == graph.root {
-            (root.clone(), root.clone())
-        } else {
-            match self {
-                Self::Isolated(isolated) => isolated.package_dir(graph, idx),
-                Self::Hoisted(hoisted) => hoisted.package_dir(graph, idx),
-                Self::Null => unreachable!("Null linker should not run scripts."),
-            }
-        };
+        let (package_dir, workspace_path) = self.package_dir(graph, idx);
 
         let is_optional = graph.is_optional(idx);
 
@@ -269,7 +605,27 @@ impl Linker {
             })?;
 
         let name = graph[idx].package.name().to_string();
-        if build_mani.scripts.contains_key(event) {
+        if let Some(command) =
+            lifecycle_script(&build_mani, &graph[idx].package, &package_dir, event)
+        {
+            // Store entries can be shared with other installs, which
+            // mustn't extract into them while their scripts are running.
+            let _entry_lock = match self {
+                Self::Isolated(isolated) if idx != graph.root && !graph.is_link(idx) => {
+                    Some(isolated.lock_entry(graph, idx).await?)
+                }
+                _ => None,
+            };
+            let log = Arc::new(ScriptLog::open(
+                opts.script_logs.as_deref(),
+                &graph[idx].package,
+                event,
+                &command,
+            ));
+            // The command might not be in the package's own scripts, so the
+            // script gets run from this copy of its manifest instead.
+            let mut build_mani = build_mani;
+            build_mani.scripts.insert(event.to_owned(), command);
             let package_dir = package_dir.clone();
             let root = root.clone();
             let event = event.to_owned();
@@ -282,30 +638,44 @@ impl Linker {
             std::mem::drop(_span_enter);
             let mut script = match async_std::task::spawn_blocking(move || {
                 OroScript::new(package_dir, event_clone)?
+                    .manifest(&build_mani)
                     .workspace_path(root)
                     .spawn()
             })
             .await
             {
                 Ok(script) => script,
-                Err(e) if is_optional => {
-                    let e: NodeMaintainerError = e.into();
-                    tracing::debug!("Error in optional dependency script: {}", e);
-                    return Ok(());
+                Err(e) => {
+                    self.record_script_failure(log.failure(
+                        &graph[idx].package,
+                        &event,
+                        is_optional,
+                    ))
+                    .await;
+                    if is_optional {
+                        let e: NodeMaintainerError = e.into();
+                        tracing::debug!("Error in optional dependency script: {}", e);
+                        return Ok(false);
+                    }
+                    return Err(e.into());
                 }
-                Err(e) => return Err(e.into()),
             };
             let stdout = script.stdout.take();
             let stderr = script.stderr.take();
             let stdout_name = name.clone();
             let stderr_name = name.clone();
-            let stdout_on_line = opts.on_script_line.clone();
-            let stderr_on_line = opts.on_script_line.clone();
+            let stdout_on_output = opts.on_script_output.clone();
+            let stderr_on_output = opts.on_script_output.clone();
             let stdout_span = span;
             let stderr_span = stdout_span.clone();
             let event_clone = event.clone();
             let stdout_resolved = graph[idx].package.resolved().clone();
             let stderr_resolved = stdout_resolved.clone();
+            let stdout_pkg = graph[idx].package.clone();
+            let stderr_pkg = stdout_pkg.clone();
+            let stdout_log = log.clone();
+            let stderr_log = log.clone();
+            let failed_event = event.clone();
             let join = futures::try_join!(
                 async_std::task::spawn_blocking(move || {
                     let _enter = stdout_span.enter();
@@ -317,8 +687,9 @@ impl Linker {
                                 )
                             })?;
                             tracing::debug!("stdout::{stdout_name}::{event}: {line}");
-                            if let Some(on_script_line) = &stdout_on_line {
-                                on_script_line(&line);
+                            stdout_log.line(&line);
+                            if let Some(on_script_output) = &stdout_on_output {
+                                on_script_output(&stdout_pkg, &event, &line);
                             }
                         }
                     }
@@ -334,8 +705,9 @@ impl Linker {
                                 )
                             })?;
                             tracing::debug!("stderr::{stderr_name}::{event_clone}: {line}");
-                            if let Some(on_script_line) = &stderr_on_line {
-                                on_script_line(&line);
+                            stderr_log.line(&line);
+                            if let Some(on_script_output) = &stderr_on_output {
+                                on_script_output(&stderr_pkg, &event_clone, &line);
                             }
                         }
                     }
@@ -346,17 +718,210 @@ impl Linker {
                     Ok::<_, NodeMaintainerError>(())
                 }),
             );
-            match join {
-                Ok(_) => {}
-                Err(e) if is_optional => {
+            if let Err(e) = join {
+                self.record_script_failure(log.failure(
+                    &graph[idx].package,
+                    &failed_event,
+                    is_optional,
+                ))
+                .await;
+                if is_optional {
                     tracing::debug!("Error in optional dependency script: {}", e);
-                    return Ok(());
+                    return Ok(false);
                 }
-                Err(e) => return Err(e),
+                return Err(e);
             }
         }
 
+        Ok(true)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn record_script_failure(&self, failure: ScriptFailure) {
+        match self {
+            Self::Isolated(isolated) => isolated.script_failures.lock().await.push(failure),
+            Self::Hoisted(hoisted) => hoisted.script_failures.lock().await.push(failure),
+            Self::Null => {}
+        }
+    }
+}
+
+/// Lifecycle events that run when a package is installed, in order.
+#[cfg(not(target_arch = "wasm32"))]
+const LIFECYCLE_EVENTS: [&str; 4] = ["preinstall", "install", "postinstall", "prepare"];
+
+/// The command `package` runs for the lifecycle `event`, if any. Like npm,
+/// packages with a `binding.gyp` and no `install` or `preinstall` script get
+/// built with `node-gyp rebuild`, and `prepare` only runs for packages that
+/// didn't come from a registry, since published packages were prepared
+/// before they were packed.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn lifecycle_script(
+    build_mani: &BuildManifest,
+    package: &Package,
+    package_dir: &Path,
+    event: &str,
+) -> Option<String> {
+    match event {
+        "prepare" if matches!(package.resolved(), PackageResolution::Npm { .. }) => None,
+        "install"
+            if !build_mani.scripts.contains_key("install")
+                && !build_mani.scripts.contains_key("preinstall")
+                && package_dir.join("binding.gyp").is_file() =>
+        {
+            Some("node-gyp rebuild".into())
+        }
+        _ => build_mani.scripts.get(event).cloned(),
+    }
+}
+
+/// Applies a node's patch from `patchedDependencies`, if it has one, to the
+/// directory it was just extracted to. If the patch doesn't apply, the
+/// directory is removed again, so later installs don't mistake it for a
+/// finished extraction.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn apply_patch(
+    graph: &Graph,
+    idx: NodeIndex,
+    package_dir: &Path,
+) -> Result<(), NodeMaintainerError> {
+    let Some(patch) = graph[idx].patch.clone() else {
+        return Ok(());
+    };
+    let package = match graph[idx].package.resolved() {
+        PackageResolution::Npm { name, version, .. } => format!("{name}@{version}"),
+        _ => graph[idx].package.name().to_string(),
+    };
+    let package_dir = package_dir.to_path_buf();
+    async_std::task::spawn_blocking(move || {
+        tracing::debug!("Applying patch {} to {package}.", patch.path.display());
+        patch.apply(&package_dir).map_err(|reason| {
+            let _ = std::fs::remove_dir_all(&package_dir);
+            NodeMaintainerError::PatchApplyError {
+                package,
+                patch: patch.path.clone(),
+                reason,
+            }
+        })
+    })
+    .await
+}
+
+/// Symlinks (or on Windows, junctions) `target` to the linked package at
+/// `dir`, replacing a link that's gone stale. Linked packages are used
+/// in place, so changes to them show up without reinstalling.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn link_dir(dir: &Path, target: &Path) -> Result<(), NodeMaintainerError> {
+    let dir = dir.to_path_buf();
+    let target = target.to_path_buf();
+    async_std::task::spawn_blocking(move || {
+        if target.symlink_metadata().is_ok() && std::fs::remove_file(&target).is_err() {
+            std::fs::remove_dir_all(&target).io_context(|| {
+                format!(
+                    "Failed to remove stale link at {} while linking package.",
+                    target.display()
+                )
+            })?;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent).io_context(|| {
+                format!(
+                    "Failed to create directory {} while linking package.",
+                    parent.display()
+                )
+            })?;
+        }
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_dir(&dir, &target)
+            .or_else(|_| junction::create(&dir, &target))
+            .map_err(|e| NodeMaintainerError::JunctionsNotSupported(dir, target, e))?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, &target).io_context(|| {
+            format!(
+                "Failed to create symlink while linking package, from {} to {}.",
+                dir.display(),
+                target.display()
+            )
+        })?;
         Ok(())
+    })
+    .await
+}
+
+/// Whether `package` has anything to run when it's installed.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn has_lifecycle_scripts(
+    build_mani: &BuildManifest,
+    package: &Package,
+    package_dir: &Path,
+) -> bool {
+    LIFECYCLE_EVENTS
+        .iter()
+        .any(|event| lifecycle_script(build_mani, package, package_dir, event).is_some())
+}
+
+/// Hashes every package's identity together with its resolved dependency
+/// closure, so two nodes get the same hash exactly when they resolved to
+/// the same package, with the same dependencies, all the way down.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn closure_hashes(graph: &Graph) -> HashMap<NodeIndex, String> {
+    let ids = graph
+        .inner
+        .node_indices()
+        .map(|idx| (idx, package_id(graph, idx)))
+        .collect::<HashMap<_, _>>();
+    let mut hashes = HashMap::new();
+    // Strongly connected components come out in reverse topological order,
+    // so dependencies are always hashed before their dependents. Members of
+    // a dependency cycle can't wait on each other's hashes, so the whole
+    // cycle gets hashed together instead.
+    for scc in tarjan_scc(&graph.inner) {
+        let members = scc.iter().copied().collect::<HashSet<_>>();
+        let mut entries = scc
+            .iter()
+            .map(|idx| {
+                let mut deps = graph
+                    .inner
+                    .edges_directed(*idx, Direction::Outgoing)
+                    .map(|edge| {
+                        let target = edge.target();
+                        let dep = if members.contains(&target) {
+                            &ids[&target]
+                        } else {
+                            &hashes[&target]
+                        };
+                        format!("{}={dep}", graph[target].package.name())
+                    })
+                    .collect::<Vec<_>>();
+                deps.sort();
+                format!("{}{{{}}}", ids[idx], deps.join(","))
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+        let component = entries.join(";");
+        for idx in scc {
+            let (_, hex) = Integrity::from(format!("{}:{component}", ids[&idx])).to_hex();
+            hashes.insert(idx, hex);
+        }
+    }
+    hashes
+}
+
+/// Identifies the package a node resolved to, regardless of where the node
+/// is in the tree.
+#[cfg(not(target_arch = "wasm32"))]
+fn package_id(graph: &Graph, idx: NodeIndex) -> String {
+    let node = &graph[idx];
+    let resolved = node.package.resolved();
+    let id = match resolved.integrity() {
+        Some(integrity) => format!("{}@{resolved}#{integrity}", node.name),
+        None => format!("{}@{resolved}", node.name),
+    };
+    // Patched packages have different contents than unpatched ones, so
+    // they can't share store entries or cached script results with them.
+    match &node.patch {
+        Some(patch) => format!("{id}+patch:{}", patch.integrity),
+        None => id,
     }
 }
 
@@ -479,6 +1044,94 @@ pub(crate) fn link_bin(from: &Path, to: &Path) -> Result<(), NodeMaintainerError
     Ok(())
 }
 
+/// Links all the bins declared by the package installed at `package_dir`
+/// into `bin_dir`, replacing any existing entries with the same names.
+/// Returns the names of the bins that were linked.
+///
+/// On Windows, this creates `.cmd`/`.ps1` shims instead of symlinks.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn link_package_bins(
+    package_dir: &Path,
+    bin_dir: &Path,
+) -> Result<Vec<String>, NodeMaintainerError> {
+    let build_mani = BuildManifest::from_path(package_dir.join("package.json")).map_err(|e| {
+        NodeMaintainerError::BuildManifestReadError(package_dir.join("package.json"), e)
+    })?;
+    std::fs::create_dir_all(bin_dir)
+        .io_context(|| format!("Failed to create bin directory at {}.", bin_dir.display()))?;
+    let mut linked = Vec::new();
+    for (name, path) in &build_mani.bin {
+        let from = package_dir.join(path);
+        let to = bin_dir.join(name);
+        // We only create a link if the target bin exists.
+        if from.symlink_metadata().is_err() {
+            continue;
+        }
+        remove_bin_entry(&to)?;
+        link_bin(&from, &to)?;
+        tracing::trace!(
+            "Linked bin for {} from {} to {}",
+            name,
+            from.display(),
+            to.display()
+        );
+        linked.push(name.clone());
+    }
+    linked.sort();
+    Ok(linked)
+}
+
+/// Removes any bins in `bin_dir` that were previously linked by
+/// [`link_package_bins`] for the package installed at `package_dir`.
+/// Returns the names of the bins that were removed.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn unlink_package_bins(
+    package_dir: &Path,
+    bin_dir: &Path,
+) -> Result<Vec<String>, NodeMaintainerError> {
+    let build_mani = BuildManifest::from_path(package_dir.join("package.json")).map_err(|e| {
+        NodeMaintainerError::BuildManifestReadError(package_dir.join("package.json"), e)
+    })?;
+    let mut unlinked = Vec::new();
+    for name in build_mani.bin.keys() {
+        let to = bin_dir.join(name);
+        let removed = remove_bin_entry(&to)?;
+        #[cfg(windows)]
+        let removed = remove_bin_entry(&to.with_extension("cmd"))?
+            | remove_bin_entry(&to.with_extension("ps1"))?
+            | removed;
+        if removed {
+            unlinked.push(name.clone());
+        }
+    }
+    unlinked.sort();
+    Ok(unlinked)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remove_bin_entry(path: &Path) -> Result<bool, NodeMaintainerError> {
+    if let Ok(meta) = path.symlink_metadata() {
+        if meta.is_dir() {
+            std::fs::remove_dir_all(path).io_context(|| {
+                format!(
+                    "Failed to rimraf existing bin directory at {}.",
+                    path.display()
+                )
+            })?;
+        } else {
+            std::fs::remove_file(path).io_context(|| {
+                format!(
+                    "Failed to rm existing file in bin directory location at {}.",
+                    path.display()
+                )
+            })?;
+        }
+        Ok(true)
+    } else {
+        Ok(false)
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 pub(crate) fn mkdirp(
     path: &Path,
@@ -491,7 +1144,11 @@ pub(crate) fn mkdirp(
             true
         };
         if grandpa_present {
-            std::fs::create_dir(path).io_context(|| {
+            match std::fs::create_dir(path) {
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(()),
+                res => res,
+            }
+            .io_context(|| {
                 format!(
                     "Failed to create directory {} while extracting.",
                     path.display()
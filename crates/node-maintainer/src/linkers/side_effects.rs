@@ -0,0 +1,241 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use nassun::package::Package;
+use oro_common::BuildManifest;
+use petgraph::stable_graph::NodeIndex;
+use serde::{Deserialize, Serialize};
+use ssri::Integrity;
+use walkdir::WalkDir;
+
+use crate::{error::IoContext, graph::Graph, NodeMaintainerError};
+
+/// Everything a package's install scripts left behind in its directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Snapshot {
+    /// Maps each file's path, relative to the package directory, to the
+    /// integrity of its contents in the cache and its mode.
+    files: BTreeMap<String, (String, u32)>,
+}
+
+/// Snapshots of package directories, taken after their install scripts
+/// succeed, so later installs can restore them instead of running the
+/// scripts again.
+///
+/// Snapshots are keyed by the package's integrity, the platform, the
+/// version of Node.js on the `PATH`, and the package's dependency closure,
+/// since any of those can change what the scripts produce. Packages without
+/// an integrity (git dependencies, local directories, etc) are never
+/// cached.
+pub(crate) struct SideEffectsCache {
+    cache: PathBuf,
+    keys: HashMap<NodeIndex, String>,
+}
+
+impl SideEffectsCache {
+    pub(crate) fn new(cache: &Path, graph: &Graph, node_version: &str) -> Self {
+        let platform = format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH);
+        let keys = super::closure_hashes(graph)
+            .into_iter()
+            .filter(|(idx, _)| *idx != graph.root)
+            .filter_map(|(idx, closure)| {
+                let integrity = graph[idx].package.resolved().integrity()?;
+                Some((
+                    idx,
+                    format!(
+                        "node-maintainer::side-effects::{integrity}::{platform}::node@{node_version}::{closure}"
+                    ),
+                ))
+            })
+            .collect();
+        Self {
+            cache: cache.to_path_buf(),
+            keys,
+        }
+    }
+
+    /// Replaces the contents of `dir` with the package's snapshot, if there
+    /// is one. Returns whether anything was restored.
+    pub(crate) fn restore(&self, idx: NodeIndex, dir: &Path) -> Result<bool, NodeMaintainerError> {
+        let Some(key) = self.keys.get(&idx) else {
+            return Ok(false);
+        };
+        let Some(entry) = cacache::metadata_sync(&self.cache, key)? else {
+            return Ok(false);
+        };
+        let snapshot: Snapshot =
+            serde_json::from_slice(&cacache::read_hash_sync(&self.cache, &entry.integrity)?)?;
+        let files = snapshot
+            .files
+            .iter()
+            .map(|(path, (sri, mode))| Ok((path, sri.parse::<Integrity>()?, *mode)))
+            .collect::<Result<Vec<_>, NodeMaintainerError>>()?;
+        // Leave the package alone unless the whole snapshot is still in the
+        // cache, so its scripts can run on a pristine copy instead.
+        if !files
+            .iter()
+            .all(|(_, sri, _)| cacache::exists_sync(&self.cache, sri))
+        {
+            return Ok(false);
+        }
+
+        for (path, full_path) in package_files(dir)? {
+            if !snapshot.files.contains_key(&path) {
+                std::fs::remove_file(&full_path).io_context(|| {
+                    format!(
+                        "Failed to remove {} while restoring cached install script side effects.",
+                        full_path.display()
+                    )
+                })?;
+            }
+        }
+        for (path, sri, mode) in files {
+            let target = dir.join(path);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent).io_context(|| {
+                    format!(
+                        "Failed to create directory at {} while restoring cached install script side effects.",
+                        parent.display()
+                    )
+                })?;
+            }
+            match std::fs::remove_file(&target) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(NodeMaintainerError::IoError(
+                        format!(
+                            "Failed to remove {} while restoring cached install script side effects.",
+                            target.display()
+                        ),
+                        e,
+                    ));
+                }
+                _ => {}
+            }
+            // Scripts can modify the files in a package as they run, so
+            // these always get their own copy instead of a link into the
+            // cache.
+            cacache::reflink_hash_sync(&self.cache, &sri, &target)
+                .or_else(|_| cacache::copy_hash_sync(&self.cache, &sri, &target).map(|_| ()))?;
+            set_mode(&target, mode)?;
+        }
+        Ok(true)
+    }
+
+    /// Snapshots `dir` into the cache, if the package has install scripts.
+    pub(crate) fn save(
+        &self,
+        idx: NodeIndex,
+        package: &Package,
+        dir: &Path,
+    ) -> Result<(), NodeMaintainerError> {
+        let Some(key) = self.keys.get(&idx) else {
+            return Ok(());
+        };
+        let build_mani = BuildManifest::from_path(dir.join("package.json")).map_err(|e| {
+            NodeMaintainerError::BuildManifestReadError(dir.join("package.json"), e)
+        })?;
+        if !super::has_lifecycle_scripts(&build_mani, package, dir) {
+            return Ok(());
+        }
+
+        let mut snapshot = Snapshot::default();
+        for (path, full_path) in package_files(dir)? {
+            let mut file = std::fs::File::open(&full_path).io_context(|| {
+                format!(
+                    "Failed to open {} while caching install script side effects.",
+                    full_path.display()
+                )
+            })?;
+            let mode = file_mode(&file, &full_path)?;
+            let mut writer = cacache::WriteOpts::new()
+                .algorithm(cacache::Algorithm::Xxh3)
+                .open_hash_sync(&self.cache)?;
+            std::io::copy(&mut file, &mut writer).io_context(|| {
+                format!(
+                    "Failed to copy {} into the cache while caching install script side effects.",
+                    full_path.display()
+                )
+            })?;
+            let sri = writer.commit()?;
+            snapshot.files.insert(path, (sri.to_string(), mode));
+        }
+        cacache::write_sync(&self.cache, key, serde_json::to_vec(&snapshot)?)?;
+        Ok(())
+    }
+}
+
+/// Figures out which version of Node.js scripts would run with, so
+/// snapshots taken with one version don't get restored for another. Scripts
+/// don't necessarily need Node.js at all, so not having it is fine too.
+pub(crate) fn node_version() -> String {
+    std::process::Command::new("node")
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "none".into())
+}
+
+/// Lists the regular files in a package directory, along with their paths
+/// relative to it. Anything under the package's own `node_modules` belongs
+/// to its dependencies, and symlinks are left out.
+fn package_files(dir: &Path) -> Result<Vec<(String, PathBuf)>, NodeMaintainerError> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| !(e.depth() == 1 && e.file_name() == OsStr::new("node_modules")))
+    {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()
+            .strip_prefix(dir)
+            .expect("walked paths are inside the package directory")
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        files.push((path, entry.into_path()));
+    }
+    Ok(files)
+}
+
+#[cfg(unix)]
+fn file_mode(file: &std::fs::File, path: &Path) -> Result<u32, NodeMaintainerError> {
+    use std::os::unix::fs::PermissionsExt;
+    let metadata = file.metadata().io_context(|| {
+        format!(
+            "Failed to read metadata for {} while caching install script side effects.",
+            path.display()
+        )
+    })?;
+    Ok(metadata.permissions().mode() & 0o777)
+}
+
+#[cfg(not(unix))]
+fn file_mode(_file: &std::fs::File, _path: &Path) -> Result<u32, NodeMaintainerError> {
+    Ok(0o644)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<(), NodeMaintainerError> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).io_context(|| {
+        format!(
+            "Failed to set permissions on {} while restoring cached install script side effects.",
+            path.display()
+        )
+    })
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<(), NodeMaintainerError> {
+    Ok(())
+}
@@ -0,0 +1,316 @@
+//! Support for `oro link`: local packages that stand in for one of the root
+//! package's dependencies. Links are recorded in
+//! `node_modules/.oro-links.kdl` rather than the lockfile, since they only
+//! make sense on the machine they were made on.
+
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use kdl::{KdlDocument, KdlNode};
+use nassun::PackageSpec;
+use oro_common::CorgiManifest;
+use petgraph::{stable_graph::NodeIndex, Direction};
+use unicase::UniCase;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::IoContext;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::LINKS_FILE_NAME;
+use crate::{
+    error::NodeMaintainerError,
+    graph::{DepType, Graph},
+    IntoKdl, Lockfile, LockfileNode,
+};
+
+/// Local packages linked into a project, by name, along with the
+/// directories they live in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkedDependencies {
+    links: IndexMap<String, PathBuf>,
+}
+
+impl LinkedDependencies {
+    /// Loads the links recorded for the project at `root`. Projects without
+    /// any get an empty set.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn load(root: impl AsRef<Path>) -> Result<Self, NodeMaintainerError> {
+        let path = root.as_ref().join("node_modules").join(LINKS_FILE_NAME);
+        match async_std::fs::read_to_string(&path).await {
+            Ok(kdl) => Self::from_kdl(kdl),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(NodeMaintainerError::IoError(
+                format!("Failed to read linked packages from {}.", path.display()),
+                e,
+            )),
+        }
+    }
+
+    /// Records these links for the project at `root`. The file is removed
+    /// once nothing is linked anymore.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn save(&self, root: impl AsRef<Path>) -> Result<(), NodeMaintainerError> {
+        let node_modules = root.as_ref().join("node_modules");
+        let path = node_modules.join(LINKS_FILE_NAME);
+        if self.links.is_empty() {
+            if async_std::path::Path::new(&path).exists().await {
+                async_std::fs::remove_file(&path).await.io_context(|| {
+                    format!(
+                        "Failed to remove linked packages file at {}.",
+                        path.display()
+                    )
+                })?;
+            }
+            return Ok(());
+        }
+        async_std::fs::create_dir_all(&node_modules)
+            .await
+            .io_context(|| format!("Failed to create {}.", node_modules.display()))?;
+        async_std::fs::write(&path, self.to_kdl().to_string())
+            .await
+            .io_context(|| format!("Failed to write linked packages to {}.", path.display()))
+    }
+
+    pub fn from_kdl(kdl: impl IntoKdl) -> Result<Self, NodeMaintainerError> {
+        let kdl: KdlDocument = kdl.into_kdl()?;
+        let mut links = IndexMap::new();
+        for node in kdl.nodes() {
+            let args = node
+                .entries()
+                .iter()
+                .filter(|e| e.name().is_none())
+                .map(|e| e.value().as_string())
+                .collect::<Vec<_>>();
+            match &args[..] {
+                [Some(name), Some(path)] if node.name().to_string() == "link" => {
+                    links.insert(name.to_string(), PathBuf::from(path));
+                }
+                _ => return Err(NodeMaintainerError::InvalidLink(node.clone())),
+            }
+        }
+        Ok(Self { links })
+    }
+
+    pub fn to_kdl(&self) -> KdlDocument {
+        let mut doc = KdlDocument::new();
+        doc.set_leading(
+            "// Local packages linked into this project with `oro link`. Use `oro unlink` to remove them.",
+        );
+        for (name, path) in &self.links {
+            let mut node = KdlNode::new("link");
+            node.push(name.as_str());
+            node.push(path.to_string_lossy().to_string());
+            doc.nodes_mut().push(node);
+        }
+        doc.fmt();
+        doc
+    }
+
+    /// The directory `name` is linked to, if it's linked.
+    pub fn get(&self, name: &str) -> Option<&Path> {
+        self.links.get(name).map(|path| path.as_path())
+    }
+
+    /// Links `name` to the package at `path`, returning the directory it was
+    /// linked to before, if any.
+    pub fn insert(&mut self, name: impl Into<String>, path: impl Into<PathBuf>) -> Option<PathBuf> {
+        self.links.insert(name.into(), path.into())
+    }
+
+    /// Unlinks `name`, returning the directory it was linked to.
+    pub fn remove(&mut self, name: &str) -> Option<PathBuf> {
+        self.links.shift_remove(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.links
+            .iter()
+            .map(|(name, path)| (name.as_str(), path.as_path()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Points `root`'s dependencies on linked packages at their directories,
+    /// adding a regular dependency for any it didn't have yet. Returns what
+    /// the root package originally asked for, so the lockfile can keep
+    /// recording that instead.
+    pub(crate) fn link_into(&self, root: &mut CorgiManifest) -> IndexMap<UniCase<String>, Link> {
+        let mut linked = IndexMap::new();
+        for (name, path) in &self.links {
+            let spec = format!("file:{}", path.display());
+            // Later dependency types win, same as when the root node gets
+            // its dependencies.
+            let mut requested = None;
+            for (dep_type, deps) in [
+                (DepType::Prod, &mut root.dependencies),
+                (DepType::Opt, &mut root.optional_dependencies),
+                (DepType::Dev, &mut root.dev_dependencies),
+            ] {
+                if let Some(original) = deps.get_mut(name) {
+                    requested = Some((dep_type, std::mem::replace(original, spec.clone())));
+                }
+            }
+            if requested.is_none() {
+                root.dependencies.insert(name.clone(), spec);
+            }
+            linked.insert(
+                UniCase::new(name.clone()),
+                Link {
+                    path: path.clone(),
+                    requested,
+                },
+            );
+        }
+        linked
+    }
+}
+
+/// A dependency of the root package that's been replaced with a link.
+#[derive(Debug, Clone)]
+pub(crate) struct Link {
+    pub(crate) path: PathBuf,
+    /// The dependency the root package had on this package before it was
+    /// linked, if any.
+    requested: Option<(DepType, String)>,
+}
+
+impl Graph {
+    /// The directory `idx` is linked to, if it's a package linked in with
+    /// `oro link`. These get symlinked into place, and nothing ever gets
+    /// written into them.
+    pub(crate) fn linked_dir(&self, idx: NodeIndex) -> Option<&Path> {
+        let name = &self[idx].name;
+        if self[self.root].children.get(name) == Some(&idx) {
+            self.links.get(name).map(|link| link.path.as_path())
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn is_link(&self, idx: NodeIndex) -> bool {
+        self.linked_dir(idx).is_some()
+    }
+
+    /// Whether `idx` sits below a linked package in the tree. With the
+    /// hoisted linker, that would put it inside the linked package's own
+    /// directory, so it's left to that package instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn is_inside_link(&self, idx: NodeIndex) -> bool {
+        self.node_parent_iter(idx)
+            .skip(1)
+            .any(|node| self.is_link(node.idx))
+    }
+
+    /// Like [`Graph::to_lockfile`], but as if nothing were linked. Linked
+    /// packages, and anything only they depend on, are left out, and the
+    /// root package gets back the dependencies it had before linking.
+    /// Whatever `previous` recorded for those is carried over, so linking
+    /// and unlinking a package doesn't churn the lockfile.
+    pub(crate) fn to_unlinked_lockfile(
+        &self,
+        previous: Option<&Lockfile>,
+    ) -> Result<Lockfile, NodeMaintainerError> {
+        let mut lockfile = self.to_lockfile()?;
+        if self.links.is_empty() {
+            return Ok(lockfile);
+        }
+
+        let mut reachable = HashSet::from([self.root]);
+        let mut queue = VecDeque::from([self.root]);
+        while let Some(idx) = queue.pop_front() {
+            for dep in self.inner.neighbors_directed(idx, Direction::Outgoing) {
+                if !self.is_link(dep) && reachable.insert(dep) {
+                    queue.push_back(dep);
+                }
+            }
+        }
+        let reachable = reachable
+            .into_iter()
+            .map(|idx| UniCase::from(self.node_path_string(idx)))
+            .collect::<HashSet<_>>();
+        lockfile.packages.retain(|path, _| reachable.contains(path));
+
+        let root = &mut lockfile.root;
+        for (name, link) in &self.links {
+            for deps in [
+                &mut root.dependencies,
+                &mut root.optional_dependencies,
+                &mut root.dev_dependencies,
+            ] {
+                deps.shift_remove(name.as_str());
+            }
+            if let Some((dep_type, requested)) = &link.requested {
+                let deps = match dep_type {
+                    DepType::Prod => &mut root.dependencies,
+                    DepType::Opt => &mut root.optional_dependencies,
+                    DepType::Dev => &mut root.dev_dependencies,
+                    DepType::Peer => &mut root.peer_dependencies,
+                };
+                // Recorded the same way the resolved graph records them.
+                let spec: PackageSpec = format!("{name}@{requested}").parse()?;
+                deps.insert(name.to_string(), spec.requested().clone());
+            }
+        }
+
+        if let Some(previous) = previous {
+            for (path, node) in previous.linked_subtrees(&self.links) {
+                lockfile.packages.entry(path).or_insert(node);
+            }
+        }
+        Ok(lockfile)
+    }
+}
+
+impl Lockfile {
+    /// This lockfile's entries for the root package's original dependencies
+    /// on `links`, along with everything those pulled in.
+    fn linked_subtrees(
+        &self,
+        links: &IndexMap<UniCase<String>, Link>,
+    ) -> IndexMap<UniCase<String>, LockfileNode> {
+        let mut found = IndexMap::new();
+        let mut queue = links
+            .iter()
+            .filter(|(_, link)| link.requested.is_some())
+            .map(|(name, _)| name.clone())
+            .collect::<VecDeque<_>>();
+        while let Some(path) = queue.pop_front() {
+            let Some(node) = self.packages.get(&path) else {
+                continue;
+            };
+            if found.contains_key(&path) {
+                continue;
+            }
+            for name in node
+                .dependencies
+                .keys()
+                .chain(node.optional_dependencies.keys())
+                .chain(node.peer_dependencies.keys())
+            {
+                queue.extend(self.resolve_from(&node.path, name));
+            }
+            found.insert(path, node.clone());
+        }
+        found
+    }
+
+    /// The entry that `name` resolves to from the package at `from`, looking
+    /// through parent `node_modules` directories the way Node does.
+    fn resolve_from(&self, from: &[UniCase<String>], name: &str) -> Option<UniCase<String>> {
+        (0..=from.len())
+            .rev()
+            .map(|depth| {
+                UniCase::from(
+                    from[..depth]
+                        .iter()
+                        .map(|segment| segment.as_str())
+                        .chain(std::iter::once(name))
+                        .collect::<Vec<_>>()
+                        .join("/node_modules/"),
+                )
+            })
+            .find(|path| self.packages.contains_key(path))
+    }
+}
@@ -1,14 +1,15 @@
 use indexmap::IndexMap;
-use kdl::{KdlDocument, KdlNode};
+use kdl::{KdlDocument, KdlNode, KdlValue};
 use nassun::{client::Nassun, package::Package, PackageResolution};
 use node_semver::Version;
-use oro_common::CorgiManifest;
+use oro_common::{CorgiManifest, DeprecationInfo};
 use oro_package_spec::PackageSpec;
 use serde::{Deserialize, Serialize};
 use ssri::Integrity;
 use unicase::UniCase;
+use url::Url;
 
-use crate::{error::NodeMaintainerError, graph::DepType, IntoKdl};
+use crate::{error::NodeMaintainerError, graph::DepType, IntoKdl, LockfileDiff};
 
 /// A representation of a resolved lockfile.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
@@ -31,6 +32,34 @@ impl Lockfile {
         &self.packages
     }
 
+    /// Records `resolved` URLs that point at one of `nassun`'s registries (or
+    /// their mirrors) as registry-relative paths, the same way freshly
+    /// resolved packages get recorded, so that lockfiles written against
+    /// another registry, or by npm, compare equal to them. Anything else is
+    /// left as-is.
+    pub(crate) fn relative_to_registries(mut self, nassun: &Nassun) -> Self {
+        for node in self.packages.values_mut() {
+            if node.version.is_none() {
+                continue;
+            }
+            let path = node
+                .resolved
+                .as_deref()
+                .and_then(|resolved| Url::parse(resolved).ok())
+                .and_then(|url| nassun.registry_path(&url));
+            if path.is_some() {
+                node.resolved = path;
+            }
+        }
+        self
+    }
+
+    /// Compares this lockfile against a newer one, classifying which
+    /// packages were added, removed, upgraded, downgraded, changed, or moved.
+    pub fn diff(&self, new: &Lockfile) -> LockfileDiff {
+        LockfileDiff::new(self, new)
+    }
+
     pub fn to_kdl(&self) -> KdlDocument {
         let mut doc = KdlDocument::new();
         doc.set_leading(
@@ -136,6 +165,15 @@ pub struct LockfileNode {
     pub resolved: Option<String>,
     pub version: Option<Version>,
     pub integrity: Option<Integrity>,
+    /// ID of the registry key this package's signature was verified
+    /// against, if signatures were verified when it was resolved.
+    pub signature_keyid: Option<String>,
+    /// Deprecation notice for this package's version, as of when it was
+    /// resolved.
+    pub deprecated: Option<DeprecationInfo>,
+    /// Hash of the patch from `patchedDependencies` that gets applied to
+    /// this package, if any.
+    pub patch: Option<Integrity>,
     pub dependencies: IndexMap<String, String>,
     pub dev_dependencies: IndexMap<String, String>,
     pub peer_dependencies: IndexMap<String, String>,
@@ -152,17 +190,22 @@ impl From<LockfileNode> for CorgiManifest {
             peer_dependencies: value.peer_dependencies,
             optional_dependencies: value.optional_dependencies,
             bundled_dependencies: None,
+            patched_dependencies: IndexMap::new(),
         }
     }
 }
 
 impl LockfileNode {
-    pub(crate) async fn to_package(
+    /// Resolves this lockfile entry into a [`Package`], without going
+    /// through the registry when the entry has enough information to do so.
+    pub async fn to_package(
         &self,
         nassun: &Nassun,
     ) -> Result<Option<Package>, NodeMaintainerError> {
         let spec = match (self.resolved.as_ref(), self.version.as_ref()) {
-            (Some(resolved), Some(version)) if resolved.starts_with("http") => {
+            (Some(resolved), Some(version))
+                if resolved.starts_with("http") || is_registry_relative(resolved) =>
+            {
                 format!("{}@{version}", self.name)
             }
             (Some(resolved), _) => format!("{}@{resolved}", self.name),
@@ -181,19 +224,23 @@ impl LockfileNode {
                 };
                 nassun.resolve_from(self.name.to_string(), spec, resolution)
             }
-            PackageSpec::Npm { name, .. } => {
+            PackageSpec::Npm { name, scope, .. } => {
                 let version = if let Some(ref version) = self.version {
                     version
                 } else {
                     return Err(NodeMaintainerError::MissingVersion);
                 };
                 if let Some(ref url) = self.resolved {
+                    let tarball = if is_registry_relative(url) {
+                        nassun.registry(scope.as_deref()).join(url)
+                    } else {
+                        url.parse()
+                    }
+                    .map_err(|e| NodeMaintainerError::UrlParseError(url.clone(), e))?;
                     let resolution = PackageResolution::Npm {
                         name: name.clone(),
                         version: version.clone(),
-                        tarball: url
-                            .parse()
-                            .map_err(|e| NodeMaintainerError::UrlParseError(url.clone(), e))?,
+                        tarball,
                         integrity: self.integrity.clone(),
                     };
                     nassun.resolve_from(self.name.to_string(), spec, resolution)
@@ -261,6 +308,23 @@ impl LockfileNode {
             .get_arg("resolved")
             .and_then(|resolved| resolved.as_string())
             .map(|resolved| resolved.to_string());
+        let signature_keyid = children
+            .get_arg("signature-keyid")
+            .and_then(|keyid| keyid.as_string())
+            .map(String::from);
+        let deprecated = children
+            .get_arg("deprecated")
+            .and_then(|deprecated| match deprecated {
+                KdlValue::String(reason) => Some(DeprecationInfo::Reason(reason.clone())),
+                KdlValue::Bool(true) => Some(DeprecationInfo::UnknownReason),
+                _ => None,
+            });
+        let patch = children
+            .get_arg("patch")
+            .and_then(|i| i.as_string())
+            .map(|i| i.parse())
+            .transpose()
+            .map_err(|e| NodeMaintainerError::KdlLockfileIntegrityParseError(node.clone(), e))?;
         Ok(Self {
             name,
             is_root,
@@ -268,6 +332,9 @@ impl LockfileNode {
             integrity,
             resolved,
             version,
+            signature_keyid,
+            deprecated,
+            patch,
             dependencies: Self::from_kdl_deps(&children, &DepType::Prod)?,
             dev_dependencies: Self::from_kdl_deps(&children, &DepType::Dev)?,
             optional_dependencies: Self::from_kdl_deps(&children, &DepType::Opt)?,
@@ -324,6 +391,27 @@ impl LockfileNode {
                     inode.push(integrity.to_string());
                     kdl_node.ensure_children().nodes_mut().push(inode);
                 }
+
+                if let Some(keyid) = &self.signature_keyid {
+                    let mut knode = KdlNode::new("signature-keyid");
+                    knode.push(keyid.clone());
+                    kdl_node.ensure_children().nodes_mut().push(knode);
+                }
+
+                if let Some(deprecated) = &self.deprecated {
+                    let mut dnode = KdlNode::new("deprecated");
+                    match deprecated {
+                        DeprecationInfo::Reason(reason) => dnode.push(reason.clone()),
+                        DeprecationInfo::UnknownReason => dnode.push(true),
+                    }
+                    kdl_node.ensure_children().nodes_mut().push(dnode);
+                }
+
+                if let Some(patch) = &self.patch {
+                    let mut pnode = KdlNode::new("patch");
+                    pnode.push(patch.to_string());
+                    kdl_node.ensure_children().nodes_mut().push(pnode);
+                }
             }
         }
         if !self.dependencies.is_empty() {
@@ -412,6 +500,9 @@ impl LockfileNode {
             integrity,
             resolved: npm.resolved.clone(),
             version,
+            signature_keyid: None,
+            deprecated: None,
+            patch: None,
             dependencies: npm.dependencies.clone(),
             dev_dependencies: npm.dev_dependencies.clone(),
             optional_dependencies: npm.optional_dependencies.clone(),
@@ -420,6 +511,13 @@ impl LockfileNode {
     }
 }
 
+/// Whether `resolved` is a registry-relative tarball path, like
+/// `foo/-/foo-1.0.0.tgz`, which is how registry packages hosted on a
+/// configured registry get recorded.
+fn is_registry_relative(resolved: &str) -> bool {
+    nassun::fetch::is_tarball_path(resolved)
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NpmPackageLock {
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use node_semver::Version;
+use serde::Serialize;
+use ssri::Integrity;
+
+use crate::{Lockfile, LockfileNode};
+
+/// Structured set of changes between two [`Lockfile`]s, as returned by
+/// [`Lockfile::diff`].
+///
+/// Packages are matched up by their `node_modules` path, and a path that
+/// now holds a different package altogether (for example, a repointed
+/// alias) counts as a removal plus an addition. Packages that disappeared
+/// from one path and showed up at another, with the same name, version and
+/// integrity, are reported as moved instead.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct LockfileDiff {
+    /// Packages that are only present in the new lockfile.
+    pub added: Vec<LockfileDiffPackage>,
+    /// Packages that are only present in the old lockfile.
+    pub removed: Vec<LockfileDiffPackage>,
+    /// Packages whose version went up.
+    pub upgraded: Vec<LockfileDiffChange>,
+    /// Packages whose version went down.
+    pub downgraded: Vec<LockfileDiffChange>,
+    /// Packages that kept their version, but now resolve to different
+    /// contents (for example, a republished tarball, a different git
+    /// commit, or a different patch).
+    pub changed: Vec<LockfileDiffChange>,
+    /// Identical packages that were placed at a different path.
+    pub moved: Vec<LockfileDiffChange>,
+}
+
+/// A single package entry referenced by a [`LockfileDiff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockfileDiffPackage {
+    pub name: String,
+    /// `node_modules` path of the package, relative to the project root.
+    pub path: String,
+    pub version: Option<Version>,
+    pub resolved: Option<String>,
+    pub integrity: Option<Integrity>,
+    /// Hash of the patch applied to the package, if it's patched.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<Integrity>,
+}
+
+/// A package that is present in both lockfiles, but differs between them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LockfileDiffChange {
+    pub from: LockfileDiffPackage,
+    pub to: LockfileDiffPackage,
+}
+
+impl LockfileDiff {
+    pub(crate) fn new(old: &Lockfile, new: &Lockfile) -> Self {
+        let mut diff = LockfileDiff::default();
+        let mut removed = Vec::new();
+        // A path that now holds a different package (say, because an alias
+        // was repointed) had one package removed and another added, rather
+        // than a single package changing.
+        let same_package = |old_node: &LockfileNode, new_node: &LockfileNode| {
+            old_node.name == new_node.name && package_name(old_node) == package_name(new_node)
+        };
+        for (path, old_node) in &old.packages {
+            match new.packages.get(path) {
+                Some(new_node) if same_package(old_node, new_node) => {
+                    diff.compare(old_node, new_node)
+                }
+                _ => removed.push(old_node),
+            }
+        }
+        let mut added = new
+            .packages
+            .iter()
+            .filter(|(path, new_node)| {
+                !old.packages
+                    .get(*path)
+                    .map_or(false, |old_node| same_package(old_node, new_node))
+            })
+            .map(|(_, node)| node)
+            .collect::<Vec<_>>();
+
+        // Anything that was both removed and added with identical contents
+        // just moved around in the tree.
+        let mut added_by_identity: HashMap<_, Vec<usize>> = HashMap::new();
+        for (i, node) in added.iter().enumerate() {
+            added_by_identity.entry(identity(node)).or_default().push(i);
+        }
+        let mut moved_to = vec![false; added.len()];
+        removed.retain(|old_node| {
+            if let Some(i) = added_by_identity
+                .get_mut(&identity(old_node))
+                .and_then(|candidates| candidates.pop())
+            {
+                moved_to[i] = true;
+                diff.moved.push(LockfileDiffChange {
+                    from: (*old_node).into(),
+                    to: added[i].into(),
+                });
+                false
+            } else {
+                true
+            }
+        });
+        let mut i = 0;
+        added.retain(|_| {
+            let keep = !moved_to[i];
+            i += 1;
+            keep
+        });
+
+        diff.added = added.into_iter().map(Into::into).collect();
+        diff.removed = removed.into_iter().map(Into::into).collect();
+        diff.sort();
+        diff
+    }
+
+    /// Whether there are no differences between the two lockfiles.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total number of package-level changes.
+    pub fn len(&self) -> usize {
+        self.added.len()
+            + self.removed.len()
+            + self.upgraded.len()
+            + self.downgraded.len()
+            + self.changed.len()
+            + self.moved.len()
+    }
+
+    fn compare(&mut self, old: &LockfileNode, new: &LockfileNode) {
+        let change = || LockfileDiffChange {
+            from: old.into(),
+            to: new.into(),
+        };
+        match (&old.version, &new.version) {
+            (Some(old_v), Some(new_v)) if old_v < new_v => self.upgraded.push(change()),
+            (Some(old_v), Some(new_v)) if old_v > new_v => self.downgraded.push(change()),
+            _ if old.version != new.version
+                || old.integrity != new.integrity
+                || old.patch != new.patch
+                || (old.integrity.is_none() && old.resolved != new.resolved) =>
+            {
+                self.changed.push(change())
+            }
+            _ => {}
+        }
+    }
+
+    fn sort(&mut self) {
+        self.added.sort_by(|a, b| a.path.cmp(&b.path));
+        self.removed.sort_by(|a, b| a.path.cmp(&b.path));
+        for changes in [
+            &mut self.upgraded,
+            &mut self.downgraded,
+            &mut self.changed,
+            &mut self.moved,
+        ] {
+            changes.sort_by(|a, b| a.to.path.cmp(&b.to.path));
+        }
+    }
+}
+
+fn identity(node: &LockfileNode) -> (String, Option<String>, Option<String>, Option<String>) {
+    (
+        node.name.to_string(),
+        node.version.as_ref().map(|v| v.to_string()),
+        node.integrity
+            .as_ref()
+            .map(|i| i.to_string())
+            .or_else(|| node.resolved.clone()),
+        node.patch.as_ref().map(|i| i.to_string()),
+    )
+}
+
+/// Name of the package that's actually installed for `node`. Aliased
+/// packages are recorded under their alias, but registry tarballs still say
+/// which package they came from.
+fn package_name(node: &LockfileNode) -> &str {
+    node.resolved
+        .as_deref()
+        .filter(|resolved| !resolved.contains(':'))
+        .and_then(|resolved| resolved.split_once("/-/"))
+        .map(|(name, _)| name)
+        .unwrap_or(node.name.as_str())
+}
+
+impl From<&LockfileNode> for LockfileDiffPackage {
+    fn from(node: &LockfileNode) -> Self {
+        LockfileDiffPackage {
+            name: node.name.to_string(),
+            path: node
+                .path
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join("/node_modules/"),
+            version: node.version.clone(),
+            resolved: node.resolved.clone(),
+            integrity: node.integrity.clone(),
+            patch: node.patch.clone(),
+        }
+    }
+}
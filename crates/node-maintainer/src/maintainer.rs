@@ -1,11 +1,16 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
 #[cfg(not(target_arch = "wasm32"))]
 use async_std::fs;
+use chrono::{DateTime, Utc};
 use nassun::client::{Nassun, NassunOpts};
 use nassun::package::Package;
+#[cfg(not(target_arch = "wasm32"))]
+use nassun::PackageResolution;
 use oro_common::CorgiManifest;
 use unicase::UniCase;
 use url::Url;
@@ -17,8 +22,11 @@ use crate::graph::{Graph, Node};
 use crate::linkers::Linker;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::linkers::LinkerOptions;
+use crate::patches::PatchedDependencies;
 use crate::resolver::Resolver;
-use crate::{IntoKdl, Lockfile};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::ScriptFailure;
+use crate::{DeprecatedPackage, IntoKdl, LinkedDependencies, Lockfile};
 
 pub const DEFAULT_CONCURRENCY: usize = 50;
 pub const DEFAULT_SCRIPT_CONCURRENCY: usize = 6;
@@ -27,12 +35,15 @@ pub const DEFAULT_SCRIPT_CONCURRENCY: usize = 6;
 pub const META_FILE_NAME: &str = ".orogene-meta.kdl";
 #[cfg(not(target_arch = "wasm32"))]
 pub const STORE_DIR_NAME: &str = ".oro-store";
+#[cfg(not(target_arch = "wasm32"))]
+pub const LINKS_FILE_NAME: &str = ".oro-links.kdl";
 
 pub type ProgressAdded = Arc<dyn Fn() + Send + Sync>;
 pub type ProgressHandler = Arc<dyn Fn(&Package, Duration) + Send + Sync>;
 pub type PruneProgress = Arc<dyn Fn(&Path) + Send + Sync>;
 pub type ScriptStartHandler = Arc<dyn Fn(&Package, &str) + Send + Sync>;
 pub type ScriptLineHandler = Arc<dyn Fn(&str) + Send + Sync>;
+pub type ScriptOutputHandler = Arc<dyn Fn(&Package, &str, &str) + Send + Sync>;
 
 #[derive(Clone)]
 pub struct NodeMaintainerOptions {
@@ -40,6 +51,8 @@ pub struct NodeMaintainerOptions {
     nassun: Option<Nassun>,
     concurrency: usize,
     locked: bool,
+    verify_signatures: bool,
+    prefer_dedupe: bool,
     kdl_lock: Option<Lockfile>,
     npm_lock: Option<Lockfile>,
 
@@ -52,6 +65,20 @@ pub struct NodeMaintainerOptions {
     #[allow(dead_code)]
     prefer_copy: bool,
     #[allow(dead_code)]
+    side_effects_cache: bool,
+    #[allow(dead_code)]
+    store: Option<PathBuf>,
+    #[allow(dead_code)]
+    script_logs: Option<PathBuf>,
+    #[allow(dead_code)]
+    public_hoist_patterns: Vec<String>,
+    #[allow(dead_code)]
+    hoist_patterns: Vec<String>,
+    #[allow(dead_code)]
+    patched_dependencies: Vec<(String, PathBuf)>,
+    #[allow(dead_code)]
+    linked_dependencies: Vec<(String, PathBuf)>,
+    #[allow(dead_code)]
     validate: bool,
     #[allow(dead_code)]
     root: Option<PathBuf>,
@@ -66,7 +93,7 @@ pub struct NodeMaintainerOptions {
     #[allow(dead_code)]
     on_script_start: Option<ScriptStartHandler>,
     #[allow(dead_code)]
-    on_script_line: Option<ScriptLineHandler>,
+    on_script_output: Option<ScriptOutputHandler>,
 }
 
 impl NodeMaintainerOptions {
@@ -98,6 +125,24 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Verify registry signatures for every registry package in the tree,
+    /// including ones already in the lockfile, and record the key each
+    /// package was signed with in the lockfile. Packages with invalid
+    /// signatures will fail resolution.
+    pub fn verify_signatures(mut self, verify: bool) -> Self {
+        self.verify_signatures = verify;
+        self
+    }
+
+    /// After resolving, collapse packages that ended up in the tree more
+    /// than once into a single copy, wherever a version already in the tree
+    /// satisfies everything that depends on them. This can mean using an
+    /// older version of a package than would otherwise be picked.
+    pub fn prefer_dedupe(mut self, prefer_dedupe: bool) -> Self {
+        self.prefer_dedupe = prefer_dedupe;
+        self
+    }
+
     /// Controls number of concurrent script executions while running
     /// `run_script`. This option is separate from `concurrency` because
     /// executing concurrent scripts is a much heavier operation.
@@ -106,6 +151,15 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Directory to write the output of each package's lifecycle scripts
+    /// to, one file per package. A package's log is replaced whenever its
+    /// scripts run again.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn script_logs(mut self, dir: impl AsRef<Path>) -> Self {
+        self.script_logs = Some(PathBuf::from(dir.as_ref()));
+        self
+    }
+
     /// Configure the KDL lockfile that NodeMaintainer will use.
     ///
     /// If this option is not specified, NodeMaintainer will try to read the
@@ -142,6 +196,36 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Adds a mirror for the default registry. Mirrors are tried in order
+    /// whenever the registry before them is unavailable. This option can be
+    /// provided multiple times.
+    pub fn mirror(mut self, mirror: Url) -> Self {
+        self.nassun_opts = self.nassun_opts.mirror(mirror);
+        self
+    }
+
+    /// Adds a mirror for the registry used by a given `@scope`. This option
+    /// can be provided multiple times.
+    pub fn scope_mirror(mut self, scope: impl AsRef<str>, mirror: Url) -> Self {
+        self.nassun_opts = self.nassun_opts.scope_mirror(scope, mirror);
+        self
+    }
+
+    /// Only resolve to package versions published at or before this time.
+    /// Packages already in the lockfile are not affected.
+    pub fn before(mut self, before: DateTime<Utc>) -> Self {
+        self.nassun_opts = self.nassun_opts.before(before);
+        self
+    }
+
+    /// Skip package versions that were published less than `age` ago,
+    /// falling back to older versions that satisfy the requested spec.
+    /// Packages already in the lockfile are not affected.
+    pub fn minimum_release_age(mut self, age: Duration) -> Self {
+        self.nassun_opts = self.nassun_opts.minimum_release_age(age);
+        self
+    }
+
     /// Sets basic auth credentials for a registry.
     pub fn basic_auth(
         mut self,
@@ -202,6 +286,17 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Whether to cache what packages' install scripts leave behind in
+    /// their directories, and restore that instead of running the scripts
+    /// again the next time the same package is installed with the same
+    /// dependencies, platform, and Node.js version. Requires a cache to be
+    /// configured. Defaults to `true`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn side_effects_cache(mut self, side_effects_cache: bool) -> Self {
+        self.side_effects_cache = side_effects_cache;
+        self
+    }
+
     /// Use the hoisted installation mode, where all dependencies and their
     /// transitive dependencies are installed as high up in the `node_modules`
     /// tree as possible. This can potentially mean that packages have access
@@ -212,6 +307,74 @@ impl NodeMaintainerOptions {
         self
     }
 
+    /// Use a package store shared with other projects for isolated installs,
+    /// instead of one inside the project's own `node_modules`. The store
+    /// should be on the same filesystem as the project and the cache. Has no
+    /// effect on hoisted installs.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn store(mut self, store: impl AsRef<Path>) -> Self {
+        self.store = Some(crate::store::absolute(store.as_ref()));
+        self
+    }
+
+    /// Adds a pattern for packages that isolated installs should link into
+    /// the project's root `node_modules`, even when the project doesn't
+    /// depend on them directly. Useful for tools like ESLint and TypeScript
+    /// that look for plugins and `@types` packages there.
+    ///
+    /// Patterns match package names, and can use `*` as a wildcard. Patterns
+    /// starting with `!` exclude anything they match. Has no effect on
+    /// hoisted installs. This option can be provided multiple times.
+    pub fn public_hoist_pattern(mut self, pattern: impl AsRef<str>) -> Self {
+        self.public_hoist_patterns.push(pattern.as_ref().into());
+        self
+    }
+
+    /// Adds a pattern for packages that isolated installs should link into
+    /// a `node_modules` directory inside the package store. Only packages in
+    /// the store can see these, which lets packages that forgot to declare
+    /// some of their dependencies keep working, without exposing those
+    /// packages to the project itself.
+    ///
+    /// Uses the same syntax as
+    /// [`NodeMaintainerOptions::public_hoist_pattern`]. Has no effect, other
+    /// than a warning, with a shared [`NodeMaintainerOptions::store`]. This
+    /// option can be provided multiple times.
+    pub fn hoist_pattern(mut self, pattern: impl AsRef<str>) -> Self {
+        self.hoist_patterns.push(pattern.as_ref().into());
+        self
+    }
+
+    /// Applies the patch file at `patch` to every package matching `spec`
+    /// after it's extracted. `spec` is either a package name, or a name and
+    /// an exact version, like `foo@1.2.3`, which takes precedence over a
+    /// plain name. Relative paths are resolved against the project root.
+    ///
+    /// These are added to, and override, the root package's own
+    /// `patchedDependencies`. This option can be provided multiple times.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn patched_dependency(mut self, spec: impl AsRef<str>, patch: impl AsRef<Path>) -> Self {
+        self.patched_dependencies
+            .push((spec.as_ref().into(), patch.as_ref().into()));
+        self
+    }
+
+    /// Replaces the root package's dependency on `name` with a link to the
+    /// local package at `path`, whose own dependencies get resolved into
+    /// the tree. The root package doesn't need to depend on `name` already.
+    /// Relative paths are resolved against the project root.
+    ///
+    /// These are added to, and override, the links recorded in the
+    /// project's `node_modules/.oro-links.kdl` (see [`LinkedDependencies`]).
+    /// Linked packages are left out of the lockfile. This option can be
+    /// provided multiple times.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn linked_dependency(mut self, name: impl AsRef<str>, path: impl AsRef<Path>) -> Self {
+        self.linked_dependencies
+            .push((name.as_ref().into(), path.as_ref().into()));
+        self
+    }
+
     #[cfg(not(target_arch = "wasm32"))]
     pub fn proxy(mut self, proxy: bool) -> Self {
         self.nassun_opts = self.nassun_opts.proxy(proxy);
@@ -274,11 +437,25 @@ impl NodeMaintainerOptions {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn on_script_line<F>(mut self, f: F) -> Self
+    #[deprecated(
+        since = "0.3.35",
+        note = "use `on_script_output`, which also says which package and script the line came from"
+    )]
+    pub fn on_script_line<F>(self, f: F) -> Self
     where
         F: Fn(&str) + Send + Sync + 'static,
     {
-        self.on_script_line = Some(Arc::new(f));
+        self.on_script_output(move |_, _, line| f(line))
+    }
+
+    /// Called with each line of output from a lifecycle script, along with
+    /// the package and the event the script is for.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn on_script_output<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Package, &str, &str) + Send + Sync + 'static,
+    {
+        self.on_script_output = Some(Arc::new(f));
         self
     }
 
@@ -330,20 +507,76 @@ impl NodeMaintainerOptions {
         Ok(None)
     }
 
+    /// Loads the root package's `patchedDependencies`, along with any
+    /// configured through [`NodeMaintainerOptions::patched_dependency`].
+    #[allow(unused_variables)]
+    async fn load_patches(
+        proj_root: Option<&Path>,
+        configured: &[(String, PathBuf)],
+        root: &CorgiManifest,
+    ) -> Result<PatchedDependencies, NodeMaintainerError> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return PatchedDependencies::load(
+            proj_root.unwrap_or_else(|| Path::new(".")),
+            root.patched_dependencies
+                .iter()
+                .map(|(spec, path)| (spec.clone(), PathBuf::from(path)))
+                .chain(configured.iter().cloned()),
+        )
+        .await;
+        #[cfg(target_arch = "wasm32")]
+        Ok(PatchedDependencies::default())
+    }
+
+    /// Loads the links recorded for the project, along with any configured
+    /// through [`NodeMaintainerOptions::linked_dependency`].
+    #[allow(unused_variables)]
+    async fn load_links(
+        proj_root: Option<&Path>,
+        configured: &[(String, PathBuf)],
+    ) -> Result<LinkedDependencies, NodeMaintainerError> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let proj_root = proj_root.unwrap_or_else(|| Path::new("."));
+            let mut links = LinkedDependencies::load(proj_root).await?;
+            for (name, path) in configured {
+                links.insert(name, proj_root.join(path));
+            }
+            let mut resolved = LinkedDependencies::default();
+            for (name, path) in links.iter() {
+                let path = std::fs::canonicalize(path).map_err(|_| {
+                    NodeMaintainerError::MissingLinkedPackage(name.into(), path.into())
+                })?;
+                resolved.insert(name, path);
+            }
+            Ok(resolved)
+        }
+        #[cfg(target_arch = "wasm32")]
+        Ok(LinkedDependencies::default())
+    }
+
     /// Resolves a [`NodeMaintainer`] using an existing [`CorgiManifest`].
     pub async fn resolve_manifest(
         self,
-        root: CorgiManifest,
+        mut root: CorgiManifest,
     ) -> Result<NodeMaintainer, NodeMaintainerError> {
         let lockfile = self.get_lockfile().await?;
         let nassun = self.nassun.unwrap_or_else(|| self.nassun_opts.build());
+        let lockfile = lockfile.map(|lockfile| lockfile.relative_to_registries(&nassun));
+        let patches =
+            Self::load_patches(self.root.as_deref(), &self.patched_dependencies, &root).await?;
+        let links = Self::load_links(self.root.as_deref(), &self.linked_dependencies).await?;
         let root_pkg = Nassun::dummy_from_manifest(root.clone());
+        let links = links.link_into(&mut root);
         let proj_root = self.root.unwrap_or_else(|| PathBuf::from("."));
         let mut resolver = Resolver {
             nassun,
             graph: Default::default(),
             concurrency: self.concurrency,
             locked: self.locked,
+            verify_signatures: self.verify_signatures,
+            prefer_dedupe: self.prefer_dedupe,
+            patches,
             root: &proj_root,
             actual_tree: None,
             on_resolution_added: self.on_resolution_added,
@@ -356,7 +589,16 @@ impl NodeMaintainerOptions {
             true,
         )?);
         resolver.graph[node].root = node;
-        let (graph, _actual_tree) = resolver.run_resolver(lockfile).await?;
+        resolver.graph.links = links;
+        // Linked packages keep whatever the lockfile had for them before.
+        let previous_lockfile = if resolver.graph.links.is_empty() {
+            None
+        } else {
+            lockfile.clone()
+        };
+        // The resolver's future is big enough to overflow small stacks when
+        // it's kept inline.
+        let (graph, _actual_tree, deduped) = Box::pin(resolver.run_resolver(lockfile)).await?;
         #[cfg(not(target_arch = "wasm32"))]
         let linker_opts = LinkerOptions {
             actual_tree: _actual_tree,
@@ -364,14 +606,21 @@ impl NodeMaintainerOptions {
             script_concurrency: self.script_concurrency,
             cache: self.cache,
             prefer_copy: self.prefer_copy,
+            side_effects_cache: self.side_effects_cache,
+            store: self.store,
+            script_logs: self.script_logs,
+            public_hoist_patterns: self.public_hoist_patterns,
+            hoist_patterns: self.hoist_patterns,
             root: proj_root,
             on_prune_progress: self.on_prune_progress,
             on_extract_progress: self.on_extract_progress,
             on_script_start: self.on_script_start,
-            on_script_line: self.on_script_line,
+            on_script_output: self.on_script_output,
         };
         let nm = NodeMaintainer {
             graph,
+            deduped,
+            previous_lockfile,
             #[cfg(target_arch = "wasm32")]
             linker: Linker::null(),
             #[cfg(not(target_arch = "wasm32"))]
@@ -394,19 +643,28 @@ impl NodeMaintainerOptions {
     ) -> Result<NodeMaintainer, NodeMaintainerError> {
         let lockfile = self.get_lockfile().await?;
         let nassun = self.nassun_opts.build();
+        let lockfile = lockfile.map(|lockfile| lockfile.relative_to_registries(&nassun));
         let root_pkg = nassun.resolve(root_spec).await?;
+        let mut corgi = root_pkg.corgi_metadata().await?.manifest;
+        let patches =
+            Self::load_patches(self.root.as_deref(), &self.patched_dependencies, &corgi).await?;
+        let links = Self::load_links(self.root.as_deref(), &self.linked_dependencies)
+            .await?
+            .link_into(&mut corgi);
         let proj_root = self.root.unwrap_or_else(|| PathBuf::from("."));
         let mut resolver = Resolver {
             nassun,
             graph: Default::default(),
             concurrency: self.concurrency,
             locked: self.locked,
+            verify_signatures: self.verify_signatures,
+            prefer_dedupe: self.prefer_dedupe,
+            patches,
             root: &proj_root,
             actual_tree: None,
             on_resolution_added: self.on_resolution_added,
             on_resolve_progress: self.on_resolve_progress,
         };
-        let corgi = root_pkg.corgi_metadata().await?.manifest;
         let node = resolver.graph.inner.add_node(Node::new(
             UniCase::new("".to_string()),
             root_pkg,
@@ -414,7 +672,16 @@ impl NodeMaintainerOptions {
             true,
         )?);
         resolver.graph[node].root = node;
-        let (graph, _actual_tree) = resolver.run_resolver(lockfile).await?;
+        resolver.graph.links = links;
+        // Linked packages keep whatever the lockfile had for them before.
+        let previous_lockfile = if resolver.graph.links.is_empty() {
+            None
+        } else {
+            lockfile.clone()
+        };
+        // The resolver's future is big enough to overflow small stacks when
+        // it's kept inline.
+        let (graph, _actual_tree, deduped) = Box::pin(resolver.run_resolver(lockfile)).await?;
         #[cfg(not(target_arch = "wasm32"))]
         let linker_opts = LinkerOptions {
             actual_tree: _actual_tree,
@@ -422,14 +689,21 @@ impl NodeMaintainerOptions {
             script_concurrency: self.script_concurrency,
             cache: self.cache,
             prefer_copy: self.prefer_copy,
+            side_effects_cache: self.side_effects_cache,
+            store: self.store,
+            script_logs: self.script_logs,
+            public_hoist_patterns: self.public_hoist_patterns,
+            hoist_patterns: self.hoist_patterns,
             root: proj_root,
             on_prune_progress: self.on_prune_progress,
             on_extract_progress: self.on_extract_progress,
             on_script_start: self.on_script_start,
-            on_script_line: self.on_script_line,
+            on_script_output: self.on_script_output,
         };
         let nm = NodeMaintainer {
             graph,
+            deduped,
+            previous_lockfile,
             #[cfg(target_arch = "wasm32")]
             linker: Linker::null(),
             #[cfg(not(target_arch = "wasm32"))]
@@ -443,6 +717,40 @@ impl NodeMaintainerOptions {
         nm.graph.validate()?;
         Ok(nm)
     }
+
+    /// Loads the dependency tree that's currently installed in the project's
+    /// `node_modules`, as recorded in its `.orogene-meta.kdl`, without
+    /// resolving or fetching anything. The installed tree's layout (hoisted
+    /// or isolated, and which store it uses) overrides the configured one.
+    ///
+    /// Fails if nothing is installed, or if `root` no longer matches what
+    /// is.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn resolve_installed(
+        mut self,
+        root: CorgiManifest,
+    ) -> Result<NodeMaintainer, NodeMaintainerError> {
+        let node_modules = self
+            .root
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("node_modules");
+        let meta = crate::store::read_meta(&node_modules)
+            .ok_or_else(|| NodeMaintainerError::NotInstalled(node_modules.join(META_FILE_NAME)))?;
+        self.hoisted = !crate::store::is_isolated(&meta);
+        self.store = crate::store::meta_store(&meta);
+        self.kdl_lock =
+            Some(Lockfile::from_kdl(meta).map_err(|_| {
+                NodeMaintainerError::NotInstalled(node_modules.join(META_FILE_NAME))
+            })?);
+        self.npm_lock = None;
+        self.locked = true;
+        self.prefer_dedupe = false;
+        self.resolve_manifest(root).await.map_err(|e| match e {
+            NodeMaintainerError::LockfileMismatch => NodeMaintainerError::InstalledTreeOutdated,
+            e => e,
+        })
+    }
 }
 
 impl Default for NodeMaintainerOptions {
@@ -454,10 +762,19 @@ impl Default for NodeMaintainerOptions {
             kdl_lock: None,
             npm_lock: None,
             locked: false,
+            verify_signatures: false,
+            prefer_dedupe: false,
             script_concurrency: DEFAULT_SCRIPT_CONCURRENCY,
             cache: None,
             hoisted: false,
             prefer_copy: false,
+            side_effects_cache: true,
+            store: None,
+            script_logs: None,
+            public_hoist_patterns: Vec::new(),
+            hoist_patterns: Vec::new(),
+            patched_dependencies: Vec::new(),
+            linked_dependencies: Vec::new(),
             validate: false,
             root: None,
             on_resolution_added: None,
@@ -465,7 +782,7 @@ impl Default for NodeMaintainerOptions {
             on_prune_progress: None,
             on_extract_progress: None,
             on_script_start: None,
-            on_script_line: None,
+            on_script_output: None,
         }
     }
 }
@@ -473,6 +790,8 @@ impl Default for NodeMaintainerOptions {
 /// Resolves and manages `node_modules` for a given project.
 pub struct NodeMaintainer {
     pub(crate) graph: Graph,
+    deduped: usize,
+    previous_lockfile: Option<Lockfile>,
     #[allow(dead_code)]
     linker: Linker,
 }
@@ -505,20 +824,49 @@ impl NodeMaintainer {
     #[cfg(not(target_arch = "wasm32"))]
     pub async fn write_lockfile(&self, path: impl AsRef<Path>) -> Result<(), NodeMaintainerError> {
         let path = path.as_ref();
-        fs::write(path, self.graph.to_kdl()?.to_string())
+        fs::write(path, self.to_kdl()?.to_string())
             .await
             .io_context(|| format!("Failed to write lockfile to {}", path.display()))?;
         Ok(())
     }
 
     /// Returns a [`crate::Lockfile`] representation of the current resolved graph.
+    /// Packages linked with [`NodeMaintainerOptions::linked_dependency`]
+    /// are recorded as they were before they were linked.
     pub fn to_lockfile(&self) -> Result<crate::Lockfile, NodeMaintainerError> {
-        self.graph.to_lockfile()
+        self.graph
+            .to_unlinked_lockfile(self.previous_lockfile.as_ref())
     }
 
     /// Returns a [`kdl::KdlDocument`] representation of the current resolved graph.
+    /// Like [`NodeMaintainer::to_lockfile`], this leaves out linked packages.
     pub fn to_kdl(&self) -> Result<kdl::KdlDocument, NodeMaintainerError> {
-        self.graph.to_kdl()
+        Ok(self.to_lockfile()?.to_kdl())
+    }
+
+    /// Generates a software bill of materials for the current resolved
+    /// graph, reading package metadata from the project's `node_modules/`
+    /// or the cache.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn to_sbom(&self) -> Result<crate::Sbom, NodeMaintainerError> {
+        let (root, cache) = match self.linker.opts() {
+            Some(opts) => (opts.root.as_path(), opts.cache.as_deref()),
+            None => (Path::new("."), None),
+        };
+        self.graph.sbom(root, cache, true)
+    }
+
+    /// Like [`NodeMaintainer::to_sbom`], but doesn't need the tree to be
+    /// extracted first, so it can be checked before anything is installed.
+    /// Packages that haven't been cached yet have their licenses looked up
+    /// in the registry.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn to_resolved_sbom(&self) -> Result<crate::Sbom, NodeMaintainerError> {
+        let (root, cache, concurrency) = match self.linker.opts() {
+            Some(opts) => (opts.root.as_path(), opts.cache.as_deref(), opts.concurrency),
+            None => (Path::new("."), None, DEFAULT_CONCURRENCY),
+        };
+        self.graph.resolved_sbom(root, cache, concurrency).await
     }
 
     /// Returns a [`Package`] for the given package spec, if it is present in
@@ -528,11 +876,23 @@ impl NodeMaintainer {
         self.graph.package_at_path(path)
     }
 
+    /// Deprecated packages in the dependency tree, along with the
+    /// dependency chain that pulled each one in.
+    pub fn deprecations(&self) -> Vec<DeprecatedPackage> {
+        self.graph.deprecations()
+    }
+
     /// Number of unique packages in the dependency tree.
     pub fn package_count(&self) -> usize {
         self.graph.inner.node_count()
     }
 
+    /// Number of duplicate packages removed from the dependency tree when
+    /// resolving with [`NodeMaintainerOptions::prefer_dedupe`].
+    pub fn deduped_count(&self) -> usize {
+        self.deduped
+    }
+
     /// Scans the `node_modules` directory and removes any extraneous files or
     /// directories, including previously-installed packages that are no
     /// longer valid.
@@ -550,10 +910,64 @@ impl NodeMaintainer {
         self.linker.extract(&self.graph).await
     }
 
-    /// Runs the `preinstall`, `install`, and `postinstall` lifecycle scripts,
-    /// as well as linking the package bins as needed.
+    /// Runs the `preinstall`, `install`, `postinstall`, and `prepare`
+    /// lifecycle scripts, as well as linking the package bins as needed. The
+    /// root package's own scripts run last.
     #[cfg(not(target_arch = "wasm32"))]
     pub async fn rebuild(&self, ignore_scripts: bool) -> Result<(), NodeMaintainerError> {
         self.linker.rebuild(&self.graph, ignore_scripts).await
     }
+
+    /// Reruns the `preinstall`, `install`, and `postinstall` lifecycle
+    /// scripts of packages that are already installed, in dependency order,
+    /// and relinks all package bins. Nothing gets extracted, and cached
+    /// script side effects are never restored.
+    ///
+    /// `packages` can be names (`foo`) or names and versions (`foo@1.2.3`).
+    /// If it's empty, every package with install scripts is rebuilt.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn rebuild_installed(
+        &self,
+        packages: &[impl AsRef<str>],
+    ) -> Result<(), NodeMaintainerError> {
+        let selected = if packages.is_empty() {
+            None
+        } else {
+            let mut selected = HashSet::new();
+            for package in packages {
+                let package = package.as_ref();
+                let matches = self
+                    .graph
+                    .inner
+                    .node_indices()
+                    .filter(|idx| *idx != self.graph.root)
+                    .filter(|idx| {
+                        let pkg = &self.graph[*idx].package;
+                        pkg.name() == package
+                            || matches!(
+                                pkg.resolved(),
+                                PackageResolution::Npm { version, .. }
+                                    if format!("{}@{version}", pkg.name()) == package
+                            )
+                    })
+                    .collect::<Vec<_>>();
+                if matches.is_empty() {
+                    return Err(NodeMaintainerError::PackageNotInstalled(package.into()));
+                }
+                selected.extend(matches);
+            }
+            Some(selected)
+        };
+        self.linker
+            .rebuild_installed(&self.graph, selected.as_ref())
+            .await
+    }
+
+    /// Lifecycle scripts that failed during [`NodeMaintainer::rebuild`].
+    /// Optional dependencies' scripts are allowed to fail without failing
+    /// the rebuild, so this can have entries even if it succeeded.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn script_failures(&self) -> Vec<ScriptFailure> {
+        self.linker.script_failures().await
+    }
 }
@@ -0,0 +1,535 @@
+//! Support for `patchedDependencies`: parsing unified diffs, applying them to
+//! extracted packages, and generating them from an edited copy of a package.
+
+use std::collections::HashSet;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use nassun::PackageResolution;
+use node_semver::Version;
+use ssri::Integrity;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::IoContext;
+use crate::error::NodeMaintainerError;
+use crate::graph::Graph;
+
+/// A parsed patch file, along with the hash that gets recorded in the
+/// lockfile for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PackagePatch {
+    pub(crate) path: PathBuf,
+    pub(crate) integrity: Integrity,
+    files: Vec<FilePatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FilePatch {
+    /// Path of the file before the patch. `None` if the patch creates it.
+    old: Option<String>,
+    /// Path of the file after the patch. `None` if the patch deletes it.
+    new: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hunk {
+    old_start: usize,
+    old_len: usize,
+    /// Lines are kept with their line endings, so files without a trailing
+    /// newline round-trip.
+    lines: Vec<HunkLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+impl PackagePatch {
+    /// Parses a unified diff, like the ones `git diff` and `oro
+    /// patch-commit` produce. File paths have their first component (`a/`
+    /// or `b/`) stripped, and are relative to the package's directory.
+    pub(crate) fn parse(path: &Path, text: &str) -> Result<Self, NodeMaintainerError> {
+        let err = |line: usize, reason: &str| {
+            NodeMaintainerError::PatchParseError(path.into(), line + 1, reason.into())
+        };
+        let lines = text.split_inclusive('\n').collect::<Vec<_>>();
+        let mut files: Vec<FilePatch> = Vec::new();
+        let mut current: Option<FilePatch> = None;
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            if let Some(header) = line.strip_prefix("diff --git ") {
+                files.extend(current.take());
+                let (old, new) = split_git_header(header.trim_end())
+                    .ok_or_else(|| err(i, "invalid `diff --git` header"))?;
+                current = Some(FilePatch {
+                    old: Some(strip_component(old).ok_or_else(|| err(i, "invalid file path"))?),
+                    new: Some(strip_component(new).ok_or_else(|| err(i, "invalid file path"))?),
+                    hunks: Vec::new(),
+                });
+            } else if line.starts_with("new file mode") {
+                if let Some(file) = current.as_mut() {
+                    file.old = None;
+                }
+            } else if line.starts_with("deleted file mode") {
+                if let Some(file) = current.as_mut() {
+                    file.new = None;
+                }
+            } else if line.starts_with("--- ")
+                && lines.get(i + 1).map_or(false, |l| l.starts_with("+++ "))
+            {
+                let old = header_path(&line[4..]).map_err(|reason| err(i, reason))?;
+                let new = header_path(&lines[i + 1][4..]).map_err(|reason| err(i + 1, reason))?;
+                if current.as_ref().map_or(true, |file| !file.hunks.is_empty()) {
+                    files.extend(current.take());
+                }
+                current = Some(FilePatch {
+                    old,
+                    new,
+                    hunks: Vec::new(),
+                });
+                i += 1;
+            } else if line.starts_with("@@ ") {
+                let file = current
+                    .as_mut()
+                    .ok_or_else(|| err(i, "hunk found before any file header"))?;
+                let (old_start, old_len, new_len) =
+                    parse_hunk_header(line).ok_or_else(|| err(i, "invalid hunk header"))?;
+                let mut hunk = Hunk {
+                    old_start,
+                    old_len,
+                    lines: Vec::new(),
+                };
+                let (mut old_seen, mut new_seen) = (0, 0);
+                while old_seen < old_len || new_seen < new_len || next_is_marker(&lines, i) {
+                    i += 1;
+                    let line = *lines
+                        .get(i)
+                        .ok_or_else(|| err(i, "unexpected end of patch inside a hunk"))?;
+                    let content = line.get(1..).unwrap_or_default();
+                    match line.as_bytes()[0] {
+                        b' ' => {
+                            old_seen += 1;
+                            new_seen += 1;
+                            hunk.lines.push(HunkLine::Context(content.into()));
+                        }
+                        // Some editors strip the trailing space off of empty
+                        // context lines.
+                        b'\n' | b'\r' => {
+                            old_seen += 1;
+                            new_seen += 1;
+                            hunk.lines.push(HunkLine::Context(line.into()));
+                        }
+                        b'-' => {
+                            old_seen += 1;
+                            hunk.lines.push(HunkLine::Remove(content.into()));
+                        }
+                        b'+' => {
+                            new_seen += 1;
+                            hunk.lines.push(HunkLine::Add(content.into()));
+                        }
+                        b'\\' => match hunk.lines.last_mut() {
+                            Some(
+                                HunkLine::Context(prev)
+                                | HunkLine::Remove(prev)
+                                | HunkLine::Add(prev),
+                            ) => {
+                                if prev.ends_with('\n') {
+                                    prev.pop();
+                                }
+                            }
+                            None => return Err(err(i, "no-newline marker without a line")),
+                        },
+                        _ => return Err(err(i, "invalid line inside a hunk")),
+                    }
+                    if old_seen > old_len || new_seen > new_len {
+                        return Err(err(i, "hunk is longer than its header says"));
+                    }
+                }
+                file.hunks.push(hunk);
+            }
+            i += 1;
+        }
+        files.extend(current);
+        Ok(Self {
+            path: path.into(),
+            integrity: Integrity::from(text),
+            files,
+        })
+    }
+
+    /// Applies this patch to the package extracted at `dir`. Files are
+    /// replaced rather than written in place, so packages hard linked from
+    /// the cache don't end up patching the cache itself.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn apply(&self, dir: &Path) -> Result<(), String> {
+        for file in &self.files {
+            match (&file.old, &file.new) {
+                (Some(old), None) => {
+                    let target = dir.join(old);
+                    std::fs::remove_file(&target)
+                        .map_err(|e| format!("failed to delete {old}: {e}"))?;
+                }
+                (None, Some(new)) => {
+                    let target = dir.join(new);
+                    if target.exists() {
+                        return Err(format!("{new} already exists"));
+                    }
+                    let contents =
+                        apply_hunks("", &file.hunks).map_err(|e| format!("{new}: {e}"))?;
+                    write_file(&target, &contents, None)?;
+                }
+                (Some(old), Some(new)) => {
+                    let source = dir.join(old);
+                    let original = std::fs::read_to_string(&source)
+                        .map_err(|e| format!("failed to read {old}: {e}"))?;
+                    let permissions = std::fs::metadata(&source)
+                        .map(|meta| meta.permissions())
+                        .ok();
+                    let contents =
+                        apply_hunks(&original, &file.hunks).map_err(|e| format!("{old}: {e}"))?;
+                    std::fs::remove_file(&source)
+                        .map_err(|e| format!("failed to replace {old}: {e}"))?;
+                    write_file(&dir.join(new), &contents, permissions)?;
+                }
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `patchedDependencies` configured for a project, loaded and parsed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PatchedDependencies {
+    entries: Vec<PatchEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct PatchEntry {
+    spec: String,
+    name: String,
+    version: Option<Version>,
+    patch: Arc<PackagePatch>,
+}
+
+impl PatchedDependencies {
+    /// Reads and parses every patch file. `specs` are `name` or
+    /// `name@version` keys, and patch paths are relative to `root`. Later
+    /// entries for the same key win.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn load(
+        root: &Path,
+        specs: impl IntoIterator<Item = (String, PathBuf)>,
+    ) -> Result<Self, NodeMaintainerError> {
+        let mut deduped: Vec<(String, PathBuf)> = Vec::new();
+        for (spec, path) in specs {
+            deduped.retain(|(existing, _)| existing != &spec);
+            deduped.push((spec, path));
+        }
+        let mut entries = Vec::with_capacity(deduped.len());
+        for (spec, path) in deduped {
+            let (name, version) = match spec.rfind('@') {
+                Some(at) if at > 0 => (
+                    spec[..at].to_string(),
+                    Some(spec[at + 1..].parse().map_err(|_| {
+                        NodeMaintainerError::InvalidPatchedDependency(spec.clone())
+                    })?),
+                ),
+                _ => (spec.clone(), None),
+            };
+            let full_path = root.join(&path);
+            let text = async_std::fs::read_to_string(&full_path)
+                .await
+                .io_context(|| format!("Failed to read patch file at {}.", full_path.display()))?;
+            entries.push(PatchEntry {
+                spec,
+                name,
+                version,
+                patch: Arc::new(PackagePatch::parse(&path, &text)?),
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Attaches the matching patch, if any, to every package in the graph.
+    /// Entries with a version take precedence over ones with just a name.
+    pub(crate) fn assign(&self, graph: &mut Graph) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let mut used = HashSet::new();
+        let indices = graph.inner.node_indices().collect::<Vec<_>>();
+        for idx in indices {
+            // Linked packages are never extracted, so there's nothing to
+            // patch.
+            if idx == graph.root || graph.is_link(idx) {
+                continue;
+            }
+            let package = &graph[idx].package;
+            let (name, version) = match package.resolved() {
+                PackageResolution::Npm { name, version, .. } => (name.as_str(), Some(version)),
+                _ => (package.name(), None),
+            };
+            let entry = self
+                .entries
+                .iter()
+                .find(|e| e.name == name && e.version.is_some() && e.version.as_ref() == version)
+                .or_else(|| {
+                    self.entries
+                        .iter()
+                        .find(|e| e.name == name && e.version.is_none())
+                });
+            if let Some(entry) = entry {
+                used.insert(&entry.spec);
+            }
+            graph[idx].patch = entry.map(|e| e.patch.clone());
+        }
+        for entry in &self.entries {
+            if !used.contains(&entry.spec) {
+                tracing::warn!(
+                    "Patch {} for `{}` doesn't match any package in the dependency tree.",
+                    entry.patch.path.display(),
+                    entry.spec
+                );
+            }
+        }
+    }
+}
+
+/// Applies the patch file at `patch_path` to the package extracted at `dir`,
+/// the same way installs apply `patchedDependencies`. `package` is only used
+/// to describe the package in errors.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn apply_patch_file(
+    patch_path: &Path,
+    dir: &Path,
+    package: &str,
+) -> Result<(), NodeMaintainerError> {
+    let text = async_std::fs::read_to_string(patch_path)
+        .await
+        .io_context(|| format!("Failed to read patch file at {}.", patch_path.display()))?;
+    let patch = PackagePatch::parse(patch_path, &text)?;
+    let dir = dir.to_path_buf();
+    async_std::task::spawn_blocking(move || patch.apply(&dir))
+        .await
+        .map_err(|reason| NodeMaintainerError::PatchApplyError {
+            package: package.into(),
+            patch: patch_path.into(),
+            reason,
+        })
+}
+
+/// Generates a unified diff that turns the package at `original` into the
+/// one at `edited`. `node_modules` directories are skipped, and only text
+/// files can be diffed, though binary files are fine as long as they're left
+/// alone. Returns an empty string if nothing changed.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn create_patch(original: &Path, edited: &Path) -> Result<String, NodeMaintainerError> {
+    let original_files = package_files(original)?;
+    let edited_files = package_files(edited)?;
+    let paths = original_files
+        .keys()
+        .chain(edited_files.keys())
+        .collect::<BTreeSet<_>>();
+    let mut patch = String::new();
+    for path in paths {
+        let old = original_files.get(path).map(|f| read_file(f)).transpose()?;
+        let new = edited_files.get(path).map(|f| read_file(f)).transpose()?;
+        if old == new {
+            continue;
+        }
+        // Only files that actually changed have to be text.
+        let old = old
+            .map(|bytes| into_text(bytes, &original_files[path]))
+            .transpose()?;
+        let new = new
+            .map(|bytes| into_text(bytes, &edited_files[path]))
+            .transpose()?;
+        patch.push_str(&format!("diff --git a/{path} b/{path}\n"));
+        match (&old, &new) {
+            (None, Some(_)) => patch.push_str("new file mode 100644\n"),
+            (Some(_), None) => patch.push_str("deleted file mode 100644\n"),
+            _ => {}
+        }
+        let old_header = old
+            .as_ref()
+            .map(|_| format!("a/{path}"))
+            .unwrap_or_else(|| "/dev/null".into());
+        let new_header = new
+            .as_ref()
+            .map(|_| format!("b/{path}"))
+            .unwrap_or_else(|| "/dev/null".into());
+        patch.push_str(
+            &similar::TextDiff::from_lines(
+                old.as_deref().unwrap_or_default(),
+                new.as_deref().unwrap_or_default(),
+            )
+            .unified_diff()
+            .context_radius(3)
+            .header(&old_header, &new_header)
+            .to_string(),
+        );
+    }
+    Ok(patch)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn package_files(dir: &Path) -> Result<HashMap<String, PathBuf>, NodeMaintainerError> {
+    let mut files = HashMap::new();
+    for entry in walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || entry.file_name() != "node_modules")
+    {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let relative = entry
+                .path()
+                .strip_prefix(dir)
+                .expect("walkdir entries are under the directory being walked");
+            files.insert(
+                relative.to_string_lossy().replace('\\', "/"),
+                entry.path().to_path_buf(),
+            );
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_file(path: &Path) -> Result<Vec<u8>, NodeMaintainerError> {
+    std::fs::read(path)
+        .io_context(|| format!("Failed to read {} while creating patch.", path.display()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn into_text(bytes: Vec<u8>, path: &Path) -> Result<String, NodeMaintainerError> {
+    String::from_utf8(bytes).map_err(|_| NodeMaintainerError::BinaryPatchFile(path.into()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_file(
+    path: &Path,
+    contents: &str,
+    permissions: Option<std::fs::Permissions>,
+) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
+    }
+    std::fs::write(path, contents)
+        .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+    if let Some(permissions) = permissions {
+        std::fs::set_permissions(path, permissions)
+            .map_err(|e| format!("failed to set permissions on {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Applies `hunks` to `original`. Hunks are tried at the line their header
+/// says first, then at increasing distances from it, in case lines were
+/// added or removed above them.
+#[cfg(not(target_arch = "wasm32"))]
+fn apply_hunks(original: &str, hunks: &[Hunk]) -> Result<String, String> {
+    let lines = original.split_inclusive('\n').collect::<Vec<_>>();
+    let mut out = String::with_capacity(original.len());
+    let mut pos = 0;
+    for (n, hunk) in hunks.iter().enumerate() {
+        let old = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                HunkLine::Context(l) | HunkLine::Remove(l) => Some(l.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect::<Vec<_>>();
+        let expected = if hunk.old_len == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start.saturating_sub(1)
+        };
+        let fits = |at: usize| {
+            at >= pos && at + old.len() <= lines.len() && lines[at..at + old.len()] == old[..]
+        };
+        let at = (0..=lines.len())
+            .flat_map(|offset| [expected.checked_add(offset), expected.checked_sub(offset)])
+            .flatten()
+            .find(|at| fits(*at))
+            .ok_or_else(|| format!("hunk #{} doesn't apply", n + 1))?;
+        out.extend(lines[pos..at].iter().copied());
+        for line in &hunk.lines {
+            match line {
+                HunkLine::Context(l) | HunkLine::Add(l) => out.push_str(l),
+                HunkLine::Remove(_) => {}
+            }
+        }
+        pos = at + old.len();
+    }
+    out.extend(lines[pos..].iter().copied());
+    Ok(out)
+}
+
+fn next_is_marker(lines: &[&str], i: usize) -> bool {
+    lines.get(i + 1).map_or(false, |l| l.starts_with('\\'))
+}
+
+/// Parses `@@ -1,2 +1,3 @@`, returning the old start, old length, and new
+/// length. Lengths of `1` can be left out.
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let mut parts = line.strip_prefix("@@ ")?.split(' ');
+    let range = |part: Option<&str>, prefix: char| -> Option<(usize, usize)> {
+        let part = part?.strip_prefix(prefix)?;
+        match part.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((part.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = range(parts.next(), '-')?;
+    let (_, new_len) = range(parts.next(), '+')?;
+    Some((old_start, old_len, new_len))
+}
+
+/// Splits `a/foo b/foo` into its two paths.
+fn split_git_header(header: &str) -> Option<(&str, &str)> {
+    // Paths can contain spaces, but both halves are the same for anything
+    // that isn't a rename, so try splitting down the middle first.
+    let mid = header.len() / 2;
+    if header.len() % 2 == 1 && header.as_bytes()[mid] == b' ' {
+        let (old, new) = (&header[..mid], &header[mid + 1..]);
+        if old.get(2..) == new.get(2..) {
+            return Some((old, new));
+        }
+    }
+    let at = header.rfind(" b/")?;
+    Some((&header[..at], &header[at + 1..]))
+}
+
+fn header_path(header: &str) -> Result<Option<String>, &'static str> {
+    let path = header.split('\t').next().unwrap_or_default().trim_end();
+    if path == "/dev/null" {
+        Ok(None)
+    } else {
+        strip_component(path).map(Some).ok_or("invalid file path")
+    }
+}
+
+/// Strips the leading `a/` or `b/` from a path in a patch, and makes sure
+/// what's left can't point outside of the package.
+fn strip_component(path: &str) -> Option<String> {
+    let (_, rest) = path.split_once('/')?;
+    let relative = Path::new(rest);
+    if rest.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(rest.into())
+}
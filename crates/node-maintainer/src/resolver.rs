@@ -5,13 +5,11 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use async_std::sync::Mutex;
-#[cfg(not(target_arch = "wasm32"))]
-use colored::Colorize;
 use futures::{StreamExt, TryFutureExt};
 use indexmap::IndexMap;
 use nassun::client::Nassun;
 use nassun::package::Package;
-use nassun::PackageSpec;
+use nassun::{PackageResolution, PackageSpec, SignatureStatus};
 use oro_common::{CorgiManifest, CorgiVersionMetadata};
 use petgraph::stable_graph::NodeIndex;
 use petgraph::visit::EdgeRef;
@@ -23,6 +21,7 @@ use crate::error::IoContext;
 use crate::error::NodeMaintainerError;
 use crate::graph::{DepType, Edge, Graph, Node};
 use crate::maintainer::{ProgressAdded, ProgressHandler};
+use crate::patches::PatchedDependencies;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::META_FILE_NAME;
 use crate::{Lockfile, LockfileNode};
@@ -41,6 +40,9 @@ pub(crate) struct Resolver<'a> {
     pub(crate) graph: Graph,
     pub(crate) concurrency: usize,
     pub(crate) locked: bool,
+    pub(crate) verify_signatures: bool,
+    pub(crate) prefer_dedupe: bool,
+    pub(crate) patches: PatchedDependencies,
     #[allow(dead_code)]
     pub(crate) root: &'a Path,
     pub(crate) actual_tree: Option<Lockfile>,
@@ -52,7 +54,7 @@ impl<'a> Resolver<'a> {
     pub(crate) async fn run_resolver(
         mut self,
         lockfile: Option<Lockfile>,
-    ) -> Result<(Graph, Option<Lockfile>), NodeMaintainerError> {
+    ) -> Result<(Graph, Option<Lockfile>, usize), NodeMaintainerError> {
         #[cfg(not(target_arch = "wasm32"))]
         let start = std::time::Instant::now();
 
@@ -156,6 +158,29 @@ impl<'a> Resolver<'a> {
                                 .await?
                             {
                                 let target_path = lockfile_node.path.clone();
+                                // The lockfile can't vouch for itself, so
+                                // anything it recorded only gets carried
+                                // over when we're not verifying.
+                                let (signature_keyid, deprecated) = if self.verify_signatures {
+                                    let keyid = self.verify_signature(&package).await?;
+                                    // Verifying needed the packument, so
+                                    // pick up any deprecations since the
+                                    // lockfile was written while we're at it.
+                                    let deprecated = if matches!(
+                                        package.resolved(),
+                                        PackageResolution::Npm { .. }
+                                    ) {
+                                        package.corgi_metadata().await?.deprecated
+                                    } else {
+                                        lockfile_node.deprecated.clone()
+                                    };
+                                    (keyid, deprecated)
+                                } else {
+                                    (
+                                        lockfile_node.signature_keyid.clone(),
+                                        lockfile_node.deprecated.clone(),
+                                    )
+                                };
 
                                 let child_idx = Self::place_child(
                                     &mut self.graph,
@@ -164,6 +189,8 @@ impl<'a> Resolver<'a> {
                                     lockfile_node.into(),
                                     Some(target_path),
                                 )?;
+                                self.graph[child_idx].signature_keyid = signature_keyid;
+                                self.graph[child_idx].deprecated = deprecated;
                                 q.push_back(child_idx);
 
                                 if let Some(handler) = &self.on_resolve_progress {
@@ -198,25 +225,11 @@ impl<'a> Resolver<'a> {
 
                         let CorgiVersionMetadata {
                             manifest,
-                            #[cfg(not(target_arch = "wasm32"))]
                             deprecated,
                             ..
                         } = &package.corgi_metadata().await?;
 
-                        #[cfg(not(target_arch = "wasm32"))]
-                        if let Some(deprecated) = deprecated {
-                            tracing::warn!(
-                                "{} {}@{}: {}",
-                                "deprecated".on_magenta(),
-                                manifest.name.as_ref().unwrap(),
-                                manifest
-                                    .version
-                                    .as_ref()
-                                    .map(|v| v.to_string())
-                                    .unwrap_or_else(|| "unknown".into()),
-                                deprecated
-                            );
-                        }
+                        let signature_keyid = self.verify_signature(&package).await?;
 
                         for dep in deps {
                             if let Some(_child_idx) =
@@ -235,6 +248,8 @@ impl<'a> Resolver<'a> {
                                 manifest.clone(),
                                 None,
                             )?;
+                            self.graph[child_idx].signature_keyid = signature_keyid.clone();
+                            self.graph[child_idx].deprecated = deprecated.clone();
 
                             q.push_back(child_idx);
 
@@ -258,9 +273,30 @@ impl<'a> Resolver<'a> {
             }
         }
 
+        let deduped = if self.prefer_dedupe {
+            self.graph.dedupe()?
+        } else {
+            0
+        };
+
+        self.patches.assign(&mut self.graph);
+
         if self.locked {
             if let Some(lockfile) = lockfile {
-                if lockfile != self.graph.to_lockfile()? {
+                let matches = |mut resolved: Lockfile| {
+                    // KDL lockfiles don't record the root package's own name
+                    // or location, so those can't count as changes.
+                    resolved.root.name = lockfile.root.name.clone();
+                    resolved.root.resolved = lockfile.root.resolved.clone();
+                    lockfile == resolved
+                };
+                // Linked packages never make it into `package-lock.kdl`, but
+                // they're in the installed tree's meta file, so either
+                // version of the tree can match.
+                if !matches(self.graph.to_lockfile()?)
+                    && (self.graph.links.is_empty()
+                        || !matches(self.graph.to_unlinked_lockfile(Some(&lockfile))?))
+                {
                     return Err(NodeMaintainerError::LockfileMismatch);
                 }
             }
@@ -268,11 +304,31 @@ impl<'a> Resolver<'a> {
 
         #[cfg(not(target_arch = "wasm32"))]
         tracing::debug!(
-            "Resolved graph of {} nodes in {}ms",
+            "Resolved graph of {} nodes in {}ms ({deduped} removed as duplicates)",
             self.graph.inner.node_count(),
             start.elapsed().as_millis()
         );
-        Ok((self.graph, self.actual_tree))
+        Ok((self.graph, self.actual_tree, deduped))
+    }
+
+    /// Checks a package's registry signatures, if signature verification is
+    /// enabled, returning the ID of the key it was signed with. Invalid
+    /// signatures are errors, but unsigned packages only get a warning.
+    async fn verify_signature(
+        &self,
+        package: &Package,
+    ) -> Result<Option<String>, NodeMaintainerError> {
+        if !self.verify_signatures || !matches!(package.resolved(), PackageResolution::Npm { .. }) {
+            return Ok(None);
+        }
+        match package.verify_signature().await? {
+            SignatureStatus::Verified { keyid } => Ok(Some(keyid)),
+            SignatureStatus::Missing => {
+                tracing::warn!("{} has no registry signatures.", package.resolved());
+                Ok(None)
+            }
+            SignatureStatus::Unsupported => Ok(None),
+        }
     }
 
     fn satisfy_dependency(
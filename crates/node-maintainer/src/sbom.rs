@@ -0,0 +1,737 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::{DateTime, SecondsFormat, Utc};
+#[cfg(not(target_arch = "wasm32"))]
+use futures::{StreamExt, TryStreamExt};
+use nassun::PackageResolution;
+use oro_common::Person;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use ssri::{Algorithm, Integrity, IntegrityOpts};
+use unicase::UniCase;
+use url::Url;
+
+use crate::graph::Graph;
+use crate::{LicenseExpr, Lockfile, LockfileNode, NodeMaintainerError};
+
+const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org/";
+const ROOT_REF: &str = ".";
+
+/// Options for generating an [`Sbom`] from a [`Lockfile`].
+#[derive(Debug, Clone)]
+pub struct SbomOptions {
+    root: PathBuf,
+    cache: Option<PathBuf>,
+    registries: HashMap<Option<String>, Url>,
+}
+
+impl Default for SbomOptions {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("."),
+            cache: None,
+            registries: HashMap::from([(
+                None,
+                DEFAULT_REGISTRY.parse().expect("valid default registry"),
+            )]),
+        }
+    }
+}
+
+impl SbomOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Project root. Package metadata is read from `node_modules/` under
+    /// this directory.
+    pub fn root(mut self, root: impl AsRef<Path>) -> Self {
+        self.root = PathBuf::from(root.as_ref());
+        self
+    }
+
+    /// Cache directory to read package metadata from, for packages that
+    /// haven't been extracted into `node_modules/`.
+    pub fn cache(mut self, cache: impl AsRef<Path>) -> Self {
+        self.cache = Some(PathBuf::from(cache.as_ref()));
+        self
+    }
+
+    /// Registry that registry-relative lockfile URLs resolve against.
+    pub fn registry(mut self, registry: Url) -> Self {
+        self.registries.insert(None, registry);
+        self
+    }
+
+    /// Registry that registry-relative lockfile URLs for a specific scope
+    /// resolve against.
+    pub fn scope_registry(mut self, scope: impl AsRef<str>, registry: Url) -> Self {
+        let scope = scope.as_ref();
+        self.registries.insert(
+            Some(scope.strip_prefix('@').unwrap_or(scope).to_string()),
+            registry,
+        );
+        self
+    }
+
+    fn download_url(&self, node: &LockfileNode) -> Option<Url> {
+        let resolved = node.resolved.as_deref()?;
+        if resolved.contains("://") {
+            return resolved.parse().ok();
+        }
+        if node.version.is_none() || !resolved.contains("/-/") {
+            return None;
+        }
+        let scope = node
+            .name
+            .strip_prefix('@')
+            .and_then(|name| name.split_once('/'))
+            .map(|(scope, _)| scope.to_string());
+        self.registries
+            .get(&scope)
+            .or_else(|| self.registries.get(&None))?
+            .join(resolved)
+            .ok()
+    }
+}
+
+/// A software bill of materials for a dependency tree, which can be
+/// rendered as CycloneDX or SPDX JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sbom {
+    /// The project itself.
+    pub root: SbomComponent,
+    /// Every package in the dependency tree, sorted by `node_modules` path.
+    pub components: Vec<SbomComponent>,
+    /// When this SBOM was generated.
+    pub created: DateTime<Utc>,
+}
+
+/// A single package in an [`Sbom`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SbomComponent {
+    /// Unique reference to this package within the SBOM: its `node_modules`
+    /// path, or `"."` for the project root.
+    pub bom_ref: String,
+    pub name: String,
+    pub version: Option<String>,
+    /// Package URL, for packages that come from a registry.
+    pub purl: Option<String>,
+    /// Where the package was downloaded from, if known.
+    pub download_url: Option<Url>,
+    pub integrity: Option<Integrity>,
+    /// License, as declared in the package's manifest.
+    pub license: Option<String>,
+    pub author: Option<Person>,
+    /// Source repository URL, as declared in the package's manifest.
+    pub repository: Option<String>,
+    /// `bom_ref`s of the packages this one depends on.
+    pub dependencies: Vec<String>,
+}
+
+impl Sbom {
+    fn new(root: SbomComponent, mut components: Vec<SbomComponent>) -> Self {
+        components.sort_by(|a, b| a.bom_ref.cmp(&b.bom_ref));
+        Self {
+            root,
+            components,
+            created: Utc::now(),
+        }
+    }
+
+    fn all_components(&self) -> impl Iterator<Item = &SbomComponent> {
+        std::iter::once(&self.root).chain(self.components.iter())
+    }
+
+    fn timestamp(&self) -> String {
+        self.created.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
+
+    /// Renders this SBOM as a CycloneDX 1.5 JSON document.
+    pub fn to_cyclonedx(&self) -> Value {
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "version": 1,
+            "metadata": {
+                "timestamp": self.timestamp(),
+                "tools": {
+                    "components": [{
+                        "type": "application",
+                        "name": "orogene",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+                "component": self.root.to_cyclonedx("application"),
+            },
+            "components": self
+                .components
+                .iter()
+                .map(|component| component.to_cyclonedx("library"))
+                .collect::<Vec<_>>(),
+            "dependencies": self
+                .all_components()
+                .map(|component| json!({
+                    "ref": component.bom_ref,
+                    "dependsOn": component.dependencies,
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Renders this SBOM as an SPDX 2.3 JSON document.
+    pub fn to_spdx(&self) -> Value {
+        let spdx_ids = self
+            .all_components()
+            .enumerate()
+            .map(|(idx, component)| (component.bom_ref.as_str(), format!("SPDXRef-Package-{idx}")))
+            .collect::<HashMap<_, _>>();
+        let root_id = &spdx_ids[self.root.bom_ref.as_str()];
+        let mut relationships = vec![json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": root_id,
+        })];
+        for component in self.all_components() {
+            for dep in &component.dependencies {
+                if let Some(dep_id) = spdx_ids.get(dep.as_str()) {
+                    relationships.push(json!({
+                        "spdxElementId": spdx_ids[component.bom_ref.as_str()],
+                        "relationshipType": "DEPENDS_ON",
+                        "relatedSpdxElement": dep_id,
+                    }));
+                }
+            }
+        }
+
+        let name = match &self.root.version {
+            Some(version) => format!("{}@{version}", self.root.name),
+            None => self.root.name.clone(),
+        };
+        // Namespaces need to be unique per document, so hash everything that
+        // went into this one.
+        let mut hasher = IntegrityOpts::new()
+            .algorithm(Algorithm::Sha256)
+            .chain(self.timestamp());
+        for component in self.all_components() {
+            hasher = hasher.chain(&component.bom_ref);
+            if let Some(version) = &component.version {
+                hasher = hasher.chain(version);
+            }
+        }
+        let (_, digest) = hasher.result().to_hex();
+
+        json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": name,
+            "documentNamespace": format!(
+                "https://orogene.dev/spdx/{}-{digest}",
+                name.replace(['@', '/'], "-").trim_start_matches('-')
+            ),
+            "creationInfo": {
+                "created": self.timestamp(),
+                "creators": [format!("Tool: orogene-{}", env!("CARGO_PKG_VERSION"))],
+            },
+            "packages": self
+                .all_components()
+                .map(|component| component.to_spdx(&spdx_ids[component.bom_ref.as_str()]))
+                .collect::<Vec<_>>(),
+            "relationships": relationships,
+        })
+    }
+}
+
+impl SbomComponent {
+    fn new(
+        bom_ref: String,
+        node: &LockfileNode,
+        download_url: Option<Url>,
+        dir: Option<&Path>,
+        cache: Option<&Path>,
+    ) -> Self {
+        let manifest = ManifestMetadata::read(node, dir, cache);
+        let name = if node.name.is_empty() {
+            manifest.name.clone()
+        } else {
+            Some(node.name.to_string())
+        };
+        let version = node
+            .version
+            .as_ref()
+            .map(|version| version.to_string())
+            .or_else(|| manifest.version.clone());
+        // node_modules directory names can be aliases, so prefer the name
+        // the package was published under.
+        let purl = node.version.as_ref().map(|version| {
+            let name = manifest.name.as_deref().unwrap_or(&node.name);
+            format!(
+                "pkg:npm/{}@{}",
+                match name.strip_prefix('@') {
+                    Some(scoped) => format!("%40{scoped}"),
+                    None => name.to_string(),
+                },
+                version.to_string().replace('+', "%2B")
+            )
+        });
+        Self {
+            bom_ref,
+            name: name.unwrap_or_default(),
+            version,
+            purl,
+            download_url,
+            integrity: node.integrity.clone(),
+            license: manifest.license(),
+            author: manifest.author(),
+            repository: manifest.repository(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn to_cyclonedx(&self, component_type: &str) -> Value {
+        let mut component = json!({
+            "type": component_type,
+            "bom-ref": self.bom_ref,
+        });
+        let obj = component.as_object_mut().expect("this is an object");
+        match self
+            .name
+            .strip_prefix('@')
+            .and_then(|name| name.split_once('/'))
+        {
+            Some((scope, name)) => {
+                obj.insert("group".into(), format!("@{scope}").into());
+                obj.insert("name".into(), name.into());
+            }
+            None => {
+                obj.insert("name".into(), self.name.clone().into());
+            }
+        }
+        if let Some(version) = &self.version {
+            obj.insert("version".into(), version.clone().into());
+        }
+        if let Some(author) = &self.author {
+            obj.insert("author".into(), fmt_person(author, "<", ">").into());
+        }
+        if let Some(license) = &self.license {
+            obj.insert(
+                "licenses".into(),
+                if LicenseExpr::parse(license).is_some() {
+                    json!([{ "expression": license }])
+                } else {
+                    json!([{ "license": { "name": license } }])
+                },
+            );
+        }
+        if let Some(purl) = &self.purl {
+            obj.insert("purl".into(), purl.clone().into());
+        }
+        let hashes = self
+            .hex_hashes()
+            .filter_map(|(algorithm, digest)| {
+                Some(json!({
+                    "alg": cyclonedx_algorithm(algorithm)?,
+                    "content": digest,
+                }))
+            })
+            .collect::<Vec<_>>();
+        if !hashes.is_empty() {
+            obj.insert("hashes".into(), hashes.into());
+        }
+        let mut references = Vec::new();
+        if let Some(repository) = &self.repository {
+            references.push(json!({ "type": "vcs", "url": repository }));
+        }
+        if let Some(url) = &self.download_url {
+            references.push(json!({ "type": "distribution", "url": url }));
+        }
+        if !references.is_empty() {
+            obj.insert("externalReferences".into(), references.into());
+        }
+        component
+    }
+
+    fn to_spdx(&self, spdx_id: &str) -> Value {
+        let mut package = json!({
+            "SPDXID": spdx_id,
+            "name": self.name,
+            "downloadLocation": self
+                .download_url
+                .as_ref()
+                .map(|url| url.to_string())
+                .unwrap_or_else(|| "NOASSERTION".into()),
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": self
+                .license
+                .as_ref()
+                .filter(|license| LicenseExpr::parse(license).is_some())
+                .cloned()
+                .unwrap_or_else(|| "NOASSERTION".into()),
+            "copyrightText": "NOASSERTION",
+        });
+        let obj = package.as_object_mut().expect("this is an object");
+        if let Some(version) = &self.version {
+            obj.insert("versionInfo".into(), version.clone().into());
+        }
+        if let Some(author) = &self.author {
+            obj.insert(
+                "originator".into(),
+                format!("Person: {}", fmt_person(author, "(", ")")).into(),
+            );
+        }
+        if let Some(repository) = &self.repository {
+            obj.insert("sourceInfo".into(), format!("from {repository}").into());
+        }
+        let checksums = self
+            .hex_hashes()
+            .filter_map(|(algorithm, digest)| {
+                Some(json!({
+                    "algorithm": spdx_algorithm(algorithm)?,
+                    "checksumValue": digest,
+                }))
+            })
+            .collect::<Vec<_>>();
+        if !checksums.is_empty() {
+            obj.insert("checksums".into(), checksums.into());
+        }
+        if let Some(purl) = &self.purl {
+            obj.insert(
+                "externalRefs".into(),
+                json!([{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": purl,
+                }]),
+            );
+        }
+        package
+    }
+
+    /// Every hash in this package's integrity, hex-encoded.
+    fn hex_hashes(&self) -> impl Iterator<Item = (Algorithm, String)> + '_ {
+        self.integrity
+            .iter()
+            .flat_map(|integrity| integrity.hashes.iter())
+            .filter_map(|hash| {
+                let digest = BASE64_STANDARD.decode(&hash.digest).ok()?;
+                Some((
+                    hash.algorithm,
+                    digest.iter().map(|byte| format!("{byte:02x}")).collect(),
+                ))
+            })
+    }
+}
+
+impl Lockfile {
+    /// Generates a software bill of materials for this lockfile. License,
+    /// author, and repository information is read from each package's
+    /// `package.json` in `node_modules/`, falling back to the cache.
+    pub fn to_sbom(&self, opts: &SbomOptions) -> Sbom {
+        let cache = opts.cache.as_deref();
+        let mut root =
+            SbomComponent::new(ROOT_REF.into(), &self.root, None, Some(&opts.root), cache);
+        root.dependencies = self.sbom_dependencies(&self.root);
+        let components = self
+            .packages
+            .values()
+            .map(|node| {
+                let mut component = SbomComponent::new(
+                    node_ref(&node.path),
+                    node,
+                    opts.download_url(node),
+                    Some(&node_dir(&opts.root, &node.path)),
+                    cache,
+                );
+                component.dependencies = self.sbom_dependencies(node);
+                component
+            })
+            .collect();
+        Sbom::new(root, components)
+    }
+
+    /// Resolves `node`'s dependencies the same way Node's module resolution
+    /// would: by looking in each ancestor's `node_modules/` in turn.
+    fn sbom_dependencies(&self, node: &LockfileNode) -> Vec<String> {
+        let mut deps = node
+            .dependencies
+            .keys()
+            .chain(node.dev_dependencies.keys())
+            .chain(node.peer_dependencies.keys())
+            .chain(node.optional_dependencies.keys())
+            .filter_map(|dep| {
+                (0..=node.path.len()).rev().find_map(|depth| {
+                    let mut path = node.path[..depth].to_vec();
+                    path.push(UniCase::new(dep.clone()));
+                    let key = UniCase::new(
+                        path.iter()
+                            .map(|x| x.to_string())
+                            .collect::<Vec<_>>()
+                            .join("/node_modules/"),
+                    );
+                    self.packages.contains_key(&key).then(|| node_ref(&path))
+                })
+            })
+            .collect::<Vec<_>>();
+        deps.sort();
+        deps.dedup();
+        deps
+    }
+}
+
+impl Graph {
+    /// Builds an SBOM for the tree. Package manifests are read from
+    /// `node_modules/` if the tree is `installed`, and from the cache
+    /// otherwise.
+    pub(crate) fn sbom(
+        &self,
+        root: &Path,
+        cache: Option<&Path>,
+        installed: bool,
+    ) -> Result<Sbom, NodeMaintainerError> {
+        let component = |idx| {
+            let is_root = idx == self.root;
+            let node = self.node_lockfile_node(idx, is_root)?;
+            let download_url = match self.inner[idx].package.resolved() {
+                PackageResolution::Npm { tarball, .. } => Some(tarball.clone()),
+                _ => None,
+            };
+            let (bom_ref, dir) = if is_root {
+                (ROOT_REF.to_string(), root.to_path_buf())
+            } else {
+                (node_ref(&node.path), node_dir(root, &node.path))
+            };
+            // Whatever's in `node_modules/` before the tree is installed
+            // could be any version of the package.
+            let dir = (is_root || installed).then_some(dir.as_path());
+            let mut component = SbomComponent::new(bom_ref, &node, download_url, dir, cache);
+            let mut deps = self
+                .inner
+                .edges_directed(idx, Direction::Outgoing)
+                .map(|edge| node_ref(&Vec::from(self.node_path(edge.target()))))
+                .collect::<Vec<_>>();
+            deps.sort();
+            deps.dedup();
+            component.dependencies = deps;
+            Ok(component)
+        };
+        Ok(Sbom::new(
+            component(self.root)?,
+            self.inner
+                .node_indices()
+                .filter(|idx| *idx != self.root)
+                .map(component)
+                .collect::<Result<_, NodeMaintainerError>>()?,
+        ))
+    }
+
+    /// Like [`Graph::sbom`], but for a tree that hasn't been installed yet.
+    /// Packages that aren't in the cache yet have their licenses looked up
+    /// in their registry's metadata instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn resolved_sbom(
+        &self,
+        root: &Path,
+        cache: Option<&Path>,
+        concurrency: usize,
+    ) -> Result<Sbom, NodeMaintainerError> {
+        let mut sbom = self.sbom(root, cache, false)?;
+        let indices = self
+            .inner
+            .node_indices()
+            .filter(|idx| *idx != self.root)
+            .map(|idx| (node_ref(&Vec::from(self.node_path(idx))), idx))
+            .collect::<HashMap<_, _>>();
+        let missing = sbom
+            .components
+            .iter()
+            .enumerate()
+            .filter(|(_, component)| component.license.is_none())
+            .filter_map(|(i, component)| Some((i, *indices.get(&component.bom_ref)?)))
+            .collect::<Vec<_>>();
+        let licenses = futures::stream::iter(missing)
+            .map(|(i, idx)| async move {
+                let metadata = self.inner[idx].package.metadata().await?;
+                Ok::<_, NodeMaintainerError>((i, metadata.manifest.license))
+            })
+            .buffer_unordered(concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+        for (i, license) in licenses {
+            sbom.components[i].license = license;
+        }
+        Ok(sbom)
+    }
+}
+
+/// The subset of `package.json` that ends up in an SBOM. Fields are kept as
+/// raw JSON, since packages in the wild use all sorts of shapes for them.
+#[derive(Debug, Default, Deserialize)]
+struct ManifestMetadata {
+    name: Option<String>,
+    version: Option<String>,
+    license: Option<Value>,
+    licenses: Option<Value>,
+    author: Option<Value>,
+    repository: Option<Value>,
+}
+
+impl ManifestMetadata {
+    /// Reads the manifest out of `dir`, if given, falling back to the
+    /// cache.
+    fn read(node: &LockfileNode, dir: Option<&Path>, cache: Option<&Path>) -> Self {
+        let data = dir
+            .and_then(|dir| std::fs::read(dir.join("package.json")).ok())
+            .or_else(|| Self::read_from_cache(node, cache));
+        data.and_then(|data| {
+            serde_json::from_slice(&data)
+                .map_err(|e| tracing::debug!("Failed to parse package.json for {}: {e}", node.name))
+                .ok()
+        })
+        .unwrap_or_default()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_from_cache(node: &LockfileNode, cache: Option<&Path>) -> Option<Vec<u8>> {
+        let (cache, integrity) = (cache?, node.integrity.as_ref()?);
+        nassun::read_cached_file(cache, integrity, "package.json")
+            .map_err(|e| {
+                tracing::debug!(
+                    "Failed to read package.json for {} from cache: {e}",
+                    node.name
+                )
+            })
+            .ok()
+            .flatten()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read_from_cache(_node: &LockfileNode, _cache: Option<&Path>) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn license(&self) -> Option<String> {
+        fn license_str(value: &Value) -> Option<String> {
+            match value {
+                Value::String(license) => Some(license.clone()),
+                // Legacy `{ "type": "MIT", "url": "..." }` form.
+                Value::Object(obj) => obj.get("type")?.as_str().map(String::from),
+                _ => None,
+            }
+        }
+        if let Some(license) = self.license.as_ref().and_then(license_str) {
+            return Some(license);
+        }
+        // Legacy `licenses` array, which lists alternatives.
+        let licenses = self
+            .licenses
+            .as_ref()?
+            .as_array()?
+            .iter()
+            .filter_map(license_str)
+            .collect::<Vec<_>>();
+        match licenses.len() {
+            0 => None,
+            1 => licenses.into_iter().next(),
+            _ => Some(format!("({})", licenses.join(" OR "))),
+        }
+    }
+
+    fn author(&self) -> Option<Person> {
+        match self.author.as_ref()? {
+            Value::String(author) => parse_person(author),
+            Value::Object(obj) => {
+                let field = |name| obj.get(name).and_then(Value::as_str).map(String::from);
+                Some(Person {
+                    name: field("name"),
+                    email: field("email"),
+                    url: field("url"),
+                })
+                .filter(|person| person.name.is_some() || person.email.is_some())
+            }
+            _ => None,
+        }
+    }
+
+    fn repository(&self) -> Option<String> {
+        match self.repository.as_ref()? {
+            Value::String(repo) => Some(repo.clone()),
+            Value::Object(obj) => obj.get("url")?.as_str().map(String::from),
+            _ => None,
+        }
+    }
+}
+
+/// Parses npm's `Name <email> (url)` shorthand for people.
+fn parse_person(person: &str) -> Option<Person> {
+    let mut rest = person.trim();
+    let mut email = None;
+    let mut url = None;
+    if let Some((before, after)) = rest.rsplit_once('(') {
+        if let Some(inner) = after.strip_suffix(')') {
+            url = Some(inner.trim().to_string());
+            rest = before.trim();
+        }
+    }
+    if let Some((before, after)) = rest.split_once('<') {
+        if let Some(inner) = after.trim_end().strip_suffix('>') {
+            email = Some(inner.trim().to_string());
+            rest = before.trim();
+        }
+    }
+    let name = Some(rest.to_string()).filter(|name| !name.is_empty());
+    if name.is_none() && email.is_none() {
+        return None;
+    }
+    Some(Person { name, email, url })
+}
+
+fn fmt_person(person: &Person, open: &str, close: &str) -> String {
+    match (&person.name, &person.email) {
+        (Some(name), Some(email)) => format!("{name} {open}{email}{close}"),
+        (Some(name), None) => name.clone(),
+        (None, Some(email)) => email.clone(),
+        (None, None) => String::new(),
+    }
+}
+
+fn cyclonedx_algorithm(algorithm: Algorithm) -> Option<&'static str> {
+    match algorithm {
+        Algorithm::Sha512 => Some("SHA-512"),
+        Algorithm::Sha384 => Some("SHA-384"),
+        Algorithm::Sha256 => Some("SHA-256"),
+        Algorithm::Sha1 => Some("SHA-1"),
+        _ => None,
+    }
+}
+
+fn spdx_algorithm(algorithm: Algorithm) -> Option<&'static str> {
+    match algorithm {
+        Algorithm::Sha512 => Some("SHA512"),
+        Algorithm::Sha384 => Some("SHA384"),
+        Algorithm::Sha256 => Some("SHA256"),
+        Algorithm::Sha1 => Some("SHA1"),
+        _ => None,
+    }
+}
+
+fn node_ref(path: &[UniCase<String>]) -> String {
+    path.iter()
+        .map(|name| format!("node_modules/{name}"))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn node_dir(root: &Path, path: &[UniCase<String>]) -> PathBuf {
+    let mut dir = root.to_path_buf();
+    for name in path {
+        dir.push("node_modules");
+        dir.push(name.as_str());
+    }
+    dir
+}
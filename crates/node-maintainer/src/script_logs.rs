@@ -0,0 +1,146 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use nassun::{package::Package, PackageResolution};
+
+/// How many lines of output [`ScriptFailure`]s hold on to.
+pub const SCRIPT_FAILURE_LINES: usize = 20;
+
+/// A lifecycle script that failed while running.
+#[derive(Debug, Clone)]
+pub struct ScriptFailure {
+    /// The package the script belongs to.
+    pub package: Package,
+    /// The lifecycle event that was running (`install`, `postinstall`, etc).
+    pub event: String,
+    /// Whether the package is an optional dependency, in which case its
+    /// failure didn't fail the install.
+    pub optional: bool,
+    /// The last [`SCRIPT_FAILURE_LINES`] lines the script wrote to stdout
+    /// and stderr.
+    pub output: Vec<String>,
+    /// The file the script's full output was written to, if any.
+    pub log: Option<PathBuf>,
+}
+
+/// Name of the file a package's lifecycle script output gets written to,
+/// inside the script logs directory. Scoped packages have the `/` in their
+/// name replaced by a `+`.
+pub fn script_log_file_name(name: &str, version: Option<&str>) -> String {
+    let name = name.replace('/', "+");
+    match version {
+        Some(version) => format!("{name}@{version}.log"),
+        None => format!("{name}.log"),
+    }
+}
+
+/// Recovers the package name from a file named by
+/// [`script_log_file_name`].
+pub fn script_log_package_name(file_name: &str) -> Option<String> {
+    let stem = file_name.strip_suffix(".log")?;
+    // The first character can be the `@` of a scope, but any `@` after that
+    // is where the version starts.
+    let start = stem.chars().next()?.len_utf8();
+    let name = match stem[start..].rfind('@') {
+        Some(at) => &stem[..start + at],
+        None => stem,
+    };
+    Some(name.replacen('+', "/", 1))
+}
+
+/// Tees the output of a single lifecycle script into its package's log
+/// file, while keeping the last few lines around in case the script fails.
+pub(crate) struct ScriptLog {
+    path: Option<PathBuf>,
+    file: Option<Mutex<File>>,
+    tail: Mutex<VecDeque<String>>,
+}
+
+impl ScriptLog {
+    /// Starts a new section in `package`'s log file in `dir`. Being unable
+    /// to write logs is no reason to fail an install, so this only ever
+    /// logs errors.
+    pub(crate) fn open(dir: Option<&Path>, package: &Package, event: &str, script: &str) -> Self {
+        let path = dir.map(|dir| dir.join(log_file_name(package)));
+        let file = path.as_deref().and_then(|path| {
+            let opened = std::fs::create_dir_all(path.parent().expect("log files have a parent"))
+                .and_then(|_| {
+                    std::fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(path)
+                })
+                .and_then(|mut file| {
+                    writeln!(file, "> {event}: {script}")?;
+                    Ok(file)
+                });
+            match opened {
+                Ok(file) => Some(Mutex::new(file)),
+                Err(e) => {
+                    tracing::debug!("Failed to open script log at {}: {e}", path.display());
+                    None
+                }
+            }
+        });
+        Self {
+            path: file.as_ref().and(path),
+            file,
+            tail: Mutex::new(VecDeque::with_capacity(SCRIPT_FAILURE_LINES)),
+        }
+    }
+
+    pub(crate) fn line(&self, line: &str) {
+        if let Some(file) = &self.file {
+            if let Err(e) = writeln!(file.lock().expect("script log lock poisoned"), "{line}") {
+                tracing::debug!("Failed to write to script log: {e}");
+            }
+        }
+        let mut tail = self.tail.lock().expect("script log lock poisoned");
+        if tail.len() == SCRIPT_FAILURE_LINES {
+            tail.pop_front();
+        }
+        tail.push_back(line.into());
+    }
+
+    pub(crate) fn failure(&self, package: &Package, event: &str, optional: bool) -> ScriptFailure {
+        ScriptFailure {
+            package: package.clone(),
+            event: event.into(),
+            optional,
+            output: self
+                .tail
+                .lock()
+                .expect("script log lock poisoned")
+                .iter()
+                .cloned()
+                .collect(),
+            log: self.path.clone(),
+        }
+    }
+}
+
+/// Removes `package`'s log file from `dir`, so the next scripts that run for
+/// it start from scratch.
+pub(crate) fn clear_log(dir: &Path, package: &Package) {
+    let path = dir.join(log_file_name(package));
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            tracing::debug!("Failed to remove old script log at {}: {e}", path.display());
+        }
+        _ => {}
+    }
+}
+
+fn log_file_name(package: &Package) -> String {
+    match package.resolved() {
+        PackageResolution::Npm { version, .. } => {
+            script_log_file_name(package.name(), Some(&version.to_string()))
+        }
+        _ => script_log_file_name(package.name(), None),
+    }
+}
@@ -0,0 +1,413 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    path::{Component, Path, PathBuf},
+};
+
+use kdl::{KdlDocument, KdlNode};
+use ssri::Integrity;
+
+use crate::{error::IoContext, NodeMaintainerError, META_FILE_NAME};
+
+/// Version of the way package directories in a store are named. It's
+/// recorded in the meta file, and stores laid out any other way get wiped
+/// on prune instead of being trusted.
+const STORE_LAYOUT_VERSION: i64 = 2;
+const STORE_LAYOUT_NODE: &str = "store-layout";
+const STORE_ENTRIES_NODE: &str = "store-entries";
+
+/// Lock held by installs (shared) and store prunes (exclusive).
+const STORE_LOCK_NAME: &str = ".oro-lock";
+/// Directory holding a pointer to every project that installs from a
+/// shared store.
+const PROJECTS_DIR_NAME: &str = ".oro-projects";
+/// Lock held while writing into an individual store entry.
+pub(crate) const ENTRY_LOCK_NAME: &str = ".oro-lock";
+/// Written into a store entry once its package is fully extracted and
+/// built. Entries without one were interrupted, and get redone.
+pub(crate) const ENTRY_COMPLETE_NAME: &str = ".oro-complete";
+/// Where a store entry's package gets extracted before it's moved into
+/// place.
+pub(crate) const ENTRY_TMP_NAME: &str = ".oro-tmp";
+
+/// A package store shared by isolated installs across multiple projects,
+/// instead of each project keeping its own `node_modules/.oro-store`.
+///
+/// Store entries are keyed by package identity and their resolved
+/// dependencies, so projects that install the same packages share them.
+/// Each project records the entries it uses in its own meta file, and
+/// [`PackageStore::prune`] removes any entries that no project uses anymore.
+#[derive(Debug, Clone)]
+pub struct PackageStore {
+    path: PathBuf,
+}
+
+impl PackageStore {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: absolute(path.as_ref()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Removes every entry in the store that isn't used by any of the
+    /// projects installed from it, returning how many were removed. Waits
+    /// for any installs currently using the store to finish first.
+    pub async fn prune(&self) -> Result<usize, NodeMaintainerError> {
+        let start = std::time::Instant::now();
+        if !self.path.exists() {
+            return Ok(0);
+        }
+        let _lock = self.lock(true).await?;
+        let store = self.clone();
+        let pruned = async_std::task::spawn_blocking(move || {
+            let referenced = store
+                .referenced_entries()?
+                .into_iter()
+                .flatten()
+                .collect::<HashSet<_>>();
+            store.remove_unreferenced(&referenced)
+        })
+        .await?;
+        tracing::debug!(
+            "Pruned {pruned} unused store entr{} from {} in {}ms.",
+            if pruned == 1 { "y" } else { "ies" },
+            self.path.display(),
+            start.elapsed().as_millis()
+        );
+        Ok(pruned)
+    }
+
+    /// Takes the store-wide lock. Installs share it, so they can run
+    /// concurrently, while prunes need it exclusively.
+    pub(crate) async fn lock(&self, exclusive: bool) -> Result<FileLock, NodeMaintainerError> {
+        std::fs::create_dir_all(&self.path)
+            .io_context(|| format!("Failed to create package store at {}.", self.path.display()))?;
+        FileLock::acquire(&self.path.join(STORE_LOCK_NAME), exclusive).await
+    }
+
+    /// Records that the project at `root` installs from this store, so its
+    /// entries are kept around when pruning.
+    pub(crate) fn register_project(&self, root: &Path) -> Result<(), NodeMaintainerError> {
+        // The same project reached through a symlink shouldn't get
+        // registered twice.
+        let root = root.canonicalize().unwrap_or_else(|_| absolute(root));
+        let projects = self.path.join(PROJECTS_DIR_NAME);
+        std::fs::create_dir_all(&projects).io_context(|| {
+            format!(
+                "Failed to create project registry in package store at {}.",
+                projects.display()
+            )
+        })?;
+        let (_, mut hex) = Integrity::from(root.to_string_lossy().as_bytes()).to_hex();
+        hex.truncate(16);
+        let file = projects.join(format!("{hex}.kdl"));
+        let mut node = KdlNode::new("project");
+        node.push(root.to_string_lossy().to_string());
+        let mut doc = KdlDocument::new();
+        doc.nodes_mut().push(node);
+        doc.fmt();
+        std::fs::write(&file, doc.to_string()).io_context(|| {
+            format!(
+                "Failed to register project {} with package store at {}.",
+                root.display(),
+                file.display()
+            )
+        })
+    }
+
+    /// The entries used by each registered project that still uses this
+    /// store. Registrations for anything else get removed.
+    fn referenced_entries(&self) -> Result<Vec<Vec<String>>, NodeMaintainerError> {
+        let projects = self.path.join(PROJECTS_DIR_NAME);
+        if !projects.exists() {
+            return Ok(Vec::new());
+        }
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(&projects).io_context(|| {
+            format!(
+                "Failed to read project registry in package store at {}.",
+                projects.display()
+            )
+        })? {
+            let entry = entry.io_context(|| {
+                format!(
+                    "Failed to read directory entry from project registry at {}.",
+                    projects.display()
+                )
+            })?;
+            let path = entry.path();
+            let entries = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|contents| contents.parse::<KdlDocument>().ok())
+                .and_then(|doc| {
+                    doc.get_arg("project")
+                        .and_then(|root| root.as_string())
+                        .map(PathBuf::from)
+                })
+                .and_then(|root| read_meta(&root.join("node_modules")))
+                .filter(|meta| store_layout_matches(meta, Some(&self.path)))
+                .map(|meta| store_entries(&meta));
+            match entries {
+                Some(entries) => found.push(entries),
+                None => {
+                    tracing::debug!(
+                        "Forgetting project registration at {}: it no longer uses this store.",
+                        path.display()
+                    );
+                    std::fs::remove_file(&path).io_context(|| {
+                        format!(
+                            "Failed to remove stale project registration at {}.",
+                            path.display()
+                        )
+                    })?;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn remove_unreferenced(
+        &self,
+        referenced: &HashSet<String>,
+    ) -> Result<usize, NodeMaintainerError> {
+        let mut pruned = 0;
+        for entry in read_dir(&self.path)? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            if name.starts_with('@') {
+                let scope = entry.path();
+                let mut remaining = 0;
+                for scoped in read_dir(&scope)? {
+                    let scoped_name = format!("{name}/{}", scoped.file_name().to_string_lossy());
+                    if referenced.contains(&scoped_name) {
+                        remaining += 1;
+                    } else {
+                        remove(&scoped.path())?;
+                        pruned += 1;
+                    }
+                }
+                if remaining == 0 {
+                    remove(&scope)?;
+                }
+            } else if !referenced.contains(&name) {
+                remove(&entry.path())?;
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+/// An advisory lock on a file, held until it's dropped. Locks are released
+/// by the OS if the process dies, so a crashed install can't wedge a store.
+pub(crate) struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    pub(crate) async fn acquire(path: &Path, exclusive: bool) -> Result<Self, NodeMaintainerError> {
+        let path = path.to_owned();
+        async_std::task::spawn_blocking(move || {
+            let file = lock_file(&path, exclusive)
+                .io_context(|| format!("Failed to lock {}.", path.display()))?;
+            Ok(Self { _file: file })
+        })
+        .await
+    }
+}
+
+#[cfg(unix)]
+fn lock_file(path: &Path, exclusive: bool) -> std::io::Result<File> {
+    use rustix::{fs::FlockOperation, io::Errno};
+
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(path)?;
+    let operation = if exclusive {
+        FlockOperation::LockExclusive
+    } else {
+        FlockOperation::LockShared
+    };
+    loop {
+        match rustix::fs::flock(&file, operation) {
+            Ok(()) => return Ok(file),
+            Err(Errno::INTR) => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn lock_file(path: &Path, exclusive: bool) -> std::io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_SHARE_READ: u32 = 0x1;
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+
+    // Windows doesn't let anyone open a file in a way its existing handles
+    // don't share, so exclusive holders share nothing, and shared holders
+    // only share reads with each other.
+    loop {
+        match std::fs::OpenOptions::new()
+            .read(true)
+            .write(exclusive)
+            .create(exclusive)
+            .share_mode(if exclusive { 0 } else { FILE_SHARE_READ })
+            .open(path)
+        {
+            Ok(file) => return Ok(file),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                drop(
+                    std::fs::OpenOptions::new()
+                        .write(true)
+                        .create(true)
+                        .open(path),
+                );
+            }
+            Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Meta file nodes recording how a project's store is laid out: which
+/// store it's in, if it's a shared one, and which entries it uses.
+pub(crate) fn store_meta_nodes<'a>(
+    store: Option<&Path>,
+    entries: impl IntoIterator<Item = &'a str>,
+) -> Vec<KdlNode> {
+    let mut layout = KdlNode::new(STORE_LAYOUT_NODE);
+    layout.push(STORE_LAYOUT_VERSION);
+    let Some(store) = store else {
+        return vec![layout];
+    };
+    layout.push(("store", store.to_string_lossy().to_string()));
+    let mut entries = entries.into_iter().collect::<Vec<_>>();
+    entries.sort();
+    entries.dedup();
+    let mut entries_node = KdlNode::new(STORE_ENTRIES_NODE);
+    let children = entries_node.ensure_children();
+    for entry in entries {
+        let mut node = KdlNode::new("-");
+        node.push(entry);
+        children.nodes_mut().push(node);
+    }
+    vec![layout, entries_node]
+}
+
+/// Whether the meta file `meta` was written for the current store layout,
+/// using `store` (or a project-local store, if `None`).
+pub(crate) fn store_layout_matches(meta: &KdlDocument, store: Option<&Path>) -> bool {
+    let Some(layout) = meta.get(STORE_LAYOUT_NODE) else {
+        return false;
+    };
+    let recorded = layout
+        .get("store")
+        .and_then(|v| v.as_string())
+        .map(Path::new);
+    layout.get(0).and_then(|v| v.as_i64()) == Some(STORE_LAYOUT_VERSION)
+        && match (recorded, store) {
+            (Some(recorded), Some(store)) => same_path(recorded, store),
+            (recorded, store) => recorded.is_none() && store.is_none(),
+        }
+}
+
+/// Whether `a` and `b` point to the same place, even if they're spelled
+/// differently or go through symlinks.
+fn same_path(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Whether the meta file `meta` was written by an isolated install. Only
+/// those record a store layout.
+pub(crate) fn is_isolated(meta: &KdlDocument) -> bool {
+    meta.get(STORE_LAYOUT_NODE).is_some()
+}
+
+/// The shared store the meta file `meta` was written for, if it used one.
+pub(crate) fn meta_store(meta: &KdlDocument) -> Option<PathBuf> {
+    meta.get(STORE_LAYOUT_NODE)?
+        .get("store")?
+        .as_string()
+        .map(PathBuf::from)
+}
+
+/// Reads the meta file out of `node_modules`, if there's a valid one.
+pub(crate) fn read_meta(node_modules: &Path) -> Option<KdlDocument> {
+    std::fs::read_to_string(node_modules.join(META_FILE_NAME))
+        .ok()
+        .and_then(|meta| meta.parse().ok())
+}
+
+fn store_entries(meta: &KdlDocument) -> Vec<String> {
+    meta.get(STORE_ENTRIES_NODE)
+        .and_then(|node| node.children())
+        .map(|children| {
+            children
+                .nodes()
+                .iter()
+                .filter_map(|node| node.get(0).and_then(|v| v.as_string()))
+                .map(|entry| entry.to_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Makes `path` absolute, without resolving any symlinks in it, so it's
+/// recorded the same way it was configured.
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_owned()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_owned())
+    };
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
+fn read_dir(dir: &Path) -> Result<Vec<std::fs::DirEntry>, NodeMaintainerError> {
+    std::fs::read_dir(dir)
+        .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+        .io_context(|| {
+            format!(
+                "Failed to read contents of package store directory at {}.",
+                dir.display()
+            )
+        })
+}
+
+fn remove(path: &Path) -> Result<(), NodeMaintainerError> {
+    let ty = path.symlink_metadata().io_context(|| {
+        format!(
+            "Failed to get file type of store entry at {}.",
+            path.display()
+        )
+    })?;
+    if ty.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+    .io_context(|| {
+        format!(
+            "Failed to remove unused store entry at {} while pruning package store.",
+            path.display()
+        )
+    })
+}
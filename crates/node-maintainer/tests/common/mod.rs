@@ -0,0 +1,152 @@
+//! Fixtures shared by the integration tests: a mock registry serving
+//! packages that get packed up on the fly, and shortcuts for installing from
+//! it.
+
+// Each test binary only uses some of these.
+#![allow(dead_code)]
+
+use std::path::Path;
+
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::{NodeMaintainer, NodeMaintainerOptions};
+use oro_common::CorgiManifest;
+use serde_json::{json, Value};
+use ssri::Integrity;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// Options for a project at `root` that installs from `mock_server`.
+pub fn builder(root: &Path, mock_server: &MockServer) -> Result<NodeMaintainerOptions> {
+    std::fs::create_dir_all(root).into_diagnostic()?;
+    Ok(NodeMaintainer::builder()
+        .concurrency(1)
+        .root(root)
+        .registry(mock_server.uri().parse().into_diagnostic()?))
+}
+
+/// Resolves and installs `root`'s dependencies.
+pub async fn install(
+    builder: NodeMaintainerOptions,
+    root: CorgiManifest,
+) -> Result<NodeMaintainer> {
+    // Boxed so that resolving more than once in a test doesn't blow the
+    // stack.
+    let nm = Box::pin(builder.resolve_manifest(root)).await?;
+    nm.prune().await?;
+    nm.extract().await?;
+    Ok(nm)
+}
+
+/// Resolves and installs the package `spec` points to, as the root package.
+pub async fn install_spec(builder: NodeMaintainerOptions, spec: &str) -> Result<NodeMaintainer> {
+    let nm = Box::pin(builder.resolve_spec(spec)).await?;
+    nm.prune().await?;
+    nm.extract().await?;
+    Ok(nm)
+}
+
+pub fn read(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).into_diagnostic()
+}
+
+/// Serves version 1.0.0 of `name`, with `manifest` as its package.json.
+pub async fn mock_package(mock_server: &MockServer, name: &str, manifest: Value) -> Result<()> {
+    mock_package_with_files(mock_server, name, manifest, &[]).await
+}
+
+/// Serves version 1.0.0 of `name`, with `manifest` as its package.json,
+/// along with any other `files`.
+pub async fn mock_package_with_files(
+    mock_server: &MockServer,
+    name: &str,
+    manifest: Value,
+    files: &[(&str, &str)],
+) -> Result<()> {
+    let manifest = mock_version(mock_server, name, "1.0.0", manifest, files).await?;
+    mount_packument(mock_server, name, &[("1.0.0", manifest)]).await
+}
+
+/// Serves every one of `versions` of `name`, each with the given manifest
+/// as its package.json. The last one is tagged `latest`.
+pub async fn mock_packument(
+    mock_server: &MockServer,
+    name: &str,
+    versions: &[(&str, Value)],
+) -> Result<()> {
+    let mut published = Vec::new();
+    for (version, manifest) in versions {
+        let manifest = mock_version(mock_server, name, version, manifest.clone(), &[]).await?;
+        published.push((*version, manifest));
+    }
+    mount_packument(mock_server, name, &published).await
+}
+
+/// Serves the tarball for one version of `name`, returning its manifest as
+/// it should appear in the packument.
+async fn mock_version(
+    mock_server: &MockServer,
+    name: &str,
+    version: &str,
+    mut manifest: Value,
+    files: &[(&str, &str)],
+) -> Result<Value> {
+    manifest["name"] = json!(name);
+    manifest["version"] = json!(version);
+    let tarball = tarball(&manifest, files)?;
+    let tarball_path = format!("/{name}/-/{name}-{version}.tgz");
+    manifest["dist"] = json!({
+        "tarball": format!("{}{tarball_path}", mock_server.uri()),
+        "integrity": Integrity::from(&tarball).to_string(),
+    });
+    Mock::given(method("GET"))
+        .and(path(tarball_path))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(tarball))
+        .mount(mock_server)
+        .await;
+    Ok(manifest)
+}
+
+async fn mount_packument(
+    mock_server: &MockServer,
+    name: &str,
+    versions: &[(&str, Value)],
+) -> Result<()> {
+    let mut packument = json!({ "versions": {}, "dist-tags": {} });
+    for (version, manifest) in versions {
+        packument["versions"][version] = manifest.clone();
+        packument["dist-tags"]["latest"] = json!(version);
+    }
+    Mock::given(method("GET"))
+        .and(path(format!("/{name}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&packument))
+        .mount(mock_server)
+        .await;
+    Ok(())
+}
+
+/// Packs up a tarball with `manifest` as its package.json, along with any
+/// other `files`.
+pub fn tarball(manifest: &Value, files: &[(&str, &str)]) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    let manifest = manifest.to_string();
+    for (path, contents) in
+        std::iter::once(("package.json", manifest.as_str())).chain(files.iter().copied())
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("package/{path}"), contents.as_bytes())
+            .into_diagnostic()?;
+    }
+    builder
+        .into_inner()
+        .and_then(|gz| gz.finish())
+        .into_diagnostic()
+}
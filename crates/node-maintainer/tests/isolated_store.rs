@@ -0,0 +1,316 @@
+use std::path::{Path, PathBuf};
+
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::{NodeMaintainer, NodeMaintainerOptions, PackageStore};
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use wiremock::MockServer;
+
+mod common;
+use common::{builder, mock_package, mock_package_with_files, mock_packument};
+
+#[async_std::test]
+async fn identical_subgraphs_share_store_dirs() -> Result<()> {
+    let mock_server = mock_registry().await?;
+    let root = tempfile::tempdir().into_diagnostic()?;
+    let nm = resolve(root.path(), None, "root", &mock_server).await?;
+
+    // root, x, y, d@1, e, and two copies of d@2.
+    assert_eq!(nm.package_count(), 7);
+
+    nm.prune().await?;
+    assert_eq!(nm.extract().await?, 5);
+
+    let store = root.path().join("node_modules").join(".oro-store");
+    assert_eq!(store_entries(&store)?.len(), 5);
+    assert_eq!(linked_dep(&store, "x", "d")?, linked_dep(&store, "y", "d")?);
+
+    // The next install keeps the store as-is, except for anything that's
+    // no longer part of the tree.
+    std::fs::create_dir(store.join("stale@1.0.0-0123456789abcdef")).into_diagnostic()?;
+    let nm = resolve(root.path(), None, "root", &mock_server).await?;
+    assert_eq!(nm.prune().await?, 1);
+    assert_eq!(nm.extract().await?, 0);
+    assert_eq!(store_entries(&store)?.len(), 5);
+    Ok(())
+}
+
+#[async_std::test]
+async fn shared_store() -> Result<()> {
+    let mock_server = mock_registry().await?;
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let store = dir.path().join("store");
+    let project_a = dir.path().join("a");
+    let project_b = dir.path().join("b");
+    std::fs::create_dir_all(&project_a).into_diagnostic()?;
+    std::fs::create_dir_all(&project_b).into_diagnostic()?;
+
+    let nm = resolve(&project_a, Some(&store), "root", &mock_server).await?;
+    nm.prune().await?;
+    assert_eq!(nm.extract().await?, 5);
+    nm.rebuild(true).await?;
+
+    // The second project finds everything it needs already in the store.
+    let nm = resolve(&project_b, Some(&store), "root", &mock_server).await?;
+    nm.prune().await?;
+    assert_eq!(nm.extract().await?, 0);
+    nm.rebuild(true).await?;
+
+    assert_eq!(store_entries(&store)?.len(), 5);
+    assert!(!project_a.join("node_modules").join(".oro-store").exists());
+    assert_eq!(
+        project_a
+            .join("node_modules")
+            .join("x")
+            .canonicalize()
+            .into_diagnostic()?,
+        project_b
+            .join("node_modules")
+            .join("x")
+            .canonicalize()
+            .into_diagnostic()?,
+    );
+    // Bins are linked into each dependent, even when the dependency was
+    // already in the store.
+    let d2 = store_entries(&store)?
+        .into_iter()
+        .find(|entry| entry.starts_with("d@2.0.0-"))
+        .expect("d@2 should be in the store");
+    assert!(store
+        .join(d2)
+        .join("node_modules")
+        .join("d")
+        .join("node_modules")
+        .join(".bin")
+        .join("e-cli")
+        .exists());
+
+    // Nothing gets pruned while both projects still use everything.
+    let package_store = PackageStore::new(&store);
+    assert_eq!(package_store.prune().await?, 0);
+
+    // Once the first project is gone and the second one only needs `e`,
+    // everything else is unused.
+    std::fs::remove_dir_all(&project_a).into_diagnostic()?;
+    let nm = resolve(&project_b, Some(&store), "other", &mock_server).await?;
+    nm.prune().await?;
+    assert_eq!(nm.extract().await?, 0);
+    assert_eq!(package_store.prune().await?, 4);
+    let remaining = store_entries(&store)?;
+    assert_eq!(remaining.len(), 1);
+    assert!(remaining[0].starts_with("e@1.0.0-"));
+    assert!(project_b
+        .join("node_modules")
+        .join("e")
+        .join("package.json")
+        .exists());
+    Ok(())
+}
+
+#[async_std::test]
+async fn prune_matches_store_paths_however_spelled() -> Result<()> {
+    let mock_server = mock_registry().await?;
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let project = dir.path().join("a");
+    std::fs::create_dir_all(&project).into_diagnostic()?;
+
+    let nm = resolve(
+        &project,
+        Some(&project.join("..").join("store")),
+        "root",
+        &mock_server,
+    )
+    .await?;
+    nm.prune().await?;
+    assert_eq!(nm.extract().await?, 5);
+
+    // The project still uses everything, even though it recorded the store
+    // by a different path.
+    let store = PackageStore::new(dir.path().join("store"));
+    assert_eq!(store.prune().await?, 0);
+    assert_eq!(store_entries(store.path())?.len(), 5);
+    Ok(())
+}
+
+#[async_std::test]
+async fn interrupted_entries_are_redone() -> Result<()> {
+    let mock_server = mock_registry().await?;
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let store = dir.path().join("store");
+    let project_a = dir.path().join("a");
+    let project_b = dir.path().join("b");
+
+    let nm = resolve(&project_a, Some(&store), "root", &mock_server).await?;
+    nm.prune().await?;
+    nm.extract().await?;
+    nm.rebuild(true).await?;
+    for entry in store_entries(&store)? {
+        assert!(store.join(entry).join(".oro-complete").exists());
+    }
+
+    // Pretend an install got interrupted partway through extracting `x`.
+    let x = store_entries(&store)?
+        .into_iter()
+        .find(|entry| entry.starts_with("x@"))
+        .expect("x should be in the store");
+    let x = store.join(x);
+    std::fs::remove_file(x.join(".oro-complete")).into_diagnostic()?;
+    std::fs::remove_file(x.join("node_modules").join("x").join("package.json"))
+        .into_diagnostic()?;
+    std::fs::create_dir(x.join(".oro-tmp")).into_diagnostic()?;
+
+    // The next install using the store starts it over, instead of
+    // trusting what's there.
+    let nm = resolve(&project_b, Some(&store), "root", &mock_server).await?;
+    nm.prune().await?;
+    assert_eq!(nm.extract().await?, 1);
+    nm.rebuild(true).await?;
+    assert!(x
+        .join("node_modules")
+        .join("x")
+        .join("package.json")
+        .exists());
+    assert!(x.join(".oro-complete").exists());
+    assert!(!x.join(".oro-tmp").exists());
+    Ok(())
+}
+
+#[async_std::test]
+async fn hoist_patterns() -> Result<()> {
+    let mock_server = mock_registry().await?;
+    let root = tempfile::tempdir().into_diagnostic()?;
+    let node_modules = root.path().join("node_modules");
+    let store = node_modules.join(".oro-store");
+    let hoist_dir = store.join("node_modules");
+
+    // Direct dependencies are never hoisted over, so `d@2` stays where it
+    // is, and `e` is excluded from the root, so only the store sees it.
+    let nm = resolve_with(
+        builder(root.path(), &mock_server)?
+            .public_hoist_pattern("*")
+            .public_hoist_pattern("!e")
+            .hoist_pattern("*"),
+        "root",
+    )
+    .await?;
+    nm.prune().await?;
+    nm.extract().await?;
+    nm.rebuild(true).await?;
+    assert!(!node_modules.join("e").exists());
+    assert_eq!(store_entries(&hoist_dir)?, vec!["e"]);
+    assert!(hoist_dir.join(".bin").join("e-cli").exists());
+
+    // `e` moves to the root, along with its bin.
+    let nm = resolve_with(
+        builder(root.path(), &mock_server)?.public_hoist_pattern("e"),
+        "root",
+    )
+    .await?;
+    nm.prune().await?;
+    nm.extract().await?;
+    nm.rebuild(true).await?;
+    assert!(node_modules.join("e").join("package.json").exists());
+    assert!(node_modules.join(".bin").join("e-cli").exists());
+    assert!(!hoist_dir.exists());
+
+    // Without any patterns, it goes back to only being linked into `d`.
+    let nm = resolve(root.path(), None, "root", &mock_server).await?;
+    nm.prune().await?;
+    nm.extract().await?;
+    assert!(node_modules.join("e").symlink_metadata().is_err());
+    assert!(node_modules
+        .join(".bin")
+        .join("e-cli")
+        .symlink_metadata()
+        .is_err());
+    assert_eq!(store_entries(&store)?.len(), 5);
+    Ok(())
+}
+
+/// Sets up a registry where `x` and `y` both need `d@2`, which conflicts
+/// with `root`'s `d@1`, so each of them gets its own nested copy of `d@2`
+/// (and its dependency on `e`). `other` only depends on `e`.
+async fn mock_registry() -> Result<MockServer> {
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "root",
+        json!({ "dependencies": { "x": "^1", "y": "^1", "d": "^1" } }),
+    )
+    .await?;
+    mock_package(
+        &mock_server,
+        "other",
+        json!({ "dependencies": { "e": "^1" } }),
+    )
+    .await?;
+    mock_package(&mock_server, "x", json!({ "dependencies": { "d": "^2" } })).await?;
+    mock_package(&mock_server, "y", json!({ "dependencies": { "d": "^2" } })).await?;
+    mock_packument(
+        &mock_server,
+        "d",
+        &[
+            ("1.0.0", json!({})),
+            ("2.0.0", json!({ "dependencies": { "e": "^1" } })),
+        ],
+    )
+    .await?;
+    mock_package_with_files(
+        &mock_server,
+        "e",
+        json!({ "bin": { "e-cli": "cli.js" } }),
+        &[("cli.js", "#!/usr/bin/env node\n")],
+    )
+    .await?;
+    Ok(mock_server)
+}
+
+async fn resolve(
+    root: &Path,
+    store: Option<&Path>,
+    spec: &str,
+    mock_server: &MockServer,
+) -> Result<NodeMaintainer> {
+    let mut builder = builder(root, mock_server)?;
+    if let Some(store) = store {
+        builder = builder.store(store);
+    }
+    resolve_with(builder, spec).await
+}
+
+async fn resolve_with(builder: NodeMaintainerOptions, spec: &str) -> Result<NodeMaintainer> {
+    // Boxed so that resolving more than once in a test doesn't blow the
+    // stack.
+    Ok(Box::pin(builder.resolve_spec(format!("{spec}@^1"))).await?)
+}
+
+/// Names of the packages in `store`, ignoring its bookkeeping files.
+fn store_entries(store: &Path) -> Result<Vec<String>> {
+    let mut entries = std::fs::read_dir(store)
+        .into_diagnostic()?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .into_diagnostic()
+        })
+        .filter(|name| !matches!(name, Ok(name) if name.starts_with('.')))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// Resolves the store directory `name`'s `dep` dependency is linked to.
+fn linked_dep(store: &Path, name: &str, dep: &str) -> Result<PathBuf> {
+    let entry = store_entries(store)?
+        .into_iter()
+        .find(|entry| entry.starts_with(&format!("{name}@")))
+        .expect("package should be in the store");
+    store
+        .join(entry)
+        .join("node_modules")
+        .join(name)
+        .join("node_modules")
+        .join(dep)
+        .canonicalize()
+        .into_diagnostic()
+}
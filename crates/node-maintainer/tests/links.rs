@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::{LinkedDependencies, NodeMaintainerError};
+use oro_common::CorgiManifest;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use wiremock::MockServer;
+
+mod common;
+use common::{builder, install, mock_package_with_files, read};
+
+#[async_std::test]
+async fn links_local_packages_until_unlinked() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let mock_server = MockServer::start().await;
+    mock_package(&mock_server, "lib", "module.exports = 'registry';").await?;
+    mock_package(&mock_server, "dep", "module.exports = 'dep';").await?;
+    let local = dir.path().join("local-lib");
+    std::fs::create_dir_all(&local).into_diagnostic()?;
+    std::fs::write(
+        local.join("package.json"),
+        json!({
+            "name": "lib",
+            "version": "2.0.0",
+            "dependencies": { "dep": "^1" },
+        })
+        .to_string(),
+    )
+    .into_diagnostic()?;
+    std::fs::write(local.join("index.js"), "module.exports = 'local';").into_diagnostic()?;
+    let local = local.canonicalize().into_diagnostic()?;
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "app",
+        "dependencies": { "lib": "^1" },
+    }))
+    .into_diagnostic()?;
+
+    for (project, hoisted) in [("isolated", false), ("hoisted", true)] {
+        let project = dir.path().join(project);
+        let installed_lib = project.join("node_modules").join("lib");
+        let nm = install(
+            builder(&project, &mock_server)?.hoisted(hoisted),
+            root.clone(),
+        )
+        .await?;
+        let lockfile = nm.to_kdl()?.to_string();
+        std::fs::write(project.join("package-lock.kdl"), &lockfile).into_diagnostic()?;
+
+        let mut links = LinkedDependencies::default();
+        links.insert("lib", &local);
+        links.save(&project).await?;
+        let nm = install(
+            builder(&project, &mock_server)?.hoisted(hoisted),
+            root.clone(),
+        )
+        .await?;
+        assert_eq!(installed_lib.canonicalize().into_diagnostic()?, local);
+        assert_eq!(
+            read(&installed_lib.join("index.js"))?,
+            "module.exports = 'local';"
+        );
+        // The linked package's own dependencies are installed with the
+        // project's, but nothing gets written into the linked package.
+        assert!(installed(&project, "dep")?);
+        assert!(!local.join("node_modules").exists());
+        // The lockfile still has what it had before linking.
+        assert_eq!(nm.to_kdl()?.to_string(), lockfile);
+
+        // Links don't get in the way of locked installs, and they survive
+        // later ones.
+        install(
+            builder(&project, &mock_server)?
+                .hoisted(hoisted)
+                .locked(true),
+            root.clone(),
+        )
+        .await?;
+        assert_eq!(LinkedDependencies::load(&project).await?, links);
+        assert_eq!(installed_lib.canonicalize().into_diagnostic()?, local);
+
+        // Unlinking goes back to the registry package.
+        LinkedDependencies::default().save(&project).await?;
+        let nm = install(
+            builder(&project, &mock_server)?.hoisted(hoisted),
+            root.clone(),
+        )
+        .await?;
+        assert_ne!(installed_lib.canonicalize().into_diagnostic()?, local);
+        assert_eq!(
+            read(&installed_lib.join("index.js"))?,
+            "module.exports = 'registry';"
+        );
+        assert_eq!(nm.to_kdl()?.to_string(), lockfile);
+        assert!(local.join("index.js").exists());
+    }
+    Ok(())
+}
+
+#[async_std::test]
+async fn links_to_missing_packages_fail() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let mock_server = MockServer::start().await;
+    mock_package(&mock_server, "lib", "module.exports = 'registry';").await?;
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "app",
+        "dependencies": { "lib": "^1" },
+    }))
+    .into_diagnostic()?;
+
+    let result = Box::pin(
+        builder(&dir.path().join("project"), &mock_server)?
+            .linked_dependency("lib", "../missing")
+            .resolve_manifest(root),
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(NodeMaintainerError::MissingLinkedPackage(name, _)) if name == "lib"
+    ));
+    Ok(())
+}
+
+/// Whether `name` got installed, either into a hoisted `node_modules`, or
+/// into the project's package store.
+fn installed(project: &Path, name: &str) -> Result<bool> {
+    let node_modules = project.join("node_modules");
+    if node_modules.join(name).join("package.json").exists() {
+        return Ok(true);
+    }
+    let store = node_modules.join(".oro-store");
+    if !store.exists() {
+        return Ok(false);
+    }
+    Ok(std::fs::read_dir(store)
+        .into_diagnostic()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().join("node_modules").join(name))
+        .any(|dir: PathBuf| dir.join("package.json").exists()))
+}
+
+/// Serves version 1.0.0 of `name`, with `index.js` as its only file.
+async fn mock_package(mock_server: &MockServer, name: &str, index: &str) -> Result<()> {
+    mock_package_with_files(mock_server, name, json!({}), &[("index.js", index)]).await
+}
@@ -0,0 +1,170 @@
+use miette::Result;
+use node_maintainer::{Lockfile, LockfileDiffPackage};
+use pretty_assertions::assert_eq;
+
+const OLD: &str = r#"
+lockfile-version 1
+root {
+    dependencies {
+        a "^1.0.0"
+        b "^2.0.0"
+        c "^1.0.0"
+        gone "^1.0.0"
+    }
+}
+pkg "a" {
+    version "1.0.0"
+    resolved "https://example.com/-/a-1.0.0.tgz"
+    integrity "sha512-aaa"
+}
+pkg "b" {
+    version "2.1.0"
+    resolved "https://example.com/-/b-2.1.0.tgz"
+    integrity "sha512-bbb"
+}
+pkg "c" {
+    version "1.0.0"
+    resolved "https://example.com/-/c-1.0.0.tgz"
+    integrity "sha512-ccc"
+}
+pkg "a" "d" {
+    version "4.0.0"
+    resolved "https://example.com/-/d-4.0.0.tgz"
+    integrity "sha512-ddd"
+}
+pkg "gone" {
+    version "1.0.0"
+    resolved "https://example.com/-/gone-1.0.0.tgz"
+    integrity "sha512-gone"
+}
+pkg "same" {
+    version "1.0.0"
+    resolved "https://example.com/-/same-1.0.0.tgz"
+    integrity "sha512-same"
+}
+"#;
+
+const NEW: &str = r#"
+lockfile-version 1
+root {
+    dependencies {
+        a "^1.0.0"
+        b "^2.0.0"
+        c "^1.0.0"
+        new "^1.0.0"
+    }
+}
+pkg "a" {
+    version "1.1.0"
+    resolved "https://example.com/-/a-1.1.0.tgz"
+    integrity "sha512-aaa2"
+}
+pkg "b" {
+    version "2.0.0"
+    resolved "https://example.com/-/b-2.0.0.tgz"
+    integrity "sha512-bbb0"
+}
+pkg "c" {
+    version "1.0.0"
+    resolved "https://example.com/-/c-1.0.0.tgz"
+    integrity "sha512-ccc2"
+}
+pkg "d" {
+    version "4.0.0"
+    resolved "https://example.com/-/d-4.0.0.tgz"
+    integrity "sha512-ddd"
+}
+pkg "new" {
+    version "1.0.0"
+    resolved "https://example.com/-/new-1.0.0.tgz"
+    integrity "sha512-new"
+}
+pkg "same" {
+    version "1.0.0"
+    resolved "https://example.com/-/same-1.0.0.tgz"
+    integrity "sha512-same"
+}
+"#;
+
+#[test]
+fn classifies_changes() -> Result<()> {
+    let old = Lockfile::from_kdl(OLD)?;
+    let new = Lockfile::from_kdl(NEW)?;
+    let diff = old.diff(&new);
+
+    assert_eq!(
+        diff.added.iter().map(|p| &p.path[..]).collect::<Vec<_>>(),
+        vec!["new"]
+    );
+    assert_eq!(
+        diff.removed.iter().map(|p| &p.path[..]).collect::<Vec<_>>(),
+        vec!["gone"]
+    );
+    assert_eq!(diff.upgraded.len(), 1);
+    assert_eq!(diff.upgraded[0].to.name, "a");
+    assert_eq!(diff.upgraded[0].from.version, Some("1.0.0".parse()?));
+    assert_eq!(diff.upgraded[0].to.version, Some("1.1.0".parse()?));
+    assert_eq!(diff.downgraded.len(), 1);
+    assert_eq!(diff.downgraded[0].to.name, "b");
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].to.name, "c");
+    assert_eq!(diff.moved.len(), 1);
+    assert_eq!(diff.moved[0].from.path, "a/node_modules/d");
+    assert_eq!(diff.moved[0].to.path, "d");
+    assert_eq!(diff.len(), 6);
+    Ok(())
+}
+
+#[test]
+fn identical_lockfiles_have_no_changes() -> Result<()> {
+    let old = Lockfile::from_kdl(OLD)?;
+    let diff = old.diff(&old.clone());
+    assert!(diff.is_empty());
+    Ok(())
+}
+
+#[test]
+fn repointed_aliases_are_removed_and_added() -> Result<()> {
+    let old = Lockfile::from_kdl(
+        r#"
+        lockfile-version 1
+        root {
+            dependencies {
+                a "npm:x@^1.0.0"
+            }
+        }
+        pkg "a" {
+            version "1.0.0"
+            resolved "x/-/x-1.0.0.tgz"
+            integrity "sha512-xxx"
+        }
+        "#,
+    )?;
+    let new = Lockfile::from_kdl(
+        r#"
+        lockfile-version 1
+        root {
+            dependencies {
+                a "npm:y@^2.0.0"
+            }
+        }
+        pkg "a" {
+            version "2.0.0"
+            resolved "y/-/y-2.0.0.tgz"
+            integrity "sha512-yyy"
+        }
+        "#,
+    )?;
+    let diff = old.diff(&new);
+    let versions = |packages: &[LockfileDiffPackage]| {
+        packages
+            .iter()
+            .map(|p| p.version.as_ref().map(|v| v.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(versions(&diff.removed), vec![Some("1.0.0".into())]);
+    assert_eq!(versions(&diff.added), vec![Some("2.0.0".into())]);
+    assert!(diff.upgraded.is_empty());
+    assert_eq!(diff.len(), 2);
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::{Lockfile, NodeMaintainer};
+use oro_common::CorgiManifest;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+const ABSOLUTE: &str = r#"
+lockfile-version 1
+root {
+    dependencies {
+        a "^1.0.0"
+        "@scope/b" "^2.0.0"
+        c "^3.0.0"
+        d "^4.0.0"
+    }
+}
+pkg "a" {
+    version "1.0.0"
+    resolved "https://mirror.example.com/api/npm/npm-remote/a/-/a-1.0.0.tgz"
+    integrity "sha512-aaa"
+}
+pkg "@scope/b" {
+    version "2.0.0"
+    resolved "https://registry.npmjs.org/@scope/b/-/b-2.0.0.tgz"
+    integrity "sha512-bbb"
+}
+pkg "c" {
+    version "3.0.0"
+    resolved "https://example.com/c.tgz"
+}
+pkg "d" {
+    version "4.0.0"
+    resolved "https://example.com/d/-/d-4.0.0.tgz"
+    integrity "sha512-ddd"
+}
+"#;
+
+fn resolved(lockfile: &Lockfile) -> Vec<&str> {
+    lockfile
+        .packages()
+        .values()
+        .map(|p| p.resolved.as_deref().unwrap())
+        .collect()
+}
+
+#[test]
+fn parsing_keeps_resolved_urls() -> Result<()> {
+    let lockfile = Lockfile::from_kdl(ABSOLUTE)?;
+    assert_eq!(
+        resolved(&lockfile),
+        vec![
+            "https://mirror.example.com/api/npm/npm-remote/a/-/a-1.0.0.tgz",
+            "https://registry.npmjs.org/@scope/b/-/b-2.0.0.tgz",
+            "https://example.com/c.tgz",
+            "https://example.com/d/-/d-4.0.0.tgz",
+        ]
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn configured_registry_urls_are_registry_agnostic() -> Result<()> {
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "root",
+        "dependencies": {
+            "a": "^1.0.0",
+            "@scope/b": "^2.0.0",
+            "c": "^3.0.0",
+            "d": "^4.0.0",
+        }
+    }))
+    .into_diagnostic()?;
+    // Everything is already in the lockfile, so nothing gets fetched.
+    let nm = NodeMaintainer::builder()
+        .registry(
+            "https://mirror.example.com/api/npm/npm-remote/"
+                .parse()
+                .into_diagnostic()?,
+        )
+        .scope_registry(
+            "@scope",
+            "https://registry.npmjs.org/".parse().into_diagnostic()?,
+        )
+        .kdl_lock(ABSOLUTE)?
+        .resolve_manifest(root)
+        .await?;
+    let lockfile = nm.to_lockfile()?;
+    // Packages are listed in the order they were resolved in, which varies.
+    let mut resolved = resolved(&lockfile);
+    resolved.sort_unstable();
+    // `d` looks like a registry tarball, but example.com isn't a configured
+    // registry, so it's kept as-is.
+    assert_eq!(
+        resolved,
+        vec![
+            "@scope/b/-/b-2.0.0.tgz",
+            "a/-/a-1.0.0.tgz",
+            "https://example.com/c.tgz",
+            "https://example.com/d/-/d-4.0.0.tgz",
+        ]
+    );
+    Ok(())
+}
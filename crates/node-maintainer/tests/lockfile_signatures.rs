@@ -0,0 +1,157 @@
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::Lockfile;
+use oro_common::CorgiManifest;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+mod common;
+use common::{builder, mock_package};
+
+const LOCKFILE: &str = r#"
+lockfile-version 1
+root {
+    dependencies {
+        a "^1.0.0"
+        b "^2.0.0"
+    }
+}
+pkg "a" {
+    version "1.0.0"
+    resolved "a/-/a-1.0.0.tgz"
+    integrity "sha512-aaa"
+    signature-keyid "SHA256:deadbeef"
+}
+pkg "b" {
+    version "2.0.0"
+    resolved "b/-/b-2.0.0.tgz"
+    integrity "sha512-bbb"
+}
+"#;
+
+#[test]
+fn signature_keyids_round_trip() -> Result<()> {
+    let lockfile = Lockfile::from_kdl(LOCKFILE)?;
+    assert_eq!(
+        lockfile
+            .packages()
+            .values()
+            .map(|p| p.signature_keyid.as_deref())
+            .collect::<Vec<_>>(),
+        vec![Some("SHA256:deadbeef"), None]
+    );
+    assert_eq!(Lockfile::from_kdl(lockfile.to_kdl())?, lockfile);
+    assert!(lockfile
+        .to_kdl()
+        .to_string()
+        .contains(r#"signature-keyid "SHA256:deadbeef""#));
+    Ok(())
+}
+
+#[async_std::test]
+async fn lockfile_signatures_are_not_trusted() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/-/npm/v1/keys"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "keys": [{
+                "expires": null,
+                "keyid": "SHA256:deadbeef",
+                "keytype": "ecdsa-sha2-nistp256",
+                "scheme": "ecdsa-sha2-nistp256",
+                "key": "MFkwEw=="
+            }]
+        })))
+        .mount(&mock_server)
+        .await;
+    mock_package(&mock_server, "a", json!({})).await?;
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "app",
+        "dependencies": { "a": "^1" },
+    }))
+    .into_diagnostic()?;
+
+    // `a` isn't signed, but someone edited the lockfile to say it was.
+    let nm = Box::pin(builder(dir.path(), &mock_server)?.resolve_manifest(root.clone())).await?;
+    let forged = nm.to_kdl()?.to_string().replace(
+        "    integrity ",
+        "    signature-keyid \"SHA256:deadbeef\"\n    integrity ",
+    );
+    let keyid = |lockfile: Lockfile| {
+        lockfile
+            .packages()
+            .values()
+            .find_map(|p| p.signature_keyid.clone())
+    };
+    assert_eq!(
+        keyid(Lockfile::from_kdl(forged.as_str())?),
+        Some("SHA256:deadbeef".into())
+    );
+
+    // Without verification, the lockfile's word is taken for it.
+    let nm = Box::pin(
+        builder(dir.path(), &mock_server)?
+            .kdl_lock(forged.as_str())?
+            .resolve_manifest(root.clone()),
+    )
+    .await?;
+    assert_eq!(keyid(nm.to_lockfile()?), Some("SHA256:deadbeef".into()));
+
+    // But verifying always checks with the registry.
+    let nm = Box::pin(
+        builder(dir.path(), &mock_server)?
+            .kdl_lock(forged.as_str())?
+            .verify_signatures(true)
+            .resolve_manifest(root),
+    )
+    .await?;
+    assert_eq!(keyid(nm.to_lockfile()?), None);
+    Ok(())
+}
+
+#[async_std::test]
+async fn verifying_refreshes_lockfile_deprecations() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let mock_server = MockServer::start().await;
+    mock_package(&mock_server, "a", json!({})).await?;
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "app",
+        "dependencies": { "a": "^1" },
+    }))
+    .into_diagnostic()?;
+    let nm = Box::pin(builder(dir.path(), &mock_server)?.resolve_manifest(root.clone())).await?;
+    let lockfile = nm.to_kdl()?.to_string();
+    assert!(nm.deprecations().is_empty());
+
+    // `a` gets deprecated after the lockfile was written.
+    mock_server.reset().await;
+    mock_package(&mock_server, "a", json!({ "deprecated": "use b" })).await?;
+
+    let nm = Box::pin(
+        builder(dir.path(), &mock_server)?
+            .kdl_lock(lockfile.as_str())?
+            .resolve_manifest(root.clone()),
+    )
+    .await?;
+    assert!(nm.deprecations().is_empty(), "the lockfile is trusted");
+
+    let nm = Box::pin(
+        builder(dir.path(), &mock_server)?
+            .kdl_lock(lockfile.as_str())?
+            .verify_signatures(true)
+            .resolve_manifest(root),
+    )
+    .await?;
+    assert_eq!(
+        nm.deprecations()
+            .into_iter()
+            .map(|pkg| pkg.reason)
+            .collect::<Vec<_>>(),
+        vec![Some("use b".into())]
+    );
+    Ok(())
+}
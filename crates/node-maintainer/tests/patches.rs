@@ -0,0 +1,240 @@
+use std::path::Path;
+
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::{create_patch, NodeMaintainerError};
+use oro_common::CorgiManifest;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use ssri::Integrity;
+use wiremock::MockServer;
+
+mod common;
+use common::{builder, install, mock_package_with_files, read};
+
+const ORIGINAL_INDEX: &str =
+    "const a = 1;\nconst b = 2;\nconst c = 3;\nmodule.exports = a + b + c;";
+const PATCHED_INDEX: &str =
+    "const a = 1;\nconst b = 20;\nconst c = 3;\nmodule.exports = a + b + c;\n";
+
+#[async_std::test]
+async fn applies_patched_dependencies() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let cache = dir.path().join("cache");
+    let mock_server = MockServer::start().await;
+    mock_lib(&mock_server).await?;
+    let patch = make_patch(dir.path())?;
+    assert!(patch.contains("\\ No newline at end of file"));
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "app",
+        "dependencies": { "lib": "^1" },
+        "patchedDependencies": { "lib@1.0.0": "patches/lib@1.0.0.patch" },
+    }))
+    .into_diagnostic()?;
+
+    for (project, hoisted) in [("isolated", false), ("hoisted", true)] {
+        let project = dir.path().join(project);
+        write_patch(&project, &patch)?;
+        let nm = install(
+            builder(&project, &mock_server)?
+                .cache(&cache)
+                .hoisted(hoisted),
+            root.clone(),
+        )
+        .await?;
+        let lib = project.join("node_modules").join("lib");
+        assert_eq!(read(&lib.join("index.js"))?, PATCHED_INDEX);
+        assert_eq!(
+            read(&lib.join("lib").join("extra.js"))?,
+            "module.exports = 4;\n"
+        );
+        assert!(!lib.join("README.md").exists());
+
+        let lockfile = nm.to_lockfile()?;
+        let locked = lockfile
+            .packages()
+            .values()
+            .find(|node| node.name.as_ref() == "lib")
+            .expect("lib should be in the lockfile");
+        assert_eq!(locked.patch, Some(Integrity::from(&patch)));
+        std::fs::write(
+            project.join("package-lock.kdl"),
+            lockfile.to_kdl().to_string(),
+        )
+        .into_diagnostic()?;
+
+        // Changing the patch changes the lockfile.
+        write_patch(&project, &patch.replace("const b = 20;", "const b = 30;"))?;
+        assert!(matches!(
+            Box::pin(
+                builder(&project, &mock_server)?
+                    .hoisted(hoisted)
+                    .locked(true)
+                    .resolve_manifest(root.clone())
+            )
+            .await,
+            Err(NodeMaintainerError::LockfileMismatch)
+        ));
+
+        // And reinstalls the package with the new patch.
+        install(
+            builder(&project, &mock_server)?.hoisted(hoisted),
+            root.clone(),
+        )
+        .await?;
+        assert_eq!(
+            read(&lib.join("index.js"))?,
+            PATCHED_INDEX.replace("20", "30")
+        );
+    }
+
+    // Patches never leak into the cache, or into unpatched installs of the
+    // same package.
+    let unpatched = dir.path().join("unpatched");
+    let mut plain = root.clone();
+    plain.patched_dependencies.clear();
+    install(builder(&unpatched, &mock_server)?.cache(&cache), plain).await?;
+    let lib = unpatched.join("node_modules").join("lib");
+    assert_eq!(read(&lib.join("index.js"))?, ORIGINAL_INDEX);
+    assert!(lib.join("README.md").exists());
+
+    // Patched packages get their own store entries.
+    let store_entry = |project: &Path| -> Result<String> {
+        Ok(project
+            .join("node_modules")
+            .join("lib")
+            .canonicalize()
+            .into_diagnostic()?
+            .parent()
+            .and_then(|nm| nm.parent())
+            .and_then(|entry| entry.file_name())
+            .expect("lib should be in a store entry")
+            .to_string_lossy()
+            .to_string())
+    };
+    assert_ne!(
+        store_entry(&dir.path().join("isolated"))?,
+        store_entry(&unpatched)?
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn configured_patches_override_package_json() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let mock_server = MockServer::start().await;
+    mock_lib(&mock_server).await?;
+    let project = dir.path().join("project");
+    write_patch(&project, &make_patch(dir.path())?)?;
+    std::fs::write(project.join("patches").join("broken.patch"), "").into_diagnostic()?;
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "app",
+        "dependencies": { "lib": "^1" },
+        "patchedDependencies": { "lib": "patches/broken.patch" },
+    }))
+    .into_diagnostic()?;
+
+    install(
+        builder(&project, &mock_server)?.patched_dependency("lib", "patches/lib@1.0.0.patch"),
+        root,
+    )
+    .await?;
+    assert_eq!(
+        read(&project.join("node_modules").join("lib").join("index.js"))?,
+        PATCHED_INDEX
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn patches_that_do_not_apply_fail() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let mock_server = MockServer::start().await;
+    mock_lib(&mock_server).await?;
+    let project = dir.path().join("project");
+    write_patch(
+        &project,
+        &make_patch(dir.path())?.replace("const b = 2;", "const b = 5;"),
+    )?;
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "app",
+        "dependencies": { "lib": "^1" },
+        "patchedDependencies": { "lib@1.0.0": "patches/lib@1.0.0.patch" },
+    }))
+    .into_diagnostic()?;
+
+    for hoisted in [false, true] {
+        let nm = Box::pin(
+            builder(&project, &mock_server)?
+                .hoisted(hoisted)
+                .resolve_manifest(root.clone()),
+        )
+        .await?;
+        nm.prune().await?;
+        assert!(matches!(
+            nm.extract().await,
+            Err(NodeMaintainerError::PatchApplyError { package, .. }) if package == "lib@1.0.0"
+        ));
+        // Nothing half-patched is left behind for the next install to trust.
+        assert!(!project.join("node_modules").join("lib").exists());
+    }
+    Ok(())
+}
+
+#[test]
+fn unchanged_binary_files_are_skipped() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let original = dir.path().join("original");
+    let edited = dir.path().join("edited");
+    for (root, index) in [(&original, ORIGINAL_INDEX), (&edited, PATCHED_INDEX)] {
+        std::fs::create_dir_all(root).into_diagnostic()?;
+        std::fs::write(root.join("index.js"), index).into_diagnostic()?;
+        std::fs::write(root.join("addon.node"), [0xff, 0xfe, 0x00]).into_diagnostic()?;
+    }
+    let patch = create_patch(&original, &edited)?;
+    assert!(patch.contains("index.js"));
+    assert!(!patch.contains("addon.node"));
+
+    // Binary files can't be diffed once they change, though.
+    std::fs::write(edited.join("addon.node"), [0xff, 0xfe, 0x01]).into_diagnostic()?;
+    assert!(matches!(
+        create_patch(&original, &edited),
+        Err(NodeMaintainerError::BinaryPatchFile(path)) if path.ends_with("addon.node")
+    ));
+    Ok(())
+}
+
+/// Builds a patch for `lib` that edits `index.js` (which is missing its
+/// trailing newline), deletes `README.md`, and adds `lib/extra.js`.
+fn make_patch(dir: &Path) -> Result<String> {
+    let original = dir.join("original");
+    let edited = dir.join("edited");
+    for (root, index) in [(&original, ORIGINAL_INDEX), (&edited, PATCHED_INDEX)] {
+        std::fs::create_dir_all(root.join("lib")).into_diagnostic()?;
+        std::fs::write(root.join("package.json"), lib_manifest().to_string()).into_diagnostic()?;
+        std::fs::write(root.join("index.js"), index).into_diagnostic()?;
+    }
+    std::fs::write(original.join("README.md"), "# lib\n").into_diagnostic()?;
+    std::fs::write(edited.join("lib").join("extra.js"), "module.exports = 4;\n")
+        .into_diagnostic()?;
+    Ok(create_patch(&original, &edited)?)
+}
+
+fn write_patch(project: &Path, patch: &str) -> Result<()> {
+    let patches = project.join("patches");
+    std::fs::create_dir_all(&patches).into_diagnostic()?;
+    std::fs::write(patches.join("lib@1.0.0.patch"), patch).into_diagnostic()
+}
+
+fn lib_manifest() -> Value {
+    json!({ "name": "lib", "version": "1.0.0" })
+}
+
+async fn mock_lib(mock_server: &MockServer) -> Result<()> {
+    mock_package_with_files(
+        mock_server,
+        "lib",
+        lib_manifest(),
+        &[("index.js", ORIGINAL_INDEX), ("README.md", "# lib\n")],
+    )
+    .await
+}
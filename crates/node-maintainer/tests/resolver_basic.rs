@@ -245,6 +245,210 @@ pkg "d" {
     Ok(())
 }
 
+#[async_std::test]
+async fn prefer_dedupe() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    // `b` gets the newest `c` placed at the root, which means `e` needs its
+    // own nested copy, even though `c@1` would have worked for both.
+    let mock_data = r#"
+    a {
+        version "1.0.0"
+        dependencies {
+            b "^2.0.0"
+            e "^1.0.0"
+        }
+    }
+    b {
+        version "2.0.0"
+        dependencies {
+            c "^1.0.0 || ^2.0.0"
+        }
+    }
+    c {
+        version "1.0.0"
+    }
+    c {
+        version "2.0.0"
+    }
+    e {
+        version "1.0.0"
+        dependencies {
+            c "^1.0.0"
+        }
+    }
+    "#;
+    mocks_from_kdl(&mock_server, mock_data.parse()?).await;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .resolve_spec("a@^1")
+        .await?;
+    assert_eq!(nm.package_count(), 5);
+    assert_eq!(nm.deduped_count(), 0);
+
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .prefer_dedupe(true)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .resolve_spec("a@^1")
+        .await?;
+    assert_eq!(nm.deduped_count(), 1);
+    assert_eq!(
+        nm.to_kdl()?.to_string(),
+        r#"// This file is automatically generated and not intended for manual editing.
+lockfile-version 1
+root {
+    version "1.0.0"
+    dependencies {
+        b ">=2.0.0 <3.0.0-0"
+        e ">=1.0.0 <2.0.0-0"
+    }
+}
+pkg "b" {
+    version "2.0.0"
+    resolved "https://example.com/-/b-2.0.0.tgz"
+    integrity "sha512-deadbeef"
+    dependencies {
+        c ">=1.0.0 <2.0.0-0||>=2.0.0 <3.0.0-0"
+    }
+}
+pkg "c" {
+    version "1.0.0"
+    resolved "https://example.com/-/c-1.0.0.tgz"
+    integrity "sha512-deadbeef"
+}
+pkg "e" {
+    version "1.0.0"
+    resolved "https://example.com/-/e-1.0.0.tgz"
+    integrity "sha512-deadbeef"
+    dependencies {
+        c ">=1.0.0 <2.0.0-0"
+    }
+}
+"#
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn deprecations() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    let mock_data = r#"
+    a {
+        version "1.0.0"
+        dependencies {
+            b "^2.0.0"
+        }
+    }
+    b {
+        version "2.0.0"
+        deprecated "use d instead"
+        dependencies {
+            c "^3.0.0"
+        }
+    }
+    c {
+        version "3.0.0"
+        deprecated true
+    }
+    "#;
+    mocks_from_kdl(&mock_server, mock_data.parse()?).await;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .resolve_spec("a@^1")
+        .await?;
+
+    let deprecations = nm.deprecations();
+    assert_eq!(
+        deprecations
+            .iter()
+            .map(|d| (
+                d.name.as_str(),
+                d.reason.as_deref(),
+                d.dependency_path.join(" > "),
+                d.is_direct()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("b", Some("use d instead"), "b".into(), true),
+            ("c", None, "b > c".into(), false),
+        ]
+    );
+
+    // Deprecations are recorded in the lockfile, so they're still reported
+    // for packages that get resolved from it.
+    let lockfile = nm.to_kdl()?.to_string();
+    assert!(lockfile.contains(r#"deprecated "use d instead""#));
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .kdl_lock(lockfile)?
+        .resolve_spec("a@^1")
+        .await?;
+    assert_eq!(nm.deprecations(), deprecations);
+    Ok(())
+}
+
+#[async_std::test]
+async fn sbom() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    let mock_data = r#"
+    a {
+        version "1.0.0"
+        dependencies {
+            b "^2.0.0"
+            c "^3.0.0"
+        }
+    }
+    b {
+        version "2.0.0"
+        dependencies {
+            c "^3.0.0"
+        }
+    }
+    c {
+        version "3.0.0"
+    }
+    "#;
+    mocks_from_kdl(&mock_server, mock_data.parse()?).await;
+    let nm = NodeMaintainer::builder()
+        .concurrency(1)
+        .registry(mock_server.uri().parse().into_diagnostic()?)
+        .resolve_spec("a@^1")
+        .await?;
+
+    let sbom = nm.to_sbom()?;
+    assert_eq!(sbom.root.name, "a");
+    assert_eq!(
+        sbom.root.dependencies,
+        vec!["node_modules/b", "node_modules/c"]
+    );
+    assert_eq!(
+        sbom.components
+            .iter()
+            .map(|c| (
+                c.bom_ref.as_str(),
+                c.purl.as_deref(),
+                c.dependencies.clone()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                "node_modules/b",
+                Some("pkg:npm/b@2.0.0"),
+                vec!["node_modules/c".to_string()]
+            ),
+            ("node_modules/c", Some("pkg:npm/c@3.0.0"), vec![]),
+        ]
+    );
+    assert_eq!(
+        sbom.components[0].download_url.as_ref().map(|u| u.as_str()),
+        Some("https://example.com/-/b-2.0.0.tgz")
+    );
+    Ok(())
+}
+
 async fn mocks_from_kdl(mock_server: &MockServer, doc: KdlDocument) {
     let mut packuments = HashMap::new();
     for node in doc.nodes() {
@@ -281,6 +485,12 @@ async fn mocks_from_kdl(mock_server: &MockServer, doc: KdlDocument) {
         if let Some(deps) = dependencies {
             packument["versions"][version.clone()]["dependencies"] = deps;
         }
+        if let Some(deprecated) = children.get_arg("deprecated") {
+            packument["versions"][version.clone()]["deprecated"] = match deprecated.as_string() {
+                Some(reason) => json!(reason),
+                None => json!(deprecated.as_bool().unwrap()),
+            };
+        }
         // Last version gets "latest"
         packument["dist-tags"]["latest"] = json!(version);
     }
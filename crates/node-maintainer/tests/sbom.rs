@@ -0,0 +1,315 @@
+use std::path::Path;
+
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::{Lockfile, SbomOptions};
+use oro_common::CorgiManifest;
+use pretty_assertions::assert_eq;
+use serde_json::{json, Value};
+use wiremock::MockServer;
+
+mod common;
+use common::{builder, mock_package};
+
+const LOCKFILE: &str = r#"
+lockfile-version 1
+root {
+    dependencies {
+        a "^1.0.0"
+        "@scope/b" "^2.0.0"
+    }
+}
+pkg "a" {
+    version "1.0.0"
+    resolved "a/-/a-1.0.0.tgz"
+    integrity "sha1-AAECAwQFBgcICQoLDA0ODxAREhM="
+    dependencies {
+        c "^1.0.0"
+    }
+}
+pkg "a" "c" {
+    version "1.0.0"
+    resolved "c/-/c-1.0.0.tgz"
+}
+pkg "@scope/b" {
+    version "2.0.0"
+    resolved "@scope/b/-/b-2.0.0.tgz"
+    dependencies {
+        c "^2.0.0"
+    }
+}
+pkg "c" {
+    version "2.0.0"
+    resolved "c/-/c-2.0.0.tgz"
+}
+"#;
+
+fn write_manifest(dir: &Path, manifest: Value) -> Result<()> {
+    std::fs::create_dir_all(dir).into_diagnostic()?;
+    std::fs::write(dir.join("package.json"), manifest.to_string()).into_diagnostic()
+}
+
+fn sbom_fixture() -> Result<(tempfile::TempDir, node_maintainer::Sbom)> {
+    let root = tempfile::tempdir().into_diagnostic()?;
+    let modules = root.path().join("node_modules");
+    write_manifest(
+        root.path(),
+        json!({ "name": "my-project", "version": "0.1.0", "license": "MIT" }),
+    )?;
+    write_manifest(
+        &modules.join("a"),
+        json!({
+            "name": "a",
+            "version": "1.0.0",
+            "license": "(MIT OR Apache-2.0)",
+            "author": "Kat <kat@example.com> (https://example.com)",
+            "repository": { "type": "git", "url": "git+https://github.com/example/a.git" }
+        }),
+    )?;
+    write_manifest(
+        &modules.join("@scope").join("b"),
+        json!({
+            "name": "@scope/b",
+            "version": "2.0.0",
+            "license": "SEE LICENSE IN LICENSE.md",
+            "author": { "name": "Someone" }
+        }),
+    )?;
+    write_manifest(
+        &modules.join("a").join("node_modules").join("c"),
+        json!({ "name": "c", "version": "1.0.0", "license": "UNLICENSED" }),
+    )?;
+    write_manifest(
+        &modules.join("c"),
+        json!({ "name": "c", "version": "2.0.0", "license": "MIT OR" }),
+    )?;
+    let sbom = Lockfile::from_kdl(LOCKFILE)?.to_sbom(
+        &SbomOptions::new()
+            .root(root.path())
+            .registry("https://my.registry.com/npm/".parse().into_diagnostic()?)
+            .scope_registry(
+                "@scope",
+                "https://scope.registry.com/".parse().into_diagnostic()?,
+            ),
+    );
+    Ok((root, sbom))
+}
+
+#[test]
+fn sbom_from_lockfile() -> Result<()> {
+    let (_root, sbom) = sbom_fixture()?;
+
+    assert_eq!(sbom.root.name, "my-project");
+    assert_eq!(sbom.root.version.as_deref(), Some("0.1.0"));
+    assert_eq!(
+        sbom.root.dependencies,
+        vec!["node_modules/@scope/b", "node_modules/a"]
+    );
+    assert_eq!(
+        sbom.components
+            .iter()
+            .map(|c| (c.bom_ref.as_str(), c.dependencies.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("node_modules/@scope/b", vec!["node_modules/c".to_string()]),
+            (
+                "node_modules/a",
+                vec!["node_modules/a/node_modules/c".to_string()]
+            ),
+            ("node_modules/a/node_modules/c", vec![]),
+            ("node_modules/c", vec![]),
+        ],
+        "dependencies resolve through nested node_modules first"
+    );
+    assert_eq!(
+        sbom.components
+            .iter()
+            .map(|c| c.download_url.as_ref().map(|u| u.to_string()))
+            .collect::<Vec<_>>(),
+        vec![
+            Some("https://scope.registry.com/@scope/b/-/b-2.0.0.tgz".into()),
+            Some("https://my.registry.com/npm/a/-/a-1.0.0.tgz".into()),
+            Some("https://my.registry.com/npm/c/-/c-1.0.0.tgz".into()),
+            Some("https://my.registry.com/npm/c/-/c-2.0.0.tgz".into()),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn sbom_cyclonedx() -> Result<()> {
+    let (_root, sbom) = sbom_fixture()?;
+    let bom = sbom.to_cyclonedx();
+
+    assert_eq!(bom["bomFormat"], "CycloneDX");
+    assert_eq!(bom["specVersion"], "1.5");
+    assert_eq!(
+        bom["metadata"]["component"],
+        json!({
+            "type": "application",
+            "bom-ref": ".",
+            "name": "my-project",
+            "version": "0.1.0",
+            "licenses": [{ "expression": "MIT" }],
+        })
+    );
+    assert_eq!(
+        bom["components"][1],
+        json!({
+            "type": "library",
+            "bom-ref": "node_modules/a",
+            "name": "a",
+            "version": "1.0.0",
+            "author": "Kat <kat@example.com>",
+            "licenses": [{ "expression": "(MIT OR Apache-2.0)" }],
+            "purl": "pkg:npm/a@1.0.0",
+            "hashes": [{
+                "alg": "SHA-1",
+                "content": "000102030405060708090a0b0c0d0e0f10111213",
+            }],
+            "externalReferences": [
+                { "type": "vcs", "url": "git+https://github.com/example/a.git" },
+                { "type": "distribution", "url": "https://my.registry.com/npm/a/-/a-1.0.0.tgz" },
+            ],
+        })
+    );
+    assert_eq!(bom["components"][0]["group"], "@scope");
+    assert_eq!(bom["components"][0]["name"], "b");
+    assert_eq!(bom["components"][0]["purl"], "pkg:npm/%40scope/b@2.0.0");
+    assert_eq!(
+        bom["components"][0]["licenses"],
+        json!([{ "license": { "name": "SEE LICENSE IN LICENSE.md" } }])
+    );
+    assert_eq!(
+        bom["dependencies"][0],
+        json!({
+            "ref": ".",
+            "dependsOn": ["node_modules/@scope/b", "node_modules/a"],
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn sbom_spdx() -> Result<()> {
+    let (_root, sbom) = sbom_fixture()?;
+    let doc = sbom.to_spdx();
+
+    assert_eq!(doc["spdxVersion"], "SPDX-2.3");
+    assert_eq!(doc["name"], "my-project@0.1.0");
+    assert!(doc["documentNamespace"]
+        .as_str()
+        .unwrap()
+        .starts_with("https://orogene.dev/spdx/my-project-0.1.0-"));
+    assert_eq!(
+        doc["packages"][2],
+        json!({
+            "SPDXID": "SPDXRef-Package-2",
+            "name": "a",
+            "versionInfo": "1.0.0",
+            "downloadLocation": "https://my.registry.com/npm/a/-/a-1.0.0.tgz",
+            "filesAnalyzed": false,
+            "licenseConcluded": "NOASSERTION",
+            "licenseDeclared": "(MIT OR Apache-2.0)",
+            "copyrightText": "NOASSERTION",
+            "originator": "Person: Kat (kat@example.com)",
+            "sourceInfo": "from git+https://github.com/example/a.git",
+            "checksums": [{
+                "algorithm": "SHA1",
+                "checksumValue": "000102030405060708090a0b0c0d0e0f10111213",
+            }],
+            "externalRefs": [{
+                "referenceCategory": "PACKAGE-MANAGER",
+                "referenceType": "purl",
+                "referenceLocator": "pkg:npm/a@1.0.0",
+            }],
+        })
+    );
+    assert_eq!(doc["packages"][1]["licenseDeclared"], "NOASSERTION");
+    assert_eq!(
+        doc["relationships"],
+        json!([
+            {
+                "spdxElementId": "SPDXRef-DOCUMENT",
+                "relationshipType": "DESCRIBES",
+                "relatedSpdxElement": "SPDXRef-Package-0",
+            },
+            {
+                "spdxElementId": "SPDXRef-Package-0",
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": "SPDXRef-Package-1",
+            },
+            {
+                "spdxElementId": "SPDXRef-Package-0",
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": "SPDXRef-Package-2",
+            },
+            {
+                "spdxElementId": "SPDXRef-Package-1",
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": "SPDXRef-Package-4",
+            },
+            {
+                "spdxElementId": "SPDXRef-Package-2",
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": "SPDXRef-Package-3",
+            },
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn sbom_non_spdx_licenses() -> Result<()> {
+    let (_root, sbom) = sbom_fixture()?;
+    let bom = sbom.to_cyclonedx();
+    assert_eq!(
+        bom["components"][2]["licenses"],
+        json!([{ "license": { "name": "UNLICENSED" } }])
+    );
+    assert_eq!(
+        bom["components"][3]["licenses"],
+        json!([{ "license": { "name": "MIT OR" } }]),
+        "incomplete expressions are reported by name"
+    );
+
+    let doc = sbom.to_spdx();
+    assert_eq!(doc["packages"][3]["licenseDeclared"], "NOASSERTION");
+    assert_eq!(doc["packages"][4]["licenseDeclared"], "NOASSERTION");
+    Ok(())
+}
+
+#[async_std::test]
+async fn resolved_sbom_licenses() -> Result<()> {
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "a",
+        json!({ "license": "MIT", "dependencies": { "b": "^1" } }),
+    )
+    .await?;
+    mock_package(&mock_server, "b", json!({ "license": "ISC" })).await?;
+    let root = tempfile::tempdir().into_diagnostic()?;
+    // Whatever's installed from before has nothing to do with the new tree.
+    let stale = root.path().join("node_modules").join("a");
+    std::fs::create_dir_all(&stale).into_diagnostic()?;
+    std::fs::write(
+        stale.join("package.json"),
+        json!({ "name": "a", "version": "0.1.0", "license": "GPL-3.0-only" }).to_string(),
+    )
+    .into_diagnostic()?;
+
+    let manifest: CorgiManifest =
+        serde_json::from_value(json!({ "dependencies": { "a": "^1" } })).into_diagnostic()?;
+    let nm = builder(root.path(), &mock_server)?
+        .resolve_manifest(manifest)
+        .await?;
+    let sbom = nm.to_resolved_sbom().await?;
+    assert_eq!(
+        sbom.components
+            .iter()
+            .map(|c| (c.name.as_str(), c.license.as_deref()))
+            .collect::<Vec<_>>(),
+        vec![("a", Some("MIT")), ("b", Some("ISC"))]
+    );
+    Ok(())
+}
@@ -0,0 +1,293 @@
+use std::path::Path;
+
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::{
+    script_log_file_name, script_log_package_name, NodeMaintainer, NodeMaintainerError,
+    NodeMaintainerOptions,
+};
+use oro_common::CorgiManifest;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use wiremock::MockServer;
+
+mod common;
+use common::{builder, install_spec, mock_package, mock_package_with_files};
+
+#[async_std::test]
+async fn restores_install_script_side_effects() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let cache = dir.path().join("cache");
+    let runs = dir.path().join("runs.log");
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "built",
+        json!({
+            "scripts": {
+                "postinstall": format!("echo built > built.txt && echo ran >> \"{}\"", runs.display())
+            }
+        }),
+    )
+    .await?;
+    mock_package(
+        &mock_server,
+        "app",
+        json!({ "dependencies": { "built": "^1" } }),
+    )
+    .await?;
+
+    // The first install runs the script, and caches what it left behind.
+    let project = dir.path().join("a");
+    install(builder(&project, &mock_server)?.cache(&cache)).await?;
+    assert_built(&project)?;
+    assert_eq!(script_runs(&runs)?, 1);
+
+    // Any other install of the same package gets that back instead of
+    // running the script again, no matter which linker it uses.
+    let project = dir.path().join("b");
+    install(builder(&project, &mock_server)?.cache(&cache).hoisted(true)).await?;
+    assert_built(&project)?;
+    assert_eq!(script_runs(&runs)?, 1);
+
+    // Unless the cache is turned off.
+    let project = dir.path().join("c");
+    install(
+        builder(&project, &mock_server)?
+            .cache(&cache)
+            .side_effects_cache(false),
+    )
+    .await?;
+    assert_built(&project)?;
+    assert_eq!(script_runs(&runs)?, 2);
+    Ok(())
+}
+
+#[async_std::test]
+async fn script_logs() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let logs = dir.path().join("logs");
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "flaky",
+        json!({ "scripts": { "install": "echo starting && echo oh no >&2 && exit 1" } }),
+    )
+    .await?;
+    mock_package(
+        &mock_server,
+        "app",
+        json!({ "optionalDependencies": { "flaky": "^1" } }),
+    )
+    .await?;
+
+    // Optional dependencies are allowed to fail, but the failure is still
+    // recorded, along with the output that led up to it.
+    for project in ["a", "b"] {
+        let nm =
+            install(builder(&dir.path().join(project), &mock_server)?.script_logs(&logs)).await?;
+        let failures = nm.script_failures().await;
+        assert_eq!(failures.len(), 1);
+        let failure = &failures[0];
+        assert_eq!(failure.package.name(), "flaky");
+        assert_eq!(failure.event, "install");
+        assert!(failure.optional);
+        // Lines from stdout and stderr can arrive in either order.
+        let mut output = failure.output.clone();
+        output.sort();
+        assert_eq!(output, vec!["oh no", "starting"]);
+        let log = logs.join("flaky@1.0.0.log");
+        assert_eq!(failure.log.as_deref(), Some(log.as_path()));
+
+        // Each time the script runs, it gets a fresh log.
+        let contents = std::fs::read_to_string(&log).into_diagnostic()?;
+        let mut lines = contents.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines.remove(0),
+            "> install: echo starting && echo oh no >&2 && exit 1"
+        );
+        lines.sort();
+        assert_eq!(lines, vec!["oh no", "starting"]);
+    }
+
+    let scoped = script_log_file_name("@scope/pkg", Some("1.0.0"));
+    assert_eq!(scoped, "@scope+pkg@1.0.0.log");
+    assert_eq!(
+        script_log_package_name(&scoped).as_deref(),
+        Some("@scope/pkg")
+    );
+    assert_eq!(
+        script_log_package_name("flaky.log").as_deref(),
+        Some("flaky")
+    );
+    Ok(())
+}
+
+#[async_std::test]
+async fn rebuilds_installed_packages() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let runs = dir.path().join("runs.log");
+    let mock_server = MockServer::start().await;
+    for name in ["built", "other"] {
+        mock_package(
+            &mock_server,
+            name,
+            json!({
+                "bin": { name: "package.json" },
+                "scripts": {
+                    "postinstall": format!("echo {name} >> \"{}\"", runs.display())
+                }
+            }),
+        )
+        .await?;
+    }
+    let root: CorgiManifest = serde_json::from_value(json!({
+        "name": "app",
+        "dependencies": { "built": "^1", "other": "^1" },
+    }))
+    .into_diagnostic()?;
+
+    for (project, hoisted) in [("isolated", false), ("hoisted", true)] {
+        let project = dir.path().join(project);
+        let _ = std::fs::remove_file(&runs);
+        common::install(
+            builder(&project, &mock_server)?.hoisted(hoisted),
+            root.clone(),
+        )
+        .await?
+        .rebuild(false)
+        .await?;
+        let bin = project.join("node_modules").join(".bin").join("built");
+        std::fs::remove_file(&bin).into_diagnostic()?;
+
+        // The layout comes from what's installed, not the options.
+        let nm = Box::pin(
+            builder(&project, &mock_server)?
+                .hoisted(!hoisted)
+                .resolve_installed(root.clone()),
+        )
+        .await?;
+        nm.rebuild_installed(&["built"]).await?;
+        assert_eq!(script_lines(&runs)?, vec!["built", "built", "other"]);
+        assert!(bin.symlink_metadata().is_ok());
+
+        nm.rebuild_installed(&[] as &[&str]).await?;
+        assert_eq!(
+            script_lines(&runs)?,
+            vec!["built", "built", "built", "other", "other"]
+        );
+
+        assert!(matches!(
+            nm.rebuild_installed(&["missing"]).await,
+            Err(NodeMaintainerError::PackageNotInstalled(name)) if name == "missing"
+        ));
+    }
+
+    // Nothing can be rebuilt before anything is installed.
+    assert!(matches!(
+        Box::pin(builder(&dir.path().join("empty"), &mock_server)?.resolve_installed(root)).await,
+        Err(NodeMaintainerError::NotInstalled(_))
+    ));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[async_std::test]
+async fn lifecycle_order() -> Result<()> {
+    let dir = tempfile::tempdir().into_diagnostic()?;
+    let runs = dir.path().join("runs.log");
+    let log = |line: &str| format!("echo {line} >> \"{}\"", runs.display());
+    let mock_server = MockServer::start().await;
+    mock_package_with_files(
+        &mock_server,
+        "node-gyp",
+        json!({ "bin": { "node-gyp": "cli.js" } }),
+        &[(
+            "cli.js",
+            &format!("#!/bin/sh\necho node-gyp $1 >> \"{}\"\n", runs.display()),
+        )],
+    )
+    .await?;
+    // Packages with a binding.gyp get built with node-gyp when they don't
+    // have an install script of their own, but registry packages were
+    // prepared before they were published.
+    mock_package_with_files(
+        &mock_server,
+        "native",
+        json!({
+            "dependencies": { "node-gyp": "^1" },
+            "scripts": {
+                "postinstall": log("native:postinstall"),
+                "prepare": log("native:prepare"),
+            }
+        }),
+        &[("binding.gyp", "{}")],
+    )
+    .await?;
+
+    let project = dir.path().join("project");
+    std::fs::create_dir_all(&project).into_diagnostic()?;
+    let manifest = json!({
+        "name": "project",
+        "dependencies": { "native": "^1" },
+        "scripts": {
+            "preinstall": log("project:preinstall"),
+            "install": log("project:install"),
+            "postinstall": log("project:postinstall"),
+            "prepare": log("project:prepare"),
+        }
+    });
+    std::fs::write(project.join("package.json"), manifest.to_string()).into_diagnostic()?;
+    let root: CorgiManifest = serde_json::from_value(manifest).into_diagnostic()?;
+    common::install(builder(&project, &mock_server)?, root)
+        .await?
+        .rebuild(false)
+        .await?;
+
+    // The project's own scripts run once all its dependencies are built.
+    let lines = std::fs::read_to_string(&runs).into_diagnostic()?;
+    assert_eq!(
+        lines.lines().map(|line| line.trim()).collect::<Vec<_>>(),
+        vec![
+            "node-gyp rebuild",
+            "native:postinstall",
+            "project:preinstall",
+            "project:install",
+            "project:postinstall",
+            "project:prepare",
+        ]
+    );
+    Ok(())
+}
+
+async fn install(builder: NodeMaintainerOptions) -> Result<NodeMaintainer> {
+    let nm = install_spec(builder, "app@^1").await?;
+    nm.rebuild(false).await?;
+    Ok(nm)
+}
+
+fn assert_built(project: &Path) -> Result<()> {
+    let built =
+        std::fs::read_to_string(project.join("node_modules").join("built").join("built.txt"))
+            .into_diagnostic()?;
+    assert_eq!(built.trim(), "built");
+    Ok(())
+}
+
+fn script_runs(runs: &Path) -> Result<usize> {
+    Ok(std::fs::read_to_string(runs)
+        .into_diagnostic()?
+        .lines()
+        .count())
+}
+
+/// Lines the scripts appended to `runs`, sorted, since scripts run
+/// concurrently.
+fn script_lines(runs: &Path) -> Result<Vec<String>> {
+    let mut lines = std::fs::read_to_string(runs)
+        .into_diagnostic()?
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect::<Vec<_>>();
+    lines.sort();
+    Ok(lines)
+}
@@ -0,0 +1,113 @@
+use node_semver::{Range, Version};
+use serde_json::Value;
+
+use crate::api::escape_name;
+use crate::{OroClient, OroClientError};
+
+impl OroClient {
+    /// Marks every published version of a package that satisfies `range` as
+    /// deprecated, with `message` as the reason. An empty message
+    /// un-deprecates them instead.
+    ///
+    /// Returns the versions that were updated.
+    pub async fn deprecate(
+        &self,
+        package_name: impl AsRef<str>,
+        range: &Range,
+        message: &str,
+        otp: Option<&str>,
+    ) -> Result<Vec<String>, OroClientError> {
+        let package_name = package_name.as_ref();
+        let mut packument = self.packument_for_write(package_name).await?;
+        let mut deprecated = Vec::new();
+        if let Some(versions) = packument
+            .get_mut("versions")
+            .and_then(|versions| versions.as_object_mut())
+        {
+            for (version, manifest) in versions.iter_mut() {
+                let matches = version
+                    .parse::<Version>()
+                    .map(|version| range.satisfies(&version))
+                    .unwrap_or(false);
+                if matches {
+                    manifest["deprecated"] = Value::String(message.into());
+                    deprecated.push(version.clone());
+                }
+            }
+        }
+        if deprecated.is_empty() {
+            return Err(OroClientError::NoMatchingVersions(
+                package_name.into(),
+                range.to_string(),
+            ));
+        }
+        self.put_packument(package_name, &escape_name(package_name), &packument, otp)
+            .await?;
+        Ok(deprecated)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn deprecate() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+
+        Mock::given(method("GET"))
+            .and(path("pkg"))
+            .and(query_param("write", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "_id": "pkg",
+                "_rev": "1-deadbeef",
+                "name": "pkg",
+                "versions": {
+                    "1.0.0": { "name": "pkg", "version": "1.0.0" },
+                    "1.1.0": { "name": "pkg", "version": "1.1.0", "deprecated": "old" },
+                    "2.0.0": { "name": "pkg", "version": "2.0.0" }
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("pkg"))
+            .and(body_json(json!({
+                "_id": "pkg",
+                "_rev": "1-deadbeef",
+                "name": "pkg",
+                "versions": {
+                    "1.0.0": { "name": "pkg", "version": "1.0.0", "deprecated": "use 2.x" },
+                    "1.1.0": { "name": "pkg", "version": "1.1.0", "deprecated": "use 2.x" },
+                    "2.0.0": { "name": "pkg", "version": "2.0.0" }
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut versions = client
+            .deprecate("pkg", &"^1".parse()?, "use 2.x", None)
+            .await?;
+        versions.sort();
+        assert_eq!(versions, vec!["1.0.0", "1.1.0"]);
+
+        assert!(
+            matches!(
+                client.deprecate("pkg", &"^3".parse()?, "nope", None).await,
+                Err(OroClientError::NoMatchingVersions(..))
+            ),
+            "Ranges that match nothing are an error"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+use indexmap::IndexMap;
+
+use crate::api::escape_name;
+use crate::notify::Notify;
+use crate::{OroClient, OroClientError};
+
+impl OroClient {
+    /// Fetches the dist-tags for a package, as a map of tag names to
+    /// versions.
+    pub async fn dist_tags(
+        &self,
+        package_name: impl AsRef<str>,
+    ) -> Result<IndexMap<String, String>, OroClientError> {
+        let package_name = package_name.as_ref();
+        let url = self.registry.join(&format!(
+            "-/package/{}/dist-tags",
+            escape_name(package_name)
+        ))?;
+        let res = self
+            .client_uncached
+            .get(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .send()
+            .await?
+            .notify();
+        if !res.status().is_success() {
+            return Err(
+                OroClientError::from_response(&self.registry, Some(package_name), res).await,
+            );
+        }
+        let text = res.text().await?;
+        serde_json::from_str(&text)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+
+    /// Points `tag` at `version` of a package, creating the tag if it
+    /// doesn't exist yet.
+    pub async fn add_dist_tag(
+        &self,
+        package_name: impl AsRef<str>,
+        tag: &str,
+        version: &str,
+        otp: Option<&str>,
+    ) -> Result<(), OroClientError> {
+        let package_name = package_name.as_ref();
+        let mut req = self
+            .client_uncached
+            .put(self.dist_tag_url(package_name, tag)?)
+            .header("X-Oro-Registry", self.registry.to_string())
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(version).expect("This type conversion should work"));
+        if let Some(otp) = otp {
+            req = req.header("npm-otp", otp);
+        }
+        let res = req.send().await?.notify();
+        if !res.status().is_success() {
+            return Err(
+                OroClientError::from_response(&self.registry, Some(package_name), res).await,
+            );
+        }
+        Ok(())
+    }
+
+    /// Removes a dist-tag from a package.
+    pub async fn remove_dist_tag(
+        &self,
+        package_name: impl AsRef<str>,
+        tag: &str,
+        otp: Option<&str>,
+    ) -> Result<(), OroClientError> {
+        let package_name = package_name.as_ref();
+        let mut req = self
+            .client_uncached
+            .delete(self.dist_tag_url(package_name, tag)?)
+            .header("X-Oro-Registry", self.registry.to_string());
+        if let Some(otp) = otp {
+            req = req.header("npm-otp", otp);
+        }
+        let res = req.send().await?.notify();
+        if !res.status().is_success() {
+            return Err(
+                OroClientError::from_response(&self.registry, Some(package_name), res).await,
+            );
+        }
+        Ok(())
+    }
+
+    fn dist_tag_url(&self, package_name: &str, tag: &str) -> Result<url::Url, OroClientError> {
+        let tag = percent_encoding::utf8_percent_encode(tag, percent_encoding::NON_ALPHANUMERIC);
+        Ok(self.registry.join(&format!(
+            "-/package/{}/dist-tags/{tag}",
+            escape_name(package_name)
+        ))?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn dist_tags() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+
+        {
+            let _guard = Mock::given(method("GET"))
+                .and(path("-/package/@scope%2fpkg/dist-tags"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "latest": "1.0.0",
+                    "next": "2.0.0-beta.1"
+                })))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            assert_eq!(
+                client.dist_tags("@scope/pkg").await?,
+                IndexMap::from([
+                    ("latest".to_string(), "1.0.0".to_string()),
+                    ("next".to_string(), "2.0.0-beta.1".to_string())
+                ])
+            );
+        }
+
+        {
+            let _guard = Mock::given(method("GET"))
+                .and(path("-/package/missing/dist-tags"))
+                .respond_with(ResponseTemplate::new(404))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            assert!(matches!(
+                client.dist_tags("missing").await,
+                Err(OroClientError::PackageNotFound(_, name)) if name == "missing"
+            ));
+        }
+
+        {
+            let _guard = Mock::given(method("PUT"))
+                .and(path("-/package/pkg/dist-tags/next"))
+                .and(header("npm-otp", "123456"))
+                .and(body_json(json!("2.0.0")))
+                .respond_with(ResponseTemplate::new(201))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            client
+                .add_dist_tag("pkg", "next", "2.0.0", Some("123456"))
+                .await?;
+        }
+
+        {
+            let _guard = Mock::given(method("DELETE"))
+                .and(path("-/package/pkg/dist-tags/next"))
+                .respond_with(ResponseTemplate::new(403).set_body_json(json!({
+                    "error": "You do not have permission to modify this package"
+                })))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            assert!(matches!(
+                client.remove_dist_tag("pkg", "next", None).await,
+                Err(OroClientError::Forbidden(msg))
+                    if msg == "You do not have permission to modify this package"
+            ));
+        }
+
+        Ok(())
+    }
+}
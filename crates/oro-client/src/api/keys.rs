@@ -0,0 +1,109 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{OroClient, OroClientError};
+
+/// A public key a registry uses to sign package versions, as returned by
+/// `/-/npm/v1/keys`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryKey {
+    /// When this key stopped being used for signing, as an ISO 8601
+    /// timestamp. `None` if the key is still active.
+    pub expires: Option<String>,
+    /// Identifier for this key, referenced by `dist.signatures[].keyid`.
+    pub keyid: String,
+    /// Type of key. Currently always `ecdsa-sha2-nistp256`.
+    pub keytype: String,
+    /// Signing scheme. Currently always `ecdsa-sha2-nistp256`.
+    pub scheme: String,
+    /// Base64-encoded, DER-encoded SubjectPublicKeyInfo for this key.
+    pub key: String,
+}
+
+#[derive(Deserialize)]
+struct RegistryKeys {
+    keys: Vec<RegistryKey>,
+}
+
+impl OroClient {
+    /// Fetches the public keys this registry signs packages with. Registries
+    /// that don't sign packages will return an empty list.
+    pub async fn registry_keys(&self) -> Result<Vec<RegistryKey>, OroClientError> {
+        let url = self.registry.join("-/npm/v1/keys")?;
+        let res = self
+            .client
+            .get(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .send()
+            .await?;
+        if res.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let text = res.error_for_status()?.text().await?;
+        serde_json::from_str::<RegistryKeys>(&text)
+            .map(|keys| keys.keys)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn registry_keys() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+
+        {
+            let _guard = Mock::given(method("GET"))
+                .and(path("-/npm/v1/keys"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                    "keys": [{
+                        "expires": null,
+                        "keyid": "SHA256:deadbeef",
+                        "keytype": "ecdsa-sha2-nistp256",
+                        "scheme": "ecdsa-sha2-nistp256",
+                        "key": "MFkwEw=="
+                    }]
+                })))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            assert_eq!(
+                client.registry_keys().await?,
+                vec![RegistryKey {
+                    expires: None,
+                    keyid: "SHA256:deadbeef".into(),
+                    keytype: "ecdsa-sha2-nistp256".into(),
+                    scheme: "ecdsa-sha2-nistp256".into(),
+                    key: "MFkwEw==".into(),
+                }]
+            );
+        }
+
+        {
+            let _guard = Mock::given(method("GET"))
+                .and(path("-/npm/v1/keys"))
+                .respond_with(ResponseTemplate::new(404))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            assert_eq!(
+                client.registry_keys().await?,
+                vec![],
+                "Registries without signing keys return an empty list"
+            );
+        }
+
+        Ok(())
+    }
+}
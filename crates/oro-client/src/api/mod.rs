@@ -1,5 +1,18 @@
+pub mod deprecate;
+pub mod dist_tags;
+pub mod keys;
 pub mod login;
 pub mod logout;
+pub mod owners;
 pub mod packument;
 pub mod ping;
+pub mod search;
 pub mod stream_external;
+pub mod tokens;
+pub mod whoami;
+
+/// Escapes a package name for use as a single registry URL path segment, so
+/// `@scope/pkg` becomes `@scope%2fpkg`.
+pub(crate) fn escape_name(name: &str) -> String {
+    name.replace('/', "%2f")
+}
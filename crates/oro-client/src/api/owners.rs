@@ -0,0 +1,311 @@
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::api::escape_name;
+use crate::notify::Notify;
+use crate::{OroClient, OroClientError};
+
+/// A user who can publish and manage a package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Maintainer {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+impl OroClient {
+    /// Lists the owners (maintainers) of a package.
+    pub async fn owners(
+        &self,
+        package_name: impl AsRef<str>,
+    ) -> Result<Vec<Maintainer>, OroClientError> {
+        let packument = self.packument_for_write(package_name.as_ref()).await?;
+        Ok(maintainers(&packument))
+    }
+
+    /// Adds a user as an owner of a package. Returns `false` if they were
+    /// already an owner.
+    pub async fn add_owner(
+        &self,
+        package_name: impl AsRef<str>,
+        username: &str,
+        otp: Option<&str>,
+    ) -> Result<bool, OroClientError> {
+        let package_name = package_name.as_ref();
+        let user = self.user(username).await?;
+        let packument = self.packument_for_write(package_name).await?;
+        let mut owners = maintainers(&packument);
+        if owners.iter().any(|owner| owner.name == user.name) {
+            return Ok(false);
+        }
+        owners.push(user);
+        self.update_owners(package_name, &packument, owners, otp)
+            .await?;
+        Ok(true)
+    }
+
+    /// Removes a user from the owners of a package. Returns `false` if they
+    /// weren't an owner to begin with.
+    pub async fn remove_owner(
+        &self,
+        package_name: impl AsRef<str>,
+        username: &str,
+        otp: Option<&str>,
+    ) -> Result<bool, OroClientError> {
+        let package_name = package_name.as_ref();
+        let packument = self.packument_for_write(package_name).await?;
+        let owners = maintainers(&packument);
+        let remaining = owners
+            .iter()
+            .filter(|owner| owner.name != username)
+            .cloned()
+            .collect::<Vec<_>>();
+        if remaining.len() == owners.len() {
+            return Ok(false);
+        }
+        if remaining.is_empty() {
+            return Err(OroClientError::LastOwner(
+                package_name.into(),
+                username.into(),
+            ));
+        }
+        self.update_owners(package_name, &packument, remaining, otp)
+            .await?;
+        Ok(true)
+    }
+
+    async fn user(&self, username: &str) -> Result<Maintainer, OroClientError> {
+        let url = self.registry.join(&format!(
+            "-/user/org.couchdb.user:{}",
+            utf8_percent_encode(username, NON_ALPHANUMERIC)
+        ))?;
+        let res = self
+            .client_uncached
+            .get(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .send()
+            .await?
+            .notify();
+        if res.status() == StatusCode::NOT_FOUND {
+            return Err(OroClientError::NoSuchUserError(username.into()));
+        } else if !res.status().is_success() {
+            return Err(OroClientError::from_response(&self.registry, None, res).await);
+        }
+        let text = res.text().await?;
+        serde_json::from_str(&text)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+
+    async fn update_owners(
+        &self,
+        package_name: &str,
+        packument: &Value,
+        owners: Vec<Maintainer>,
+        otp: Option<&str>,
+    ) -> Result<(), OroClientError> {
+        let rev = packument["_rev"].as_str().unwrap_or_default();
+        self.put_packument(
+            package_name,
+            &format!(
+                "{}/-rev/{}",
+                escape_name(package_name),
+                utf8_percent_encode(rev, NON_ALPHANUMERIC)
+            ),
+            &json!({
+                "_id": packument["_id"],
+                "_rev": rev,
+                "maintainers": owners,
+            }),
+            otp,
+        )
+        .await
+    }
+
+    /// Fetches the full, uncached packument for a package, in the form the
+    /// registry expects it to be sent back in when modifying it.
+    pub(crate) async fn packument_for_write(
+        &self,
+        package_name: &str,
+    ) -> Result<Value, OroClientError> {
+        let url = self
+            .registry
+            .join(&format!("{}?write=true", escape_name(package_name)))?;
+        let res = self
+            .client_uncached
+            .get(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .header("Accept", "application/json")
+            .send()
+            .await?
+            .notify();
+        if !res.status().is_success() {
+            return Err(
+                OroClientError::from_response(&self.registry, Some(package_name), res).await,
+            );
+        }
+        let text = res.text().await?;
+        serde_json::from_str(&text)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+
+    pub(crate) async fn put_packument(
+        &self,
+        package_name: &str,
+        path: &str,
+        body: &Value,
+        otp: Option<&str>,
+    ) -> Result<(), OroClientError> {
+        let mut req = self
+            .client_uncached
+            .put(self.registry.join(path)?)
+            .header("X-Oro-Registry", self.registry.to_string())
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+        if let Some(otp) = otp {
+            req = req.header("npm-otp", otp);
+        }
+        let res = req.send().await?.notify();
+        if !res.status().is_success() {
+            return Err(
+                OroClientError::from_response(&self.registry, Some(package_name), res).await,
+            );
+        }
+        Ok(())
+    }
+}
+
+fn maintainers(packument: &Value) -> Vec<Maintainer> {
+    packument["maintainers"]
+        .as_array()
+        .map(|maintainers| {
+            maintainers
+                .iter()
+                .filter_map(|maintainer| serde_json::from_value(maintainer.clone()).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    async fn mock_packument(mock_server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("@scope%2fpkg"))
+            .and(query_param("write", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "_id": "@scope/pkg",
+                "_rev": "3-deadbeef",
+                "name": "@scope/pkg",
+                "maintainers": [{ "name": "kat", "email": "kat@example.com" }],
+                "versions": {}
+            })))
+            .mount(mock_server)
+            .await;
+    }
+
+    #[async_std::test]
+    async fn owners() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+        mock_packument(&mock_server).await;
+
+        assert_eq!(
+            client.owners("@scope/pkg").await?,
+            vec![Maintainer {
+                name: "kat".into(),
+                email: Some("kat@example.com".into())
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn add_owner() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+        mock_packument(&mock_server).await;
+        Mock::given(method("GET"))
+            .and(path("-/user/org.couchdb.user:zkat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": "zkat",
+                "email": "zkat@example.com"
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("-/user/org.couchdb.user:kat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "name": "kat",
+                "email": "kat@example.com"
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("-/user/org.couchdb.user:nobody"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("@scope%2fpkg/-rev/3%2Ddeadbeef"))
+            .and(header("npm-otp", "123456"))
+            .and(body_json(json!({
+                "_id": "@scope/pkg",
+                "_rev": "3-deadbeef",
+                "maintainers": [
+                    { "name": "kat", "email": "kat@example.com" },
+                    { "name": "zkat", "email": "zkat@example.com" }
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert!(
+            client
+                .add_owner("@scope/pkg", "zkat", Some("123456"))
+                .await?
+        );
+        assert!(
+            !client.add_owner("@scope/pkg", "kat", None).await?,
+            "Existing owners are left alone"
+        );
+        assert!(matches!(
+            client.add_owner("@scope/pkg", "nobody", None).await,
+            Err(OroClientError::NoSuchUserError(user)) if user == "nobody"
+        ));
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn remove_owner() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+        mock_packument(&mock_server).await;
+
+        assert!(
+            !client.remove_owner("@scope/pkg", "zkat", None).await?,
+            "Removing a non-owner is a no-op"
+        );
+        assert!(
+            matches!(
+                client.remove_owner("@scope/pkg", "kat", None).await,
+                Err(OroClientError::LastOwner(..))
+            ),
+            "Packages can't be left without owners"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::notify::Notify;
+use crate::{OroClient, OroClientError};
+
+/// Options for a registry search, sent as query parameters to
+/// `/-/v1/search`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchOptions {
+    /// Search text. Supports the registry's qualifiers, like
+    /// `keywords:foo` or `author:bar`.
+    pub text: String,
+    /// Maximum number of results to return. Registries default to 20, and
+    /// cap this at 250.
+    pub size: Option<usize>,
+    /// Offset to start returning results from, for paging.
+    pub from: Option<usize>,
+    /// How much to weigh package quality when scoring results, from 0 to 1.
+    pub quality: Option<f64>,
+    /// How much to weigh package popularity when scoring results, from 0 to
+    /// 1.
+    pub popularity: Option<f64>,
+    /// How much to weigh package maintenance when scoring results, from 0 to
+    /// 1.
+    pub maintenance: Option<f64>,
+}
+
+impl SearchOptions {
+    pub fn new(text: impl AsRef<str>) -> Self {
+        Self {
+            text: text.as_ref().into(),
+            ..Default::default()
+        }
+    }
+
+    /// The scope the search text is restricted to, if it mentions one (for
+    /// example, `@myorg` or `@myorg/utils`). Doesn't include the leading
+    /// `@`.
+    pub fn scope(&self) -> Option<&str> {
+        self.text
+            .split_whitespace()
+            .filter_map(|term| term.strip_prefix('@'))
+            .map(|term| term.split('/').next().unwrap_or(term))
+            .find(|scope| !scope.is_empty())
+    }
+}
+
+/// A page of results from a registry search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResults {
+    /// Results on this page, best matches first.
+    pub objects: Vec<SearchResult>,
+    /// Total number of packages that matched, across all pages.
+    pub total: usize,
+    /// When the search was performed, according to the registry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub package: SearchPackage,
+    #[serde(default)]
+    pub score: SearchScore,
+    #[serde(default)]
+    pub search_score: f64,
+}
+
+/// Summary of a package, as included in search results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchPackage {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// When the version was published, as an ISO 8601 timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// Links to the package's `npm`, `homepage`, `repository`, and `bugs`
+    /// pages, when available.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub links: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<SearchUser>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintainers: Vec<SearchUser>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchUser {
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// How well a package scored, from 0 to 1, overall and on each criteria.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchScore {
+    #[serde(rename = "final")]
+    pub final_score: f64,
+    pub detail: SearchScoreDetail,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchScoreDetail {
+    pub quality: f64,
+    pub popularity: f64,
+    pub maintenance: f64,
+}
+
+impl OroClient {
+    /// Searches the registry for packages.
+    pub async fn search(&self, options: &SearchOptions) -> Result<SearchResults, OroClientError> {
+        let mut url = self.registry.join("-/v1/search")?;
+        {
+            let mut query = url.query_pairs_mut();
+            query.append_pair("text", &options.text);
+            if let Some(size) = options.size {
+                query.append_pair("size", &size.to_string());
+            }
+            if let Some(from) = options.from {
+                query.append_pair("from", &from.to_string());
+            }
+            if let Some(quality) = options.quality {
+                query.append_pair("quality", &quality.to_string());
+            }
+            if let Some(popularity) = options.popularity {
+                query.append_pair("popularity", &popularity.to_string());
+            }
+            if let Some(maintenance) = options.maintenance {
+                query.append_pair("maintenance", &maintenance.to_string());
+            }
+        }
+        let res = self
+            .client_uncached
+            .get(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .send()
+            .await?
+            .notify();
+        if !res.status().is_success() {
+            return Err(OroClientError::from_response(&self.registry, None, res).await);
+        }
+        let text = res.text().await?;
+        serde_json::from_str(&text)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn search() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+
+        Mock::given(method("GET"))
+            .and(path("-/v1/search"))
+            .and(query_param("text", "keywords:kdl parser"))
+            .and(query_param("size", "5"))
+            .and(query_param("from", "10"))
+            .and(query_param("popularity", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "objects": [{
+                    "package": {
+                        "name": "kdljs",
+                        "scope": "unscoped",
+                        "version": "0.2.0",
+                        "description": "KDL parser",
+                        "keywords": ["kdl"],
+                        "date": "2023-01-01T00:00:00.000Z",
+                        "links": { "npm": "https://www.npmjs.com/package/kdljs" },
+                        "publisher": { "username": "kat", "email": "kat@example.com" },
+                        "maintainers": [{ "username": "kat", "email": "kat@example.com" }]
+                    },
+                    "score": {
+                        "final": 0.5,
+                        "detail": { "quality": 0.6, "popularity": 0.1, "maintenance": 0.9 }
+                    },
+                    "searchScore": 0.001
+                }],
+                "total": 11,
+                "time": "Sun Jan 01 2023 00:00:00 GMT+0000 (Coordinated Universal Time)"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let results = client
+            .search(&SearchOptions {
+                size: Some(5),
+                from: Some(10),
+                popularity: Some(1.0),
+                ..SearchOptions::new("keywords:kdl parser")
+            })
+            .await?;
+        assert_eq!(results.total, 11);
+        assert_eq!(results.objects.len(), 1);
+        let result = &results.objects[0];
+        assert_eq!(result.package.name, "kdljs");
+        assert_eq!(result.package.description.as_deref(), Some("KDL parser"));
+        assert_eq!(
+            result
+                .package
+                .publisher
+                .as_ref()
+                .map(|p| p.username.as_str()),
+            Some("kat")
+        );
+        assert_eq!(result.score.final_score, 0.5);
+        assert_eq!(result.score.detail.maintenance, 0.9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn search_scope() {
+        assert_eq!(SearchOptions::new("@myorg/utils").scope(), Some("myorg"));
+        assert_eq!(SearchOptions::new("parser @myorg").scope(), Some("myorg"));
+        assert_eq!(SearchOptions::new("keywords:kdl").scope(), None);
+        assert_eq!(SearchOptions::new("@ foo").scope(), None);
+    }
+}
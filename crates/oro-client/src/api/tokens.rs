@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+
+use crate::notify::Notify;
+use crate::{OroClient, OroClientError};
+
+/// An access token for a registry account, as returned by
+/// `/-/npm/v1/tokens`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessToken {
+    /// Hash of the token, used to identify it when revoking it.
+    pub key: String,
+    /// The token itself. Registries only return a truncated prefix of it
+    /// when listing tokens. The full token is only available right after
+    /// creating it.
+    pub token: String,
+    /// Whether this token can only be used for read operations.
+    #[serde(default)]
+    pub readonly: bool,
+    /// Whether this token is an automation token, which skips two-factor
+    /// authentication checks.
+    #[serde(default)]
+    pub automation: bool,
+    /// IP ranges this token is restricted to, if any.
+    #[serde(default)]
+    pub cidr_whitelist: Option<Vec<String>>,
+    /// When this token was created, as an ISO 8601 timestamp.
+    pub created: Option<String>,
+    /// When this token was last updated, as an ISO 8601 timestamp.
+    pub updated: Option<String>,
+}
+
+/// Options for creating a new access token.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenOptions {
+    /// Create a read-only token.
+    pub readonly: bool,
+    /// Restrict the token to these IP ranges.
+    pub cidr_whitelist: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenPage {
+    objects: Vec<AccessToken>,
+    #[serde(default)]
+    urls: TokenPageUrls,
+}
+
+#[derive(Default, Deserialize)]
+struct TokenPageUrls {
+    next: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CreateToken<'a> {
+    password: &'a str,
+    readonly: bool,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    cidr_whitelist: &'a [String],
+}
+
+impl OroClient {
+    /// Lists all the access tokens for the current user, following
+    /// pagination until every token has been fetched.
+    pub async fn tokens(&self) -> Result<Vec<AccessToken>, OroClientError> {
+        let mut tokens = Vec::new();
+        let mut next = Some(self.registry.join("-/npm/v1/tokens")?);
+        while let Some(url) = next.take() {
+            let res = self
+                .client_uncached
+                .get(url.clone())
+                .header("X-Oro-Registry", self.registry.to_string())
+                .send()
+                .await?
+                .notify();
+            if !res.status().is_success() {
+                return Err(OroClientError::from_response(&self.registry, None, res).await);
+            }
+            let text = res.text().await?;
+            let page = serde_json::from_str::<TokenPage>(&text)
+                .map_err(|e| OroClientError::from_json_err(e, url.to_string(), text))?;
+            // Some registries keep returning a `next` URL for the last page,
+            // so an empty page also ends the listing.
+            if !page.objects.is_empty() {
+                next = page
+                    .urls
+                    .next
+                    .map(|next| self.registry.join(&next))
+                    .transpose()?;
+            }
+            tokens.extend(page.objects);
+        }
+        Ok(tokens)
+    }
+
+    /// Creates a new access token for the current user. The returned
+    /// token's `token` field contains the full token, which can't be
+    /// retrieved again later.
+    pub async fn create_token(
+        &self,
+        password: &str,
+        options: &TokenOptions,
+        otp: Option<&str>,
+    ) -> Result<AccessToken, OroClientError> {
+        let url = self.registry.join("-/npm/v1/tokens")?;
+        let mut req = self
+            .client_uncached
+            .post(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .header("Content-Type", "application/json")
+            .body(
+                serde_json::to_string(&CreateToken {
+                    password,
+                    readonly: options.readonly,
+                    cidr_whitelist: &options.cidr_whitelist,
+                })
+                .expect("This type conversion should work"),
+            );
+        if let Some(otp) = otp {
+            req = req.header("npm-otp", otp);
+        }
+        let res = req.send().await?.notify();
+        if !res.status().is_success() {
+            return Err(OroClientError::from_response(&self.registry, None, res).await);
+        }
+        let text = res.text().await?;
+        serde_json::from_str(&text)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+
+    /// Revokes an access token, identified by its `key`.
+    pub async fn revoke_token(&self, key: &str, otp: Option<&str>) -> Result<(), OroClientError> {
+        let mut req = self
+            .client_uncached
+            .delete(
+                self.registry
+                    .join(&format!("-/npm/v1/tokens/token/{key}"))?,
+            )
+            .header("X-Oro-Registry", self.registry.to_string());
+        if let Some(otp) = otp {
+            req = req.header("npm-otp", otp);
+        }
+        let res = req.send().await?.notify();
+        if !res.status().is_success() {
+            return Err(OroClientError::from_response(&self.registry, None, res).await);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    fn token_json(key: &str) -> serde_json::Value {
+        json!({
+            "key": key,
+            "token": "npm_abc",
+            "readonly": false,
+            "cidr_whitelist": null,
+            "created": "2023-01-01T00:00:00.000Z",
+            "updated": "2023-01-01T00:00:00.000Z"
+        })
+    }
+
+    #[async_std::test]
+    async fn tokens() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let registry: url::Url = mock_server.uri().parse().into_diagnostic()?;
+        let client = OroClient::builder()
+            .registry(registry.clone())
+            .token_auth(registry, "deadbeef".into())
+            .build();
+
+        Mock::given(method("GET"))
+            .and(path("-/npm/v1/tokens"))
+            .and(query_param("page", "1"))
+            .and(header("authorization", "Bearer deadbeef"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "objects": [token_json("key2")],
+                "total": 2,
+                "urls": {}
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("-/npm/v1/tokens"))
+            .and(header("authorization", "Bearer deadbeef"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "objects": [token_json("key1")],
+                "total": 2,
+                "urls": { "next": "/-/npm/v1/tokens?page=1" }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert_eq!(
+            client
+                .tokens()
+                .await?
+                .into_iter()
+                .map(|token| token.key)
+                .collect::<Vec<_>>(),
+            vec!["key1", "key2"],
+            "Follows pagination"
+        );
+
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn create_and_revoke_token() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let client = OroClient::new(mock_server.uri().parse().into_diagnostic()?);
+
+        {
+            let _guard = Mock::given(method("POST"))
+                .and(path("-/npm/v1/tokens"))
+                .and(header("npm-otp", "123456"))
+                .and(body_json(json!({
+                    "password": "hunter2",
+                    "readonly": true,
+                    "cidr_whitelist": ["192.168.1.0/24"]
+                })))
+                .respond_with(ResponseTemplate::new(200).set_body_json(token_json("key1")))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            let token = client
+                .create_token(
+                    "hunter2",
+                    &TokenOptions {
+                        readonly: true,
+                        cidr_whitelist: vec!["192.168.1.0/24".into()],
+                    },
+                    Some("123456"),
+                )
+                .await?;
+            assert_eq!(token.key, "key1");
+            assert_eq!(token.token, "npm_abc");
+        }
+
+        {
+            let _guard = Mock::given(method("POST"))
+                .and(path("-/npm/v1/tokens"))
+                .respond_with(ResponseTemplate::new(401).append_header("www-authenticate", "OTP"))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            assert!(
+                matches!(
+                    client
+                        .create_token("hunter2", &TokenOptions::default(), None)
+                        .await,
+                    Err(OroClientError::OTPRequiredError)
+                ),
+                "Asks for a one-time password when the registry wants one"
+            );
+        }
+
+        {
+            let _guard = Mock::given(method("DELETE"))
+                .and(path("-/npm/v1/tokens/token/key1"))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            client.revoke_token("key1", None).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use serde::Deserialize;
+
+use crate::notify::Notify;
+use crate::{OroClient, OroClientError};
+
+#[derive(Deserialize)]
+struct Whoami {
+    username: String,
+}
+
+impl OroClient {
+    /// Fetches the username the registry associates with the current
+    /// credentials.
+    pub async fn whoami(&self) -> Result<String, OroClientError> {
+        let url = self.registry.join("-/whoami")?;
+        let res = self
+            .client_uncached
+            .get(url.clone())
+            .header("X-Oro-Registry", self.registry.to_string())
+            .send()
+            .await?
+            .notify();
+        if !res.status().is_success() {
+            return Err(OroClientError::from_response(&self.registry, None, res).await);
+        }
+        let text = res.text().await?;
+        serde_json::from_str::<Whoami>(&text)
+            .map(|whoami| whoami.username)
+            .map_err(move |e| OroClientError::from_json_err(e, url.to_string(), text))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn whoami() -> Result<()> {
+        let mock_server = MockServer::start().await;
+        let registry: url::Url = mock_server.uri().parse().into_diagnostic()?;
+        let client = OroClient::builder()
+            .registry(registry.clone())
+            .token_auth(registry, "deadbeef".into())
+            .build();
+
+        {
+            let _guard = Mock::given(method("GET"))
+                .and(path("-/whoami"))
+                .and(header("authorization", "Bearer deadbeef"))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(json!({ "username": "kat" })),
+                )
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            assert_eq!(client.whoami().await?, "kat");
+        }
+
+        {
+            let _guard = Mock::given(method("GET"))
+                .and(path("-/whoami"))
+                .respond_with(ResponseTemplate::new(401))
+                .expect(1)
+                .mount_as_scoped(&mock_server)
+                .await;
+
+            assert!(
+                matches!(client.whoami().await, Err(OroClientError::Unauthorized(_))),
+                "Missing or bad credentials are reported as such"
+            );
+        }
+
+        Ok(())
+    }
+}
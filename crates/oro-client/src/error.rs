@@ -1,5 +1,5 @@
 use miette::{Diagnostic, NamedSource, SourceOffset};
-use reqwest::Url;
+use reqwest::{StatusCode, Url};
 use thiserror::Error;
 
 #[derive(Debug)]
@@ -83,9 +83,111 @@ pub enum OroClientError {
     #[error(transparent)]
     #[diagnostic(code(oro_client::base64_decode_error), url(docsrs))]
     Base64DecodeError(#[from] base64::DecodeError),
+
+    /// This operation requires you to be logged in to the registry.
+    #[error("You need to be logged in to {0} to do this.")]
+    #[diagnostic(
+        code(oro_client::unauthorized),
+        url(docsrs),
+        help(
+            "Log in to this registry first, or check that your credentials for it are still valid."
+        )
+    )]
+    Unauthorized(Url),
+
+    /// The registry refused to perform this operation for the current user.
+    #[error("Permission denied: {0}")]
+    #[diagnostic(code(oro_client::forbidden), url(docsrs))]
+    Forbidden(String),
+
+    /// None of the package's published versions matched the given range.
+    #[error("No published versions of `{0}` match `{1}`.")]
+    #[diagnostic(code(oro_client::no_matching_versions), url(docsrs))]
+    NoMatchingVersions(String, String),
+
+    /// Every package needs at least one owner, so the last one can't be
+    /// removed.
+    #[error("Can't remove `{1}`, the last owner of `{0}`.")]
+    #[diagnostic(
+        code(oro_client::last_owner),
+        url(docsrs),
+        help("Add another owner to the package first.")
+    )]
+    LastOwner(String, String),
 }
 
 impl OroClientError {
+    /// Whether this error means the registry itself was unable to serve the
+    /// request (a 5xx response, a timeout, or a failure to connect), rather
+    /// than the request itself being bad. Used to decide when to fall back
+    /// to a mirror registry.
+    pub fn is_registry_unavailable(&self) -> bool {
+        fn unavailable(err: &reqwest::Error) -> bool {
+            #[cfg(not(target_arch = "wasm32"))]
+            if err.is_connect() {
+                return true;
+            }
+            err.is_timeout()
+                || err
+                    .status()
+                    .map(|status| status.is_server_error())
+                    .unwrap_or(false)
+        }
+        match self {
+            Self::RequestError(err) => unavailable(err),
+            Self::RequestMiddlewareError(reqwest_middleware::Error::Reqwest(err)) => {
+                unavailable(err)
+            }
+            _ => false,
+        }
+    }
+
+    /// Converts an unsuccessful registry response into an error, telling
+    /// apart missing packages, missing credentials, missing one-time
+    /// passwords, and permission errors.
+    pub(crate) async fn from_response(
+        registry: &Url,
+        package_name: Option<&str>,
+        res: reqwest::Response,
+    ) -> Self {
+        let status = res.status();
+        let www_authenticate = res
+            .headers()
+            .get(reqwest::header::WWW_AUTHENTICATE)
+            .and_then(|header| header.to_str().ok())
+            .map(|header| header.to_lowercase())
+            .unwrap_or_default();
+        let err = res.error_for_status_ref().err();
+        let text = res.text().await.unwrap_or_default();
+        match (status, package_name) {
+            (StatusCode::NOT_FOUND, Some(name)) => {
+                Self::PackageNotFound(registry.clone(), name.into())
+            }
+            (StatusCode::UNAUTHORIZED, _)
+                if www_authenticate.contains("otp")
+                    || text.to_lowercase().contains("one-time pass") =>
+            {
+                Self::OTPRequiredError
+            }
+            (StatusCode::UNAUTHORIZED, _) => Self::Unauthorized(registry.clone()),
+            (StatusCode::FORBIDDEN, _) => Self::Forbidden(
+                serde_json::from_str::<serde_json::Value>(&text)
+                    .ok()
+                    .and_then(|json| {
+                        json.get("error")
+                            .or_else(|| json.get("message"))
+                            .and_then(|msg| msg.as_str())
+                            .map(String::from)
+                    })
+                    .unwrap_or(text),
+            ),
+            _ => match err {
+                Some(err) => Self::RequestError(err),
+                None => Self::ResponseError(Some(text).into()),
+            },
+        }
+    }
+
     pub fn from_json_err(err: serde_json::Error, url: String, json: String) -> Self {
         // These json strings can get VERY LONG and miette doesn't (yet?)
         // support any "windowing" mechanism for displaying stuff, so we have
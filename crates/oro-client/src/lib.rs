@@ -7,8 +7,12 @@ mod credentials;
 mod error;
 mod notify;
 
+pub use api::keys;
 pub use api::login;
+pub use api::owners;
 pub use api::packument;
+pub use api::search;
+pub use api::tokens;
 pub use auth_middleware::nerf_dart;
 pub use client::{OroClient, OroClientBuilder};
 pub use error::OroClientError;
@@ -25,6 +25,8 @@ pub struct CorgiManifest {
     pub peer_dependencies: IndexMap<String, String>,
     #[serde(default, alias = "bundleDependencies", alias = "bundledDependencies")]
     pub bundled_dependencies: Option<BundledDependencies>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub patched_dependencies: IndexMap<String, String>,
 }
 
 #[derive(Builder, Default, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -188,6 +190,13 @@ pub struct Manifest {
     #[builder(default)]
     pub bundled_dependencies: Option<BundledDependencies>,
 
+    /// Patch files to apply to dependencies after they're extracted, keyed
+    /// by `name` or `name@version`, with paths relative to the package.
+    /// Only the root project's patches are applied.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    #[builder(default)]
+    pub patched_dependencies: IndexMap<String, String>,
+
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     #[builder(default)]
     pub workspaces: Vec<String>,
@@ -207,6 +216,7 @@ impl From<CorgiManifest> for Manifest {
             optional_dependencies: value.optional_dependencies,
             peer_dependencies: value.peer_dependencies,
             bundled_dependencies: value.bundled_dependencies,
+            patched_dependencies: value.patched_dependencies,
             ..Default::default()
         }
     }
@@ -222,6 +232,7 @@ impl From<Manifest> for CorgiManifest {
             optional_dependencies: value.optional_dependencies,
             peer_dependencies: value.peer_dependencies,
             bundled_dependencies: value.bundled_dependencies,
+            patched_dependencies: value.patched_dependencies,
         }
     }
 }
@@ -18,6 +18,11 @@ pub struct CorgiPackument {
     pub versions: HashMap<Version, CorgiVersionMetadata>,
     #[serde(default, rename = "dist-tags")]
     pub tags: HashMap<String, Version>,
+    /// Publish times, keyed by version. Abbreviated ("Corgi") registry
+    /// responses don't include these, so this is only populated when the
+    /// packument was converted from a full [`Packument`].
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub time: HashMap<String, String>,
 }
 
 /// A serializable representation of a Packument -- the toplevel metadata
@@ -43,6 +48,7 @@ impl From<CorgiPackument> for Packument {
                 .map(|(k, v)| (k, v.into()))
                 .collect(),
             tags: value.tags,
+            time: value.time,
             ..Default::default()
         }
     }
@@ -57,6 +63,7 @@ impl From<Packument> for CorgiPackument {
                 .map(|(k, v)| (k, v.into()))
                 .collect(),
             tags: value.tags,
+            time: value.time,
         }
     }
 }
@@ -218,6 +225,8 @@ pub struct CorgiDist {
     pub integrity: Option<String>,
     #[serde(rename = "npm-signature")]
     pub npm_signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<DistSignature>,
 }
 
 /// Distribution information for a particular package version.
@@ -233,11 +242,23 @@ pub struct Dist {
     pub unpacked_size: Option<usize>,
     #[serde(rename = "npm-signature")]
     pub npm_signature: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<DistSignature>,
 
     #[serde(flatten)]
     pub rest: HashMap<String, Value>,
 }
 
+/// A registry signature for a package version. The signature is an ECDSA
+/// signature over `<name>@<version>:<integrity>`, made with the registry key
+/// identified by `keyid`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DistSignature {
+    pub keyid: String,
+    /// Base64-encoded, DER-encoded signature.
+    pub sig: String,
+}
+
 impl From<CorgiDist> for Dist {
     fn from(value: CorgiDist) -> Self {
         Dist {
@@ -245,6 +266,7 @@ impl From<CorgiDist> for Dist {
             tarball: value.tarball,
             integrity: value.integrity,
             npm_signature: value.npm_signature,
+            signatures: value.signatures,
             ..Default::default()
         }
     }
@@ -257,6 +279,7 @@ impl From<Dist> for CorgiDist {
             tarball: value.tarball,
             integrity: value.integrity,
             npm_signature: value.npm_signature,
+            signatures: value.signatures,
         }
     }
 }
@@ -0,0 +1,352 @@
+use std::path::{Path, PathBuf};
+
+use config::{Map, Value};
+use kdl::{KdlDocument, KdlEntry, KdlNode, KdlValue};
+
+use crate::error::OroConfigError;
+use crate::kdl_source::node_value;
+
+/// Indentation used for nodes added to files that don't have any yet.
+const INDENT: &str = "    ";
+
+/// An `oro.kdl` file, opened for reading and editing individual options
+/// under its `options` node. Edits leave the rest of the file, including
+/// comments and formatting, untouched.
+///
+/// Keys are option names, like `registry`. A key can also point at a child
+/// of an option using a dot, like `scoped-registries.@mycorp`. Only the
+/// first dot counts, so `auth.https://my.registry.com` works too.
+#[derive(Debug, Clone)]
+pub struct OroConfigFile {
+    path: PathBuf,
+    doc: KdlDocument,
+}
+
+impl OroConfigFile {
+    /// Reads the config file at `path`. A missing file is treated as an
+    /// empty one, and will be created by [`Self::save`].
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, OroConfigError> {
+        let path = path.as_ref().to_owned();
+        let doc = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .parse()
+                .map_err(|e| OroConfigError::InvalidConfigFile(path.clone(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => KdlDocument::new(),
+            Err(e) => return Err(OroConfigError::ConfigFileIoError(path, e)),
+        };
+        Ok(Self { path, doc })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the file back to disk, creating it and its parent directories
+    /// if needed.
+    pub fn save(&self) -> Result<(), OroConfigError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| OroConfigError::ConfigFileIoError(self.path.clone(), e))?;
+        }
+        std::fs::write(&self.path, self.doc.to_string())
+            .map_err(|e| OroConfigError::ConfigFileIoError(self.path.clone(), e))
+    }
+
+    /// All the option nodes in the file, in the order they appear.
+    pub fn options(&self) -> &[KdlNode] {
+        self.doc
+            .get("options")
+            .and_then(|options| options.children())
+            .map(|children| children.nodes())
+            .unwrap_or_default()
+    }
+
+    /// All the options in the file, interpreted the same way they are when
+    /// loading configuration.
+    pub fn values(&self) -> Map<String, Value> {
+        self.options()
+            .iter()
+            .map(|node| (node.name().value().to_string(), node_value(node)))
+            .collect()
+    }
+
+    /// The node for `key`, if the file sets it.
+    pub fn get(&self, key: &str) -> Option<&KdlNode> {
+        let (name, child) = split_key(key);
+        let node = self.options().iter().find(|n| n.name().value() == name)?;
+        match child {
+            Some(child) => node.children()?.get(child),
+            None => Some(node),
+        }
+    }
+
+    /// The value for `key`, interpreted the same way it is when loading
+    /// configuration.
+    pub fn value(&self, key: &str) -> Option<Value> {
+        self.get(key).map(node_value)
+    }
+
+    /// Sets `key` to `entries`, replacing any values it had. If the key
+    /// already exists, its position and comments are kept. Otherwise, it's
+    /// added at the end of its parent.
+    pub fn set(&mut self, key: &str, entries: Vec<KdlEntry>) {
+        let (name, child) = split_key(key);
+        let options = ensure_child(&mut self.doc, "options", 0);
+        let node = ensure_child(options.ensure_children(), name, 1);
+        let node = match child {
+            Some(child) => {
+                node.clear_entries();
+                ensure_child(node.ensure_children(), child, 2)
+            }
+            None => {
+                node.clear_children();
+                node
+            }
+        };
+        node.clear_entries();
+        for entry in entries {
+            node.push(entry);
+        }
+    }
+
+    /// Removes `key` from the file. Returns `false` if it wasn't there.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let (name, child) = split_key(key);
+        let Some(options) = self
+            .doc
+            .get_mut("options")
+            .and_then(|options| options.children_mut().as_mut())
+        else {
+            return false;
+        };
+        match child {
+            Some(child) => options
+                .get_mut(name)
+                .and_then(|node| node.children_mut().as_mut())
+                .map(|children| remove_nodes(children, child))
+                .unwrap_or(false),
+            None => remove_nodes(options, name),
+        }
+    }
+
+    /// Parses a value given on the command line. `name=value` becomes a
+    /// property, `true` and `false` become booleans, integers become
+    /// numbers, and anything else is a string.
+    pub fn parse_entry(arg: &str) -> KdlEntry {
+        match arg.split_once('=') {
+            Some((name, value)) if is_property_name(name) => {
+                KdlEntry::new_prop(name, parse_value(value))
+            }
+            _ => KdlEntry::new(parse_value(arg)),
+        }
+    }
+}
+
+fn split_key(key: &str) -> (&str, Option<&str>) {
+    match key.split_once('.') {
+        Some((name, child)) => (name, Some(child)),
+        None => (key, None),
+    }
+}
+
+fn is_property_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn parse_value(value: &str) -> KdlValue {
+    match value {
+        "true" => KdlValue::Bool(true),
+        "false" => KdlValue::Bool(false),
+        _ => value
+            .parse::<i64>()
+            .map(KdlValue::Base10)
+            .unwrap_or_else(|_| KdlValue::String(value.into())),
+    }
+}
+
+/// Removes all nodes called `name` from `doc`. Returns `false` if there
+/// weren't any.
+fn remove_nodes(doc: &mut KdlDocument, name: &str) -> bool {
+    let nodes = doc.nodes_mut();
+    let len = nodes.len();
+    let first_removed = nodes
+        .first()
+        .filter(|node| node.name().value() == name)
+        .map(|node| node.leading().unwrap_or_default().to_string());
+    nodes.retain(|node| node.name().value() != name);
+    if let (Some(leading), Some(first)) = (first_removed, nodes.first_mut()) {
+        // The first node's leading whitespace holds the newline after the
+        // opening brace, so it needs to carry over to the new first node.
+        if let Some((before, _)) = leading.split_once('\n') {
+            let rest = first.leading().unwrap_or_default().to_string();
+            first.set_leading(format!("{before}\n{rest}"));
+        }
+    }
+    nodes.len() != len
+}
+
+/// Finds the last node called `name` in `doc`, adding one if there isn't
+/// any. New nodes are indented like their siblings, or by `depth` levels if
+/// they're the first node in their block.
+fn ensure_child<'a>(doc: &'a mut KdlDocument, name: &str, depth: usize) -> &'a mut KdlNode {
+    let nodes = doc.nodes_mut();
+    match nodes.iter().rposition(|node| node.name().value() == name) {
+        Some(idx) => &mut nodes[idx],
+        None => {
+            let mut node = KdlNode::new(name);
+            let (leading, trailing) = nodes
+                .last()
+                .map(|sibling| {
+                    let leading = sibling.leading().unwrap_or_default();
+                    // Only take the sibling's indentation, not any comments
+                    // or blank lines that come before it.
+                    let indent = leading.rsplit('\n').next().unwrap_or_default();
+                    (
+                        indent.to_string(),
+                        sibling.trailing().unwrap_or("\n").to_string(),
+                    )
+                })
+                .unwrap_or_else(|| (INDENT.repeat(depth), "\n".into()));
+            if let Some(last) = nodes.last_mut() {
+                // The last node in a block might not end in a newline, but
+                // it needs one now that something comes after it.
+                if !last.trailing().unwrap_or_default().contains('\n') {
+                    last.set_trailing("\n");
+                }
+            }
+            node.set_leading(leading);
+            node.set_trailing(if trailing.contains('\n') {
+                trailing
+            } else {
+                "\n".into()
+            });
+            nodes.push(node);
+            nodes.last_mut().expect("just pushed")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn config(contents: &str) -> Result<(tempfile::TempDir, OroConfigFile)> {
+        let dir = tempdir().into_diagnostic()?;
+        let path = dir.path().join("oro.kdl");
+        std::fs::write(&path, contents).into_diagnostic()?;
+        let file = OroConfigFile::open(path)?;
+        Ok((dir, file))
+    }
+
+    fn saved(file: &OroConfigFile) -> Result<String> {
+        file.save()?;
+        std::fs::read_to_string(file.path()).into_diagnostic()
+    }
+
+    #[test]
+    fn set_preserves_formatting() -> Result<()> {
+        let (_dir, mut file) = config(
+            "// My config\noptions {\n    // Where packages come from.\n    registry \"https://registry.npmjs.org\"\n    retries 2\n}\n",
+        )?;
+        file.set(
+            "registry",
+            vec![OroConfigFile::parse_entry("https://my.registry.com")],
+        );
+        file.set("emoji", vec![OroConfigFile::parse_entry("false")]);
+        assert_eq!(
+            saved(&file)?,
+            "// My config\noptions {\n    // Where packages come from.\n    registry \"https://my.registry.com\"\n    retries 2\n    emoji false\n}\n"
+        );
+
+        let (_dir, mut file) = config(
+            "options {\n    // The registry.\n    registry \"https://registry.npmjs.org\"\n}\n",
+        )?;
+        file.set("emoji", vec![OroConfigFile::parse_entry("false")]);
+        assert_eq!(
+            saved(&file)?,
+            "options {\n    // The registry.\n    registry \"https://registry.npmjs.org\"\n    emoji false\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn set_in_empty_file() -> Result<()> {
+        let (_dir, mut file) = config("")?;
+        file.set("retries", vec![OroConfigFile::parse_entry("3")]);
+        file.set(
+            "scoped-registries.@mycorp",
+            vec![OroConfigFile::parse_entry("https://corp.registry.com")],
+        );
+        file.set(
+            "auth.https://corp.registry.com",
+            vec![OroConfigFile::parse_entry("token=deadbeef")],
+        );
+        assert_eq!(
+            saved(&file)?,
+            "options {\n    retries 3\n    scoped-registries {\n        @mycorp \"https://corp.registry.com\"\n    }\n    auth {\n        \"https://corp.registry.com\" token=\"deadbeef\"\n    }\n}\n"
+        );
+        let reopened = OroConfigFile::open(file.path())?;
+        assert_eq!(
+            reopened
+                .value("retries")
+                .map(|v| v.into_int().unwrap_or_default()),
+            Some(3)
+        );
+        assert_eq!(
+            reopened
+                .value("scoped-registries.@mycorp")
+                .map(|v| v.to_string()),
+            Some("https://corp.registry.com".into()),
+        );
+        assert_eq!(
+            reopened
+                .get("auth.https://corp.registry.com")
+                .and_then(|node| node.get("token"))
+                .and_then(|v| v.as_string()),
+            Some("deadbeef")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn remove() -> Result<()> {
+        let (_dir, mut file) = config(
+            "options {\n    registry \"https://registry.npmjs.org\"\n    scoped-registries {\n        \"@a\" \"https://a.com\"\n        \"@b\" \"https://b.com\"\n    }\n}\n",
+        )?;
+        assert!(file.remove("scoped-registries.@a"));
+        assert!(!file.remove("scoped-registries.@c"));
+        assert!(file.remove("registry"));
+        assert!(!file.remove("registry"));
+        assert_eq!(
+            saved(&file)?,
+            "options {\n    scoped-registries {\n        \"@b\" \"https://b.com\"\n    }\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_entry() {
+        assert_eq!(
+            OroConfigFile::parse_entry("true").value(),
+            &KdlValue::Bool(true)
+        );
+        assert_eq!(
+            OroConfigFile::parse_entry("42").value(),
+            &KdlValue::Base10(42)
+        );
+        assert_eq!(
+            OroConfigFile::parse_entry("https://example.com/?a=b").value(),
+            &KdlValue::String("https://example.com/?a=b".into())
+        );
+        let prop = OroConfigFile::parse_entry("token=abc");
+        assert_eq!(prop.name().map(|n| n.value()), Some("token"));
+        assert_eq!(prop.value(), &KdlValue::String("abc".into()));
+    }
+}
@@ -1,4 +1,7 @@
-use miette::Diagnostic;
+use std::{path::PathBuf, sync::Arc};
+
+use kdl::KdlError;
+use miette::{Diagnostic, NamedSource, SourceSpan};
 use thiserror::Error;
 
 #[derive(Debug, Error, Diagnostic)]
@@ -14,4 +17,67 @@ pub enum OroConfigError {
     #[error(transparent)]
     #[diagnostic(code(oro_config::error), url(docsrs))]
     ConfigParseError(#[from] Box<dyn std::error::Error + Send + Sync>),
+
+    /// Failed to read or write a config file.
+    #[error("Failed to access config file at {}", .0.display())]
+    #[diagnostic(
+        code(oro_config::config_file_io_error),
+        url(docsrs),
+        help("Make sure the file and its parent directory exist and are readable and writable.")
+    )]
+    ConfigFileIoError(PathBuf, #[source] std::io::Error),
+
+    /// A config file couldn't be parsed as KDL, so it can't be edited.
+    #[error("Failed to parse config file at {}", .0.display())]
+    #[diagnostic(
+        code(oro_config::invalid_config_file),
+        url(docsrs),
+        help("Fix the syntax error in the file, or remove it to start over.")
+    )]
+    InvalidConfigFile(PathBuf, #[source] KdlError),
+
+    /// Some options in a config file are unknown, or have values that
+    /// aren't valid for them. Each problem is listed separately.
+    #[error("Invalid options in config file at {}", .path.display())]
+    #[diagnostic(
+        code(oro_config::invalid_options),
+        url(docsrs),
+        help("Run `oro <command> --help` to see which options a command accepts.")
+    )]
+    InvalidOptions {
+        path: PathBuf,
+        #[related]
+        errors: Vec<OroConfigOptionError>,
+    },
+}
+
+/// A single problem with an option in an `oro.kdl` file. These are reported
+/// together through [`OroConfigError::InvalidOptions`].
+#[derive(Debug, Error, Diagnostic)]
+pub enum OroConfigOptionError {
+    /// The option doesn't match any command line option, nor any
+    /// subcommand that could hold a block of options.
+    #[error("Unknown option `{name}`.")]
+    #[diagnostic(code(oro_config::unknown_option), url(docsrs))]
+    UnknownOption {
+        name: String,
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("unknown option")]
+        span: SourceSpan,
+        #[help]
+        help: Option<String>,
+    },
+
+    /// The option's value doesn't have the right shape or type for it.
+    #[error("Invalid value for option `{name}`: {reason}")]
+    #[diagnostic(code(oro_config::invalid_option_value), url(docsrs))]
+    InvalidOptionValue {
+        name: String,
+        reason: String,
+        #[source_code]
+        src: Arc<NamedSource>,
+        #[label("{reason}")]
+        span: SourceSpan,
+    },
 }
@@ -63,7 +63,7 @@ fn array_kind(value: impl Iterator<Item = Value>) -> ValueKind {
     ValueKind::Array(value.collect())
 }
 
-fn node_value(node: &KdlNode) -> Value {
+pub(crate) fn node_value(node: &KdlNode) -> Value {
     let mut entries = node.entries().iter().filter(|e| e.name().is_none());
     let len = entries.clone().count();
     if len == 1 {
@@ -1,21 +1,43 @@
 //! Configuration loader for Orogene config files.
 
-use std::{collections::HashSet, ffi::OsString, path::PathBuf};
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fmt,
+    path::{Path, PathBuf},
+};
 
 pub use clap::{ArgMatches, Command};
-pub use config::Config as OroConfig;
-use config::{builder::DefaultState, ConfigBuilder, Environment, File, ValueKind};
+use config::{builder::DefaultState, ConfigBuilder, Environment, File, Source, ValueKind};
+pub use config::{Config as OroConfig, Value};
 use kdl_source::KdlFormat;
 use miette::Result;
+use npmrc_source::NpmrcFormat;
 
-use error::OroConfigError;
+pub use config_file::OroConfigFile;
+pub use error::{OroConfigError, OroConfigOptionError};
 
+mod config_file;
 mod error;
 mod kdl_source;
+mod npmrc_source;
+mod validate;
+
+/// Options whose values are `kind=value` rules, like `license-policy`'s
+/// `allow=MIT`. These are the only ones where a single entry of their block
+/// can list several values, as in `allow "MIT" "ISC"`, since each kind of
+/// rule can be given any number of times.
+const RULE_OPTIONS: &[&str] = &["license-policy"];
 
 pub trait OroConfigLayerExt {
     fn with_negations(self) -> Self;
     fn layered_args(&self, args: &mut Vec<OsString>, config: &OroConfig) -> Result<()>;
+    fn layered_section_args(
+        &self,
+        args: &mut Vec<OsString>,
+        config: &OroConfig,
+        section: &[&str],
+    ) -> Result<()>;
 }
 
 impl OroConfigLayerExt for Command {
@@ -52,34 +74,59 @@ impl OroConfigLayerExt for Command {
     }
 
     fn layered_args(&self, args: &mut Vec<OsString>, config: &OroConfig) -> Result<()> {
+        self.layered_section_args(args, config, &[])
+    }
+
+    /// Like [`OroConfigLayerExt::layered_args`], but reads options from a
+    /// per-subcommand block, like `options { apply { ... } }`. `section` is
+    /// the path of subcommand names leading to the block.
+    fn layered_section_args(
+        &self,
+        args: &mut Vec<OsString>,
+        config: &OroConfig,
+        section: &[&str],
+    ) -> Result<()> {
         let mut long_opts = HashSet::new();
         for opt in self.get_arguments() {
             if opt.get_long().is_some() {
-                long_opts.insert(opt.get_id().to_string());
+                long_opts.insert((opt.get_id().to_string(), opt.get_action().takes_values()));
             }
         }
         let matches = self
             .clone()
             .ignore_errors(true)
             .get_matches_from(&args.clone());
-        for opt in long_opts {
+        for (opt, takes_values) in long_opts {
             // TODO: _prepend_ args unconditionally if they're coming from
             // config, so multi-args get parsed right. Right now, if you have
             // something in your config, it'll get completely overridden by
             // the command line.
             if matches.value_source(&opt) != Some(clap::parser::ValueSource::CommandLine) {
                 let opt = opt.replace('_', "-");
+                let key = section
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(opt.as_str()))
+                    .collect::<Vec<_>>()
+                    .join(".");
                 if !args.contains(&OsString::from(format!("--no-{opt}"))) {
-                    if let Ok(bool) = config.get_bool(&opt) {
+                    // Numbers convert to booleans too, so only switches
+                    // should be treated that way.
+                    let switch = if takes_values {
+                        None
+                    } else {
+                        config.get_bool(&key).ok()
+                    };
+                    if let Some(bool) = switch {
                         if bool {
                             args.push(OsString::from(format!("--{}", opt)));
                         } else {
                             args.push(OsString::from(format!("--no-{}", opt)));
                         }
-                    } else if let Ok(value) = config.get_string(&opt) {
+                    } else if let Ok(value) = config.get_string(&key) {
                         args.push(OsString::from(format!("--{}", opt)));
                         args.push(OsString::from(value));
-                    } else if let Ok(value) = config.get_table(&opt) {
+                    } else if let Ok(value) = config.get_table(&key) {
                         for (key, val) in value {
                             match &val.kind {
                                 ValueKind::Table(map) => {
@@ -88,14 +135,19 @@ impl OroConfigLayerExt for Command {
                                         args.push(OsString::from(format!("{{{key}}}{k}={v}")));
                                     }
                                 }
-                                // TODO: error if val.kind is an Array
+                                ValueKind::Array(items) if RULE_OPTIONS.contains(&opt.as_str()) => {
+                                    for item in items {
+                                        args.push(OsString::from(format!("--{}", opt)));
+                                        args.push(OsString::from(format!("{key}={item}")));
+                                    }
+                                }
                                 _ => {
                                     args.push(OsString::from(format!("--{}", opt)));
                                     args.push(OsString::from(format!("{key}={val}")));
                                 }
                             }
                         }
-                    } else if let Ok(value) = config.get_array(&opt) {
+                    } else if let Ok(value) = config.get_array(&key) {
                         for val in value {
                             if let Ok(val) = val.into_string() {
                                 args.push(OsString::from(format!("--{}", opt)));
@@ -117,6 +169,9 @@ pub struct OroConfigOptions {
     env: bool,
     pkg_root: Option<PathBuf>,
     global_config_file: Option<PathBuf>,
+    npmrc: bool,
+    user_npmrc_file: Option<PathBuf>,
+    command: Option<Command>,
 }
 
 impl Default for OroConfigOptions {
@@ -127,6 +182,9 @@ impl Default for OroConfigOptions {
             env: true,
             pkg_root: None,
             global_config_file: None,
+            npmrc: true,
+            user_npmrc_file: None,
+            command: None,
         }
     }
 }
@@ -156,28 +214,239 @@ impl OroConfigOptions {
         self
     }
 
+    /// Whether to read registry and credential settings from `.npmrc` files.
+    /// Defaults to `true`.
+    pub fn npmrc(mut self, npmrc: bool) -> Self {
+        self.npmrc = npmrc;
+        self
+    }
+
+    /// Location of the user-level `.npmrc` file (usually `~/.npmrc`).
+    pub fn user_npmrc_file(mut self, file: Option<PathBuf>) -> Self {
+        self.user_npmrc_file = file;
+        self
+    }
+
+    /// Validates the options in `oro.kdl` files against `command` when
+    /// loading. Unknown options, and values `command` wouldn't accept, are
+    /// reported as errors pointing at the offending part of the file.
+    pub fn validate_against(mut self, command: Command) -> Self {
+        self.command = Some(command);
+        self
+    }
+
     pub fn set_default(mut self, key: &str, value: &str) -> Result<Self, OroConfigError> {
         self.builder = self.builder.set_default(key, value)?;
         Ok(self)
     }
 
+    /// Loads configuration from all enabled sources. From lowest to highest
+    /// precedence, these are: defaults, the user `.npmrc`, the global
+    /// `oro.kdl`, `ORO_CONFIG_*` environment variables, the project
+    /// `.npmrc`, and the project `oro.kdl`.
+    ///
+    /// If a command was given through [`Self::validate_against`], `oro.kdl`
+    /// files are validated first.
     pub fn load(self) -> Result<OroConfig> {
-        let mut builder = self.builder;
+        if let Some(command) = &self.command {
+            for (layer, _) in self.layers() {
+                if let OroConfigLayer::GlobalConfig(path) | OroConfigLayer::ProjectConfig(path) =
+                    layer
+                {
+                    validate::validate_file(&path, command)?;
+                }
+            }
+        }
+        let sources = self
+            .layers()
+            .into_iter()
+            .map(|(_, source)| source)
+            .collect::<Vec<_>>();
+        Ok(self
+            .builder
+            .add_source(sources)
+            .build()
+            .map_err(OroConfigError::ConfigError)?)
+    }
+
+    /// Looks `key` up in each enabled source on its own, and returns the
+    /// ones that set it, along with the value they set, from lowest to
+    /// highest precedence. The last one is the value [`Self::load`] ends up
+    /// with, unless it's overridden on the command line.
+    pub fn explain(self, key: &str) -> Result<OroConfigLayers> {
+        let mut found = Vec::new();
+        let defaults = self
+            .builder
+            .clone()
+            .build()
+            .map_err(OroConfigError::ConfigError)?;
+        if let Ok(value) = defaults.get::<Value>(key) {
+            found.push((OroConfigLayer::Default, value));
+        }
+        for (layer, source) in self.layers() {
+            let config = OroConfig::builder()
+                .add_source(vec![source])
+                .build()
+                .map_err(OroConfigError::ConfigError)?;
+            if let Ok(value) = config.get::<Value>(key) {
+                found.push((layer, value));
+            }
+        }
+        Ok(found)
+    }
+
+    /// Like [`Self::explain`], but also looks `key` up in the per-subcommand
+    /// blocks of `command` that [`OroConfigLayerExt::layered_section_args`]
+    /// reads, like `apply { hoisted true }`. Returns the path of subcommand
+    /// names leading to each block that sets `key`, along with what
+    /// [`Self::explain`] found there. Top-level options come first, with an
+    /// empty path, whether or not they set `key`.
+    ///
+    /// When running a subcommand, values from its block take precedence over
+    /// top-level ones from any source, and more deeply nested blocks take
+    /// precedence over their parents.
+    pub fn explain_sections(
+        self,
+        key: &str,
+        command: &Command,
+    ) -> Result<Vec<(Vec<String>, OroConfigLayers)>> {
+        let mut sections = Vec::new();
+        subcommand_paths(command, &mut Vec::new(), &mut sections);
+        let mut found = vec![(Vec::new(), self.clone().explain(key)?)];
+        for section in sections {
+            let layers = self
+                .clone()
+                .explain(&format!("{}.{key}", section.join(".")))?;
+            if !layers.is_empty() {
+                found.push((section, layers));
+            }
+        }
+        Ok(found)
+    }
+
+    /// Every enabled configuration source, from lowest to highest
+    /// precedence. Defaults aren't included.
+    fn layers(&self) -> Vec<(OroConfigLayer, Box<dyn Source + Send + Sync>)> {
+        let mut layers: Vec<(OroConfigLayer, Box<dyn Source + Send + Sync>)> = Vec::new();
+        if self.global && self.npmrc {
+            if let Some(npmrc) = &self.user_npmrc_file {
+                let path = npmrc.display().to_string();
+                layers.push((
+                    OroConfigLayer::UserNpmrc(npmrc.clone()),
+                    Box::new(File::new(&path, NpmrcFormat).required(false)),
+                ));
+            }
+        }
         if self.global {
-            if let Some(config_file) = self.global_config_file {
+            if let Some(config_file) = &self.global_config_file {
                 let path = config_file.display().to_string();
-                builder = builder.add_source(File::new(&path, KdlFormat).required(false));
+                layers.push((
+                    OroConfigLayer::GlobalConfig(config_file.clone()),
+                    Box::new(File::new(&path, KdlFormat).required(false)),
+                ));
             }
         }
         if self.env {
-            builder = builder.add_source(Environment::with_prefix("oro_config"));
+            layers.push((
+                OroConfigLayer::Env,
+                Box::new(Environment::with_prefix("oro_config")),
+            ));
+        }
+        if let Some(root) = &self.pkg_root {
+            if self.npmrc {
+                let npmrc = root.join(".npmrc");
+                let path = npmrc.display().to_string();
+                layers.push((
+                    OroConfigLayer::ProjectNpmrc(npmrc),
+                    Box::new(File::new(&path, NpmrcFormat).required(false)),
+                ));
+            }
+            let config_file = root.join("oro.kdl");
+            let path = config_file.display().to_string();
+            layers.push((
+                OroConfigLayer::ProjectConfig(config_file),
+                Box::new(File::new(&path, KdlFormat).required(false)),
+            ));
+        }
+        layers
+    }
+}
+
+/// The sources that set a key, along with the value they set, from lowest
+/// to highest precedence.
+pub type OroConfigLayers = Vec<(OroConfigLayer, Value)>;
+
+/// A source of configuration values. See [`OroConfigOptions::load`] for how
+/// they're layered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OroConfigLayer {
+    /// Built-in defaults.
+    Default,
+    /// The user-level `.npmrc` file.
+    UserNpmrc(PathBuf),
+    /// The global `oro.kdl` file.
+    GlobalConfig(PathBuf),
+    /// `ORO_CONFIG_*` environment variables.
+    Env,
+    /// The project's `.npmrc` file.
+    ProjectNpmrc(PathBuf),
+    /// The project's `oro.kdl` file.
+    ProjectConfig(PathBuf),
+    /// Command line flags. [`OroConfigOptions::explain`] never returns this,
+    /// since flags are layered on top of the loaded configuration by the
+    /// CLI itself.
+    CommandLine,
+}
+
+impl OroConfigLayer {
+    /// A short, stable identifier for this layer, suitable for
+    /// machine-readable output.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::UserNpmrc(_) => "user-npmrc",
+            Self::GlobalConfig(_) => "global-config",
+            Self::Env => "env",
+            Self::ProjectNpmrc(_) => "project-npmrc",
+            Self::ProjectConfig(_) => "project-config",
+            Self::CommandLine => "command-line",
         }
-        if let Some(root) = self.pkg_root {
-            builder = builder.add_source(
-                File::new(&root.join("oro.kdl").display().to_string(), KdlFormat).required(false),
-            );
+    }
+
+    /// The file this layer was read from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::UserNpmrc(path)
+            | Self::GlobalConfig(path)
+            | Self::ProjectNpmrc(path)
+            | Self::ProjectConfig(path) => Some(path),
+            Self::Default | Self::Env | Self::CommandLine => None,
         }
-        Ok(builder.build().map_err(OroConfigError::ConfigError)?)
+    }
+}
+
+impl fmt::Display for OroConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::UserNpmrc(path) => write!(f, "user .npmrc ({})", path.display()),
+            Self::GlobalConfig(path) => write!(f, "global oro.kdl ({})", path.display()),
+            Self::Env => write!(f, "environment"),
+            Self::ProjectNpmrc(path) => write!(f, "project .npmrc ({})", path.display()),
+            Self::ProjectConfig(path) => write!(f, "project oro.kdl ({})", path.display()),
+            Self::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// Collects the path of names leading to every subcommand of `command`,
+/// parents first.
+fn subcommand_paths(command: &Command, path: &mut Vec<String>, paths: &mut Vec<Vec<String>>) {
+    for subcommand in command.get_subcommands() {
+        path.push(subcommand.get_name().to_string());
+        paths.push(path.clone());
+        subcommand_paths(subcommand, path, paths);
+        path.pop();
     }
 }
 
@@ -221,6 +490,189 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn layered_switches() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let file = dir.path().join("oro.kdl");
+        fs::write(&file, "options{\nretries 1\nemoji true\nprogress false\n}").into_diagnostic()?;
+        let config = OroConfigOptions::new()
+            .env(false)
+            .global_config_file(Some(file))
+            .load()?;
+        let command = Command::new("oro")
+            .arg(clap::Arg::new("retries").long("retries"))
+            .arg(
+                clap::Arg::new("emoji")
+                    .long("emoji")
+                    .action(clap::ArgAction::SetTrue),
+            )
+            .arg(
+                clap::Arg::new("progress")
+                    .long("progress")
+                    .action(clap::ArgAction::SetTrue),
+            )
+            .with_negations();
+        let mut args = vec![OsString::from("oro")];
+        command.layered_args(&mut args, &config)?;
+        let args = args
+            .iter()
+            .map(|arg| arg.to_str().expect("ascii"))
+            .collect::<Vec<_>>();
+        assert_eq!(args.len(), 5);
+        assert!(args.contains(&"--emoji"));
+        assert!(args.contains(&"--no-progress"));
+        // `1` would convert to `true`, but `--retries` takes a value.
+        assert!(args.windows(2).any(|pair| pair == ["--retries", "1"]));
+        Ok(())
+    }
+
+    #[test]
+    fn npmrc_precedence() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let user_npmrc = dir.path().join("user-npmrc");
+        fs::write(
+            &user_npmrc,
+            "registry=https://user.registry.com/\n@mycorp:registry=https://corp.registry.com/\n//user.registry.com/:_authToken=user-token\n",
+        )
+        .into_diagnostic()?;
+        let project = dir.path().join("project");
+        fs::create_dir(&project).into_diagnostic()?;
+        fs::write(
+            project.join(".npmrc"),
+            "registry=https://project.registry.com/\n",
+        )
+        .into_diagnostic()?;
+
+        let config = OroConfigOptions::new()
+            .env(false)
+            .user_npmrc_file(Some(user_npmrc.clone()))
+            .pkg_root(Some(project.clone()))
+            .load()?;
+        assert_eq!(
+            config.get_string("registry").into_diagnostic()?,
+            "https://project.registry.com/"
+        );
+        assert_eq!(
+            config
+                .get_table("scoped-registries")
+                .into_diagnostic()?
+                .remove("@mycorp")
+                .map(|v| v.to_string()),
+            Some("https://corp.registry.com/".into())
+        );
+        assert!(config
+            .get_table("auth")
+            .into_diagnostic()?
+            .contains_key("https://user.registry.com/"));
+
+        // oro.kdl always wins over a .npmrc at the same level.
+        fs::write(
+            project.join("oro.kdl"),
+            "options {\nregistry \"https://kdl.registry.com/\"\n}",
+        )
+        .into_diagnostic()?;
+        let config = OroConfigOptions::new()
+            .env(false)
+            .user_npmrc_file(Some(user_npmrc))
+            .pkg_root(Some(project))
+            .load()?;
+        assert_eq!(
+            config.get_string("registry").into_diagnostic()?,
+            "https://kdl.registry.com/"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn explain_layers() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let global = dir.path().join("global.kdl");
+        fs::write(&global, "options {\nretries 2\nemoji false\n}").into_diagnostic()?;
+        let project = dir.path().join("project");
+        fs::create_dir(&project).into_diagnostic()?;
+        fs::write(project.join("oro.kdl"), "options {\nretries 5\n}").into_diagnostic()?;
+        let options = OroConfigOptions::new()
+            .env(false)
+            .npmrc(false)
+            .set_default("retries", "1")?
+            .global_config_file(Some(global.clone()))
+            .pkg_root(Some(project.clone()));
+
+        let layers = options
+            .clone()
+            .explain("retries")?
+            .into_iter()
+            .map(|(layer, value)| (layer, value.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            layers,
+            vec![
+                (OroConfigLayer::Default, "1".to_string()),
+                (
+                    OroConfigLayer::GlobalConfig(global.clone()),
+                    "2".to_string()
+                ),
+                (
+                    OroConfigLayer::ProjectConfig(project.join("oro.kdl")),
+                    "5".to_string()
+                ),
+            ]
+        );
+        let layers = options.clone().explain("emoji")?;
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].0, OroConfigLayer::GlobalConfig(global));
+        assert!(options.explain("store")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn explain_sections() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let project = dir.path().join("project");
+        fs::create_dir(&project).into_diagnostic()?;
+        fs::write(
+            project.join("oro.kdl"),
+            "options {\nhoisted false\napply {\nhoisted true\n}\nview {\nretries 3\n}\n}",
+        )
+        .into_diagnostic()?;
+        let command = Command::new("oro")
+            .subcommand(Command::new("apply"))
+            .subcommand(Command::new("view"));
+        let options = OroConfigOptions::new()
+            .env(false)
+            .npmrc(false)
+            .pkg_root(Some(project.clone()));
+
+        let sections = options
+            .clone()
+            .explain_sections("hoisted", &command)?
+            .into_iter()
+            .map(|(section, layers)| {
+                (
+                    section,
+                    layers
+                        .into_iter()
+                        .map(|(layer, value)| (layer, value.to_string()))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let layer = OroConfigLayer::ProjectConfig(project.join("oro.kdl"));
+        assert_eq!(
+            sections,
+            vec![
+                (vec![], vec![(layer.clone(), "false".to_string())]),
+                (vec!["apply".to_string()], vec![(layer, "true".to_string())]),
+            ]
+        );
+
+        let sections = options.explain_sections("retries", &command)?;
+        assert_eq!(sections.len(), 2);
+        assert!(sections[0].1.is_empty());
+        assert_eq!(sections[1].0, vec!["view".to_string()]);
+        Ok(())
+    }
+
     #[test]
     fn missing_config() -> Result<()> {
         let config = OroConfigOptions::new().global(false).env(false).load()?;
@@ -0,0 +1,325 @@
+use base64::{prelude::BASE64_STANDARD, Engine};
+use config::{ConfigError, FileStoredFormat, Format, Map, Source, Value, ValueKind};
+
+const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org/";
+
+/// Reads registry and credential settings out of npm's `.npmrc` files, and
+/// maps them to their Orogene equivalents:
+///
+/// * `registry=<url>` => `registry`
+/// * `@scope:registry=<url>` => `scoped-registries`
+/// * `//host/path/:_authToken`, `:_auth`, `:username`, and `:_password` =>
+///   `auth`, keyed by the registry they apply to, over both `https://` and
+///   `http://`.
+/// * Unprefixed `_authToken`, `_auth`, `username`, and `_password` => `auth`
+///   for the default registry.
+///
+/// `${VAR}` references in both keys and values are replaced with the
+/// contents of the corresponding environment variable. All other `.npmrc`
+/// settings are ignored.
+#[derive(Clone, Debug)]
+pub(crate) struct NpmrcSource {
+    origin: Option<String>,
+    entries: Vec<(String, String)>,
+}
+
+impl NpmrcSource {
+    pub(crate) fn parse(origin: Option<String>, text: &str) -> Self {
+        Self::parse_with_env(origin, text, |name| std::env::var(name).ok())
+    }
+
+    /// Like [`NpmrcSource::parse`], but looks up environment variables with
+    /// `env` instead of reading them from the process environment.
+    fn parse_with_env(
+        origin: Option<String>,
+        text: &str,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let entries = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| {
+                !line.is_empty()
+                    && !line.starts_with('#')
+                    && !line.starts_with(';')
+                    && !line.starts_with('[')
+            })
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| {
+                (
+                    env_replace(key.trim(), &env),
+                    env_replace(&unquote(value.trim()), &env),
+                )
+            })
+            .collect();
+        Self { origin, entries }
+    }
+
+    fn value(&self, kind: ValueKind) -> Value {
+        Value::new(self.origin.as_ref(), kind)
+    }
+}
+
+impl Source for NpmrcSource {
+    fn clone_into_box(&self) -> Box<dyn Source + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn collect(&self) -> Result<Map<String, Value>, ConfigError> {
+        let mut registry = None;
+        let mut scoped_registries = Map::new();
+        let mut auth: Map<String, Map<String, Value>> = Map::new();
+        let mut default_auth = Map::new();
+        for (key, value) in &self.entries {
+            if key == "registry" {
+                registry = Some(value.clone());
+            } else if let Some(scope) = key
+                .strip_suffix(":registry")
+                .filter(|scope| scope.starts_with('@'))
+            {
+                scoped_registries.insert(
+                    scope.to_string(),
+                    self.value(ValueKind::String(value.clone())),
+                );
+            } else if let Some((nerf_dart, field)) =
+                key.strip_prefix("//").and_then(|key| key.rsplit_once(':'))
+            {
+                if let Some((field, value)) = auth_field(field, value) {
+                    // Nerf darts don't say which scheme the registry uses, so
+                    // the credentials apply to both.
+                    for scheme in ["https", "http"] {
+                        auth.entry(format!("{scheme}://{nerf_dart}"))
+                            .or_default()
+                            .insert(field.into(), self.value(ValueKind::String(value.clone())));
+                    }
+                }
+            } else if let Some((field, value)) = auth_field(key, value) {
+                default_auth.insert(field.into(), self.value(ValueKind::String(value)));
+            }
+        }
+
+        let mut map = Map::new();
+        if !default_auth.is_empty() {
+            let registry = registry.as_deref().unwrap_or(DEFAULT_REGISTRY);
+            let entry = auth.entry(registry.to_string()).or_default();
+            for (field, value) in default_auth {
+                // Registry-specific credentials take precedence.
+                entry.entry(field).or_insert(value);
+            }
+        }
+        if let Some(registry) = registry {
+            map.insert("registry".into(), self.value(ValueKind::String(registry)));
+        }
+        if !scoped_registries.is_empty() {
+            map.insert(
+                "scoped-registries".into(),
+                self.value(ValueKind::Table(scoped_registries)),
+            );
+        }
+        if !auth.is_empty() {
+            map.insert(
+                "auth".into(),
+                self.value(ValueKind::Table(
+                    auth.into_iter()
+                        .map(|(registry, fields)| (registry, self.value(ValueKind::Table(fields))))
+                        .collect(),
+                )),
+            );
+        }
+        Ok(map)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct NpmrcFormat;
+
+impl Format for NpmrcFormat {
+    fn parse(
+        &self,
+        uri: Option<&String>,
+        text: &str,
+    ) -> Result<Map<String, Value>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(NpmrcSource::parse(uri.cloned(), text).collect()?)
+    }
+}
+
+impl FileStoredFormat for NpmrcFormat {
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+}
+
+/// Maps an npm credential field to its Orogene `auth` field name.
+fn auth_field(field: &str, value: &str) -> Option<(&'static str, String)> {
+    match field {
+        "_authToken" | "token" => Some(("token", value.into())),
+        "_auth" => Some(("legacy-auth", value.into())),
+        "username" => Some(("username", value.into())),
+        // npm stores passwords base64-encoded.
+        "_password" => Some((
+            "password",
+            BASE64_STANDARD
+                .decode(value)
+                .ok()
+                .and_then(|decoded| String::from_utf8(decoded).ok())
+                .unwrap_or_else(|| value.into()),
+        )),
+        _ => None,
+    }
+}
+
+fn unquote(value: &str) -> String {
+    if value.len() >= 2
+        && ((value.starts_with('"') && value.ends_with('"'))
+            || (value.starts_with('\'') && value.ends_with('\'')))
+    {
+        value[1..value.len() - 1].into()
+    } else {
+        value.into()
+    }
+}
+
+/// Replaces `${VAR}` references with the value of the `VAR` environment
+/// variable, as looked up by `env`. As with npm, references to unset
+/// variables are left as-is, unless written as `${VAR?}`, in which case
+/// they're replaced with an empty string.
+fn env_replace(input: &str, env: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let reference = &rest[start..start + len + 1];
+        let name = &reference[2..reference.len() - 1];
+        let (name, optional) = match name.strip_suffix('?') {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        match env(name) {
+            Some(value) => output.push_str(&value),
+            None if optional => {}
+            None => output.push_str(reference),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    fn get(map: &Map<String, Value>, path: &[&str]) -> Option<String> {
+        let mut value = map.get(path[0])?.clone();
+        for key in &path[1..] {
+            value = value.into_table().ok()?.get(*key)?.clone();
+        }
+        value.into_string().ok()
+    }
+
+    #[test]
+    fn maps_registries_and_auth() {
+        let map = NpmrcSource::parse_with_env(
+            None,
+            r#"
+            # a comment
+            ; another comment
+            registry = https://my.registry.com/npm/
+            @mycorp:registry="https://corp.registry.com/"
+            //my.registry.com/npm/:_authToken=${ORO_NPMRC_TEST_TOKEN}
+            //corp.registry.com/:username=me
+            //corp.registry.com/:_password=aHVudGVyMg==
+            //legacy.registry.com/:_auth=bWU6aHVudGVyMg==
+            //other.registry.com/:_authToken=${ORO_NPMRC_TEST_UNSET}
+            //optional.registry.com/:_authToken=${ORO_NPMRC_TEST_UNSET?}
+            save-exact=true
+            "#,
+            |name| (name == "ORO_NPMRC_TEST_TOKEN").then(|| "deadbeef".into()),
+        )
+        .collect()
+        .unwrap();
+
+        assert_eq!(
+            get(&map, &["registry"]).as_deref(),
+            Some("https://my.registry.com/npm/")
+        );
+        assert_eq!(
+            get(&map, &["scoped-registries", "@mycorp"]).as_deref(),
+            Some("https://corp.registry.com/")
+        );
+        assert_eq!(
+            get(&map, &["auth", "https://my.registry.com/npm/", "token"]).as_deref(),
+            Some("deadbeef")
+        );
+        assert_eq!(
+            get(&map, &["auth", "https://corp.registry.com/", "username"]).as_deref(),
+            Some("me")
+        );
+        assert_eq!(
+            get(&map, &["auth", "https://corp.registry.com/", "password"]).as_deref(),
+            Some("hunter2")
+        );
+        assert_eq!(
+            get(
+                &map,
+                &["auth", "https://legacy.registry.com/", "legacy-auth"]
+            )
+            .as_deref(),
+            Some("bWU6aHVudGVyMg==")
+        );
+        assert_eq!(
+            get(&map, &["auth", "https://other.registry.com/", "token"]).as_deref(),
+            Some("${ORO_NPMRC_TEST_UNSET}")
+        );
+        assert_eq!(
+            get(&map, &["auth", "https://optional.registry.com/", "token"]).as_deref(),
+            Some("")
+        );
+        assert!(map.get("save-exact").is_none());
+    }
+
+    #[test]
+    fn auth_applies_to_http_registries() {
+        let map = NpmrcSource::parse(
+            None,
+            "registry=http://localhost:4873/\n//localhost:4873/:_authToken=deadbeef\n_authToken=fallback\n",
+        )
+        .collect()
+        .unwrap();
+        assert_eq!(
+            get(&map, &["auth", "http://localhost:4873/", "token"]).as_deref(),
+            Some("deadbeef")
+        );
+        assert_eq!(
+            get(&map, &["auth", "https://localhost:4873/", "token"]).as_deref(),
+            Some("deadbeef")
+        );
+    }
+
+    #[test]
+    fn unprefixed_auth_applies_to_default_registry() {
+        let map = NpmrcSource::parse(None, "_authToken=deadbeef\n")
+            .collect()
+            .unwrap();
+        assert_eq!(
+            get(&map, &["auth", "https://registry.npmjs.org/", "token"]).as_deref(),
+            Some("deadbeef")
+        );
+
+        let map = NpmrcSource::parse(
+            None,
+            "registry=https://my.registry.com/\n_authToken=deadbeef\n",
+        )
+        .collect()
+        .unwrap();
+        assert_eq!(
+            get(&map, &["auth", "https://my.registry.com/", "token"]).as_deref(),
+            Some("deadbeef")
+        );
+    }
+}
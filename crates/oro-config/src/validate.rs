@@ -0,0 +1,446 @@
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+use clap::{Arg, ArgAction, Command};
+use kdl::{KdlDocument, KdlNode, KdlValue};
+use miette::{NamedSource, SourceSpan};
+
+use crate::error::{OroConfigError, OroConfigOptionError};
+
+/// Minimum similarity for an option to be suggested in place of an unknown
+/// one. This is the same threshold clap uses for its suggestions.
+const SUGGESTION_THRESHOLD: f64 = 0.8;
+
+/// Checks the `options` block of the `oro.kdl` file at `path` against the
+/// options `command` and its subcommands accept. Missing files are fine,
+/// and files that fail to parse are left for the loader to report.
+///
+/// At the top level, any option accepted by any subcommand is allowed.
+/// Blocks named after a subcommand, like `apply { ... }`, only allow that
+/// subcommand's options (including those of its own subcommands), plus
+/// global options.
+pub(crate) fn validate_file(path: &Path, command: &Command) -> Result<(), OroConfigError> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(OroConfigError::ConfigFileIoError(path.into(), e)),
+    };
+    let Ok(doc) = text.parse::<KdlDocument>() else {
+        return Ok(());
+    };
+    let mut validator = Validator {
+        root: command,
+        src: Arc::new(NamedSource::new(path.display().to_string(), text)),
+        errors: Vec::new(),
+    };
+    if let Some(options) = doc.get("options").and_then(|node| node.children()) {
+        validator.section(command, options);
+    }
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(OroConfigError::InvalidOptions {
+            path: path.into(),
+            errors: validator.errors,
+        })
+    }
+}
+
+struct Validator<'a> {
+    root: &'a Command,
+    src: Arc<NamedSource>,
+    errors: Vec<OroConfigOptionError>,
+}
+
+impl<'a> Validator<'a> {
+    fn section(&mut self, scope: &'a Command, doc: &KdlDocument) {
+        let mut options: BTreeMap<String, Vec<(&Command, &Arg)>> = BTreeMap::new();
+        for arg in self.root.get_arguments().filter(|arg| arg.is_global_set()) {
+            options
+                .entry(option_name(arg))
+                .or_default()
+                .push((self.root, arg));
+        }
+        collect_options(scope, &mut options);
+
+        for node in doc.nodes() {
+            let name = node.name().value();
+            // Subcommand blocks come first, since some subcommands share a
+            // name with an option, like `apply` and `--no-apply`.
+            let block = scope
+                .get_subcommands()
+                .find(|cmd| cmd.get_name() == name)
+                .filter(|_| node.entries().is_empty())
+                .zip(node.children());
+            if let Some((subcommand, children)) = block {
+                self.section(subcommand, children);
+            } else if let Some(candidates) = options.get(name) {
+                self.option(name, candidates, node);
+            } else {
+                let suggestion = options
+                    .keys()
+                    .map(String::as_str)
+                    .chain(scope.get_subcommands().map(|cmd| cmd.get_name()))
+                    .map(|candidate| (strsim::jaro(name, candidate), candidate))
+                    .filter(|(similarity, _)| *similarity > SUGGESTION_THRESHOLD)
+                    .max_by(|(a, _), (b, _)| a.total_cmp(b))
+                    .map(|(_, candidate)| format!("Did you mean `{candidate}`?"));
+                self.errors.push(OroConfigOptionError::UnknownOption {
+                    name: name.into(),
+                    src: self.src.clone(),
+                    span: node.name().span(),
+                    help: suggestion,
+                });
+            }
+        }
+    }
+
+    /// Checks an option node against every argument it could apply to. It's
+    /// valid as long as any of them accepts it.
+    fn option(&mut self, name: &str, candidates: &[(&Command, &Arg)], node: &KdlNode) {
+        let mut first_err = None;
+        for (command, arg) in candidates {
+            match check_value(command, arg, node) {
+                Ok(()) => return,
+                Err(err) => {
+                    first_err.get_or_insert(err);
+                }
+            }
+        }
+        if let Some((reason, span)) = first_err {
+            self.errors.push(OroConfigOptionError::InvalidOptionValue {
+                name: name.into(),
+                reason,
+                src: self.src.clone(),
+                span,
+            });
+        }
+    }
+}
+
+/// Option names, as used in `oro.kdl`, for all the arguments of `command`
+/// and its subcommands.
+fn collect_options<'a>(
+    command: &'a Command,
+    options: &mut BTreeMap<String, Vec<(&'a Command, &'a Arg)>>,
+) {
+    for arg in command
+        .get_arguments()
+        .filter(|arg| arg.get_long().is_some())
+    {
+        options
+            .entry(option_name(arg))
+            .or_default()
+            .push((command, arg));
+    }
+    for subcommand in command.get_subcommands() {
+        collect_options(subcommand, options);
+    }
+}
+
+fn option_name(arg: &Arg) -> String {
+    arg.get_id().as_str().replace('_', "-")
+}
+
+/// Checks that `node` has the right shape for `arg`, and that each value it
+/// would be passed as on the command line is accepted by `arg`'s parser.
+/// The shapes mirror how options get layered onto the command line.
+fn check_value(command: &Command, arg: &Arg, node: &KdlNode) -> Result<(), (String, SourceSpan)> {
+    let args = node
+        .entries()
+        .iter()
+        .filter(|entry| entry.name().is_none())
+        .collect::<Vec<_>>();
+    let props = node
+        .entries()
+        .iter()
+        .filter(|entry| entry.name().is_some())
+        .collect::<Vec<_>>();
+
+    if !arg.get_action().takes_values() {
+        return match (&args[..], &props[..], node.children()) {
+            ([], [], None) => Ok(()),
+            ([entry], [], None) if entry.value().as_bool().is_some() => Ok(()),
+            _ => Err(("expected `true` or `false`".into(), node.span())),
+        };
+    }
+
+    let multiple = matches!(arg.get_action(), ArgAction::Append);
+    let mut values = Vec::new();
+    match (&args[..], &props[..], node.children()) {
+        ([], [], None) => return Err(("expected a value".into(), node.span())),
+        ([entry], [], None) => values.push((scalar(entry.value(), entry.span())?, entry.span())),
+        (args, [], None) => {
+            for entry in args {
+                values.push((scalar(entry.value(), entry.span())?, entry.span()));
+            }
+        }
+        ([], props, None) => {
+            for entry in props {
+                let key = entry.name().expect("props have names").value();
+                let value = scalar(entry.value(), entry.span())?;
+                values.push((format!("{key}={value}"), entry.span()));
+            }
+        }
+        ([], [], Some(children)) => {
+            let rules = crate::RULE_OPTIONS.contains(&option_name(arg).as_str());
+            let dashes = children
+                .nodes()
+                .iter()
+                .all(|child| child.name().value() == "-");
+            for child in children.nodes() {
+                let key = child.name().value();
+                match child.entries() {
+                    [entry] if entry.name().is_none() && child.children().is_none() => {
+                        let value = scalar(entry.value(), entry.span())?;
+                        values.push((
+                            if dashes {
+                                value
+                            } else {
+                                format!("{key}={value}")
+                            },
+                            entry.span(),
+                        ));
+                    }
+                    // For rules, `key 1 2` is the same as `key 1` and
+                    // `key 2`.
+                    entries
+                        if rules
+                            && !dashes
+                            && !entries.is_empty()
+                            && child.children().is_none()
+                            && entries.iter().all(|entry| entry.name().is_none()) =>
+                    {
+                        for entry in entries {
+                            let value = scalar(entry.value(), entry.span())?;
+                            values.push((format!("{key}={value}"), entry.span()));
+                        }
+                    }
+                    entries
+                        if !dashes
+                            && !entries.is_empty()
+                            && child.children().is_none()
+                            && entries.iter().all(|entry| entry.name().is_some()) =>
+                    {
+                        for entry in entries {
+                            let name = entry.name().expect("checked for names").value();
+                            let value = scalar(entry.value(), entry.span())?;
+                            values.push((format!("{{{key}}}{name}={value}"), entry.span()));
+                        }
+                    }
+                    _ => return Err(("expected a single value".into(), child.span())),
+                }
+            }
+        }
+        _ => {
+            return Err((
+                "expected either values, properties, or children, but not a mix of them".into(),
+                node.span(),
+            ))
+        }
+    }
+
+    if values.len() > 1 && !multiple {
+        return Err(("expected a single value".into(), node.span()));
+    }
+    // Run each value through a command with just this argument's parser,
+    // so it's checked exactly like it would be on the command line, without
+    // tripping over any of the argument's relationships to others.
+    let long = arg.get_long().expect("options always have a long flag");
+    let parser = Command::new(command.get_name().to_string())
+        .no_binary_name(true)
+        .disable_help_flag(true)
+        .arg(
+            Arg::new(arg.get_id().clone())
+                .long(long.to_string())
+                .value_name(
+                    arg.get_value_names()
+                        .and_then(|names| names.first())
+                        .cloned()
+                        .unwrap_or_else(|| arg.get_id().as_str().to_uppercase().into()),
+                )
+                .value_parser(arg.get_value_parser().clone())
+                .action(ArgAction::Set),
+        );
+    for (value, span) in values {
+        parser
+            .clone()
+            .try_get_matches_from([format!("--{long}={value}")])
+            .map_err(|e| (clap_reason(&e), span))?;
+    }
+    Ok(())
+}
+
+fn scalar(value: &KdlValue, span: SourceSpan) -> Result<String, (String, SourceSpan)> {
+    if let Some(s) = value.as_string() {
+        Ok(s.into())
+    } else if let Some(b) = value.as_bool() {
+        Ok(b.to_string())
+    } else if let Some(i) = value.as_i64() {
+        Ok(i.to_string())
+    } else if let Some(f) = value.as_f64() {
+        Ok(f.to_string())
+    } else {
+        Err(("expected a value, not null".into(), span))
+    }
+}
+
+/// Extracts the useful part of a clap parse error, without the `error:`
+/// prefix or the suggestion to try `--help`.
+fn clap_reason(err: &clap::Error) -> String {
+    let rendered = err.to_string();
+    let first = rendered.lines().next().unwrap_or_default();
+    first.strip_prefix("error: ").unwrap_or(first).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::{value_parser, Arg, ArgAction, Command};
+    use miette::{IntoDiagnostic, Result};
+    use pretty_assertions::assert_eq;
+    use tempfile::tempdir;
+
+    fn command() -> Command {
+        Command::new("oro")
+            .arg(
+                Arg::new("registry")
+                    .long("registry")
+                    .global(true)
+                    .value_parser(value_parser!(u16)),
+            )
+            .arg(
+                Arg::new("emoji")
+                    .long("no-emoji")
+                    .global(true)
+                    .action(ArgAction::SetFalse),
+            )
+            .subcommand(
+                Command::new("apply")
+                    .arg(
+                        Arg::new("hoisted")
+                            .long("hoisted")
+                            .action(ArgAction::SetTrue),
+                    )
+                    .arg(
+                        Arg::new("scoped_registries")
+                            .long("scoped-registry")
+                            .action(ArgAction::Append),
+                    )
+                    .arg(
+                        Arg::new("license_policy")
+                            .long("license-policy")
+                            .action(ArgAction::Append),
+                    ),
+            )
+            .subcommand(Command::new("ls").arg(Arg::new("depth").long("depth")))
+    }
+
+    fn validate(contents: &str) -> Result<Vec<String>> {
+        let dir = tempdir().into_diagnostic()?;
+        let path = dir.path().join("oro.kdl");
+        std::fs::write(&path, contents).into_diagnostic()?;
+        Ok(match validate_file(&path, &command()) {
+            Ok(()) => Vec::new(),
+            Err(OroConfigError::InvalidOptions { errors, .. }) => errors
+                .into_iter()
+                .map(|err| match err {
+                    OroConfigOptionError::UnknownOption { name, help, .. } => {
+                        format!("unknown {name}: {}", help.unwrap_or_default())
+                    }
+                    OroConfigOptionError::InvalidOptionValue { name, reason, .. } => {
+                        format!("invalid {name}: {reason}")
+                    }
+                })
+                .collect(),
+            Err(err) => return Err(err.into()),
+        })
+    }
+
+    #[test]
+    fn valid_options() -> Result<()> {
+        assert_eq!(
+            validate(
+                r#"
+                options {
+                    registry 8080
+                    emoji false
+                    hoisted
+                    depth "2"
+                    scoped-registries {
+                        "@a" "https://a.com"
+                    }
+                    license-policy {
+                        allow "MIT" "ISC"
+                        deny "GPL-3.0-only"
+                    }
+                    apply {
+                        hoisted true
+                        registry 1234
+                    }
+                }
+                "#
+            )?,
+            Vec::<String>::new()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_options() -> Result<()> {
+        assert_eq!(
+            validate(
+                r#"
+                options {
+                    hoistd true
+                    frobnicate 1
+                    apply {
+                        depth "1"
+                    }
+                }
+                "#
+            )?,
+            vec![
+                "unknown hoistd: Did you mean `hoisted`?".to_string(),
+                "unknown frobnicate: ".to_string(),
+                "unknown depth: ".to_string(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_values() -> Result<()> {
+        let errors = validate(
+            r#"
+            options {
+                registry "not a number"
+                emoji "yes"
+                depth "1" "2"
+                scoped-registries {
+                    "@a" "https://a.com" "https://b.com"
+                }
+            }
+            "#,
+        )?;
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].starts_with("invalid registry: invalid value 'not a number'"));
+        assert_eq!(errors[1], "invalid emoji: expected `true` or `false`");
+        assert_eq!(errors[2], "invalid depth: expected a single value");
+        assert_eq!(
+            errors[3],
+            "invalid scoped-registries: expected a single value"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ignores_missing_and_unparseable_files() -> Result<()> {
+        let dir = tempdir().into_diagnostic()?;
+        let path = dir.path().join("oro.kdl");
+        validate_file(&path, &command())?;
+        std::fs::write(&path, "options {").into_diagnostic()?;
+        validate_file(&path, &command())?;
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
-use base64::{engine::general_purpose, Engine as _};
 use kdl::{KdlDocument, KdlEntry, KdlNode, KdlValue};
 use reqwest::header::HeaderValue;
 use url::Url;
 
+#[derive(Clone, PartialEq, Eq)]
 pub enum Credentials {
     Token(String),
     /// Decryptable username and password combinations
@@ -93,6 +93,13 @@ pub fn set_scoped_registry(scope: &str, registry: &Url, config: &mut KdlDocument
     }
 }
 
+/// Looks up the credentials in `config`'s `options { auth { ... } }` block
+/// for the registry at `uri`.
+///
+/// `token` becomes [`Credentials::Token`], `legacy-auth` becomes
+/// [`Credentials::LegacyAuth`], and `username` (with an optional `password`)
+/// becomes [`Credentials::BasicAuth`]. Passwords are used as-is: like
+/// everywhere else in `oro.kdl`, they're not base64-encoded.
 pub fn get_credentials_by_uri(uri: &Url, config: &KdlDocument) -> Option<Credentials> {
     config
         .get("options")
@@ -113,21 +120,15 @@ pub fn get_credentials_by_uri(uri: &Url, config: &KdlDocument) -> Option<Credent
             let username = credentials.get("username");
             let password = credentials.get("password");
 
-            match (token, legacy_auth, username, password) {
-                (_, Some(token), ..) => Some(Credentials::Token(token.as_string()?.into())),
-                (.., Some(username), Some(password)) => {
-                    let username = username.as_string()?;
-                    let password = password.as_string()?;
-                    let password = general_purpose::STANDARD.decode(password).ok()?;
-                    let password = String::from_utf8_lossy(&password).to_string();
-
-                    Some(Credentials::LegacyAuth(
-                        general_purpose::STANDARD.encode(format!("{username}:{password}")),
-                    ))
-                }
-                (Some(legacy_auth), ..) => {
+            match (token, legacy_auth, username) {
+                (Some(token), ..) => Some(Credentials::Token(token.as_string()?.into())),
+                (_, Some(legacy_auth), _) => {
                     Some(Credentials::LegacyAuth(legacy_auth.as_string()?.into()))
                 }
+                (.., Some(username)) => Some(Credentials::BasicAuth {
+                    username: username.as_string()?.into(),
+                    password: password.and_then(|p| p.as_string()).map(String::from),
+                }),
                 _ => None,
             }
         })
@@ -180,3 +181,48 @@ impl TryFrom<Credentials> for HeaderValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials(entry: &str) -> Option<Credentials> {
+        let config = format!(
+            r#"
+            options {{
+                auth {{
+                    "https://registry.example.com/" {entry}
+                }}
+            }}
+            "#
+        )
+        .parse()
+        .unwrap();
+        get_credentials_by_uri(&"https://registry.example.com/".parse().unwrap(), &config)
+    }
+
+    #[test]
+    fn reads_each_kind_of_credentials() {
+        assert!(credentials(r#"token="deadbeef""#) == Some(Credentials::Token("deadbeef".into())));
+        assert!(
+            credentials(r#"legacy-auth="bWU6aHVudGVyMg==""#)
+                == Some(Credentials::LegacyAuth("bWU6aHVudGVyMg==".into()))
+        );
+        // Passwords aren't base64-decoded, or folded into legacy auth.
+        assert!(
+            credentials(r#"username="me" password="hunter2""#)
+                == Some(Credentials::BasicAuth {
+                    username: "me".into(),
+                    password: Some("hunter2".into()),
+                })
+        );
+        assert!(
+            credentials(r#"username="me""#)
+                == Some(Credentials::BasicAuth {
+                    username: "me".into(),
+                    password: None,
+                })
+        );
+        assert!(credentials(r#"email="me@example.com""#).is_none());
+    }
+}
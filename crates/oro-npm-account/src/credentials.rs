@@ -0,0 +1,475 @@
+//! Storage backends for registry credentials.
+//!
+//! [`KdlCredentialStore`] keeps credentials in plain text in an `oro.kdl`
+//! file, [`EncryptedCredentialStore`] keeps them in a passphrase-encrypted
+//! [age](https://age-encryption.org) file, and [`HelperCredentialStore`] delegates to an external command, the
+//! same way git credential helpers work.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use age::secrecy::SecretString;
+use age::{DecryptError, Decryptor, Encryptor};
+use kdl::KdlDocument;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::config::{self, Credentials};
+use crate::error::OroNpmAccountError;
+
+/// A place registry credentials can be read from and saved to. Credentials
+/// are keyed by registry, and looked up by the registry's "nerf dart"
+/// (`//host/path/`), so `http://` and `https://` URLs for the same registry
+/// share credentials.
+pub trait CredentialStore: Send + Sync {
+    /// Looks up the credentials saved for `registry`, if any.
+    fn get(&self, registry: &Url) -> Result<Option<Credentials>, OroNpmAccountError>;
+
+    /// Saves `credentials` for `registry`, replacing any previously-saved
+    /// ones.
+    fn set(&mut self, registry: &Url, credentials: &Credentials) -> Result<(), OroNpmAccountError>;
+
+    /// Removes any credentials saved for `registry`.
+    fn clear(&mut self, registry: &Url) -> Result<(), OroNpmAccountError>;
+}
+
+/// Stores credentials in plain text, in the `options { auth { ... } }`
+/// block of an `oro.kdl` file.
+pub struct KdlCredentialStore {
+    path: PathBuf,
+    doc: KdlDocument,
+}
+
+impl KdlCredentialStore {
+    /// Reads the config file at `path`. It's fine if it doesn't exist yet:
+    /// it'll be created the first time credentials are saved.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, OroNpmAccountError> {
+        let path = path.as_ref().to_owned();
+        let doc = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .parse()
+                .map_err(|e| OroNpmAccountError::InvalidCredentialsFile(path.clone(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => KdlDocument::new(),
+            Err(e) => return Err(OroNpmAccountError::CredentialsIoError(path, e)),
+        };
+        Ok(Self { path, doc })
+    }
+
+    fn save(&self) -> Result<(), OroNpmAccountError> {
+        create_parent_dir(&self.path)?;
+        std::fs::write(&self.path, self.doc.to_string())
+            .map_err(|e| OroNpmAccountError::CredentialsIoError(self.path.clone(), e))
+    }
+}
+
+impl CredentialStore for KdlCredentialStore {
+    fn get(&self, registry: &Url) -> Result<Option<Credentials>, OroNpmAccountError> {
+        Ok(config::get_credentials_by_uri(registry, &self.doc))
+    }
+
+    fn set(&mut self, registry: &Url, credentials: &Credentials) -> Result<(), OroNpmAccountError> {
+        config::set_credentials_by_uri(registry, credentials, &mut self.doc);
+        self.save()
+    }
+
+    fn clear(&mut self, registry: &Url) -> Result<(), OroNpmAccountError> {
+        config::clear_crendentials_by_uri(registry, &mut self.doc);
+        self.save()
+    }
+}
+
+/// Stores credentials in a file encrypted with a passphrase.
+///
+/// The file is a standard [age](https://age-encryption.org) file using a
+/// passphrase (scrypt) recipient, so it can also be decrypted with
+/// `age --decrypt`. Once decrypted, the contents are the same
+/// `options { auth { ... } }` KDL that [`KdlCredentialStore`] writes.
+pub struct EncryptedCredentialStore {
+    path: PathBuf,
+    passphrase: SecretString,
+    doc: KdlDocument,
+}
+
+impl EncryptedCredentialStore {
+    /// Decrypts the credentials file at `path` using `passphrase`. If the
+    /// file doesn't exist yet, it'll be created, encrypted with
+    /// `passphrase`, the first time credentials are saved.
+    pub fn open(
+        path: impl AsRef<Path>,
+        passphrase: impl Into<String>,
+    ) -> Result<Self, OroNpmAccountError> {
+        let path = path.as_ref().to_owned();
+        let passphrase = SecretString::new(passphrase.into());
+        let doc = match std::fs::read(&path) {
+            Ok(contents) => decrypt(&path, &passphrase, &contents)?
+                .parse()
+                .map_err(|e| OroNpmAccountError::InvalidCredentialsFile(path.clone(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => KdlDocument::new(),
+            Err(e) => return Err(OroNpmAccountError::CredentialsIoError(path, e)),
+        };
+        Ok(Self {
+            path,
+            passphrase,
+            doc,
+        })
+    }
+
+    fn save(&self) -> Result<(), OroNpmAccountError> {
+        let contents = encrypt(&self.path, &self.passphrase, &self.doc.to_string())?;
+        create_parent_dir(&self.path)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(&contents))
+            .map_err(|e| OroNpmAccountError::CredentialsIoError(self.path.clone(), e))
+    }
+}
+
+impl CredentialStore for EncryptedCredentialStore {
+    fn get(&self, registry: &Url) -> Result<Option<Credentials>, OroNpmAccountError> {
+        Ok(config::get_credentials_by_uri(registry, &self.doc))
+    }
+
+    fn set(&mut self, registry: &Url, credentials: &Credentials) -> Result<(), OroNpmAccountError> {
+        config::set_credentials_by_uri(registry, credentials, &mut self.doc);
+        self.save()
+    }
+
+    fn clear(&mut self, registry: &Url) -> Result<(), OroNpmAccountError> {
+        config::clear_crendentials_by_uri(registry, &mut self.doc);
+        self.save()
+    }
+}
+
+fn encrypt(
+    path: &Path,
+    passphrase: &SecretString,
+    plaintext: &str,
+) -> Result<Vec<u8>, OroNpmAccountError> {
+    let failed = || OroNpmAccountError::CredentialsEncryptionFailed(path.to_owned());
+    let mut contents = Vec::new();
+    let mut writer = Encryptor::with_user_passphrase(passphrase.clone())
+        .wrap_output(&mut contents)
+        .map_err(|_| failed())?;
+    writer
+        .write_all(plaintext.as_bytes())
+        .and_then(|_| writer.finish())
+        .map_err(|_| failed())?;
+    Ok(contents)
+}
+
+fn decrypt(
+    path: &Path,
+    passphrase: &SecretString,
+    contents: &[u8],
+) -> Result<String, OroNpmAccountError> {
+    let error = |e| match e {
+        DecryptError::DecryptionFailed | DecryptError::KeyDecryptionFailed => {
+            OroNpmAccountError::CredentialsDecryptionFailed(path.to_owned())
+        }
+        _ => OroNpmAccountError::UnsupportedCredentialsFile(path.to_owned()),
+    };
+    let Decryptor::Passphrase(decryptor) = Decryptor::new(contents).map_err(error)? else {
+        // Encrypted to some other kind of recipient, like an age key.
+        return Err(OroNpmAccountError::UnsupportedCredentialsFile(
+            path.to_owned(),
+        ));
+    };
+    let mut plaintext = String::new();
+    decryptor
+        .decrypt(passphrase, None)
+        .map_err(error)?
+        .read_to_string(&mut plaintext)
+        .map_err(|_| OroNpmAccountError::UnsupportedCredentialsFile(path.to_owned()))?;
+    Ok(plaintext)
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), OroNpmAccountError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| OroNpmAccountError::CredentialsIoError(path.to_owned(), e))?;
+    }
+    Ok(())
+}
+
+/// Delegates credential storage to an external command.
+///
+/// The command is run through the system shell, with an action and the
+/// registry's nerf dart (`//registry.example.com/`) appended as arguments:
+///
+/// * `<command> get <nerf-dart>` should print the registry's credentials as
+///   a JSON object with a `token`, a `legacy-auth`, or a `username` and
+///   (optional) `password` field. Printing nothing means there are no
+///   credentials for the registry.
+/// * `<command> store <nerf-dart>` receives the same kind of JSON object on
+///   stdin, and should save it.
+/// * `<command> erase <nerf-dart>` should forget the registry's credentials.
+///
+/// A non-zero exit status is treated as an error.
+pub struct HelperCredentialStore {
+    command: String,
+}
+
+impl HelperCredentialStore {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+
+    fn run(
+        &self,
+        action: &str,
+        registry: &Url,
+        input: Option<String>,
+    ) -> Result<String, OroNpmAccountError> {
+        let spawn_err = |e| OroNpmAccountError::CredentialHelperSpawnError(self.command.clone(), e);
+        let mut child = shell_command(&self.command, action, &oro_client::nerf_dart(registry))
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(spawn_err)?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes()).map_err(spawn_err)?;
+        }
+        let output = child.wait_with_output().map_err(spawn_err)?;
+        if !output.status.success() {
+            return Err(OroNpmAccountError::CredentialHelperFailed {
+                helper: self.command.clone(),
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().into(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl CredentialStore for HelperCredentialStore {
+    fn get(&self, registry: &Url) -> Result<Option<Credentials>, OroNpmAccountError> {
+        let output = self.run("get", registry, None)?;
+        if output.trim().is_empty() {
+            return Ok(None);
+        }
+        let credentials: HelperCredentials = serde_json::from_str(&output).map_err(|e| {
+            OroNpmAccountError::InvalidCredentialHelperOutput(self.command.clone(), e)
+        })?;
+        Ok(credentials.into())
+    }
+
+    fn set(&mut self, registry: &Url, credentials: &Credentials) -> Result<(), OroNpmAccountError> {
+        let input = serde_json::to_string(&HelperCredentials::from(credentials))
+            .expect("Serializing a struct of strings can't fail.");
+        self.run("store", registry, Some(input))?;
+        Ok(())
+    }
+
+    fn clear(&mut self, registry: &Url) -> Result<(), OroNpmAccountError> {
+        self.run("erase", registry, None)?;
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+fn shell_command(helper: &str, action: &str, nerf_dart: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(format!("{helper} {action} {nerf_dart}"));
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_command(helper: &str, action: &str, nerf_dart: &str) -> Command {
+    // Same trick git uses for its credential helpers: the helper string can
+    // contain its own arguments, and ours get appended through `"$@"`.
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(format!("{helper} \"$@\""))
+        .arg(helper)
+        .arg(action)
+        .arg(nerf_dart);
+    cmd
+}
+
+/// JSON representation of [`Credentials`] used by credential helpers. Field
+/// names match the ones used in `oro.kdl`'s `auth` block.
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct HelperCredentials {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    legacy_auth: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+}
+
+impl From<&Credentials> for HelperCredentials {
+    fn from(credentials: &Credentials) -> Self {
+        match credentials {
+            Credentials::Token(token) => Self {
+                token: Some(token.clone()),
+                ..Default::default()
+            },
+            Credentials::LegacyAuth(legacy_auth) => Self {
+                legacy_auth: Some(legacy_auth.clone()),
+                ..Default::default()
+            },
+            Credentials::BasicAuth { username, password } => Self {
+                username: Some(username.clone()),
+                password: password.clone(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<HelperCredentials> for Option<Credentials> {
+    fn from(credentials: HelperCredentials) -> Self {
+        match credentials {
+            HelperCredentials {
+                token: Some(token), ..
+            } => Some(Credentials::Token(token)),
+            HelperCredentials {
+                legacy_auth: Some(legacy_auth),
+                ..
+            } => Some(Credentials::LegacyAuth(legacy_auth)),
+            HelperCredentials {
+                username: Some(username),
+                password,
+                ..
+            } => Some(Credentials::BasicAuth { username, password }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> Url {
+        "https://registry.example.com/".parse().unwrap()
+    }
+
+    #[test]
+    fn kdl_store_round_trip() -> Result<(), OroNpmAccountError> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("oro.kdl");
+        std::fs::write(
+            &path,
+            "options {\n    registry \"https://registry.example.com\"\n}\n",
+        )
+        .unwrap();
+
+        let mut store = KdlCredentialStore::open(&path)?;
+        assert!(store.get(&registry())?.is_none());
+        store.set(&registry(), &Credentials::Token("deadbeef".into()))?;
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("registry \"https://registry.example.com\""));
+        assert!(contents.contains("token=\"deadbeef\""));
+
+        let mut store = KdlCredentialStore::open(&path)?;
+        assert!(
+            store.get(&"http://registry.example.com".parse().unwrap())?
+                == Some(Credentials::Token("deadbeef".into()))
+        );
+        store.clear(&registry())?;
+        assert!(KdlCredentialStore::open(&path)?.get(&registry())?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn encrypted_store_round_trip() -> Result<(), OroNpmAccountError> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("credentials.enc");
+        let credentials = Credentials::BasicAuth {
+            username: "me".into(),
+            password: Some("hunter2".into()),
+        };
+
+        let mut store = EncryptedCredentialStore::open(&path, "correct horse")?;
+        assert!(store.get(&registry())?.is_none());
+        store.set(&registry(), &credentials)?;
+
+        let contents = std::fs::read(&path).unwrap();
+        assert!(contents.starts_with(b"age-encryption.org/v1\n"));
+        assert!(!String::from_utf8_lossy(&contents).contains("hunter2"));
+
+        let store = EncryptedCredentialStore::open(&path, "correct horse")?;
+        assert!(store.get(&registry())? == Some(credentials));
+
+        assert!(matches!(
+            EncryptedCredentialStore::open(&path, "battery staple"),
+            Err(OroNpmAccountError::CredentialsDecryptionFailed(_))
+        ));
+
+        std::fs::write(&path, "options {}").unwrap();
+        assert!(matches!(
+            EncryptedCredentialStore::open(&path, "correct horse"),
+            Err(OroNpmAccountError::UnsupportedCredentialsFile(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn helper_store() -> Result<(), OroNpmAccountError> {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("log");
+        let script = dir.path().join("helper.sh");
+        std::fs::write(
+            &script,
+            format!(
+                r#"
+echo "$1 $2" >> {log}
+case "$1" in
+    get) echo '{{"token": "from-helper"}}' ;;
+    store) cat >> {log} ;;
+    erase) ;;
+esac
+"#,
+                log = log.display()
+            ),
+        )
+        .unwrap();
+
+        let mut store = HelperCredentialStore::new(format!("sh {}", script.display()));
+        assert!(store.get(&registry())? == Some(Credentials::Token("from-helper".into())));
+        store.set(
+            &registry(),
+            &Credentials::LegacyAuth("bWU6aHVudGVyMg==".into()),
+        )?;
+        store.clear(&registry())?;
+
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "get //registry.example.com/\nstore //registry.example.com/\n{\"legacy-auth\":\"bWU6aHVudGVyMg==\"}erase //registry.example.com/\n"
+        );
+
+        let store = HelperCredentialStore::new("echo");
+        assert!(matches!(
+            store.get(&registry()),
+            Err(OroNpmAccountError::InvalidCredentialHelperOutput(..))
+        ));
+        let store = HelperCredentialStore::new("false");
+        assert!(matches!(
+            store.get(&registry()),
+            Err(OroNpmAccountError::CredentialHelperFailed { .. })
+        ));
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use miette::Diagnostic;
 use thiserror::Error;
 
@@ -32,4 +34,68 @@ pub enum OroNpmAccountError {
     #[error("Received unexpected response.")]
     #[diagnostic(code(oro_npm_account::unexpected_response_error), url(docsrs))]
     UnexpectedResponseError,
+
+    /// Failed to read or write a credentials file.
+    #[error("Failed to access credentials file at {0}.")]
+    #[diagnostic(code(oro_npm_account::credentials_io_error), url(docsrs))]
+    CredentialsIoError(PathBuf, #[source] std::io::Error),
+
+    /// A credentials file could be read, but its contents aren't a valid KDL
+    /// document.
+    #[error("Credentials file at {0} is not a valid KDL document.")]
+    #[diagnostic(code(oro_npm_account::invalid_credentials_file), url(docsrs))]
+    InvalidCredentialsFile(PathBuf, #[source] kdl::KdlError),
+
+    /// An encrypted credentials file is truncated, or isn't a
+    /// passphrase-encrypted age file.
+    #[error("{0} is not a passphrase-encrypted age file, or is corrupted.")]
+    #[diagnostic(
+        code(oro_npm_account::unsupported_credentials_file),
+        url(docsrs),
+        help("Delete the file and log in again to recreate it.")
+    )]
+    UnsupportedCredentialsFile(PathBuf),
+
+    /// The encrypted credentials file couldn't be decrypted. This usually
+    /// means the passphrase was wrong.
+    #[error("Failed to decrypt credentials file at {0}.")]
+    #[diagnostic(
+        code(oro_npm_account::credentials_decryption_failed),
+        url(docsrs),
+        help("Double-check your passphrase. It can also be provided through the `ORO_CREDENTIALS_PASSPHRASE` environment variable.")
+    )]
+    CredentialsDecryptionFailed(PathBuf),
+
+    /// The credentials couldn't be encrypted before writing them to disk.
+    #[error("Failed to encrypt credentials for {0}.")]
+    #[diagnostic(code(oro_npm_account::credentials_encryption_failed), url(docsrs))]
+    CredentialsEncryptionFailed(PathBuf),
+
+    /// The credential helper command couldn't be started.
+    #[error("Failed to run credential helper `{0}`.")]
+    #[diagnostic(
+        code(oro_npm_account::credential_helper_spawn_error),
+        url(docsrs),
+        help("Make sure the `credential-helper` command is installed and on your PATH.")
+    )]
+    CredentialHelperSpawnError(String, #[source] std::io::Error),
+
+    /// The credential helper command exited with an error.
+    #[error("Credential helper `{helper}` failed ({status}): {stderr}")]
+    #[diagnostic(code(oro_npm_account::credential_helper_failed), url(docsrs))]
+    CredentialHelperFailed {
+        helper: String,
+        status: std::process::ExitStatus,
+        stderr: String,
+    },
+
+    /// The credential helper printed something other than a JSON
+    /// credentials object.
+    #[error("Credential helper `{0}` returned invalid credentials.")]
+    #[diagnostic(
+        code(oro_npm_account::invalid_credential_helper_output),
+        url(docsrs),
+        help("Credential helpers should print a JSON object with a `token`, `legacy-auth`, or `username` and `password` fields, or nothing at all if they have no credentials for the registry.")
+    )]
+    InvalidCredentialHelperOutput(String, #[source] serde_json::Error),
 }
@@ -1,4 +1,5 @@
 pub mod config;
+pub mod credentials;
 mod error;
 pub mod login;
 
@@ -60,6 +60,13 @@ impl<'a> OroScript<'a> {
         })
     }
 
+    /// Look the script up in an already-loaded [`BuildManifest`], instead of
+    /// reading the package's `package.json`.
+    pub fn manifest(mut self, manifest: &'a BuildManifest) -> Self {
+        self.manifest = Some(manifest);
+        self
+    }
+
     /// If specified, `node_modules/.bin` directories above this path will not
     /// be added to the $PATH variable when running the script.
     pub fn workspace_path(mut self, path: impl AsRef<Path>) -> Self {
@@ -1,16 +1,31 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
+use chrono::{DateTime, Utc};
 use clap::Args;
+use colored::*;
+use indexmap::IndexMap;
 use indicatif::ProgressStyle;
 use miette::Result;
-use node_maintainer::{NodeMaintainer, NodeMaintainerOptions};
+use node_maintainer::{
+    DeprecatedPackage, Lockfile, NodeMaintainer, NodeMaintainerOptions, ScriptFailure,
+};
 use oro_common::CorgiManifest;
 use rand::seq::IteratorRandom;
 use tracing::{Instrument, Span};
 use tracing_indicatif::span_ext::IndicatifSpanExt;
 use url::Url;
 
-use crate::nassun_args::NassunArgs;
+use crate::apply_events::{ApplyEvent, ApplyPhase};
+use crate::commands::diff::summarize_diff;
+use crate::commands::logs::script_logs_dir;
+use crate::credential_args::CredentialArgs;
+use crate::error::OroError;
+use crate::license_policy::{LicensePolicy, LicensePolicyArgs};
+use crate::nassun_args::{parse_before, parse_release_age, NassunArgs};
+use crate::parse_key_value;
 
 /// Applies the current project's requested dependencies to `node_modules/`,
 /// adding, removing, and updating dependencies as needed. This command is
@@ -45,14 +60,69 @@ pub struct ApplyArgs {
     #[arg(long, visible_alias = "frozen")]
     pub locked: bool,
 
+    /// Verify registry signatures for every registry package in the tree,
+    /// failing if any of them are invalid.
+    ///
+    /// Packages already in the lockfile are checked again too. The ID of
+    /// the key each package was signed with is recorded in the lockfile,
+    /// but that record is never trusted in place of a real check.
+    #[arg(long)]
+    pub verify_signatures: bool,
+
+    /// Fail if any of the project's direct dependencies resolve to a
+    /// deprecated version.
+    ///
+    /// Deprecated transitive dependencies are still only reported.
+    /// Deprecation notices for packages that are already in the lockfile
+    /// are read from it, so they can be out of date. They're refreshed when
+    /// the package is resolved again, or on every run with
+    /// `--verify-signatures`.
+    #[arg(long)]
+    pub fail_on_deprecated: bool,
+
+    /// Collapse packages that end up in the tree more than once into a
+    /// single copy, wherever a version that's already in the tree satisfies
+    /// everything that depends on them.
+    ///
+    /// This can mean using an older version of a package than would
+    /// otherwise be picked. `oro dedupe` does this for you.
+    #[arg(long)]
+    pub prefer_dedupe: bool,
+
     /// Skip running install scripts.
     #[arg(long = "no-scripts", alias = "ignore-scripts", action = clap::ArgAction::SetFalse)]
     pub scripts: bool,
 
+    /// Run install scripts every time, instead of restoring what they left
+    /// behind the last time the same package was installed.
+    ///
+    /// Install script results are cached per package, platform, Node.js
+    /// version, and set of resolved dependencies.
+    #[arg(long = "no-side-effects-cache", action = clap::ArgAction::SetFalse)]
+    pub side_effects_cache: bool,
+
     /// Default dist-tag to use when resolving package versions.
     #[arg(long, default_value = "latest")]
     pub default_tag: String,
 
+    /// Only resolve to package versions published at or before this date,
+    /// as if resolving at that point in the past. Packages already in the
+    /// lockfile are not affected.
+    ///
+    /// Accepts an RFC 3339 timestamp (`2023-04-01T12:00:00Z`) or a plain
+    /// date (`2023-04-01`, meaning midnight UTC).
+    #[arg(long, value_parser = parse_before)]
+    pub before: Option<DateTime<Utc>>,
+
+    /// Skip package versions published more recently than this, falling
+    /// back to older versions that satisfy the requested spec. Packages
+    /// already in the lockfile are not affected.
+    ///
+    /// Accepts a number followed by a unit: `s`, `m`, `h`, `d`, or `w`
+    /// (for example, `3d`). Plain numbers are treated as minutes.
+    #[arg(long, value_parser = parse_release_age)]
+    pub minimum_release_age: Option<Duration>,
+
     /// Controls number of concurrent operations during various apply steps
     /// (resolution fetches, extractions, etc).
     ///
@@ -89,12 +159,55 @@ pub struct ApplyArgs {
     #[arg(long)]
     pub hoisted: bool,
 
+    /// Package name pattern for dependencies to link into the project's
+    /// root `node_modules`, even if it doesn't depend on them directly.
+    ///
+    /// Useful for tools like ESLint, Prettier, and TypeScript, which look for
+    /// plugins and `@types` packages there. Patterns can use `*` as a
+    /// wildcard, and patterns starting with `!` exclude anything they match.
+    /// Can be provided multiple times. Only applies to isolated installs.
+    #[arg(long)]
+    pub public_hoist_pattern: Vec<String>,
+
+    /// Package name pattern for dependencies to link into a `node_modules`
+    /// directory inside the package store.
+    ///
+    /// Packages there can be found by every package in the store, but not
+    /// by the project itself, which keeps packages that forgot to declare
+    /// some of their dependencies working. Uses the same syntax as
+    /// `--public-hoist-pattern`. Can be provided multiple times. Only applies
+    /// to isolated installs without a shared `--store`, and is ignored with
+    /// a warning otherwise.
+    #[arg(long)]
+    pub hoist_pattern: Vec<String>,
+
+    /// Patch file to apply to a dependency after it's extracted, in
+    /// `--patched-dependency foo@1.2.3=patches/foo@1.2.3.patch` format.
+    ///
+    /// The dependency can be given by name, or by name and exact version.
+    /// Paths are relative to the project root. Can be provided multiple
+    /// times. These add to, and take precedence over, the
+    /// `patchedDependencies` in package.json. Use `oro patch` to create
+    /// patches.
+    #[arg(
+        long = "patched-dependency",
+        alias = "patched-dependencies",
+        value_parser = parse_key_value::<String, PathBuf>
+    )]
+    pub patched_dependencies: Vec<(String, PathBuf)>,
+
+    #[command(flatten)]
+    pub license_policy: LicensePolicyArgs,
+
     #[arg(from_global)]
     pub registry: Url,
 
     #[arg(from_global)]
     pub scoped_registries: Vec<(String, Url)>,
 
+    #[arg(from_global)]
+    pub registry_mirrors: Vec<(Option<String>, Url)>,
+
     #[arg(from_global)]
     pub proxy: bool,
 
@@ -110,6 +223,9 @@ pub struct ApplyArgs {
     #[arg(from_global)]
     pub auth: Vec<(String, String, String)>,
 
+    #[command(flatten)]
+    pub credentials: CredentialArgs,
+
     #[arg(from_global)]
     pub json: bool,
 
@@ -119,6 +235,9 @@ pub struct ApplyArgs {
     #[arg(from_global)]
     pub cache: Option<PathBuf>,
 
+    #[arg(from_global)]
+    pub store: Option<PathBuf>,
+
     #[arg(from_global)]
     pub emoji: bool,
 }
@@ -132,15 +251,42 @@ impl ApplyArgs {
             return Ok(());
         }
 
+        let license_policy = LicensePolicy::from_args(&self.license_policy)?;
         let root = &self.root;
+        self.emit(|| ApplyEvent::start(root));
+        let lockfile_path = root.join("package-lock.kdl");
+        let old_lockfile = self.read_old_lockfile(&lockfile_path).await;
+        let scripts_run = Arc::new(AtomicUsize::new(0));
         let maintainer = self
-            .resolve(manifest, self.configured_maintainer()?)
+            .resolve(manifest, self.configured_maintainer(scripts_run.clone())?)
             .await?;
+        let deprecations = maintainer.deprecations();
+        if self.fail_on_deprecated {
+            let direct = deprecations
+                .iter()
+                .filter(|pkg| pkg.is_direct())
+                .map(fmt_deprecated)
+                .collect::<Vec<_>>();
+            if !direct.is_empty() {
+                // Still say what's deprecated (and why), so `--json`
+                // consumers get their `deprecated` events too.
+                self.report_deprecations(&deprecations);
+                return Err(OroError::DeprecatedDirectDependencies(direct).into());
+            }
+        }
+        // Nothing gets installed until the whole tree passes the license
+        // policy.
+        if !license_policy.is_empty() {
+            license_policy.enforce(&maintainer.to_resolved_sbom().await?)?;
+        }
 
+        let mut pruned = 0;
+        let mut extracted = 0;
+        let mut script_failures = Vec::new();
         if !self.lockfile_only {
-            self.prune(&maintainer).await?;
-            self.extract(&maintainer).await?;
-            self.rebuild(&maintainer).await?;
+            pruned = self.prune(&maintainer).await?;
+            extracted = self.extract(&maintainer).await?;
+            script_failures = self.rebuild(&maintainer, &scripts_run).await?;
         } else {
             tracing::info!(
                 "{}Skipping installing node_modules/, only writing lockfile.",
@@ -148,16 +294,45 @@ impl ApplyArgs {
             );
         }
 
+        let diff = old_lockfile.diff(&maintainer.to_lockfile()?);
+
         if self.lockfile {
-            maintainer
-                .write_lockfile(root.join("package-lock.kdl"))
-                .await?;
+            let lockfile_time = std::time::Instant::now();
+            self.emit(|| ApplyEvent::PhaseStart {
+                phase: ApplyPhase::Lockfile,
+            });
+            maintainer.write_lockfile(&lockfile_path).await?;
+            self.emit(|| ApplyEvent::phase_end(ApplyPhase::Lockfile, 1, lockfile_time.elapsed()));
             tracing::info!(
                 "{}Wrote lockfile to package-lock.kdl.",
                 self.emoji_writing()
             );
         }
 
+        self.report_deprecations(&deprecations);
+        self.report_script_failures(&script_failures);
+
+        self.emit(|| ApplyEvent::Summary {
+            packages: maintainer.package_count(),
+            pruned,
+            extracted,
+            scripts: self.scripts && !self.lockfile_only,
+            lockfile: if self.lockfile {
+                Some(lockfile_path.clone())
+            } else {
+                None
+            },
+            changes: (&diff).into(),
+            deprecated: deprecations.len(),
+            elapsed_ms: total_time.elapsed().as_millis(),
+        });
+
+        tracing::info!(
+            "{}Dependency changes: {}.",
+            self.emoji_writing(),
+            summarize_diff(&diff)
+        );
+
         tracing::info!(
             "{}Applied node_modules/ in {}s. {}",
             self.emoji_tada(),
@@ -167,37 +342,138 @@ impl ApplyArgs {
         Ok(())
     }
 
-    fn configured_maintainer(&self) -> Result<NodeMaintainerOptions> {
+    /// The lockfile from before this apply, to report what changed. A
+    /// lockfile that can't be read gets a warning, since every package will
+    /// look new next to it.
+    async fn read_old_lockfile(&self, path: &Path) -> Lockfile {
+        let kdl = match async_std::fs::read_to_string(path).await {
+            Ok(kdl) => kdl,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Lockfile::default(),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to read {}, so all packages will be reported as added: {e}",
+                    path.display()
+                );
+                return Lockfile::default();
+            }
+        };
+        Lockfile::from_kdl(kdl).unwrap_or_else(|e| {
+            tracing::warn!(
+                "Failed to parse {}, so all packages will be reported as added: {e}",
+                path.display()
+            );
+            Lockfile::default()
+        })
+    }
+
+    /// Reports deprecated packages in the tree, grouped by package version,
+    /// along with the dependency chains that pulled them in.
+    fn report_deprecations(&self, deprecations: &[DeprecatedPackage]) {
+        if self.json {
+            for pkg in deprecations {
+                ApplyEvent::Deprecated {
+                    package: pkg.clone(),
+                }
+                .emit();
+            }
+            return;
+        }
+        if deprecations.is_empty() {
+            return;
+        }
+        let mut grouped: IndexMap<String, (Option<&str>, Vec<String>)> = IndexMap::new();
+        for pkg in deprecations {
+            grouped
+                .entry(fmt_deprecated(pkg))
+                .or_insert_with(|| (pkg.reason.as_deref(), Vec::new()))
+                .1
+                .push(pkg.dependency_path.join(" > "));
+        }
+        grouped.sort_keys();
+        tracing::warn!(
+            "{}{} deprecated {}:",
+            self.emoji_warning(),
+            grouped.len(),
+            if grouped.len() == 1 {
+                "package"
+            } else {
+                "packages"
+            }
+        );
+        for (pkg, (reason, paths)) in grouped {
+            tracing::warn!(
+                "  {} {}",
+                pkg.magenta(),
+                reason.unwrap_or("(no reason given)")
+            );
+            for path in paths {
+                tracing::warn!("    {}", format!("via {path}").dimmed());
+            }
+        }
+    }
+
+    fn report_script_failures(&self, failures: &[ScriptFailure]) {
+        report_script_failures(failures, self.json, self.emoji_warning());
+    }
+
+    /// Emits an event to the `--json` event stream, if enabled.
+    fn emit(&self, event: impl FnOnce() -> ApplyEvent) {
+        if self.json {
+            event().emit();
+        }
+    }
+
+    fn configured_maintainer(
+        &self,
+        scripts_run: Arc<AtomicUsize>,
+    ) -> Result<NodeMaintainerOptions> {
         let root = &self.root;
+        let json = self.json;
         let nassun = NassunArgs::from_apply_args(self).to_nassun()?;
         let mut nm = NodeMaintainerOptions::new();
         nm = nm
             .nassun(nassun)
             .locked(self.locked)
+            .verify_signatures(self.verify_signatures)
+            .prefer_dedupe(self.prefer_dedupe)
             .concurrency(self.concurrency)
             .script_concurrency(self.script_concurrency)
             .root(root)
             .prefer_copy(self.prefer_copy)
+            .side_effects_cache(self.side_effects_cache)
             .hoisted(self.hoisted)
             .on_resolution_added(move || {
                 Span::current().pb_inc_length(1);
             })
             .on_resolve_progress(move |pkg, elapsed| {
+                if json {
+                    ApplyEvent::resolved(pkg, elapsed).emit();
+                }
                 let span = Span::current();
                 span.pb_inc(1);
                 span.pb_set_message(&format!("{:?} ({}ms)", pkg.resolved(), elapsed.as_micros() / 1000));
             })
             .on_prune_progress(move |path| {
+                if json {
+                    ApplyEvent::Pruned { path: path.to_owned() }.emit();
+                }
                 let span = Span::current();
                 span.pb_inc(1);
                 span.pb_set_message(&format!("{}", path.display()));
             })
             .on_extract_progress(move |pkg, elapsed| {
+                if json {
+                    ApplyEvent::extracted(pkg, elapsed).emit();
+                }
                 let span = Span::current();
                 span.pb_inc(1);
                 span.pb_set_message(&format!("{:?} ({}ms)", pkg.resolved(), elapsed.as_micros() / 1000))
             })
-            .on_script_start(|pkg, event| {
+            .on_script_start(move |pkg, event| {
+                scripts_run.fetch_add(1, Ordering::Relaxed);
+                if json {
+                    ApplyEvent::script_start(pkg, event).emit();
+                }
                 let span = Span::current();
                 span.pb_set_style(
                     &ProgressStyle::default_bar()
@@ -208,14 +484,33 @@ impl ApplyArgs {
                         .unwrap(),
                 );
             })
-            .on_script_line(|line| {
+            .on_script_output(move |pkg, event, line| {
+                if json {
+                    ApplyEvent::script_line(pkg, event, line).emit();
+                }
                 let span = Span::current();
                 span.pb_inc(1);
                 span.pb_set_message(line);
             });
 
         if let Some(cache) = self.cache.as_deref() {
-            nm = nm.cache(cache);
+            nm = nm.cache(cache).script_logs(script_logs_dir(cache));
+        }
+
+        if let Some(store) = self.store.as_deref() {
+            nm = nm.store(store);
+        }
+
+        for pattern in &self.public_hoist_pattern {
+            nm = nm.public_hoist_pattern(pattern);
+        }
+
+        for pattern in &self.hoist_pattern {
+            nm = nm.hoist_pattern(pattern);
+        }
+
+        for (spec, patch) in &self.patched_dependencies {
+            nm = nm.patched_dependency(spec, patch);
         }
 
         Ok(nm)
@@ -240,6 +535,9 @@ impl ApplyArgs {
         );
         resolve_span.pb_set_length(0);
         let resolve_span_enter = resolve_span.enter();
+        self.emit(|| ApplyEvent::PhaseStart {
+            phase: ApplyPhase::Resolve,
+        });
 
         // Actually do a resolve.
         let resolved_nm = builder.resolve_manifest(root_manifest).await?;
@@ -247,12 +545,27 @@ impl ApplyArgs {
         // Wrap up progress bar and print messages.
         std::mem::drop(resolve_span_enter);
         std::mem::drop(resolve_span);
+        self.emit(|| {
+            ApplyEvent::phase_end(
+                ApplyPhase::Resolve,
+                resolved_nm.package_count(),
+                resolve_time.elapsed(),
+            )
+        });
         tracing::info!(
             "{}Resolved {} packages in {}s.",
             self.emoji_magnifying_glass(),
             resolved_nm.package_count(),
             resolve_time.elapsed().as_millis() as f32 / 1000.0
         );
+        if self.prefer_dedupe {
+            let deduped = resolved_nm.deduped_count();
+            tracing::info!(
+                "{}Removed {deduped} duplicate package{}.",
+                self.emoji_magnifying_glass(),
+                if deduped == 1 { "" } else { "s" },
+            );
+        }
 
         Ok(resolved_nm)
     }
@@ -272,6 +585,9 @@ impl ApplyArgs {
         );
         prune_span.pb_set_length(maintainer.package_count() as u64);
         let prune_span_enter = prune_span.enter();
+        self.emit(|| ApplyEvent::PhaseStart {
+            phase: ApplyPhase::Prune,
+        });
 
         // Actually do the pruning.
         let pruned = maintainer.prune().await?;
@@ -279,6 +595,7 @@ impl ApplyArgs {
         // Wrap up progress bar and message.
         std::mem::drop(prune_span_enter);
         std::mem::drop(prune_span);
+        self.emit(|| ApplyEvent::phase_end(ApplyPhase::Prune, pruned, prune_time.elapsed()));
         tracing::info!(
             "{}Pruned {pruned} packages in {}s.",
             self.emoji_broom(),
@@ -303,6 +620,9 @@ impl ApplyArgs {
         );
         extract_span.pb_set_length(maintainer.package_count() as u64);
         let extract_span_enter = extract_span.enter();
+        self.emit(|| ApplyEvent::PhaseStart {
+            phase: ApplyPhase::Extract,
+        });
 
         // Actually do the extraction.
         let extracted = maintainer.extract().await?;
@@ -310,6 +630,7 @@ impl ApplyArgs {
         // Wrap up progress bar and message.
         std::mem::drop(extract_span_enter);
         std::mem::drop(extract_span);
+        self.emit(|| ApplyEvent::phase_end(ApplyPhase::Extract, extracted, extract_time.elapsed()));
         tracing::info!(
             "{}Extracted {extracted} package{} in {}s.",
             self.emoji_package(),
@@ -320,8 +641,18 @@ impl ApplyArgs {
         Ok(extracted)
     }
 
-    async fn rebuild(&self, maintainer: &NodeMaintainer) -> Result<()> {
+    /// Runs lifecycle scripts, returning any that failed without failing
+    /// the apply. If one fails the apply, the failures are reported before
+    /// returning the error.
+    async fn rebuild(
+        &self,
+        maintainer: &NodeMaintainer,
+        scripts_run: &AtomicUsize,
+    ) -> Result<Vec<ScriptFailure>> {
         let script_time = std::time::Instant::now();
+        self.emit(|| ApplyEvent::PhaseStart {
+            phase: ApplyPhase::Scripts,
+        });
         let script_span = if self.scripts {
             tracing::info_span!("Building")
         } else {
@@ -337,10 +668,22 @@ impl ApplyArgs {
                     .unwrap(),
             );
         }
-        maintainer
+        let rebuilt = maintainer
             .rebuild(!self.scripts)
             .instrument(script_span)
-            .await?;
+            .await;
+        let failures = maintainer.script_failures().await;
+        if rebuilt.is_err() {
+            self.report_script_failures(&failures);
+        }
+        rebuilt?;
+        self.emit(|| {
+            ApplyEvent::phase_end(
+                ApplyPhase::Scripts,
+                scripts_run.load(Ordering::Relaxed),
+                script_time.elapsed(),
+            )
+        });
         if self.scripts {
             tracing::info!(
                 "{}Ran lifecycle scripts in {}s.",
@@ -354,7 +697,7 @@ impl ApplyArgs {
                 script_time.elapsed().as_millis() as f32 / 1000.0
             );
         }
-        Ok(())
+        Ok(failures)
     }
 
     fn emoji_run(&self) -> &'static str {
@@ -381,6 +724,10 @@ impl ApplyArgs {
         self.maybe_emoji("🎉 ")
     }
 
+    fn emoji_warning(&self) -> &'static str {
+        self.maybe_emoji("⚠️  ")
+    }
+
     fn emoji_link(&self) -> &'static str {
         self.maybe_emoji("🔗 ")
     }
@@ -394,6 +741,42 @@ impl ApplyArgs {
     }
 }
 
+/// Reports lifecycle scripts that failed, along with the last of their
+/// output and where to find the rest.
+pub(crate) fn report_script_failures(failures: &[ScriptFailure], json: bool, warning: &str) {
+    if json {
+        for failure in failures {
+            ApplyEvent::script_failed(failure).emit();
+        }
+        return;
+    }
+    for failure in failures {
+        tracing::warn!(
+            "{warning}{}::{} failed{}.",
+            failure.package.name().magenta(),
+            failure.event,
+            if failure.optional {
+                " (optional, so it was skipped)"
+            } else {
+                ""
+            },
+        );
+        if !failure.output.is_empty() {
+            tracing::warn!("  Last {} lines of output:", failure.output.len());
+            for line in &failure.output {
+                tracing::warn!("    {}", line.dimmed());
+            }
+        }
+        if let Some(log) = &failure.log {
+            tracing::warn!(
+                "  Full output is in {} (or run `oro logs scripts {}`).",
+                log.display(),
+                failure.package.name()
+            );
+        }
+    }
+}
+
 // Inspired and brazenly taken from SLIME:
 // https://github.com/slime/slime/blob/e193bc5f3431a2f71f1d7a0e3f28e6dc4dd5de2d/slime.el#L1360-L1375
 fn hackerish_encouragement() -> &'static str {
@@ -416,3 +799,10 @@ fn hackerish_encouragement() -> &'static str {
         .choose(&mut rng)
         .expect("Iterator should not be empty.")
 }
+
+fn fmt_deprecated(pkg: &DeprecatedPackage) -> String {
+    match &pkg.version {
+        Some(version) => format!("{}@{version}", pkg.name),
+        None => pkg.name.clone(),
+    }
+}
@@ -0,0 +1,432 @@
+//! Machine-readable event stream for `oro apply --json`.
+//!
+//! When `--json` is passed, apply operations emit one JSON object per line
+//! (NDJSON) to stdout, describing each phase and package as it's processed.
+//! Every line has a `schema` field with [`APPLY_EVENT_SCHEMA_VERSION`] and
+//! an `event` field naming the kind of event. Fields are only ever added
+//! within a schema version; any removal or change in meaning bumps it.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use nassun::{Package, PackageResolution};
+use node_maintainer::{DeprecatedPackage, LockfileDiff, ScriptFailure};
+use serde::Serialize;
+
+/// Version of the apply event schema.
+pub const APPLY_EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// A phase of the apply process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyPhase {
+    Resolve,
+    Prune,
+    Extract,
+    Scripts,
+    Lockfile,
+}
+
+/// A single event in the apply event stream.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ApplyEvent {
+    /// Apply has started for the project at `root`.
+    Start { root: PathBuf },
+    /// A phase has started.
+    PhaseStart { phase: ApplyPhase },
+    /// A phase has finished. `count` is the number of items it processed.
+    PhaseEnd {
+        phase: ApplyPhase,
+        count: usize,
+        elapsed_ms: u128,
+    },
+    /// A package was resolved.
+    Resolved {
+        #[serde(flatten)]
+        package: PackageInfo,
+        elapsed_ms: u128,
+    },
+    /// An extraneous path was removed from `node_modules`.
+    Pruned { path: PathBuf },
+    /// A package was extracted into `node_modules`.
+    Extracted {
+        #[serde(flatten)]
+        package: PackageInfo,
+        elapsed_ms: u128,
+    },
+    /// A lifecycle script started running.
+    ScriptStart {
+        #[serde(flatten)]
+        package: PackageInfo,
+        script: String,
+    },
+    /// A line of output from a running lifecycle script.
+    ScriptLine {
+        #[serde(flatten)]
+        package: PackageInfo,
+        script: String,
+        line: String,
+    },
+    /// A lifecycle script failed. `output` has its last few lines, and `log`
+    /// is where all of it was written, if anywhere. Optional dependencies'
+    /// scripts failing doesn't fail the apply.
+    ScriptFailed {
+        #[serde(flatten)]
+        package: PackageInfo,
+        script: String,
+        optional: bool,
+        output: Vec<String>,
+        log: Option<PathBuf>,
+    },
+    /// A deprecated package was found in the resolved tree.
+    Deprecated {
+        #[serde(flatten)]
+        package: DeprecatedPackage,
+    },
+    /// Apply finished successfully.
+    Summary {
+        packages: usize,
+        pruned: usize,
+        extracted: usize,
+        scripts: bool,
+        lockfile: Option<PathBuf>,
+        changes: ChangeCounts,
+        deprecated: usize,
+        elapsed_ms: u128,
+    },
+}
+
+/// Number of package-level changes to the lockfile made by an apply.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ChangeCounts {
+    pub added: usize,
+    pub removed: usize,
+    pub upgraded: usize,
+    pub downgraded: usize,
+    pub changed: usize,
+    pub moved: usize,
+}
+
+impl From<&LockfileDiff> for ChangeCounts {
+    fn from(diff: &LockfileDiff) -> Self {
+        ChangeCounts {
+            added: diff.added.len(),
+            removed: diff.removed.len(),
+            upgraded: diff.upgraded.len(),
+            downgraded: diff.downgraded.len(),
+            changed: diff.changed.len(),
+            moved: diff.moved.len(),
+        }
+    }
+}
+
+/// Identifying information for a package referenced by an [`ApplyEvent`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageInfo {
+    pub name: String,
+    pub version: Option<String>,
+    pub resolved: String,
+}
+
+impl From<&Package> for PackageInfo {
+    fn from(pkg: &Package) -> Self {
+        let resolved = pkg.resolved();
+        let version = if let PackageResolution::Npm { version, .. } = resolved {
+            Some(version.to_string())
+        } else {
+            None
+        };
+        PackageInfo {
+            name: pkg.name().to_string(),
+            version,
+            resolved: resolved.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ApplyEventLine<'a> {
+    schema: u32,
+    #[serde(flatten)]
+    event: &'a ApplyEvent,
+}
+
+impl ApplyEvent {
+    pub fn start(root: &Path) -> Self {
+        ApplyEvent::Start {
+            root: root.to_owned(),
+        }
+    }
+
+    pub fn phase_end(phase: ApplyPhase, count: usize, elapsed: Duration) -> Self {
+        ApplyEvent::PhaseEnd {
+            phase,
+            count,
+            elapsed_ms: elapsed.as_millis(),
+        }
+    }
+
+    pub fn resolved(pkg: &Package, elapsed: Duration) -> Self {
+        ApplyEvent::Resolved {
+            package: pkg.into(),
+            elapsed_ms: elapsed.as_millis(),
+        }
+    }
+
+    pub fn extracted(pkg: &Package, elapsed: Duration) -> Self {
+        ApplyEvent::Extracted {
+            package: pkg.into(),
+            elapsed_ms: elapsed.as_millis(),
+        }
+    }
+
+    pub fn script_start(pkg: &Package, script: &str) -> Self {
+        ApplyEvent::ScriptStart {
+            package: pkg.into(),
+            script: script.into(),
+        }
+    }
+
+    pub fn script_line(pkg: &Package, script: &str, line: &str) -> Self {
+        ApplyEvent::ScriptLine {
+            package: pkg.into(),
+            script: script.into(),
+            line: line.into(),
+        }
+    }
+
+    pub fn script_failed(failure: &ScriptFailure) -> Self {
+        ApplyEvent::ScriptFailed {
+            package: (&failure.package).into(),
+            script: failure.event.clone(),
+            optional: failure.optional,
+            output: failure.output.clone(),
+            log: failure.log.clone(),
+        }
+    }
+
+    /// Serializes this event as a single line of the event stream, without
+    /// a trailing newline.
+    pub fn to_line(&self) -> String {
+        serde_json::to_string(&ApplyEventLine {
+            schema: APPLY_EVENT_SCHEMA_VERSION,
+            event: self,
+        })
+        .expect("ApplyEvent serialization is infallible")
+    }
+
+    /// Writes this event to stdout as a line of NDJSON.
+    pub fn emit(&self) {
+        let line = self.to_line();
+        let mut stdout = std::io::stdout().lock();
+        // If stdout is gone, there's no one left to tell.
+        let _ = writeln!(stdout, "{line}");
+        let _ = stdout.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{json, Value};
+
+    fn parse(event: ApplyEvent) -> Value {
+        let line = event.to_line();
+        assert!(!line.contains('\n'), "events must be single lines");
+        serde_json::from_str(&line).unwrap()
+    }
+
+    fn pkg_info() -> PackageInfo {
+        PackageInfo {
+            name: "foo".into(),
+            version: Some("1.2.3".into()),
+            resolved: "https://registry.npmjs.org/foo/-/foo-1.2.3.tgz".into(),
+        }
+    }
+
+    #[test]
+    fn start_and_phases() {
+        assert_eq!(
+            parse(ApplyEvent::start(Path::new("/proj"))),
+            json!({"schema": 1, "event": "start", "root": "/proj"})
+        );
+        assert_eq!(
+            parse(ApplyEvent::PhaseStart {
+                phase: ApplyPhase::Resolve
+            }),
+            json!({"schema": 1, "event": "phase_start", "phase": "resolve"})
+        );
+        assert_eq!(
+            parse(ApplyEvent::phase_end(
+                ApplyPhase::Scripts,
+                3,
+                Duration::from_millis(42)
+            )),
+            json!({
+                "schema": 1,
+                "event": "phase_end",
+                "phase": "scripts",
+                "count": 3,
+                "elapsed_ms": 42
+            })
+        );
+    }
+
+    #[test]
+    fn package_events() {
+        assert_eq!(
+            parse(ApplyEvent::Resolved {
+                package: pkg_info(),
+                elapsed_ms: 7
+            }),
+            json!({
+                "schema": 1,
+                "event": "resolved",
+                "name": "foo",
+                "version": "1.2.3",
+                "resolved": "https://registry.npmjs.org/foo/-/foo-1.2.3.tgz",
+                "elapsed_ms": 7
+            })
+        );
+        assert_eq!(
+            parse(ApplyEvent::Extracted {
+                package: pkg_info(),
+                elapsed_ms: 9
+            }),
+            json!({
+                "schema": 1,
+                "event": "extracted",
+                "name": "foo",
+                "version": "1.2.3",
+                "resolved": "https://registry.npmjs.org/foo/-/foo-1.2.3.tgz",
+                "elapsed_ms": 9
+            })
+        );
+        assert_eq!(
+            parse(ApplyEvent::Pruned {
+                path: "node_modules/bar".into()
+            }),
+            json!({"schema": 1, "event": "pruned", "path": "node_modules/bar"})
+        );
+    }
+
+    #[test]
+    fn script_events() {
+        assert_eq!(
+            parse(ApplyEvent::ScriptStart {
+                package: pkg_info(),
+                script: "install".into()
+            }),
+            json!({
+                "schema": 1,
+                "event": "script_start",
+                "name": "foo",
+                "version": "1.2.3",
+                "resolved": "https://registry.npmjs.org/foo/-/foo-1.2.3.tgz",
+                "script": "install"
+            })
+        );
+        assert_eq!(
+            parse(ApplyEvent::ScriptLine {
+                package: pkg_info(),
+                script: "install".into(),
+                line: "compiling \"stuff\"".into()
+            }),
+            json!({
+                "schema": 1,
+                "event": "script_line",
+                "name": "foo",
+                "version": "1.2.3",
+                "resolved": "https://registry.npmjs.org/foo/-/foo-1.2.3.tgz",
+                "script": "install",
+                "line": "compiling \"stuff\""
+            })
+        );
+        assert_eq!(
+            parse(ApplyEvent::ScriptFailed {
+                package: pkg_info(),
+                script: "postinstall".into(),
+                optional: false,
+                output: vec!["gyp ERR! build error".into()],
+                log: Some("/cache/_logs/scripts/foo@1.2.3.log".into()),
+            }),
+            json!({
+                "schema": 1,
+                "event": "script_failed",
+                "name": "foo",
+                "version": "1.2.3",
+                "resolved": "https://registry.npmjs.org/foo/-/foo-1.2.3.tgz",
+                "script": "postinstall",
+                "optional": false,
+                "output": ["gyp ERR! build error"],
+                "log": "/cache/_logs/scripts/foo@1.2.3.log"
+            })
+        );
+    }
+
+    #[test]
+    fn deprecated() {
+        assert_eq!(
+            parse(ApplyEvent::Deprecated {
+                package: DeprecatedPackage {
+                    name: "bar".into(),
+                    version: Some("2.0.0".parse().unwrap()),
+                    path: "foo/node_modules/bar".into(),
+                    reason: Some("use baz instead".into()),
+                    dependency_path: vec!["foo".into(), "bar".into()],
+                }
+            }),
+            json!({
+                "schema": 1,
+                "event": "deprecated",
+                "name": "bar",
+                "version": "2.0.0",
+                "path": "foo/node_modules/bar",
+                "reason": "use baz instead",
+                "dependency_path": ["foo", "bar"]
+            })
+        );
+    }
+
+    #[test]
+    fn summary() {
+        assert_eq!(
+            parse(ApplyEvent::Summary {
+                packages: 10,
+                pruned: 1,
+                extracted: 9,
+                scripts: true,
+                lockfile: Some("package-lock.kdl".into()),
+                changes: ChangeCounts {
+                    added: 2,
+                    upgraded: 1,
+                    ..Default::default()
+                },
+                deprecated: 1,
+                elapsed_ms: 1234
+            }),
+            json!({
+                "schema": 1,
+                "event": "summary",
+                "packages": 10,
+                "pruned": 1,
+                "extracted": 9,
+                "scripts": true,
+                "lockfile": "package-lock.kdl",
+                "changes": {
+                    "added": 2,
+                    "removed": 0,
+                    "upgraded": 1,
+                    "downgraded": 0,
+                    "changed": 0,
+                    "moved": 0
+                },
+                "deprecated": 1,
+                "elapsed_ms": 1234
+            })
+        );
+    }
+}
@@ -2,9 +2,10 @@ use std::path::PathBuf;
 
 use clap::Args;
 use oro_client::{OroClientBuilder, OroClientError};
+use oro_npm_account::config::Credentials;
 use url::Url;
 
-use crate::{apply_args::ApplyArgs, nassun_args::NassunArgs};
+use crate::{apply_args::ApplyArgs, credential_args::CredentialArgs, nassun_args::NassunArgs};
 
 #[derive(Debug, Args)]
 pub struct ClientArgs {
@@ -25,6 +26,63 @@ pub struct ClientArgs {
 
     #[arg(from_global)]
     pub auth: Vec<(String, String, String)>,
+
+    #[command(flatten)]
+    pub credentials: CredentialArgs,
+}
+
+impl ClientArgs {
+    /// Looks up credentials for `registries` in the configured credential
+    /// store, and adds them to `auth`. Registries that already have
+    /// credentials, from `--auth` or from oro.kdl, are left alone.
+    ///
+    /// A store that can't be read is only warned about, so a broken
+    /// credential helper doesn't get in the way of anonymous requests.
+    pub fn with_stored_credentials<'a>(
+        mut self,
+        registries: impl IntoIterator<Item = &'a Url>,
+    ) -> Self {
+        let store = match self.credentials.open_external() {
+            Ok(Some(store)) => store,
+            Ok(None) => return self,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to open credential store, so no stored credentials will be used: {e}"
+                );
+                return self;
+            }
+        };
+        for registry in registries {
+            let nerf_dart = oro_client::nerf_dart(registry);
+            let configured = self.auth.iter().any(|(reg, ..)| {
+                Url::parse(reg)
+                    .map(|url| oro_client::nerf_dart(&url) == nerf_dart)
+                    .unwrap_or(false)
+            });
+            if configured {
+                continue;
+            }
+            let reg = registry.to_string();
+            let credentials = store.get(registry).unwrap_or_else(|e| {
+                tracing::warn!("Failed to look up stored credentials for {registry}: {e}");
+                None
+            });
+            match credentials {
+                Some(Credentials::Token(token)) => self.auth.push((reg, "token".into(), token)),
+                Some(Credentials::LegacyAuth(auth)) => {
+                    self.auth.push((reg, "legacy-auth".into(), auth))
+                }
+                Some(Credentials::BasicAuth { username, password }) => {
+                    self.auth.push((reg.clone(), "username".into(), username));
+                    if let Some(password) = password {
+                        self.auth.push((reg, "password".into(), password));
+                    }
+                }
+                None => {}
+            }
+        }
+        self
+    }
 }
 
 impl From<ApplyArgs> for ClientArgs {
@@ -36,6 +94,7 @@ impl From<ApplyArgs> for ClientArgs {
             no_proxy_domain: value.no_proxy_domain,
             retries: value.retries,
             auth: value.auth,
+            credentials: value.credentials,
         }
     }
 }
@@ -49,13 +108,14 @@ impl From<NassunArgs> for ClientArgs {
             no_proxy_domain: value.no_proxy_domain,
             retries: value.retries,
             auth: value.auth,
+            credentials: value.credentials,
         }
     }
 }
 
 impl TryFrom<ClientArgs> for OroClientBuilder {
     type Error = OroClientError;
-    fn try_from(value: ClientArgs) -> Result<Self, Self::Error> {
+    fn try_from(value: ClientArgs) -> std::result::Result<Self, Self::Error> {
         let mut builder = OroClientBuilder::new()
             .retries(value.retries)
             .proxy(value.proxy);
@@ -91,3 +151,40 @@ impl TryFrom<ClientArgs> for OroClientBuilder {
         Ok(builder)
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::credential_args::CredentialStoreKind;
+
+    fn client_args(credential_helper: &str) -> ClientArgs {
+        ClientArgs {
+            cache: None,
+            proxy: false,
+            proxy_url: None,
+            no_proxy_domain: None,
+            retries: 0,
+            auth: Vec::new(),
+            credentials: CredentialArgs {
+                credential_store: CredentialStoreKind::Helper,
+                credential_helper: Some(credential_helper.into()),
+                credentials_file: None,
+            },
+        }
+    }
+
+    #[test]
+    fn stored_credentials() {
+        let registry: Url = "https://registry.example.com/".parse().unwrap();
+        let args = client_args(r#"echo '{"token": "deadbeef"}'; true"#)
+            .with_stored_credentials([&registry]);
+        assert_eq!(
+            args.auth,
+            vec![(registry.to_string(), "token".into(), "deadbeef".into())]
+        );
+
+        // A broken helper just means there are no stored credentials.
+        let args = client_args("false").with_stored_credentials([&registry]);
+        assert!(args.auth.is_empty());
+    }
+}
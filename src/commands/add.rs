@@ -8,6 +8,7 @@ use oro_pretty_json::Formatted;
 
 use crate::apply_args::ApplyArgs;
 use crate::commands::OroCommand;
+use crate::global_install_args::GlobalInstallArgs;
 use crate::nassun_args::NassunArgs;
 
 /// Adds one or more dependencies to the target package.
@@ -24,13 +25,21 @@ pub struct AddCmd {
     prefix: String,
 
     /// Add packages as devDependencies.
-    #[arg(long, short = 'D')]
+    #[arg(long, short = 'D', conflicts_with = "global")]
     dev: bool,
 
     /// Add packages as optionalDependencies.
-    #[arg(long, short = 'O', visible_alias = "optional")]
+    #[arg(
+        long,
+        short = 'O',
+        visible_alias = "optional",
+        conflicts_with = "global"
+    )]
     opt: bool,
 
+    #[command(flatten)]
+    global: GlobalInstallArgs,
+
     #[command(flatten)]
     apply: ApplyArgs,
 }
@@ -38,16 +47,66 @@ pub struct AddCmd {
 #[async_trait]
 impl OroCommand for AddCmd {
     async fn execute(mut self) -> Result<()> {
+        if self.apply.locked {
+            // NOTE: we force locked to be false here, because it doesn't make
+            // sense to run this command in locked mode.
+            tracing::info!("Ignoring --locked option. It doesn't make sense to run this command in locked mode.");
+            self.apply.locked = false;
+        }
+
+        if self.global.global {
+            return self.execute_global().await;
+        }
+
         let mut manifest = oro_pretty_json::from_str(
             &async_std::fs::read_to_string(self.apply.root.join("package.json"))
                 .await
                 .into_diagnostic()?,
         )
         .into_diagnostic()?;
+        let mut count = 0;
+        for (name, resolved_spec) in self.resolve_specs().await? {
+            self.remove_from_manifest(&mut manifest, &name);
+            self.add_to_manifest(&mut manifest, &name, &resolved_spec);
+            count += 1;
+        }
+
+        let corgi: CorgiManifest =
+            serde_json::from_str(&oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?)
+                .into_diagnostic()?;
+
+        // Then, we apply the change.
+        self.apply.execute(corgi).await?;
+
+        async_std::fs::write(
+            self.apply.root.join("package.json"),
+            oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?,
+        )
+        .await
+        .into_diagnostic()?;
+
+        tracing::info!(
+            "{}Updated package.json with {count} new {}.",
+            if self.apply.emoji { "📝 " } else { "" },
+            if count == 1 {
+                self.dep_kind_str_singular()
+            } else {
+                self.dep_kind_str()
+            }
+        );
+
+        Ok(())
+    }
+}
+
+impl AddCmd {
+    /// Resolves each of the requested specs, returning the package name and
+    /// the spec that should be written to `package.json` for it.
+    async fn resolve_specs(&self) -> Result<Vec<(String, String)>> {
         let nassun = NassunArgs::from_apply_args(&self.apply).to_nassun()?;
         use PackageResolution as Pr;
         use PackageSpec as Ps;
-        let mut count = 0;
+        let mut resolved = Vec::with_capacity(self.specs.len());
         for spec in &self.specs {
             let pkg = nassun.resolve(spec).await?;
             let name = pkg.name();
@@ -60,7 +119,11 @@ impl OroCommand for AddCmd {
                     format!("{info}")
                 }
                 Ps::Dir { path } => {
-                    {
+                    if self.global.global {
+                        // Global packages are installed into their own
+                        // project, so relative paths would be meaningless.
+                        self.apply.root.join(path).to_string_lossy().to_string()
+                    } else {
                         // TODO: make relative to root?
                         path.to_string_lossy().to_string()
                     }
@@ -90,47 +153,66 @@ impl OroCommand for AddCmd {
                 "{}Resolved {spec} to {name}@{resolved_spec}.",
                 if self.apply.emoji { "🔍 " } else { "" }
             );
-            self.remove_from_manifest(&mut manifest, name);
-            self.add_to_manifest(&mut manifest, name, &resolved_spec);
-            count += 1;
-        }
-
-        if self.apply.locked {
-            // NOTE: we force locked to be false here, because it doesn't make
-            // sense to run this command in locked mode.
-            tracing::info!("Ignoring --locked option. It doesn't make sense to run this command in locked mode.");
-            self.apply.locked = false;
+            resolved.push((name.to_string(), resolved_spec));
         }
+        Ok(resolved)
+    }
 
-        let corgi: CorgiManifest =
-            serde_json::from_str(&oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?)
+    /// Installs each package into its own isolated project under the global
+    /// prefix, and links its bins into the global bin directory.
+    async fn execute_global(mut self) -> Result<()> {
+        let bin_dir = self.global.bin_dir()?;
+        let mut count = 0;
+        for (name, resolved_spec) in self.resolve_specs().await? {
+            let pkg_root = self.global.package_root(&name)?;
+            async_std::fs::create_dir_all(&pkg_root)
+                .await
                 .into_diagnostic()?;
+            let mut manifest = oro_pretty_json::from_str("{}").into_diagnostic()?;
+            manifest.value["private"] = serde_json::Value::Bool(true);
+            self.add_to_manifest(&mut manifest, &name, &resolved_spec);
+            let corgi: CorgiManifest = serde_json::from_str(
+                &oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?,
+            )
+            .into_diagnostic()?;
 
-        // Then, we apply the change.
-        self.apply.execute(corgi).await?;
+            self.apply.root = pkg_root.clone();
+            self.apply.execute(corgi).await?;
 
-        async_std::fs::write(
-            self.apply.root.join("package.json"),
-            oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?,
-        )
-        .await
-        .into_diagnostic()?;
+            async_std::fs::write(
+                pkg_root.join("package.json"),
+                oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?,
+            )
+            .await
+            .into_diagnostic()?;
+
+            if self.apply.apply && !self.apply.lockfile_only {
+                let linked =
+                    node_maintainer::link_package_bins(&self.global.package_dir(&name)?, &bin_dir)?;
+                for bin in &linked {
+                    tracing::debug!("Linked global bin `{bin}` for {name}.");
+                }
+                tracing::info!(
+                    "{}Installed {name}@{resolved_spec} globally with {} bin{}.",
+                    if self.apply.emoji { "🌐 " } else { "" },
+                    linked.len(),
+                    if linked.len() == 1 { "" } else { "s" },
+                );
+            }
+            count += 1;
+        }
 
         tracing::info!(
-            "{}Updated package.json with {count} new {}.",
+            "{}Installed {count} global package{} into {}.",
             if self.apply.emoji { "📝 " } else { "" },
-            if count == 1 {
-                self.dep_kind_str_singular()
-            } else {
-                self.dep_kind_str()
-            }
+            if count == 1 { "" } else { "s" },
+            self.global.prefix()?.display(),
         );
+        self.global.check_path()?;
 
         Ok(())
     }
-}
 
-impl AddCmd {
     fn add_to_manifest(&self, mani: &mut Formatted, name: &str, spec: &str) {
         let deps = self.dep_kind_str();
         tracing::debug!("Adding {name}@{spec} to {deps}.");
@@ -0,0 +1,207 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use colored::*;
+use futures::{StreamExt, TryStreamExt};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use nassun::{Nassun, NassunError, SignatureStatus};
+use node_maintainer::{Lockfile, LockfileNode};
+use serde::Serialize;
+
+use crate::commands::OroCommand;
+use crate::nassun_args::NassunArgs;
+use crate::OroError;
+
+/// Audits the packages in the current project's lockfile.
+#[derive(Debug, Args)]
+pub struct AuditCmd {
+    #[command(subcommand)]
+    subcommand: AuditSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum AuditSubCmd {
+    Signatures(AuditSignaturesCmd),
+}
+
+#[async_trait]
+impl OroCommand for AuditCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            AuditSubCmd::Signatures(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+/// Verifies the registry signatures of every registry package in the
+/// lockfile, against the public keys published by the registry.
+///
+/// Exits with an error if any package's signature is invalid, or if a
+/// package is missing signatures from a registry that signs its packages.
+#[derive(Debug, Args)]
+struct AuditSignaturesCmd {
+    /// Controls number of concurrent registry requests.
+    #[arg(long, default_value_t = node_maintainer::DEFAULT_CONCURRENCY)]
+    concurrency: usize,
+
+    #[arg(from_global)]
+    root: PathBuf,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[command(flatten)]
+    nassun_args: NassunArgs,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditedPackage {
+    path: String,
+    name: String,
+    version: Option<String>,
+    #[serde(flatten)]
+    status: AuditStatus,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum AuditStatus {
+    Verified { keyid: String },
+    Missing,
+    Unsupported,
+    Invalid { error: String },
+}
+
+#[async_trait]
+impl OroCommand for AuditSignaturesCmd {
+    async fn execute(self) -> Result<()> {
+        let lockfile_path = self.root.join("package-lock.kdl");
+        let lockfile = Lockfile::from_kdl(
+            async_std::fs::read_to_string(&lockfile_path)
+                .await
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    format!("Failed to read lockfile at {}", lockfile_path.display())
+                })?,
+        )?;
+        let nassun = self.nassun_args.to_nassun()?;
+        let packages = lockfile
+            .packages()
+            .iter()
+            .filter(|(_, node)| node.version.is_some())
+            .map(|(path, node)| (path.to_string(), node.clone()))
+            .collect::<Vec<_>>();
+        let mut audited = futures::stream::iter(packages)
+            .map(|(path, node)| {
+                let nassun = nassun.clone();
+                async move { audit_package(&nassun, path, &node).await }
+            })
+            .buffer_unordered(self.concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+        audited.sort_by(|a, b| a.path.cmp(&b.path));
+
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&audited)
+                    .into_diagnostic()
+                    .wrap_err("audit::json_serialize")?
+            );
+        }
+
+        let count = |f: fn(&AuditStatus) -> bool| audited.iter().filter(|p| f(&p.status)).count();
+        let verified = count(|s| matches!(s, AuditStatus::Verified { .. }));
+        let missing = count(|s| matches!(s, AuditStatus::Missing));
+        let invalid = count(|s| matches!(s, AuditStatus::Invalid { .. }));
+
+        if !self.json {
+            println!(
+                "Audited {} packages in {}.",
+                audited.len(),
+                lockfile_path.display()
+            );
+            if verified > 0 {
+                println!(
+                    "{} packages have {} registry signatures.",
+                    verified,
+                    "verified".green()
+                );
+            }
+            if missing > 0 {
+                println!();
+                println!(
+                    "{} packages have {} registry signatures:",
+                    missing,
+                    "missing".yellow()
+                );
+                for pkg in audited
+                    .iter()
+                    .filter(|p| matches!(p.status, AuditStatus::Missing))
+                {
+                    println!("  {}", fmt_pkg(pkg));
+                }
+            }
+            if invalid > 0 {
+                println!();
+                println!(
+                    "{} packages have {} registry signatures:",
+                    invalid,
+                    "invalid".red()
+                );
+                for pkg in &audited {
+                    if let AuditStatus::Invalid { error } = &pkg.status {
+                        println!("  {} {}", fmt_pkg(pkg), error.dimmed());
+                    }
+                }
+            }
+        }
+
+        if missing > 0 || invalid > 0 {
+            Err(OroError::SignatureAuditFailed { invalid, missing }.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+async fn audit_package(
+    nassun: &Nassun,
+    path: String,
+    node: &LockfileNode,
+) -> Result<AuditedPackage> {
+    let status = if let Some(package) = node.to_package(nassun).await? {
+        match package.verify_signature().await {
+            Ok(SignatureStatus::Verified { keyid }) => AuditStatus::Verified { keyid },
+            Ok(SignatureStatus::Missing) => AuditStatus::Missing,
+            Ok(SignatureStatus::Unsupported) => AuditStatus::Unsupported,
+            Err(
+                err @ (NassunError::InvalidSignature(..)
+                | NassunError::UnknownSignatureKey(..)
+                | NassunError::ExpiredSignatureKey(..)
+                | NassunError::MissingSignedIntegrity(..)),
+            ) => AuditStatus::Invalid {
+                error: err.to_string(),
+            },
+            Err(err) => return Err(err.into()),
+        }
+    } else {
+        AuditStatus::Unsupported
+    };
+    Ok(AuditedPackage {
+        path,
+        name: node.name.to_string(),
+        version: node.version.as_ref().map(|v| v.to_string()),
+        status,
+    })
+}
+
+fn fmt_pkg(pkg: &AuditedPackage) -> String {
+    format!(
+        "{}@{} {}",
+        pkg.name.bright_cyan(),
+        pkg.version.as_deref().unwrap_or_default().bright_blue(),
+        format!("(node_modules/{})", pkg.path).dimmed()
+    )
+}
@@ -0,0 +1,538 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use clap::{Args, CommandFactory, Subcommand};
+use colored::*;
+use directories::ProjectDirs;
+use kdl::{KdlDocument, KdlNode};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use oro_config::{OroConfigFile, OroConfigLayer, Value};
+
+use crate::commands::OroCommand;
+use crate::OroError;
+
+/// Gets, sets, and explains Orogene configuration.
+///
+/// `get`, `set`, `delete`, `list`, and `edit` work on your global oro.kdl by
+/// default, or on the file passed to `--config`. Pass `--project` to work on
+/// the project's oro.kdl instead. Edits keep the file's comments and
+/// formatting intact.
+///
+/// Keys are option names, like `registry`. Use a dot to refer to an entry
+/// inside an option, like `scoped-registries.@mycorp` or
+/// `auth.https://my.registry.com`.
+#[derive(Debug, Args)]
+pub struct ConfigCmd {
+    #[command(subcommand)]
+    subcommand: ConfigSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum ConfigSubCmd {
+    Get(ConfigGetCmd),
+    Set(ConfigSetCmd),
+    #[command(visible_alias = "rm")]
+    Delete(ConfigDeleteCmd),
+    #[command(visible_alias = "ls")]
+    List(ConfigListCmd),
+    Edit(ConfigEditCmd),
+    Explain(ConfigExplainCmd),
+}
+
+#[async_trait]
+impl OroCommand for ConfigCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            ConfigSubCmd::Get(cmd) => cmd.execute().await,
+            ConfigSubCmd::Set(cmd) => cmd.execute().await,
+            ConfigSubCmd::Delete(cmd) => cmd.execute().await,
+            ConfigSubCmd::List(cmd) => cmd.execute().await,
+            ConfigSubCmd::Edit(cmd) => cmd.execute().await,
+            ConfigSubCmd::Explain(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct ConfigFileArgs {
+    /// Use the project's oro.kdl instead of the global one.
+    #[arg(long)]
+    project: bool,
+
+    #[arg(from_global)]
+    root: PathBuf,
+
+    #[arg(from_global)]
+    config: Option<PathBuf>,
+}
+
+impl ConfigFileArgs {
+    fn path(&self) -> Result<PathBuf> {
+        if self.project {
+            Ok(self.root.join("oro.kdl"))
+        } else if let Some(config) = &self.config {
+            Ok(config.clone())
+        } else {
+            ProjectDirs::from("", "", "orogene")
+                .map(|dirs| dirs.config_dir().join("oro.kdl"))
+                .ok_or_else(|| OroError::MissingGlobalConfig.into())
+        }
+    }
+
+    fn open(&self) -> Result<OroConfigFile> {
+        Ok(OroConfigFile::open(self.path()?)?)
+    }
+}
+
+/// Prints the value of a config key.
+#[derive(Debug, Args)]
+struct ConfigGetCmd {
+    /// Key to look up.
+    key: String,
+
+    #[command(flatten)]
+    file: ConfigFileArgs,
+
+    #[arg(from_global)]
+    json: bool,
+}
+
+#[async_trait]
+impl OroCommand for ConfigGetCmd {
+    async fn execute(self) -> Result<()> {
+        let file = self.file.open()?;
+        let node = file
+            .get(&self.key)
+            .ok_or_else(|| OroError::ConfigKeyNotSet(self.key.clone(), file.path().into()))?;
+        if self.json {
+            let value = file.value(&self.key).expect("node exists");
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&to_json(value)?)
+                    .into_diagnostic()
+                    .wrap_err("config::get::serialize")?
+            );
+        } else {
+            println!("{}", display_node(node));
+        }
+        Ok(())
+    }
+}
+
+/// Sets a config key, replacing its current value.
+///
+/// Values are parsed the same way they'd be written in oro.kdl: `true` and
+/// `false` are booleans, integers are numbers, and anything else is a
+/// string. `name=value` sets a property, which is how `auth` entries are
+/// configured: `oro config set auth.https://my.registry.com token=deadbeef`.
+#[derive(Debug, Args)]
+struct ConfigSetCmd {
+    /// Key to set.
+    key: String,
+
+    /// Value(s) to set it to.
+    #[arg(required = true)]
+    values: Vec<String>,
+
+    #[command(flatten)]
+    file: ConfigFileArgs,
+}
+
+#[async_trait]
+impl OroCommand for ConfigSetCmd {
+    async fn execute(self) -> Result<()> {
+        let mut file = self.file.open()?;
+        file.set(
+            &self.key,
+            self.values
+                .iter()
+                .map(|value| OroConfigFile::parse_entry(value))
+                .collect(),
+        );
+        file.save()?;
+        tracing::info!("Set `{}` in {}.", self.key, file.path().display());
+        Ok(())
+    }
+}
+
+/// Removes a config key.
+#[derive(Debug, Args)]
+struct ConfigDeleteCmd {
+    /// Key to remove.
+    key: String,
+
+    #[command(flatten)]
+    file: ConfigFileArgs,
+}
+
+#[async_trait]
+impl OroCommand for ConfigDeleteCmd {
+    async fn execute(self) -> Result<()> {
+        let mut file = self.file.open()?;
+        if !file.remove(&self.key) {
+            return Err(OroError::ConfigKeyNotSet(self.key, file.path().into()).into());
+        }
+        file.save()?;
+        tracing::info!("Removed `{}` from {}.", self.key, file.path().display());
+        Ok(())
+    }
+}
+
+/// Lists all the options set in a config file.
+#[derive(Debug, Args)]
+struct ConfigListCmd {
+    #[command(flatten)]
+    file: ConfigFileArgs,
+
+    #[arg(from_global)]
+    json: bool,
+}
+
+#[async_trait]
+impl OroCommand for ConfigListCmd {
+    async fn execute(self) -> Result<()> {
+        let file = self.file.open()?;
+        if self.json {
+            let values = file
+                .values()
+                .into_iter()
+                .map(|(key, value)| Ok((key, to_json(value)?)))
+                .collect::<Result<serde_json::Map<_, _>>>()?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&values)
+                    .into_diagnostic()
+                    .wrap_err("config::list::serialize")?
+            );
+        } else {
+            let mut doc = KdlDocument::new();
+            doc.nodes_mut().extend(file.options().iter().cloned());
+            doc.fmt();
+            print!("{doc}");
+        }
+        Ok(())
+    }
+}
+
+/// Opens a config file in your editor.
+///
+/// Uses `$VISUAL` or `$EDITOR`, and falls back to `vi` (`notepad` on
+/// Windows). The file is checked for syntax errors once the editor exits.
+#[derive(Debug, Args)]
+struct ConfigEditCmd {
+    #[command(flatten)]
+    file: ConfigFileArgs,
+}
+
+#[async_trait]
+impl OroCommand for ConfigEditCmd {
+    async fn execute(self) -> Result<()> {
+        let path = self.file.path()?;
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).into_diagnostic()?;
+            }
+            std::fs::write(&path, "options {\n}\n").into_diagnostic()?;
+        }
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| {
+                if cfg!(windows) {
+                    "notepad".into()
+                } else {
+                    "vi".into()
+                }
+            });
+        let status = editor_command(&editor, &path)
+            .status()
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to launch editor `{editor}`."))?;
+        if !status.success() {
+            return Err(OroError::EditorFailed(editor, status).into());
+        }
+        OroConfigFile::open(&path)?;
+        Ok(())
+    }
+}
+
+/// Explains where the effective value of a config key comes from.
+///
+/// Configuration is layered, from lowest to highest precedence: built-in
+/// defaults, your user `.npmrc`, your global oro.kdl, `ORO_CONFIG_*`
+/// environment variables, the project's `.npmrc`, the project's oro.kdl,
+/// and finally command line flags. Values set in a subcommand's block, like
+/// `apply { hoisted true }`, are listed separately, since they only apply
+/// to that subcommand.
+#[derive(Debug, Args)]
+struct ConfigExplainCmd {
+    /// Key to explain.
+    key: String,
+
+    #[arg(from_global)]
+    root: PathBuf,
+
+    #[arg(from_global)]
+    config: Option<PathBuf>,
+
+    #[arg(from_global)]
+    npmrc: bool,
+
+    #[arg(from_global)]
+    json: bool,
+}
+
+#[async_trait]
+impl OroCommand for ConfigExplainCmd {
+    async fn execute(self) -> Result<()> {
+        let mut sections = crate::config_options(&self.root, self.config.as_deref(), self.npmrc)?
+            .explain_sections(&self.key, &crate::Orogene::command())?
+            .into_iter()
+            .map(|(section, layers)| {
+                let layers = layers
+                    .into_iter()
+                    .map(|(layer, value)| Ok((layer, to_json(value)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((section, layers))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (_, layers) = sections
+            .first_mut()
+            .expect("top-level options always come first");
+        if let Some(value) = cli_value(&self.key, takes_value(&self.key), std::env::args_os()) {
+            layers.push((OroConfigLayer::CommandLine, value));
+        }
+        let ((_, layers), sections) = sections
+            .split_first()
+            .expect("top-level options always come first");
+
+        if self.json {
+            let (value, layers) = json_layers(layers);
+            let commands = sections
+                .iter()
+                .map(|(section, layers)| {
+                    let (value, layers) = json_layers(layers);
+                    serde_json::json!({
+                        "command": section.join(" "),
+                        "value": value,
+                        "layers": layers,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let output = serde_json::json!({
+                "key": self.key,
+                "value": value,
+                "layers": layers,
+                "commands": commands,
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&output)
+                    .into_diagnostic()
+                    .wrap_err("config::explain::serialize")?
+            );
+            return Ok(());
+        }
+
+        if layers.is_empty() {
+            println!(
+                "`{}` is not set. Orogene will use its built-in default, if it has one.",
+                self.key
+            );
+        } else {
+            print_layers(&self.key, layers);
+        }
+        for (section, layers) in sections {
+            println!();
+            println!("When running `oro {}`:", section.join(" "));
+            print_layers(&self.key, layers);
+        }
+        Ok(())
+    }
+}
+
+/// Prints the effective value out of `layers`, along with the ones it
+/// overrides.
+fn print_layers(key: &str, layers: &[(OroConfigLayer, serde_json::Value)]) {
+    let Some(((layer, value), overridden)) = layers.split_last() else {
+        return;
+    };
+    println!("{} = {}", key.bold(), display_json(value));
+    println!("  from {layer}");
+    if !overridden.is_empty() {
+        println!("{}", "Overrides:".dimmed());
+        for (layer, value) in overridden.iter().rev() {
+            println!(
+                "{}",
+                format!("  {} from {layer}", display_json(value)).dimmed()
+            );
+        }
+    }
+}
+
+/// The `--json` form of [`print_layers`]: the effective value, and every
+/// layer that sets one, from highest to lowest precedence.
+fn json_layers(
+    layers: &[(OroConfigLayer, serde_json::Value)],
+) -> (serde_json::Value, Vec<serde_json::Value>) {
+    let layers = layers
+        .iter()
+        .rev()
+        .map(|(layer, value)| {
+            serde_json::json!({
+                "source": layer.id(),
+                "path": layer.path(),
+                "value": value,
+            })
+        })
+        .collect::<Vec<_>>();
+    let value = layers
+        .first()
+        .map(|layer| layer["value"].clone())
+        .unwrap_or_default();
+    (value, layers)
+}
+
+/// Looks for a flag that sets `key` among the command line arguments. Only
+/// the last occurrence counts, like it does when parsing them.
+fn cli_value(
+    key: &str,
+    takes_value: bool,
+    args: impl IntoIterator<Item = OsString>,
+) -> Option<serde_json::Value> {
+    let flag = format!("--{key}");
+    let negated = format!("--no-{key}");
+    let mut found = None;
+    let mut args = args
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned());
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == negated {
+            found = Some(false.into());
+        } else if let Some(value) = arg
+            .strip_prefix(&flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            found = Some(value.into());
+        } else if arg == flag {
+            found = if takes_value {
+                args.next().map(Into::into)
+            } else {
+                Some(true.into())
+            };
+        }
+    }
+    found
+}
+
+/// Whether the global option for `key` takes a value, as opposed to being a
+/// switch.
+fn takes_value(key: &str) -> bool {
+    let id = key.replace('-', "_");
+    crate::Orogene::command()
+        .get_arguments()
+        .find(|arg| arg.get_id() == id.as_str())
+        .map(|arg| arg.get_action().takes_values())
+        .unwrap_or(true)
+}
+
+fn to_json(value: Value) -> Result<serde_json::Value> {
+    value
+        .try_deserialize()
+        .into_diagnostic()
+        .wrap_err("config::value::deserialize")
+}
+
+fn display_json(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Prints simple values on their own, so they're easy to use in scripts,
+/// and anything else as KDL.
+fn display_node(node: &KdlNode) -> String {
+    match (node.entries(), node.children()) {
+        ([entry], None) if entry.name().is_none() => match entry.value().as_string() {
+            Some(s) => s.into(),
+            None => entry.value().to_string(),
+        },
+        _ => {
+            let mut node = node.clone();
+            node.clear_fmt_recursive();
+            node.fmt();
+            node.to_string().trim().into()
+        }
+    }
+}
+
+fn editor_command(editor: &str, path: &Path) -> std::process::Command {
+    // Editors are often configured with arguments, like `code --wait`, so
+    // they need to go through the shell.
+    if cfg!(windows) {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.arg("/C").arg(editor).arg(path);
+        cmd
+    } else {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("{editor} \"$1\""))
+            .arg(editor)
+            .arg(path);
+        cmd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn finds_cli_values() {
+        assert_eq!(
+            cli_value(
+                "registry",
+                true,
+                args(&["oro", "--registry", "https://a.com", "config", "explain"])
+            ),
+            Some("https://a.com".into())
+        );
+        assert_eq!(
+            cli_value("registry", true, args(&["oro", "--registry=https://b.com"])),
+            Some("https://b.com".into())
+        );
+        assert_eq!(
+            cli_value("emoji", false, args(&["oro", "--no-emoji", "config"])),
+            Some(false.into())
+        );
+        assert_eq!(
+            cli_value(
+                "emoji",
+                false,
+                args(&["oro", "--emoji", "--no-emoji", "--emoji", "config"])
+            ),
+            Some(true.into())
+        );
+        assert_eq!(
+            cli_value("registry", true, args(&["oro", "--", "--registry", "x"])),
+            None
+        );
+        assert_eq!(
+            cli_value("registry", true, args(&["oro", "--registry-extra", "x"])),
+            None
+        );
+    }
+
+    #[test]
+    fn flag_arity() {
+        assert!(takes_value("registry"));
+        assert!(!takes_value("emoji"));
+    }
+}
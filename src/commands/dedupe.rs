@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use clap::Args;
+use miette::{IntoDiagnostic, Result};
+use oro_common::CorgiManifest;
+
+use crate::apply_args::ApplyArgs;
+use crate::commands::OroCommand;
+
+/// Removes duplicate packages from the dependency tree and applies the
+/// result to `node_modules/`.
+///
+/// Packages get placed as they're found during resolution, which can leave
+/// more than one copy of a package in the tree even when a single version
+/// would satisfy everything that depends on it. This collapses those copies
+/// into one, using versions that are already in the tree, and reports how
+/// many packages were removed. It's the same as `oro apply --prefer-dedupe`.
+#[derive(Debug, Args)]
+pub struct DedupeCmd {
+    #[command(flatten)]
+    apply: ApplyArgs,
+}
+
+#[async_trait]
+impl OroCommand for DedupeCmd {
+    async fn execute(mut self) -> Result<()> {
+        let corgi: CorgiManifest = serde_json::from_str(
+            &async_std::fs::read_to_string(self.apply.root.join("package.json"))
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+        self.apply.prefer_dedupe = true;
+        // Same as `oro apply`: `apply false` in a config shouldn't stop an
+        // explicit dedupe from happening.
+        self.apply.apply = true;
+        self.apply.execute(corgi).await
+    }
+}
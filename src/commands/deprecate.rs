@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_semver::Range;
+use oro_package_spec::VersionSpec;
+
+use crate::commands::OroCommand;
+use crate::registry_args::{parse_registry_spec, RegistryArgs};
+use crate::OroError;
+
+/// Marks published versions of a package as deprecated.
+///
+/// Deprecated versions can still be installed, but Orogene and other
+/// package managers will warn anyone who installs them, showing your
+/// message.
+#[derive(Debug, Args)]
+pub struct DeprecateCmd {
+    /// Package and versions to deprecate, in `<package>[@<range>]` format.
+    /// Every version is deprecated if no range is given.
+    spec: String,
+
+    /// Deprecation message to show users. Pass an empty string (`""`) to
+    /// un-deprecate the versions instead.
+    message: String,
+
+    /// One-time password from your authenticator, if your account uses
+    /// two-factor authentication.
+    #[arg(long)]
+    otp: Option<String>,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for DeprecateCmd {
+    async fn execute(self) -> Result<()> {
+        let (name, requested) = parse_registry_spec(&self.spec)?;
+        let range = match requested {
+            None => Range::any(),
+            Some(VersionSpec::Range(range)) => range,
+            Some(VersionSpec::Version(version)) => version.to_string().parse()?,
+            Some(VersionSpec::Tag(_)) => {
+                return Err(OroError::InvalidDeprecateRange(self.spec).into());
+            }
+        };
+        let mut versions = self
+            .registry_args
+            .client(Some(&name))?
+            .deprecate(&name, &range, &self.message, self.otp.as_deref())
+            .await?;
+        versions.sort_by_cached_key(|version| version.parse::<node_semver::Version>().ok());
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&versions)
+                    .into_diagnostic()
+                    .wrap_err("deprecate::serialize")?
+            );
+        } else {
+            let action = if self.message.is_empty() {
+                "Un-deprecated"
+            } else {
+                "Deprecated"
+            };
+            tracing::info!("{action} {} version(s) of {name}:", versions.len());
+            for version in versions {
+                tracing::info!("    {name}@{version}");
+            }
+        }
+        Ok(())
+    }
+}
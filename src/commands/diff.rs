@@ -0,0 +1,185 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use clap::Args;
+use colored::*;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_maintainer::{Lockfile, LockfileDiff, LockfileDiffPackage};
+
+use crate::commands::OroCommand;
+use crate::OroError;
+
+/// Compares two lockfiles, showing which packages were added, removed,
+/// upgraded, downgraded, changed, or moved between them.
+///
+/// Each lockfile can be given either as a path to a `package-lock.kdl` or
+/// `package-lock.json` file, or as a git ref, in which case the project's
+/// `package-lock.kdl` is read from that ref. With no arguments, the lockfile
+/// at `HEAD` is compared against the one in the working tree.
+#[derive(Debug, Args)]
+pub struct DiffCmd {
+    /// Old lockfile (path or git ref). Defaults to `HEAD`.
+    #[arg()]
+    old: Option<String>,
+
+    /// New lockfile (path or git ref). Defaults to the project's current
+    /// lockfile.
+    #[arg()]
+    new: Option<String>,
+
+    #[arg(from_global)]
+    root: PathBuf,
+
+    #[arg(from_global)]
+    json: bool,
+}
+
+#[async_trait]
+impl OroCommand for DiffCmd {
+    async fn execute(self) -> Result<()> {
+        let old = self.load_lockfile(self.old.as_deref().unwrap_or("HEAD"))?;
+        let new = if let Some(new) = self.new.as_deref() {
+            self.load_lockfile(new)?
+        } else {
+            self.read_lockfile_file(&self.root.join("package-lock.kdl"))?
+        };
+        let diff = old.diff(&new);
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&diff)
+                    .into_diagnostic()
+                    .wrap_err("diff::json_serialize")?
+            );
+        } else {
+            print_diff(&diff);
+        }
+        Ok(())
+    }
+}
+
+impl DiffCmd {
+    fn load_lockfile(&self, spec: &str) -> Result<Lockfile> {
+        // Relative paths are relative to the project, same as git refs.
+        let path = self.root.join(spec);
+        if path.is_file() {
+            self.read_lockfile_file(&path)
+        } else {
+            self.read_lockfile_git(spec)
+        }
+    }
+
+    fn read_lockfile_file(&self, path: &Path) -> Result<Lockfile> {
+        let contents = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to read lockfile at {}", path.display()))?;
+        if path.extension().map(|ext| ext == "json").unwrap_or(false) {
+            Ok(Lockfile::from_npm(contents)?)
+        } else {
+            Ok(Lockfile::from_kdl(contents)?)
+        }
+    }
+
+    fn read_lockfile_git(&self, git_ref: &str) -> Result<Lockfile> {
+        let output = std::process::Command::new("git")
+            .arg("show")
+            .arg(format!("{git_ref}:./package-lock.kdl"))
+            .current_dir(&self.root)
+            .output()
+            .map_err(|e| OroError::GitLockfileRead(git_ref.into(), e.to_string()))?;
+        if !output.status.success() {
+            return Err(OroError::GitLockfileRead(
+                git_ref.into(),
+                String::from_utf8_lossy(&output.stderr).trim().into(),
+            )
+            .into());
+        }
+        Ok(Lockfile::from_kdl(
+            String::from_utf8(output.stdout).into_diagnostic()?,
+        )?)
+    }
+}
+
+fn fmt_pkg(pkg: &LockfileDiffPackage) -> String {
+    format!(
+        "{}@{}",
+        pkg.name.bright_cyan(),
+        pkg.version
+            .as_ref()
+            .map(|v| v.to_string())
+            .or_else(|| pkg.resolved.clone())
+            .unwrap_or_default()
+            .bright_blue()
+    )
+}
+
+fn fmt_path(path: &str) -> ColoredString {
+    format!("(node_modules/{path})").dimmed()
+}
+
+/// Prints a detailed, human-readable version of a [`LockfileDiff`].
+pub fn print_diff(diff: &LockfileDiff) {
+    if diff.is_empty() {
+        println!("No changes.");
+        return;
+    }
+    for pkg in &diff.added {
+        println!("{} {} {}", "+".green(), fmt_pkg(pkg), fmt_path(&pkg.path));
+    }
+    for pkg in &diff.removed {
+        println!("{} {} {}", "-".red(), fmt_pkg(pkg), fmt_path(&pkg.path));
+    }
+    for (sigil, changes) in [
+        ("↑".green(), &diff.upgraded),
+        ("↓".yellow(), &diff.downgraded),
+        ("~".yellow(), &diff.changed),
+    ] {
+        for change in changes {
+            println!(
+                "{} {} -> {} {}",
+                sigil,
+                fmt_pkg(&change.from),
+                change
+                    .to
+                    .version
+                    .as_ref()
+                    .map(|v| v.to_string())
+                    .or_else(|| change.to.resolved.clone())
+                    .unwrap_or_default()
+                    .bright_blue(),
+                fmt_path(&change.to.path)
+            );
+        }
+    }
+    for change in &diff.moved {
+        println!(
+            "{} {} {} -> {}",
+            ">".cyan(),
+            fmt_pkg(&change.to),
+            fmt_path(&change.from.path),
+            fmt_path(&change.to.path)
+        );
+    }
+    println!();
+    println!("{}", summarize_diff(diff));
+}
+
+/// One-line summary of a [`LockfileDiff`], like `2 added, 1 upgraded`.
+pub fn summarize_diff(diff: &LockfileDiff) -> String {
+    if diff.is_empty() {
+        return "no changes".into();
+    }
+    [
+        (diff.added.len(), "added"),
+        (diff.removed.len(), "removed"),
+        (diff.upgraded.len(), "upgraded"),
+        (diff.downgraded.len(), "downgraded"),
+        (diff.changed.len(), "changed"),
+        (diff.moved.len(), "moved"),
+    ]
+    .into_iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, kind)| format!("{count} {kind}"))
+    .collect::<Vec<_>>()
+    .join(", ")
+}
@@ -0,0 +1,146 @@
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use oro_package_spec::VersionSpec;
+
+use crate::commands::OroCommand;
+use crate::registry_args::{parse_registry_spec, RegistryArgs};
+use crate::OroError;
+
+/// Manages the dist-tags of a package on the registry.
+///
+/// Dist-tags are names, like `latest` or `next`, that point at specific
+/// versions of a package, so they can be installed with `oro add
+/// <package>@<tag>`.
+#[derive(Debug, Args)]
+pub struct DistTagCmd {
+    #[command(subcommand)]
+    subcommand: DistTagSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum DistTagSubCmd {
+    #[command(visible_alias = "list")]
+    Ls(DistTagLsCmd),
+    Add(DistTagAddCmd),
+    #[command(visible_alias = "remove")]
+    Rm(DistTagRmCmd),
+}
+
+#[async_trait]
+impl OroCommand for DistTagCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            DistTagSubCmd::Ls(cmd) => cmd.execute().await,
+            DistTagSubCmd::Add(cmd) => cmd.execute().await,
+            DistTagSubCmd::Rm(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+/// Lists a package's dist-tags and the versions they point at.
+#[derive(Debug, Args)]
+struct DistTagLsCmd {
+    /// Name of the package.
+    package: String,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for DistTagLsCmd {
+    async fn execute(self) -> Result<()> {
+        let (name, _) = parse_registry_spec(&self.package)?;
+        let mut tags = self
+            .registry_args
+            .client(Some(&name))?
+            .dist_tags(&name)
+            .await?;
+        tags.sort_keys();
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&tags)
+                    .into_diagnostic()
+                    .wrap_err("dist_tag::ls::serialize")?
+            );
+        } else {
+            for (tag, version) in tags {
+                println!("{tag}: {version}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Points a dist-tag at a version of a package, creating the tag if
+/// needed.
+#[derive(Debug, Args)]
+struct DistTagAddCmd {
+    /// Package and version to tag, in `<package>@<version>` format.
+    spec: String,
+
+    /// Name of the dist-tag.
+    #[arg(default_value = "latest")]
+    tag: String,
+
+    /// One-time password from your authenticator, if your account uses
+    /// two-factor authentication.
+    #[arg(long)]
+    otp: Option<String>,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for DistTagAddCmd {
+    async fn execute(self) -> Result<()> {
+        let (name, requested) = parse_registry_spec(&self.spec)?;
+        let Some(VersionSpec::Version(version)) = requested else {
+            return Err(OroError::DistTagVersionRequired(self.spec).into());
+        };
+        let version = version.to_string();
+        self.registry_args
+            .client(Some(&name))?
+            .add_dist_tag(&name, &self.tag, &version, self.otp.as_deref())
+            .await?;
+        tracing::info!("{name}: {} -> {version}", self.tag);
+        Ok(())
+    }
+}
+
+/// Removes a dist-tag from a package.
+#[derive(Debug, Args)]
+struct DistTagRmCmd {
+    /// Name of the package.
+    package: String,
+
+    /// Name of the dist-tag to remove.
+    tag: String,
+
+    /// One-time password from your authenticator, if your account uses
+    /// two-factor authentication.
+    #[arg(long)]
+    otp: Option<String>,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for DistTagRmCmd {
+    async fn execute(self) -> Result<()> {
+        let (name, _) = parse_registry_spec(&self.package)?;
+        self.registry_args
+            .client(Some(&name))?
+            .remove_dist_tag(&name, &self.tag, self.otp.as_deref())
+            .await?;
+        tracing::info!("Removed dist-tag {} from {name}.", self.tag);
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Args;
+use colored::*;
+use indexmap::IndexMap;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_maintainer::{Lockfile, SbomOptions};
+use serde::Serialize;
+use url::Url;
+
+use crate::commands::OroCommand;
+use crate::license_policy::{fail_on_violations, LicensePolicy, LicensePolicyArgs};
+
+const UNKNOWN_LICENSE: &str = "UNKNOWN";
+
+/// Lists the licenses of every package in the current project's lockfile,
+/// grouped by license.
+///
+/// Licenses are read from each package's `package.json`, either from
+/// `node_modules/` or from the cache.
+///
+/// If a license policy is configured (`--license-policy`, or a
+/// `license-policy` block in `oro.kdl`), packages that violate it are
+/// reported, and the command exits with an error.
+#[derive(Debug, Args)]
+pub struct LicensesCmd {
+    #[command(flatten)]
+    license_policy: LicensePolicyArgs,
+
+    #[arg(from_global)]
+    root: PathBuf,
+
+    #[arg(from_global)]
+    cache: Option<PathBuf>,
+
+    #[arg(from_global)]
+    registry: Url,
+
+    #[arg(from_global)]
+    scoped_registries: Vec<(String, Url)>,
+
+    #[arg(from_global)]
+    json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct LicensedPackage {
+    name: String,
+    version: Option<String>,
+    path: String,
+    license: Option<String>,
+    violation: Option<String>,
+}
+
+#[async_trait]
+impl OroCommand for LicensesCmd {
+    async fn execute(self) -> Result<()> {
+        let policy = LicensePolicy::from_args(&self.license_policy)?;
+        let lockfile_path = self.root.join("package-lock.kdl");
+        let lockfile = Lockfile::from_kdl(
+            async_std::fs::read_to_string(&lockfile_path)
+                .await
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    format!("Failed to read lockfile at {}", lockfile_path.display())
+                })?,
+        )?;
+
+        let mut opts = SbomOptions::new()
+            .root(&self.root)
+            .registry(self.registry.clone());
+        for (scope, registry) in &self.scoped_registries {
+            opts = opts.scope_registry(scope, registry.clone());
+        }
+        if let Some(cache) = &self.cache {
+            opts = opts.cache(cache);
+        }
+        let sbom = lockfile.to_sbom(&opts);
+
+        let violations = policy.check(&sbom);
+        let packages = sbom
+            .components
+            .iter()
+            .map(|component| LicensedPackage {
+                name: component.name.clone(),
+                version: component.version.clone(),
+                path: component.bom_ref.clone(),
+                license: component.license.clone(),
+                violation: violations
+                    .iter()
+                    .find(|violation| violation.path == component.bom_ref)
+                    .map(|violation| violation.reason.clone()),
+            })
+            .collect::<Vec<_>>();
+
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&packages)
+                    .into_diagnostic()
+                    .wrap_err("licenses::json_serialize")?
+            );
+        } else {
+            print_grouped(&packages);
+        }
+
+        fail_on_violations(violations)
+    }
+}
+
+fn print_grouped(packages: &[LicensedPackage]) {
+    let mut groups: IndexMap<&str, Vec<&LicensedPackage>> = IndexMap::new();
+    for pkg in packages {
+        groups
+            .entry(pkg.license.as_deref().unwrap_or(UNKNOWN_LICENSE))
+            .or_default()
+            .push(pkg);
+    }
+    // Most common licenses first.
+    groups.sort_by(|a_lic, a, b_lic, b| b.len().cmp(&a.len()).then(a_lic.cmp(b_lic)));
+    for (license, pkgs) in groups {
+        println!("{} ({})", license.bold(), pkgs.len());
+        for pkg in pkgs {
+            let id = match &pkg.version {
+                Some(version) => format!("{}@{version}", pkg.name),
+                None => pkg.name.clone(),
+            };
+            match &pkg.violation {
+                Some(reason) => println!("    {} {}", id.red(), format!("({reason})").dimmed()),
+                None => println!("    {id}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Args;
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::LinkedDependencies;
+use oro_common::{CorgiManifest, Manifest};
+
+use crate::apply_args::ApplyArgs;
+use crate::commands::OroCommand;
+use crate::OroError;
+
+/// Links a local package into the project in place of one of its
+/// dependencies, so changes to it show up without reinstalling.
+///
+/// The package's directory gets symlinked into `node_modules`, and its own
+/// dependencies are resolved and installed along with the rest of the
+/// project's. Packages the project doesn't depend on yet get linked in as
+/// regular dependencies. Links stay in place across `oro apply` runs until
+/// they're removed with `oro unlink`.
+///
+/// Links are recorded in `node_modules/.oro-links.kdl`. `package.json` and
+/// `package-lock.kdl` keep the dependency as it was before it was linked.
+#[derive(Debug, Args)]
+pub struct LinkCmd {
+    /// Directory of the package to link.
+    path: PathBuf,
+
+    #[command(flatten)]
+    apply: ApplyArgs,
+}
+
+#[async_trait]
+impl OroCommand for LinkCmd {
+    async fn execute(self) -> Result<()> {
+        let path = std::fs::canonicalize(&self.path)
+            .map_err(|_| OroError::NotALinkablePackage(self.path.clone()))?;
+        let name = async_std::fs::read_to_string(path.join("package.json"))
+            .await
+            .ok()
+            .and_then(|json| serde_json::from_str::<Manifest>(&json).ok())
+            .and_then(|manifest| manifest.name)
+            .ok_or_else(|| OroError::NotALinkablePackage(self.path.clone()))?;
+
+        let previous = LinkedDependencies::load(&self.apply.root).await?;
+        let mut links = previous.clone();
+        links.insert(&name, &path);
+        links.save(&self.apply.root).await?;
+
+        let corgi: CorgiManifest = serde_json::from_str(
+            &async_std::fs::read_to_string(self.apply.root.join("package.json"))
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+
+        if let Err(e) = self.apply.execute(corgi).await {
+            // A link that couldn't be installed would break every `oro
+            // apply` after this one, so it doesn't get kept.
+            previous.save(&self.apply.root).await?;
+            return Err(e);
+        }
+
+        tracing::info!(
+            "{}Linked {name} to {}.",
+            if self.apply.emoji { "🔗 " } else { "" },
+            path.display()
+        );
+        Ok(())
+    }
+}
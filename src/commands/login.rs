@@ -72,10 +72,7 @@ impl OroCommand for LoginCmd {
             ProjectDirs::from("", "", "orogene")
                 .map(|config| config.config_dir().to_path_buf().join("oro.kdl"))
         }) {
-            std::fs::create_dir_all(config_path.parent().expect("must have parent")).unwrap();
-            let mut config: KdlDocument = std::fs::read_to_string(config_path)
-                .into_diagnostic()?
-                .parse()?;
+            let mut store = self.client_args.credentials.open(config_path)?;
 
             tracing::info!("Logging in to {}", self.registry);
 
@@ -105,13 +102,17 @@ impl OroCommand for LoginCmd {
                 Credentials::Token(token.token)
             };
 
-            config::set_credentials_by_uri(&self.registry, &credentials, &mut config);
+            store.set(&self.registry, &credentials)?;
 
             if let Some(scope) = self.scope {
+                std::fs::create_dir_all(config_path.parent().expect("must have parent"))
+                    .into_diagnostic()?;
+                let mut config: KdlDocument = std::fs::read_to_string(config_path)
+                    .unwrap_or_default()
+                    .parse()?;
                 config::set_scoped_registry(&scope, &self.registry, &mut config);
+                std::fs::write(config_path, config.to_string()).into_diagnostic()?;
             }
-
-            std::fs::write(config_path, config.to_string()).into_diagnostic()?;
         }
         Ok(())
     }
@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_maintainer::script_log_package_name;
+
+use crate::commands::OroCommand;
+use crate::{OroError, LOGS_DIR_NAME};
+
+/// Shows logs written by previous commands.
+#[derive(Debug, Args)]
+pub struct LogsCmd {
+    #[command(subcommand)]
+    subcommand: LogsSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum LogsSubCmd {
+    Scripts(LogsScriptsCmd),
+}
+
+#[async_trait]
+impl OroCommand for LogsCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            LogsSubCmd::Scripts(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+/// Shows the output of packages' lifecycle scripts.
+///
+/// Whenever a package's `preinstall`, `install`, or `postinstall` scripts
+/// run, everything they print is written to a log file for that package,
+/// replacing the one from the last time they ran. Without a package, lists
+/// the packages that have logs.
+#[derive(Debug, Args)]
+struct LogsScriptsCmd {
+    /// Package to show script logs for. Add a version (`foo@1.2.3`) to only
+    /// show the logs for that version.
+    package: Option<String>,
+
+    #[arg(from_global)]
+    cache: Option<PathBuf>,
+
+    #[arg(from_global)]
+    json: bool,
+}
+
+#[async_trait]
+impl OroCommand for LogsScriptsCmd {
+    async fn execute(self) -> Result<()> {
+        let logs = match self.cache.as_deref() {
+            Some(cache) => script_logs(&script_logs_dir(cache))?,
+            None => Vec::new(),
+        };
+        let Some(package) = self.package else {
+            if self.json {
+                let output = serde_json::to_string_pretty(
+                    &logs
+                        .iter()
+                        .map(|log| serde_json::json!({ "package": log.package, "path": log.path }))
+                        .collect::<Vec<_>>(),
+                )
+                .into_diagnostic()
+                .wrap_err("logs::scripts::serialize")?;
+                println!("{output}");
+            } else if logs.is_empty() {
+                tracing::info!("No script logs found.");
+            } else {
+                for log in &logs {
+                    println!("{} {}", log.package, log.path.display());
+                }
+            }
+            return Ok(());
+        };
+
+        let logs = logs
+            .into_iter()
+            .filter(|log| log.name == package || log.package == package)
+            .collect::<Vec<_>>();
+        if logs.is_empty() {
+            return Err(OroError::NoScriptLogs(package).into());
+        }
+        let mut output = Vec::new();
+        for log in logs {
+            let contents = std::fs::read_to_string(&log.path)
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to read script log at {}", log.path.display()))?;
+            output.push((log, contents));
+        }
+        if self.json {
+            let output = serde_json::to_string_pretty(
+                &output
+                    .iter()
+                    .map(|(log, contents)| {
+                        serde_json::json!({
+                            "package": log.package,
+                            "path": log.path,
+                            "contents": contents,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .into_diagnostic()
+            .wrap_err("logs::scripts::serialize")?;
+            println!("{output}");
+        } else {
+            let headers = output.len() > 1;
+            for (log, contents) in output {
+                if headers {
+                    println!("==> {} <==", log.package);
+                }
+                print!("{contents}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Directory inside `cache` that lifecycle script logs are written to.
+pub(crate) fn script_logs_dir(cache: &Path) -> PathBuf {
+    cache.join(LOGS_DIR_NAME).join("scripts")
+}
+
+struct ScriptLog {
+    /// Name of the package the log is for.
+    name: String,
+    /// The package's name, plus its version if it has one.
+    package: String,
+    path: PathBuf,
+}
+
+fn script_logs(dir: &Path) -> Result<Vec<ScriptLog>> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut logs = Vec::new();
+    for entry in entries {
+        let entry = entry
+            .into_diagnostic()
+            .wrap_err("Failed to read script logs directory")?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if let Some(name) = script_log_package_name(&file_name) {
+            let package = file_name
+                .strip_suffix(".log")
+                .unwrap_or(&file_name)
+                .replacen('+', "/", 1);
+            logs.push(ScriptLog {
+                name,
+                package,
+                path: entry.path(),
+            });
+        }
+    }
+    logs.sort_by(|a, b| a.package.cmp(&b.package));
+    Ok(logs)
+}
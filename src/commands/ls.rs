@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Args;
+use colored::*;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use oro_common::CorgiManifest;
+
+use crate::commands::OroCommand;
+use crate::global_install_args::GlobalInstallArgs;
+
+/// Lists the direct dependencies of the current project, or the
+/// globally-installed packages when used with `--global`.
+#[derive(Debug, Args)]
+#[clap(visible_aliases(["list"]))]
+pub struct LsCmd {
+    #[command(flatten)]
+    global: GlobalInstallArgs,
+
+    #[arg(from_global)]
+    root: PathBuf,
+
+    #[arg(from_global)]
+    json: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct ListedDependency {
+    name: String,
+    requested: String,
+    version: Option<String>,
+}
+
+#[async_trait]
+impl OroCommand for LsCmd {
+    async fn execute(self) -> Result<()> {
+        if self.global.global {
+            self.list_global()
+        } else {
+            self.list_project().await
+        }
+    }
+}
+
+impl LsCmd {
+    fn list_global(&self) -> Result<()> {
+        let packages = self.global.installed()?;
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&packages)
+                    .into_diagnostic()
+                    .wrap_err("ls::json_serialize")?
+            );
+        } else {
+            println!("{}", self.global.prefix()?.display());
+            for pkg in packages {
+                let version = pkg.version.as_deref().unwrap_or("<missing>");
+                if pkg.bins.is_empty() {
+                    println!("{}@{}", pkg.name.bright_cyan(), version.bright_blue());
+                } else {
+                    println!(
+                        "{}@{} {}",
+                        pkg.name.bright_cyan(),
+                        version.bright_blue(),
+                        format!("({})", pkg.bins.join(", ")).dimmed()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn list_project(&self) -> Result<()> {
+        let manifest: CorgiManifest = serde_json::from_str(
+            &async_std::fs::read_to_string(self.root.join("package.json"))
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+        let mut deps = Vec::new();
+        for (name, requested) in manifest
+            .dependencies
+            .iter()
+            .chain(manifest.optional_dependencies.iter())
+            .chain(manifest.dev_dependencies.iter())
+        {
+            let version = async_std::fs::read_to_string(
+                self.root
+                    .join("node_modules")
+                    .join(name)
+                    .join("package.json"),
+            )
+            .await
+            .ok()
+            .and_then(|s| serde_json::from_str::<CorgiManifest>(&s).ok())
+            .and_then(|m| m.version)
+            .map(|v| v.to_string());
+            deps.push(ListedDependency {
+                name: name.clone(),
+                requested: requested.clone(),
+                version,
+            });
+        }
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&deps)
+                    .into_diagnostic()
+                    .wrap_err("ls::json_serialize")?
+            );
+        } else {
+            for dep in deps {
+                let version = dep
+                    .version
+                    .as_deref()
+                    .map(|v| v.bright_blue())
+                    .unwrap_or_else(|| "<missing>".red());
+                println!(
+                    "{}@{} {}",
+                    dep.name.bright_cyan(),
+                    version,
+                    format!("({})", dep.requested).dimmed()
+                );
+            }
+        }
+        Ok(())
+    }
+}
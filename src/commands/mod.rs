@@ -3,12 +3,32 @@ use miette::Result;
 
 pub mod add;
 pub mod apply;
+pub mod audit;
+pub mod config;
+pub mod dedupe;
+pub mod deprecate;
+pub mod diff;
+pub mod dist_tag;
+pub mod licenses;
+pub mod link;
 pub mod login;
 pub mod logout;
+pub mod logs;
+pub mod ls;
+pub mod owner;
+pub mod patch;
+pub mod patch_commit;
 pub mod ping;
 pub mod reapply;
+pub mod rebuild;
 pub mod remove;
+pub mod sbom;
+pub mod search;
+pub mod store;
+pub mod token;
+pub mod unlink;
 pub mod view;
+pub mod whoami;
 
 #[async_trait]
 pub trait OroCommand {
@@ -0,0 +1,147 @@
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use miette::{IntoDiagnostic, Result, WrapErr};
+
+use crate::commands::OroCommand;
+use crate::registry_args::{parse_registry_spec, RegistryArgs};
+
+/// Manages the owners of a package on the registry. Owners can publish new
+/// versions of a package and change its settings.
+#[derive(Debug, Args)]
+pub struct OwnerCmd {
+    #[command(subcommand)]
+    subcommand: OwnerSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum OwnerSubCmd {
+    #[command(visible_alias = "list")]
+    Ls(OwnerLsCmd),
+    Add(OwnerAddCmd),
+    #[command(visible_alias = "remove")]
+    Rm(OwnerRmCmd),
+}
+
+#[async_trait]
+impl OroCommand for OwnerCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            OwnerSubCmd::Ls(cmd) => cmd.execute().await,
+            OwnerSubCmd::Add(cmd) => cmd.execute().await,
+            OwnerSubCmd::Rm(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+/// Lists the owners of a package.
+#[derive(Debug, Args)]
+struct OwnerLsCmd {
+    /// Name of the package.
+    package: String,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for OwnerLsCmd {
+    async fn execute(self) -> Result<()> {
+        let (name, _) = parse_registry_spec(&self.package)?;
+        let owners = self
+            .registry_args
+            .client(Some(&name))?
+            .owners(&name)
+            .await?;
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&owners)
+                    .into_diagnostic()
+                    .wrap_err("owner::ls::serialize")?
+            );
+        } else {
+            for owner in owners {
+                match owner.email {
+                    Some(email) => println!("{} <{email}>", owner.name),
+                    None => println!("{}", owner.name),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds a registry user as an owner of a package.
+#[derive(Debug, Args)]
+struct OwnerAddCmd {
+    /// Username of the new owner.
+    user: String,
+
+    /// Name of the package.
+    package: String,
+
+    /// One-time password from your authenticator, if your account uses
+    /// two-factor authentication.
+    #[arg(long)]
+    otp: Option<String>,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for OwnerAddCmd {
+    async fn execute(self) -> Result<()> {
+        let (name, _) = parse_registry_spec(&self.package)?;
+        let added = self
+            .registry_args
+            .client(Some(&name))?
+            .add_owner(&name, &self.user, self.otp.as_deref())
+            .await?;
+        if added {
+            tracing::info!("Added {} as an owner of {name}.", self.user);
+        } else {
+            tracing::info!("{} is already an owner of {name}.", self.user);
+        }
+        Ok(())
+    }
+}
+
+/// Removes a registry user from the owners of a package.
+#[derive(Debug, Args)]
+struct OwnerRmCmd {
+    /// Username of the owner to remove.
+    user: String,
+
+    /// Name of the package.
+    package: String,
+
+    /// One-time password from your authenticator, if your account uses
+    /// two-factor authentication.
+    #[arg(long)]
+    otp: Option<String>,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for OwnerRmCmd {
+    async fn execute(self) -> Result<()> {
+        let (name, _) = parse_registry_spec(&self.package)?;
+        let removed = self
+            .registry_args
+            .client(Some(&name))?
+            .remove_owner(&name, &self.user, self.otp.as_deref())
+            .await?;
+        if removed {
+            tracing::info!("Removed {} from the owners of {name}.", self.user);
+        } else {
+            tracing::info!("{} isn't an owner of {name}.", self.user);
+        }
+        Ok(())
+    }
+}
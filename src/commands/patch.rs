@@ -0,0 +1,180 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use nassun::{package::Package, ExtractMode, Nassun};
+use node_maintainer::Lockfile;
+use oro_common::CorgiManifest;
+
+use crate::commands::OroCommand;
+use crate::nassun_args::NassunArgs;
+use crate::OroError;
+
+/// Extracts a clean copy of a dependency into a directory where it can be
+/// edited, to create a patch for it with `oro patch-commit`.
+///
+/// The package is looked up in the project's `package-lock.kdl`, so the copy
+/// matches what's installed. Packages that aren't in the lockfile are
+/// resolved from the registry instead. If the project's
+/// `patchedDependencies` already has a patch for the package, it gets
+/// applied to the copy, so the new patch builds on it.
+#[derive(Debug, Args)]
+pub struct PatchCmd {
+    /// Package to patch, by name (`foo`) or name and version (`foo@1.2.3`).
+    package: String,
+
+    /// Directory to extract the package into. Defaults to a new temporary
+    /// directory.
+    #[arg(long)]
+    edit_dir: Option<PathBuf>,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[arg(from_global)]
+    emoji: bool,
+
+    #[command(flatten)]
+    nassun_args: NassunArgs,
+}
+
+#[async_trait]
+impl OroCommand for PatchCmd {
+    async fn execute(self) -> Result<()> {
+        let nassun = self.nassun_args.to_nassun()?;
+        let (package, label) =
+            locate_package(&nassun, &self.nassun_args.root, &self.package).await?;
+        let edit_dir = match self.edit_dir {
+            Some(dir) => {
+                let occupied = std::fs::read_dir(&dir)
+                    .map(|mut entries| entries.next().is_some())
+                    .unwrap_or(false);
+                if occupied {
+                    return Err(OroError::PatchEditDirNotEmpty(dir).into());
+                }
+                dir
+            }
+            None => tempfile::Builder::new()
+                .prefix("oro-patch-")
+                .tempdir()
+                .into_diagnostic()
+                .wrap_err("Failed to create a directory to extract the package into.")?
+                .into_path(),
+        };
+        package.extract_to_dir(&edit_dir, ExtractMode::Copy).await?;
+        let existing_patch = existing_patch(&self.nassun_args.root, package.name(), &label).await;
+        if let Some(patch) = &existing_patch {
+            node_maintainer::apply_patch_file(
+                &self.nassun_args.root.join(patch),
+                &edit_dir,
+                &label,
+            )
+            .await?;
+        }
+
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&serde_json::json!({
+                    "package": label,
+                    "dir": edit_dir,
+                    "patch": existing_patch,
+                }))
+                .into_diagnostic()
+                .wrap_err("patch::json_serialize")?
+            );
+        } else {
+            tracing::info!(
+                "{}Extracted {label} to {}.",
+                if self.emoji { "📦 " } else { "" },
+                edit_dir.display()
+            );
+            if let Some(patch) = &existing_patch {
+                tracing::info!("Applied its existing patch, {patch}.");
+            }
+            tracing::info!(
+                "Edit it, then run `oro patch-commit {}` to save your changes as a patch.",
+                edit_dir.display()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// The patch the project's `patchedDependencies` has for `label`
+/// (`name@version`), if any. Patches for a specific version take precedence
+/// over ones for every version of `name`.
+async fn existing_patch(root: &Path, name: &str, label: &str) -> Option<String> {
+    let manifest: CorgiManifest = async_std::fs::read_to_string(root.join("package.json"))
+        .await
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())?;
+    manifest
+        .patched_dependencies
+        .get(label)
+        .or_else(|| manifest.patched_dependencies.get(name))
+        .cloned()
+}
+
+/// Finds the package `spec` (`foo` or `foo@1.2.3`) refers to in the
+/// project's lockfile, falling back to resolving it from the registry.
+/// Returns the package along with a `name@version` label for it.
+pub(crate) async fn locate_package(
+    nassun: &Nassun,
+    root: &Path,
+    spec: &str,
+) -> Result<(Package, String)> {
+    let (name, version) = match spec.rfind('@') {
+        Some(at) if at > 0 => (&spec[..at], Some(&spec[at + 1..])),
+        _ => (spec, None),
+    };
+    let lockfile = async_std::fs::read_to_string(root.join("package-lock.kdl"))
+        .await
+        .ok()
+        .and_then(|kdl| Lockfile::from_kdl(kdl).ok())
+        .unwrap_or_default();
+    let mut candidates = Vec::new();
+    for node in lockfile.packages().values() {
+        let node_version = node.version.as_ref().map(|v| v.to_string());
+        if node.name.as_ref() == name
+            && version.map_or(true, |v| node_version.as_deref() == Some(v))
+            && !candidates
+                .iter()
+                .any(|(_, existing)| existing == &node_version)
+        {
+            candidates.push((node, node_version));
+        }
+    }
+    match &candidates[..] {
+        [] => {
+            let package = nassun.resolve(spec).await?;
+            let label = match package.resolved() {
+                nassun::PackageResolution::Npm { version, .. } => {
+                    format!("{}@{version}", package.name())
+                }
+                _ => package.name().to_string(),
+            };
+            Ok((package, label))
+        }
+        [(node, node_version)] => {
+            let package = node
+                .to_package(nassun)
+                .await?
+                .ok_or_else(|| node_maintainer::NodeMaintainerError::MissingResolution)?;
+            let label = match node_version {
+                Some(v) => format!("{name}@{v}"),
+                None => name.to_string(),
+            };
+            Ok((package, label))
+        }
+        _ => Err(OroError::AmbiguousPatchPackage(
+            name.into(),
+            candidates
+                .iter()
+                .filter_map(|(_, version)| version.clone())
+                .collect(),
+        )
+        .into()),
+    }
+}
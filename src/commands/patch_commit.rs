@@ -0,0 +1,117 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use nassun::ExtractMode;
+use oro_common::{CorgiManifest, Manifest};
+
+use crate::apply_args::ApplyArgs;
+use crate::commands::patch::locate_package;
+use crate::commands::OroCommand;
+use crate::nassun_args::NassunArgs;
+use crate::OroError;
+
+/// Saves the changes made to a package extracted by `oro patch` as a patch
+/// file, and adds it to the project's `patchedDependencies`.
+///
+/// The edited directory is compared against a fresh copy of the same
+/// package, and the differences are written as a unified diff to
+/// `patches/<name>@<version>.patch`. The patch gets applied whenever the
+/// package is installed from then on.
+#[derive(Debug, Args)]
+pub struct PatchCommitCmd {
+    /// Directory containing the edited package.
+    edit_dir: PathBuf,
+
+    /// Directory to write the patch file to, relative to the project root.
+    #[arg(long, default_value = "patches")]
+    patches_dir: PathBuf,
+
+    #[command(flatten)]
+    apply: ApplyArgs,
+}
+
+#[async_trait]
+impl OroCommand for PatchCommitCmd {
+    async fn execute(mut self) -> Result<()> {
+        if self.apply.locked {
+            // NOTE: we force locked to be false here, because the new patch
+            // always changes the lockfile.
+            tracing::info!("Ignoring --locked option. It doesn't make sense to run this command in locked mode.");
+            self.apply.locked = false;
+        }
+
+        let edited: Manifest = async_std::fs::read_to_string(self.edit_dir.join("package.json"))
+            .await
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .ok_or_else(|| OroError::NotAPatchDir(self.edit_dir.clone()))?;
+        let (Some(name), Some(version)) = (edited.name, edited.version) else {
+            return Err(OroError::NotAPatchDir(self.edit_dir.clone()).into());
+        };
+        let spec = format!("{name}@{version}");
+
+        let nassun = NassunArgs::from_apply_args(&self.apply).to_nassun()?;
+        let (package, _) = locate_package(&nassun, &self.apply.root, &spec).await?;
+        let original = tempfile::tempdir()
+            .into_diagnostic()
+            .wrap_err("Failed to create a directory to extract the original package into.")?;
+        package
+            .extract_to_dir(original.path(), ExtractMode::Copy)
+            .await?;
+        let patch = node_maintainer::create_patch(original.path(), &self.edit_dir)?;
+        if patch.is_empty() {
+            return Err(OroError::NoPatchChanges(self.edit_dir.clone()).into());
+        }
+
+        let patch_path = self
+            .patches_dir
+            .join(format!("{}.patch", spec.replace('/', "+")));
+        let patch_file = self.apply.root.join(&patch_path);
+        if let Some(parent) = patch_file.parent() {
+            async_std::fs::create_dir_all(parent)
+                .await
+                .into_diagnostic()?;
+        }
+        async_std::fs::write(&patch_file, patch)
+            .await
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to write patch to {}.", patch_file.display()))?;
+        let patch_path = patch_path.to_string_lossy().replace('\\', "/");
+        tracing::info!(
+            "{}Wrote patch for {spec} to {patch_path}.",
+            if self.apply.emoji { "🩹 " } else { "" }
+        );
+
+        let mut manifest = oro_pretty_json::from_str(
+            async_std::fs::read_to_string(self.apply.root.join("package.json"))
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+        manifest.value["patchedDependencies"][&spec] =
+            serde_json::to_value(&patch_path).expect("Value is always a valid string");
+
+        let corgi: CorgiManifest =
+            serde_json::from_str(&oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?)
+                .into_diagnostic()?;
+
+        // Then, we apply the change.
+        self.apply.execute(corgi).await?;
+
+        async_std::fs::write(
+            self.apply.root.join("package.json"),
+            oro_pretty_json::to_string_pretty(&manifest).into_diagnostic()?,
+        )
+        .await
+        .into_diagnostic()?;
+
+        tracing::info!(
+            "{}Updated package.json with a patched dependency.",
+            if self.apply.emoji { "📝 " } else { "" },
+        );
+
+        Ok(())
+    }
+}
@@ -31,7 +31,10 @@ impl OroCommand for PingCmd {
         let start = Instant::now();
         let registry = self.registry;
         tracing::info!("{}ping: {registry}", if self.emoji { "➡️ " } else { "" });
-        let client_builder: OroClientBuilder = self.client_args.try_into()?;
+        let client_builder: OroClientBuilder = self
+            .client_args
+            .with_stored_credentials([&registry])
+            .try_into()?;
         let client = client_builder.registry(registry.clone()).build();
         let payload = client.ping().await?;
         let time = start.elapsed().as_micros() as f32 / 1000.0;
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
 use clap::Args;
 use miette::{IntoDiagnostic, Result};
+use node_maintainer::LinkedDependencies;
 use oro_common::CorgiManifest;
 
 use crate::apply_args::ApplyArgs;
@@ -8,6 +9,8 @@ use crate::commands::OroCommand;
 
 /// Removes the existing `node_modules`, if any, and reapplies it from
 /// scratch. You can use this to make sure you have a pristine `node_modules`.
+///
+/// Packages linked with `oro link` stay linked.
 #[derive(Debug, Args)]
 pub struct ReapplyCmd {
     #[command(flatten)]
@@ -20,6 +23,7 @@ impl OroCommand for ReapplyCmd {
         let total_time = std::time::Instant::now();
 
         let nm = self.apply.root.join("node_modules");
+        let links = LinkedDependencies::load(&self.apply.root).await?;
 
         if nm.exists() {
             tracing::info!(
@@ -28,6 +32,7 @@ impl OroCommand for ReapplyCmd {
             );
 
             std::fs::remove_dir_all(nm).into_diagnostic()?;
+            links.save(&self.apply.root).await?;
 
             tracing::info!(
                 "{}node_modules/ cleared in {}s.",
@@ -0,0 +1,198 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Args;
+use indicatif::ProgressStyle;
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::NodeMaintainerOptions;
+use oro_common::CorgiManifest;
+use tracing::{Instrument, Span};
+use tracing_indicatif::span_ext::IndicatifSpanExt;
+use url::Url;
+
+use crate::apply_args::report_script_failures;
+use crate::apply_events::ApplyEvent;
+use crate::commands::logs::script_logs_dir;
+use crate::commands::OroCommand;
+use crate::credential_args::CredentialArgs;
+use crate::nassun_args::NassunArgs;
+use crate::parse_key_value;
+
+/// Reruns the install scripts of packages that are already installed, and
+/// relinks their bins.
+///
+/// Useful after switching Node.js versions, or when a native module needs
+/// to be rebuilt. The installed dependency tree is read from
+/// `node_modules/.orogene-meta.kdl`, and nothing gets resolved, downloaded,
+/// or extracted. Scripts run in dependency order, and cached install script
+/// results are never restored.
+#[derive(Debug, Args)]
+pub struct RebuildCmd {
+    /// Packages to rebuild, by name (`foo`) or name and version
+    /// (`foo@1.2.3`). Defaults to every package with install scripts.
+    packages: Vec<String>,
+
+    /// Controls number of concurrent script executions.
+    #[arg(long, default_value_t = node_maintainer::DEFAULT_SCRIPT_CONCURRENCY)]
+    script_concurrency: usize,
+
+    /// Patch file applied to a dependency when it was installed, in
+    /// `--patched-dependency foo@1.2.3=patches/foo@1.2.3.patch` format.
+    ///
+    /// These have to match the ones the project was installed with. Can be
+    /// provided multiple times.
+    #[arg(
+        long = "patched-dependency",
+        alias = "patched-dependencies",
+        value_parser = parse_key_value::<String, PathBuf>
+    )]
+    patched_dependencies: Vec<(String, PathBuf)>,
+
+    #[arg(from_global)]
+    registry: Url,
+
+    #[arg(from_global)]
+    scoped_registries: Vec<(String, Url)>,
+
+    #[arg(from_global)]
+    registry_mirrors: Vec<(Option<String>, Url)>,
+
+    #[arg(from_global)]
+    proxy: bool,
+
+    #[arg(from_global)]
+    proxy_url: Option<String>,
+
+    #[arg(from_global)]
+    no_proxy_domain: Option<String>,
+
+    #[arg(from_global)]
+    retries: u32,
+
+    #[arg(from_global)]
+    auth: Vec<(String, String, String)>,
+
+    #[command(flatten)]
+    credentials: CredentialArgs,
+
+    #[arg(from_global)]
+    root: PathBuf,
+
+    #[arg(from_global)]
+    cache: Option<PathBuf>,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[arg(from_global)]
+    emoji: bool,
+}
+
+#[async_trait]
+impl OroCommand for RebuildCmd {
+    async fn execute(self) -> Result<()> {
+        let total_time = std::time::Instant::now();
+        let root = &self.root;
+        let corgi: CorgiManifest = serde_json::from_str(
+            &async_std::fs::read_to_string(root.join("package.json"))
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+        let json = self.json;
+        let mut nm = NodeMaintainerOptions::new()
+            .nassun(self.nassun_args().to_nassun()?)
+            .root(root)
+            .script_concurrency(self.script_concurrency)
+            .on_script_start(move |pkg, event| {
+                if json {
+                    ApplyEvent::script_start(pkg, event).emit();
+                }
+                let span = Span::current();
+                span.pb_set_style(
+                    &ProgressStyle::default_bar()
+                        .template(&format!(
+                            "{{span_child_prefix}}{{spinner}} {}::{event} ({{elapsed}}): {{wide_msg:.dim}}",
+                            pkg.name(),
+                        ))
+                        .unwrap(),
+                );
+            })
+            .on_script_output(move |pkg, event, line| {
+                if json {
+                    ApplyEvent::script_line(pkg, event, line).emit();
+                }
+                let span = Span::current();
+                span.pb_inc(1);
+                span.pb_set_message(line);
+            });
+        if let Some(cache) = self.cache.as_deref() {
+            nm = nm.cache(cache).script_logs(script_logs_dir(cache));
+        }
+        for (spec, patch) in &self.patched_dependencies {
+            nm = nm.patched_dependency(spec, patch);
+        }
+        let maintainer = nm.resolve_installed(corgi).await?;
+
+        let script_span = tracing::info_span!("Building");
+        script_span.pb_set_style(
+            &ProgressStyle::default_bar()
+                .template(&format!(
+                    "{{spinner}} {}Running scripts {{wide_msg:.dim}}",
+                    self.maybe_emoji("🏃 "),
+                ))
+                .unwrap(),
+        );
+        let rebuilt = maintainer
+            .rebuild_installed(&self.packages)
+            .instrument(script_span)
+            .await;
+        let failures = maintainer.script_failures().await;
+        report_script_failures(&failures, self.json, self.maybe_emoji("⚠️  "));
+        rebuilt?;
+
+        tracing::info!(
+            "{}Rebuilt {} in {}s.",
+            self.maybe_emoji("🎉 "),
+            if self.packages.is_empty() {
+                "all packages".into()
+            } else {
+                self.packages.join(", ")
+            },
+            total_time.elapsed().as_millis() as f32 / 1000.0
+        );
+        Ok(())
+    }
+}
+
+impl RebuildCmd {
+    /// Packages are only ever loaded from what's installed, so resolution
+    /// options don't apply here, but the registries still have to match the
+    /// ones they were installed from.
+    fn nassun_args(&self) -> NassunArgs {
+        NassunArgs {
+            default_tag: "latest".into(),
+            before: None,
+            minimum_release_age: None,
+            registry: self.registry.clone(),
+            scoped_registries: self.scoped_registries.clone(),
+            registry_mirrors: self.registry_mirrors.clone(),
+            root: self.root.clone(),
+            cache: self.cache.clone(),
+            proxy: self.proxy,
+            proxy_url: self.proxy_url.clone(),
+            no_proxy_domain: self.no_proxy_domain.clone(),
+            retries: self.retries,
+            auth: self.auth.clone(),
+            credentials: self.credentials.clone(),
+        }
+    }
+
+    fn maybe_emoji(&self, emoji: &'static str) -> &'static str {
+        if self.emoji {
+            emoji
+        } else {
+            ""
+        }
+    }
+}
@@ -7,6 +7,7 @@ use oro_pretty_json::Formatted;
 
 use crate::apply_args::ApplyArgs;
 use crate::commands::OroCommand;
+use crate::global_install_args::GlobalInstallArgs;
 use crate::OroError;
 
 /// Removes one or more dependencies from the target package.
@@ -18,6 +19,9 @@ pub struct RemoveCmd {
     #[arg(required = true)]
     names: Vec<String>,
 
+    #[command(flatten)]
+    global: GlobalInstallArgs,
+
     #[command(flatten)]
     apply: ApplyArgs,
 }
@@ -25,6 +29,10 @@ pub struct RemoveCmd {
 #[async_trait]
 impl OroCommand for RemoveCmd {
     async fn execute(mut self) -> Result<()> {
+        if self.global.global {
+            return self.execute_global();
+        }
+
         let mut manifest = oro_pretty_json::from_str(
             &async_std::fs::read_to_string(self.apply.root.join("package.json"))
                 .await
@@ -78,6 +86,37 @@ impl OroCommand for RemoveCmd {
 }
 
 impl RemoveCmd {
+    /// Removes the isolated installations of global packages, along with
+    /// their linked bins.
+    fn execute_global(&self) -> Result<()> {
+        let mut count = 0;
+        for name in &self.names {
+            if let Ok(PackageSpec::Npm {
+                name: spec_name, ..
+            }) = name.parse()
+            {
+                if &spec_name != name {
+                    tracing::warn!("Ignoring version specifier in `{name}`. Arguments to `oro remove` should only be package names. Proceeding with `{spec_name}` instead.");
+                }
+                if self.global.uninstall(&spec_name)? {
+                    count += 1;
+                } else {
+                    tracing::warn!("{spec_name} is not installed globally.");
+                }
+            } else {
+                return Err(OroError::InvalidPackageName(name.clone()).into());
+            }
+        }
+
+        tracing::info!(
+            "{}Removed {count} global package{}.",
+            if self.apply.emoji { "🧹 " } else { "" },
+            if count == 1 { "" } else { "s" },
+        );
+
+        Ok(())
+    }
+
     fn remove_from_manifest(&self, mani: &mut Formatted, name: &str) -> usize {
         let mut count = 0;
         for ty in [
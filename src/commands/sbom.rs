@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::{clap_derive::ValueEnum, Args};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_maintainer::{Lockfile, SbomOptions};
+use url::Url;
+
+use crate::commands::OroCommand;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SbomFormat {
+    /// CycloneDX 1.5 JSON.
+    Cyclonedx,
+    /// SPDX 2.3 JSON.
+    Spdx,
+}
+
+/// Generates a software bill of materials (SBOM) for the current project.
+///
+/// The SBOM lists every package in `package-lock.kdl`, along with its
+/// package URL, integrity hashes, declared license, author, and source
+/// repository, as well as the dependency relationships between packages.
+/// Package metadata is read from `node_modules/` when the project has been
+/// applied, and from the cache otherwise.
+#[derive(Debug, Args)]
+pub struct SbomCmd {
+    /// SBOM format to generate.
+    #[arg(long, value_enum, default_value_t = SbomFormat::Cyclonedx)]
+    format: SbomFormat,
+
+    /// File to write the SBOM to. Defaults to printing it to stdout.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    #[arg(from_global)]
+    root: PathBuf,
+
+    #[arg(from_global)]
+    cache: Option<PathBuf>,
+
+    #[arg(from_global)]
+    registry: Url,
+
+    #[arg(from_global)]
+    scoped_registries: Vec<(String, Url)>,
+}
+
+#[async_trait]
+impl OroCommand for SbomCmd {
+    async fn execute(self) -> Result<()> {
+        let lockfile_path = self.root.join("package-lock.kdl");
+        let lockfile = Lockfile::from_kdl(
+            async_std::fs::read_to_string(&lockfile_path)
+                .await
+                .into_diagnostic()
+                .wrap_err_with(|| {
+                    format!("Failed to read lockfile at {}", lockfile_path.display())
+                })?,
+        )?;
+
+        let mut opts = SbomOptions::new()
+            .root(&self.root)
+            .registry(self.registry.clone());
+        for (scope, registry) in &self.scoped_registries {
+            opts = opts.scope_registry(scope, registry.clone());
+        }
+        if let Some(cache) = &self.cache {
+            opts = opts.cache(cache);
+        }
+        let sbom = lockfile.to_sbom(&opts);
+        let doc = match self.format {
+            SbomFormat::Cyclonedx => sbom.to_cyclonedx(),
+            SbomFormat::Spdx => sbom.to_spdx(),
+        };
+        let output = serde_json::to_string_pretty(&doc)
+            .into_diagnostic()
+            .wrap_err("sbom::json_serialize")?;
+
+        if let Some(path) = &self.output {
+            async_std::fs::write(path, output)
+                .await
+                .into_diagnostic()
+                .wrap_err_with(|| format!("Failed to write SBOM to {}", path.display()))?;
+            tracing::info!(
+                "Wrote SBOM for {} packages to {}.",
+                sbom.components.len(),
+                path.display()
+            );
+        } else {
+            println!("{output}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use async_trait::async_trait;
+use clap::Args;
+use colored::*;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use oro_client::search::{SearchOptions, SearchResults};
+use term_grid::{Cell, Direction, Filling, Grid, GridOptions};
+
+use crate::commands::OroCommand;
+use crate::nassun_args::NassunArgs;
+
+const COLUMN_SPACING: usize = 3;
+const DATE_WIDTH: usize = "YYYY-MM-DD".len();
+
+/// Searches the registry for packages.
+#[derive(Debug, Args)]
+#[clap(visible_aliases(["s", "find"]))]
+pub struct SearchCmd {
+    /// Search terms. Supports the registry's search qualifiers, like
+    /// `keywords:cli` or `author:someone`.
+    ///
+    /// Mentioning a scope (`@myorg` or `@myorg/pkg`) sends the search to
+    /// that scope's registry, if one is configured.
+    #[arg(required = true)]
+    terms: Vec<String>,
+
+    /// Number of results to show per page. Most registries return at most
+    /// 250 results at a time.
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    /// Page of results to show, starting at 1.
+    #[arg(long, default_value_t = 1)]
+    page: usize,
+
+    /// How much to weigh package quality when ranking results, from 0 to 1.
+    #[arg(long)]
+    quality: Option<f64>,
+
+    /// How much to weigh package popularity when ranking results, from 0 to
+    /// 1.
+    #[arg(long)]
+    popularity: Option<f64>,
+
+    /// How much to weigh package maintenance when ranking results, from 0
+    /// to 1.
+    #[arg(long)]
+    maintenance: Option<f64>,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[command(flatten)]
+    nassun_args: NassunArgs,
+}
+
+#[async_trait]
+impl OroCommand for SearchCmd {
+    async fn execute(self) -> Result<()> {
+        let from = self.page.saturating_sub(1) * self.limit;
+        let options = SearchOptions {
+            size: Some(self.limit),
+            from: Some(from),
+            quality: self.quality,
+            popularity: self.popularity,
+            maintenance: self.maintenance,
+            ..SearchOptions::new(self.terms.join(" "))
+        };
+        let results = self.nassun_args.to_nassun()?.search(&options).await?;
+
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&results)
+                    .into_diagnostic()
+                    .wrap_err("search::json_serialize")?
+            );
+            return Ok(());
+        }
+
+        if results.objects.is_empty() {
+            tracing::info!("No packages found matching `{}`.", options.text);
+            return Ok(());
+        }
+
+        print_table(&results);
+
+        let shown = from + results.objects.len();
+        if shown < results.total {
+            tracing::info!(
+                "Showing {}-{shown} of {} results. Use `--page {}` to see more.",
+                from + 1,
+                results.total,
+                self.page.max(1) + 1
+            );
+        }
+        Ok(())
+    }
+}
+
+fn print_table(results: &SearchResults) {
+    let width = term_size::dimensions().map(|(w, _)| w).unwrap_or(80);
+    let name_width = results
+        .objects
+        .iter()
+        .map(|result| result.package.name.len())
+        .chain(["NAME".len()])
+        .max()
+        .unwrap_or_default();
+    let version_width = results
+        .objects
+        .iter()
+        .map(|result| result.package.version.len())
+        .chain(["VERSION".len()])
+        .max()
+        .unwrap_or_default();
+    let description_width = width
+        .saturating_sub(name_width + version_width + DATE_WIDTH + COLUMN_SPACING * 3)
+        .max("DESCRIPTION".len());
+
+    let mut grid = Grid::new(GridOptions {
+        filling: Filling::Spaces(COLUMN_SPACING),
+        direction: Direction::LeftToRight,
+    });
+    for header in ["NAME", "VERSION", "DATE", "DESCRIPTION"] {
+        grid.add(styled_cell(header, |s| s.bold()));
+    }
+    for result in &results.objects {
+        let pkg = &result.package;
+        grid.add(styled_cell(&pkg.name, |s| s.cyan()));
+        grid.add(styled_cell(&pkg.version, |s| s.yellow()));
+        grid.add(Cell::from(
+            pkg.date
+                .as_deref()
+                .and_then(|date| date.get(..DATE_WIDTH))
+                .unwrap_or(""),
+        ));
+        grid.add(Cell::from(truncate(
+            pkg.description.as_deref().unwrap_or("").trim(),
+            description_width,
+        )));
+    }
+    print!("{}", grid.fit_into_columns(4));
+}
+
+/// Makes a cell with colored contents, keeping the width of the uncolored
+/// text so the columns still line up.
+fn styled_cell(text: &str, style: impl Fn(&str) -> ColoredString) -> Cell {
+    let mut cell = Cell::from(text);
+    cell.contents = style(text).to_string();
+    cell
+}
+
+fn truncate(text: &str, max_width: usize) -> String {
+    if text.chars().count() <= max_width {
+        text.into()
+    } else {
+        let mut truncated = text
+            .chars()
+            .take(max_width.saturating_sub(1))
+            .collect::<String>();
+        truncated.push('…');
+        truncated
+    }
+}
@@ -0,0 +1,75 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use node_maintainer::PackageStore;
+
+use crate::commands::OroCommand;
+use crate::OroError;
+
+/// Manages the package store shared between projects.
+///
+/// Projects only use the shared store when `--store` (or `store` in
+/// oro.kdl) is set, and their installs are isolated.
+#[derive(Debug, Args)]
+pub struct StoreCmd {
+    #[command(subcommand)]
+    subcommand: StoreSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum StoreSubCmd {
+    Prune(StorePruneCmd),
+}
+
+#[async_trait]
+impl OroCommand for StoreCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            StoreSubCmd::Prune(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+/// Removes packages from the shared store that no project uses anymore.
+///
+/// Projects that were deleted, or that stopped using the store, no longer
+/// keep anything in it. Waits for any `oro apply` currently using the store
+/// to finish first.
+#[derive(Debug, Args)]
+struct StorePruneCmd {
+    #[arg(from_global)]
+    store: Option<PathBuf>,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[arg(from_global)]
+    emoji: bool,
+}
+
+#[async_trait]
+impl OroCommand for StorePruneCmd {
+    async fn execute(self) -> Result<()> {
+        let store = PackageStore::new(self.store.ok_or(OroError::MissingStore)?);
+        let pruned = store.prune().await?;
+        if self.json {
+            let output = serde_json::to_string_pretty(&serde_json::json!({
+                "store": store.path(),
+                "pruned": pruned,
+            }))
+            .into_diagnostic()
+            .wrap_err("store::prune::serialize")?;
+            println!("{output}");
+        } else {
+            tracing::info!(
+                "{}Pruned {pruned} unused package{} from {}.",
+                if self.emoji { "🧹 " } else { "" },
+                if pruned == 1 { "" } else { "s" },
+                store.path().display()
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use async_trait::async_trait;
+use clap::{Args, Subcommand};
+use colored::*;
+use dialoguer::{theme::ColorfulTheme, Password};
+use miette::{IntoDiagnostic, Result, WrapErr};
+use oro_client::tokens::TokenOptions;
+
+use crate::commands::OroCommand;
+use crate::registry_args::RegistryArgs;
+use crate::OroError;
+
+/// Number of characters of a token's key used as its ID in `oro token ls`.
+const TOKEN_ID_LEN: usize = 6;
+
+/// Manages the access tokens for your registry account.
+#[derive(Debug, Args)]
+pub struct TokenCmd {
+    #[command(subcommand)]
+    subcommand: TokenSubCmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum TokenSubCmd {
+    #[command(visible_alias = "list")]
+    Ls(TokenLsCmd),
+    Create(TokenCreateCmd),
+    Revoke(TokenRevokeCmd),
+}
+
+#[async_trait]
+impl OroCommand for TokenCmd {
+    async fn execute(self) -> Result<()> {
+        match self.subcommand {
+            TokenSubCmd::Ls(cmd) => cmd.execute().await,
+            TokenSubCmd::Create(cmd) => cmd.execute().await,
+            TokenSubCmd::Revoke(cmd) => cmd.execute().await,
+        }
+    }
+}
+
+/// Lists your access tokens. Only the start of each token is shown.
+#[derive(Debug, Args)]
+struct TokenLsCmd {
+    #[arg(from_global)]
+    json: bool,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for TokenLsCmd {
+    async fn execute(self) -> Result<()> {
+        let tokens = self.registry_args.client(None)?.tokens().await?;
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&tokens)
+                    .into_diagnostic()
+                    .wrap_err("token::ls::serialize")?
+            );
+            return Ok(());
+        }
+        for token in &tokens {
+            let kind = if token.readonly {
+                "read-only"
+            } else if token.automation {
+                "automation"
+            } else {
+                "publish"
+            };
+            let mut line = format!("{}  {}…  {kind}", token_id(&token.key).bold(), token.token);
+            if let Some(created) = &token.created {
+                line.push_str(&format!(
+                    "  created {}",
+                    created.split('T').next().unwrap_or(created)
+                ));
+            }
+            if let Some(cidrs) = token
+                .cidr_whitelist
+                .as_ref()
+                .filter(|cidrs| !cidrs.is_empty())
+            {
+                line.push_str(&format!("  {}", cidrs.join(", ").dimmed()));
+            }
+            println!("{line}");
+        }
+        Ok(())
+    }
+}
+
+/// Creates a new access token, and prints it. Make sure to copy it
+/// somewhere safe: registries don't show full tokens again after creating
+/// them.
+#[derive(Debug, Args)]
+struct TokenCreateCmd {
+    /// Create a token that can only be used to read packages, not publish
+    /// or modify them.
+    #[arg(long)]
+    read_only: bool,
+
+    /// Only allow the token to be used from this IP range, in CIDR notation
+    /// (for example, `192.168.1.0/24`).
+    ///
+    /// Can be provided multiple times.
+    #[arg(long = "cidr")]
+    cidr_whitelist: Vec<String>,
+
+    /// Your account password. You'll be prompted for it if it isn't given.
+    #[arg(long)]
+    password: Option<String>,
+
+    /// One-time password from your authenticator, if your account uses
+    /// two-factor authentication.
+    #[arg(long)]
+    otp: Option<String>,
+
+    #[arg(from_global)]
+    json: bool,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for TokenCreateCmd {
+    async fn execute(self) -> Result<()> {
+        let password = match self.password {
+            Some(password) => password,
+            None => Password::with_theme(&ColorfulTheme::default())
+                .with_prompt("Password:")
+                .interact()
+                .into_diagnostic()?,
+        };
+        let token = self
+            .registry_args
+            .client(None)?
+            .create_token(
+                &password,
+                &TokenOptions {
+                    readonly: self.read_only,
+                    cidr_whitelist: self.cidr_whitelist,
+                },
+                self.otp.as_deref(),
+            )
+            .await?;
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&token)
+                    .into_diagnostic()
+                    .wrap_err("token::create::serialize")?
+            );
+        } else {
+            tracing::info!("Created token {}.", token_id(&token.key));
+            println!("{}", token.token);
+        }
+        Ok(())
+    }
+}
+
+/// Revokes access tokens, so they can no longer be used.
+#[derive(Debug, Args)]
+struct TokenRevokeCmd {
+    /// IDs of the tokens to revoke, as shown by `oro token ls`. Any unique
+    /// prefix of a token's key, or of the token itself, works.
+    #[arg(required = true)]
+    ids: Vec<String>,
+
+    /// One-time password from your authenticator, if your account uses
+    /// two-factor authentication.
+    #[arg(long)]
+    otp: Option<String>,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for TokenRevokeCmd {
+    async fn execute(self) -> Result<()> {
+        let client = self.registry_args.client(None)?;
+        let tokens = client.tokens().await?;
+        let mut keys = Vec::new();
+        for id in &self.ids {
+            let matches = tokens
+                .iter()
+                .filter(|token| token.key.starts_with(id) || token.token.starts_with(id))
+                .collect::<Vec<_>>();
+            match &matches[..] {
+                [] => return Err(OroError::TokenNotFound(id.clone()).into()),
+                [token] => keys.push(&token.key),
+                _ => return Err(OroError::AmbiguousToken(id.clone()).into()),
+            }
+        }
+        for key in keys {
+            client.revoke_token(key, self.otp.as_deref()).await?;
+            tracing::info!("Revoked token {}.", token_id(key));
+        }
+        Ok(())
+    }
+}
+
+fn token_id(key: &str) -> &str {
+    &key[..TOKEN_ID_LEN.min(key.len())]
+}
@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use clap::Args;
+use miette::{IntoDiagnostic, Result};
+use node_maintainer::LinkedDependencies;
+use oro_common::CorgiManifest;
+
+use crate::apply_args::ApplyArgs;
+use crate::commands::OroCommand;
+use crate::OroError;
+
+/// Removes packages linked with `oro link`, and goes back to installing
+/// them from `package.json`.
+#[derive(Debug, Args)]
+pub struct UnlinkCmd {
+    /// Names of the linked packages to unlink. Unlinks every package if none
+    /// are given.
+    names: Vec<String>,
+
+    #[command(flatten)]
+    apply: ApplyArgs,
+}
+
+#[async_trait]
+impl OroCommand for UnlinkCmd {
+    async fn execute(self) -> Result<()> {
+        let mut links = LinkedDependencies::load(&self.apply.root).await?;
+        if links.is_empty() {
+            tracing::info!("No packages are linked. Nothing to unlink.");
+            return Ok(());
+        }
+        let names = if self.names.is_empty() {
+            links.iter().map(|(name, _)| name.to_string()).collect()
+        } else {
+            self.names.clone()
+        };
+        for name in &names {
+            if links.remove(name).is_none() {
+                return Err(OroError::NotLinked(name.clone()).into());
+            }
+        }
+        links.save(&self.apply.root).await?;
+
+        let corgi: CorgiManifest = serde_json::from_str(
+            &async_std::fs::read_to_string(self.apply.root.join("package.json"))
+                .await
+                .into_diagnostic()?,
+        )
+        .into_diagnostic()?;
+
+        self.apply.execute(corgi).await?;
+
+        tracing::info!(
+            "{}Unlinked {}.",
+            if self.apply.emoji { "🔗 " } else { "" },
+            names.join(", ")
+        );
+        Ok(())
+    }
+}
@@ -188,6 +188,51 @@ impl OroCommand for ViewCmd {
                 println!();
             }
 
+            // versions:
+            // 1.2.3   1.2.2   1.2.1 (deprecated)
+            if !packument.versions.is_empty() {
+                let max_versions = 25_usize;
+                let mut grid = Grid::new(GridOptions {
+                    filling: Filling::Spaces(3),
+                    direction: Direction::LeftToRight,
+                });
+                let width = term_size::dimensions().map(|(w, _)| w).unwrap_or(80);
+                let mut versions = packument.versions.iter().collect::<Vec<_>>();
+                versions.sort_by(|(a, _), (b, _)| b.cmp(a));
+                for (version, meta) in versions.iter().take(max_versions) {
+                    let val = if meta.deprecated.is_some() {
+                        format!(
+                            "{} {}",
+                            version.to_string().magenta(),
+                            "(deprecated)".dimmed()
+                        )
+                    } else {
+                        version.to_string().yellow().to_string()
+                    };
+                    grid.add(Cell::from(val));
+                }
+                if let Some(out) = grid.fit_into_width(width) {
+                    print!("versions:\n{out}");
+                    let count = versions.len();
+                    if count > max_versions {
+                        println!("(...and {} more)", count - max_versions);
+                    }
+                    let deprecated = versions
+                        .iter()
+                        .filter(|(_, meta)| meta.deprecated.is_some())
+                        .count();
+                    if deprecated > 0 {
+                        println!(
+                            "{} of {} versions are {}",
+                            deprecated,
+                            count,
+                            "deprecated".magenta()
+                        );
+                    }
+                }
+                println!();
+            }
+
             // maintainers:
             // - Alex <something@email.com>
             if !maintainers.is_empty() {
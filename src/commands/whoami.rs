@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+
+use crate::commands::OroCommand;
+use crate::registry_args::RegistryArgs;
+
+/// Prints the username the registry knows you by.
+#[derive(Debug, Args)]
+pub struct WhoamiCmd {
+    #[arg(from_global)]
+    json: bool,
+
+    #[command(flatten)]
+    registry_args: RegistryArgs,
+}
+
+#[async_trait]
+impl OroCommand for WhoamiCmd {
+    async fn execute(self) -> Result<()> {
+        let registry = self.registry_args.registry.clone();
+        let username = self.registry_args.client(None)?.whoami().await?;
+        if self.json {
+            let output = serde_json::to_string_pretty(&serde_json::json!({
+                "registry": registry.to_string(),
+                "username": username,
+            }))
+            .into_diagnostic()
+            .wrap_err("whoami::serialize")?;
+            println!("{output}");
+        } else {
+            println!("{username}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+
+use clap::{clap_derive::ValueEnum, Args};
+use dialoguer::{theme::ColorfulTheme, Password};
+use directories::ProjectDirs;
+use is_terminal::IsTerminal;
+use miette::{IntoDiagnostic, Result};
+use oro_npm_account::credentials::{
+    CredentialStore, EncryptedCredentialStore, HelperCredentialStore, KdlCredentialStore,
+};
+
+use crate::OroError;
+
+/// Environment variable the encrypted credential store reads its passphrase
+/// from, before falling back to prompting for it.
+const PASSPHRASE_ENV: &str = "ORO_CREDENTIALS_PASSPHRASE";
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CredentialStoreKind {
+    /// Plain text, in the `auth` block of your global oro.kdl.
+    #[default]
+    Config,
+    /// A file encrypted with a passphrase.
+    Encrypted,
+    /// An external credential helper command.
+    Helper,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct CredentialArgs {
+    #[arg(from_global)]
+    pub credential_store: CredentialStoreKind,
+
+    #[arg(from_global)]
+    pub credential_helper: Option<String>,
+
+    #[arg(from_global)]
+    pub credentials_file: Option<PathBuf>,
+}
+
+impl CredentialArgs {
+    /// Opens the configured credential store. `config` is the oro.kdl file
+    /// the `config` store reads and writes.
+    pub fn open(&self, config: &Path) -> Result<Box<dyn CredentialStore>> {
+        Ok(match self.credential_store {
+            CredentialStoreKind::Config => Box::new(KdlCredentialStore::open(config)?),
+            CredentialStoreKind::Encrypted => self.open_encrypted()?,
+            CredentialStoreKind::Helper => self.open_helper()?,
+        })
+    }
+
+    /// Opens the configured credential store, unless it's the `config`
+    /// store. Credentials in oro.kdl are already applied through `--auth`,
+    /// so there's nothing more to look up for them.
+    pub fn open_external(&self) -> Result<Option<Box<dyn CredentialStore>>> {
+        Ok(match self.credential_store {
+            CredentialStoreKind::Config => None,
+            CredentialStoreKind::Encrypted => Some(self.open_encrypted()?),
+            CredentialStoreKind::Helper => Some(self.open_helper()?),
+        })
+    }
+
+    fn open_encrypted(&self) -> Result<Box<dyn CredentialStore>> {
+        let path = self
+            .credentials_file
+            .clone()
+            .or_else(|| {
+                ProjectDirs::from("", "", "orogene")
+                    .map(|dirs| dirs.config_dir().join("credentials.enc"))
+            })
+            .ok_or(OroError::MissingCredentialsFile)?;
+        let passphrase = match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) => passphrase,
+            Err(_) if std::io::stderr().is_terminal() => {
+                Password::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("Passphrase for {}:", path.display()))
+                    .interact()
+                    .into_diagnostic()?
+            }
+            Err(_) => return Err(OroError::MissingCredentialsPassphrase.into()),
+        };
+        Ok(Box::new(EncryptedCredentialStore::open(path, passphrase)?))
+    }
+
+    fn open_helper(&self) -> Result<Box<dyn CredentialStore>> {
+        let helper = self
+            .credential_helper
+            .as_deref()
+            .ok_or(OroError::MissingCredentialHelper)?;
+        Ok(Box::new(HelperCredentialStore::new(helper)))
+    }
+}
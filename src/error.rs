@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use miette::Diagnostic;
 use thiserror::Error;
 
@@ -15,4 +17,272 @@ pub enum OroError {
         help("Use the package name as it appears in your package.json instead.")
     )]
     InvalidPackageName(String),
+
+    /// Orogene was unable to figure out where globally-installed packages
+    /// should go. This usually happens when your platform doesn't have a
+    /// standard data directory.
+    #[error("Unable to determine the global package prefix.")]
+    #[diagnostic(
+        code(oro::global::missing_prefix),
+        url(docsrs),
+        help("Pass `--global-prefix <path>`, or set `global-prefix` in your oro.kdl.")
+    )]
+    MissingGlobalPrefix,
+
+    /// Failed to read `package-lock.kdl` from a git ref. Make sure the ref
+    /// exists, and that the lockfile was committed at that point.
+    #[error("Failed to read package-lock.kdl from git ref `{0}`: {1}")]
+    #[diagnostic(
+        code(oro::diff::git_lockfile_read),
+        url(docsrs),
+        help("Pass a path to a lockfile, or a git ref where package-lock.kdl was committed.")
+    )]
+    GitLockfileRead(String, String),
+
+    /// Some packages in the lockfile have missing or invalid registry
+    /// signatures. Invalid signatures may mean the package was tampered
+    /// with, either at the registry, at a mirror, or in transit.
+    #[error("{invalid} packages have invalid registry signatures, and {missing} are missing signatures.")]
+    #[diagnostic(
+        code(oro::audit::signatures_failed),
+        url(docsrs),
+        help("Confirm these packages with their registry before installing them.")
+    )]
+    SignatureAuditFailed { invalid: usize, missing: usize },
+
+    /// Some of the project's direct dependencies resolved to deprecated
+    /// versions, and `--fail-on-deprecated` was set.
+    #[error("Some direct dependencies are deprecated: {}", .0.join(", "))]
+    #[diagnostic(
+        code(oro::apply::deprecated_dependencies),
+        url(docsrs),
+        help("Upgrade or replace these dependencies, or drop `--fail-on-deprecated`/`fail-on-deprecated` to only warn about them.")
+    )]
+    DeprecatedDirectDependencies(Vec<String>),
+
+    /// A `license-policy` `exception` couldn't be parsed. Exceptions should be
+    /// package names, optionally followed by `@` and a semver range.
+    #[error("Invalid license exception `{0}`.")]
+    #[diagnostic(
+        code(oro::licenses::invalid_exception),
+        url(docsrs),
+        help(
+            "Use `name` or `name@<semver range>`, for example `left-pad` or `@scope/pkg@^1.2.0`."
+        )
+    )]
+    InvalidLicenseException(String),
+
+    /// A package's license doesn't satisfy the configured license policy.
+    #[error("{package} (at {path}): {reason}")]
+    #[diagnostic(
+        code(oro::licenses::violation),
+        url(docsrs),
+        help("Replace this package, or add it as a `license-policy` `exception` if its license has been reviewed.")
+    )]
+    LicenseViolation {
+        package: String,
+        path: String,
+        reason: String,
+    },
+
+    /// Some packages have licenses that aren't allowed by the configured
+    /// `license-policy`.
+    #[error("{count} package(s) don't satisfy the license policy.")]
+    #[diagnostic(code(oro::licenses::policy_failed), url(docsrs))]
+    LicensePolicyFailed {
+        count: usize,
+        #[related]
+        violations: Vec<OroError>,
+    },
+
+    /// Commands that manage packages on a registry only work with registry
+    /// packages, not git repositories, local directories, or aliases.
+    #[error("`{0}` is not a registry package.")]
+    #[diagnostic(
+        code(oro::registry::not_a_registry_package),
+        url(docsrs),
+        help("Pass the package's name as it's published on the registry, like `my-pkg` or `@scope/my-pkg`.")
+    )]
+    NotARegistryPackage(String),
+
+    /// `oro dist-tag add` needs an exact version to point the tag at.
+    #[error("`{0}` doesn't specify an exact version to tag.")]
+    #[diagnostic(
+        code(oro::dist_tag::version_required),
+        url(docsrs),
+        help("Use `<package>@<version>`, for example `my-pkg@1.2.3`.")
+    )]
+    DistTagVersionRequired(String),
+
+    /// `oro deprecate` only accepts semver versions or ranges, since dist-tags
+    /// can move after the fact.
+    #[error("`{0}` should be a version or semver range, not a dist-tag.")]
+    #[diagnostic(
+        code(oro::deprecate::invalid_range),
+        url(docsrs),
+        help("Use `<package>@<range>`, for example `my-pkg@<2.0.0`, or just `<package>` to deprecate every version.")
+    )]
+    InvalidDeprecateRange(String),
+
+    /// No token on the account matched the ID given to `oro token revoke`.
+    #[error("No token matches `{0}`.")]
+    #[diagnostic(
+        code(oro::token::not_found),
+        url(docsrs),
+        help("Run `oro token ls` to see the IDs of your tokens.")
+    )]
+    TokenNotFound(String),
+
+    /// More than one token on the account matched the ID given to `oro
+    /// token revoke`.
+    #[error("More than one token matches `{0}`.")]
+    #[diagnostic(
+        code(oro::token::ambiguous),
+        url(docsrs),
+        help("Use more characters of the token ID, as shown by `oro token ls`.")
+    )]
+    AmbiguousToken(String),
+
+    /// `credential-store` was set to `helper`, but no helper command was
+    /// configured.
+    #[error("No credential helper was configured.")]
+    #[diagnostic(
+        code(oro::credentials::missing_helper),
+        url(docsrs),
+        help("Pass `--credential-helper <command>`, or set `credential-helper` in your oro.kdl.")
+    )]
+    MissingCredentialHelper,
+
+    /// The encrypted credential store needs a passphrase, but there was no
+    /// terminal to prompt for one, and `ORO_CREDENTIALS_PASSPHRASE` wasn't
+    /// set.
+    #[error("A passphrase is needed to unlock the encrypted credentials file.")]
+    #[diagnostic(
+        code(oro::credentials::missing_passphrase),
+        url(docsrs),
+        help("Set the `ORO_CREDENTIALS_PASSPHRASE` environment variable, or run Orogene from an interactive terminal.")
+    )]
+    MissingCredentialsPassphrase,
+
+    /// Orogene was unable to figure out where the encrypted credentials
+    /// file should go. This usually happens when your platform doesn't have
+    /// a standard config directory.
+    #[error("Unable to determine where to keep the encrypted credentials file.")]
+    #[diagnostic(
+        code(oro::credentials::missing_file),
+        url(docsrs),
+        help("Pass `--credentials-file <path>`, or set `credentials-file` in your oro.kdl.")
+    )]
+    MissingCredentialsFile,
+
+    /// `oro store` commands need a shared package store to work on, but
+    /// none was configured.
+    #[error("No shared package store is configured.")]
+    #[diagnostic(
+        code(oro::store::missing_store),
+        url(docsrs),
+        help("Pass `--store <path>`, or set `store` in your oro.kdl.")
+    )]
+    MissingStore,
+
+    /// `oro logs scripts` was asked for a package that no script logs were
+    /// written for.
+    #[error("No script logs found for {0}.")]
+    #[diagnostic(
+        code(oro::logs::no_script_logs),
+        url(docsrs),
+        help("Logs are only kept for scripts that ran with the current cache. Run `oro logs scripts` to list the packages that have them.")
+    )]
+    NoScriptLogs(String),
+
+    /// Orogene was unable to figure out where your global oro.kdl lives.
+    /// This usually happens when your platform doesn't have a standard
+    /// config directory.
+    #[error("Unable to determine the location of the global oro.kdl.")]
+    #[diagnostic(
+        code(oro::config::missing_global_config),
+        url(docsrs),
+        help("Pass `--config <file>` to pick a config file, or `--project` to use the project's oro.kdl.")
+    )]
+    MissingGlobalConfig,
+
+    /// The requested key isn't set in the config file that was looked at.
+    /// Note that it might still be set somewhere else, like in another
+    /// config file or an environment variable.
+    #[error("`{0}` is not set in {}.", .1.display())]
+    #[diagnostic(
+        code(oro::config::key_not_set),
+        url(docsrs),
+        help("Use `oro config explain {0}` to see where its effective value comes from.")
+    )]
+    ConfigKeyNotSet(String, PathBuf),
+
+    /// The editor launched by `oro config edit` exited with an error. The
+    /// config file was left as the editor saved it.
+    #[error("Editor `{0}` exited with {1}.")]
+    #[diagnostic(
+        code(oro::config::editor_failed),
+        url(docsrs),
+        help("Set `$VISUAL` or `$EDITOR` to the editor you want to use.")
+    )]
+    EditorFailed(String, std::process::ExitStatus),
+
+    /// `oro patch` was given just a package name, but the lockfile has more
+    /// than one version of that package.
+    #[error("More than one version of {0} is installed: {}.", .1.join(", "))]
+    #[diagnostic(
+        code(oro::patch::ambiguous_package),
+        url(docsrs),
+        help("Pick a version to patch, like `{0}@{}`.", .1[0])
+    )]
+    AmbiguousPatchPackage(String, Vec<String>),
+
+    /// `oro patch` extracts packages into a fresh directory, so it won't
+    /// overwrite anything that's already there.
+    #[error("{} already exists and isn't empty.", .0.display())]
+    #[diagnostic(
+        code(oro::patch::edit_dir_not_empty),
+        url(docsrs),
+        help("Pass an `--edit-dir` that doesn't exist yet, or leave it out to use a new temporary directory.")
+    )]
+    PatchEditDirNotEmpty(PathBuf),
+
+    /// `oro patch-commit` needs to know which package was edited, from the
+    /// `name` and `version` in the directory's package.json.
+    #[error("{} doesn't contain a package.json with a name and version.", .0.display())]
+    #[diagnostic(
+        code(oro::patch::not_a_package),
+        url(docsrs),
+        help("Pass the directory `oro patch` extracted the package into.")
+    )]
+    NotAPatchDir(PathBuf),
+
+    /// The edited copy of the package is identical to the original, so
+    /// there's nothing to write a patch for.
+    #[error("No changes found in {}.", .0.display())]
+    #[diagnostic(
+        code(oro::patch::no_changes),
+        url(docsrs),
+        help("Edit the package's files in that directory, then run `oro patch-commit` again.")
+    )]
+    NoPatchChanges(PathBuf),
+
+    /// `oro link` needs the directory of a package, with a package.json that
+    /// gives the package's name.
+    #[error("{} isn't a directory with a package.json that has a name.", .0.display())]
+    #[diagnostic(
+        code(oro::link::not_a_package),
+        url(docsrs),
+        help("Pass the directory of the package to link, like `oro link ../my-package`.")
+    )]
+    NotALinkablePackage(PathBuf),
+
+    /// `oro unlink` was given a package that isn't linked.
+    #[error("{0} isn't linked.")]
+    #[diagnostic(
+        code(oro::link::not_linked),
+        url(docsrs),
+        help("Run `oro unlink` without any package names to remove every link.")
+    )]
+    NotLinked(String),
 }
@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use miette::{IntoDiagnostic, Result, WrapErr};
+use oro_common::CorgiManifest;
+
+use crate::OroError;
+
+/// Options for operating on globally-installed packages, instead of the
+/// current project.
+#[derive(Clone, Debug, Args)]
+pub struct GlobalInstallArgs {
+    /// Operate on globally-installed packages instead of the current
+    /// project.
+    ///
+    /// Each global package gets its own isolated installation under the
+    /// global prefix, and its bins are linked into `<global-prefix>/bin`.
+    #[arg(long, short = 'g')]
+    pub global: bool,
+
+    #[arg(from_global)]
+    pub global_prefix: Option<PathBuf>,
+}
+
+/// A package installed under the global prefix.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct GlobalPackage {
+    pub name: String,
+    pub version: Option<String>,
+    pub path: PathBuf,
+    pub bins: Vec<String>,
+}
+
+impl GlobalInstallArgs {
+    /// Root of the global prefix.
+    pub fn prefix(&self) -> Result<&Path> {
+        self.global_prefix
+            .as_deref()
+            .ok_or_else(|| OroError::MissingGlobalPrefix.into())
+    }
+
+    /// Directory where global package bins are linked.
+    pub fn bin_dir(&self) -> Result<PathBuf> {
+        Ok(self.prefix()?.join("bin"))
+    }
+
+    /// Directory containing the individual global package installations.
+    pub fn packages_dir(&self) -> Result<PathBuf> {
+        Ok(self.prefix()?.join("packages"))
+    }
+
+    /// Project root for the isolated installation of a single global
+    /// package.
+    pub fn package_root(&self, name: &str) -> Result<PathBuf> {
+        Ok(self.packages_dir()?.join(name))
+    }
+
+    /// Directory the global package itself is extracted to, inside its
+    /// isolated installation.
+    pub fn package_dir(&self, name: &str) -> Result<PathBuf> {
+        Ok(self.package_root(name)?.join("node_modules").join(name))
+    }
+
+    /// Lists all packages currently installed under the global prefix.
+    pub fn installed(&self) -> Result<Vec<GlobalPackage>> {
+        let packages_dir = self.packages_dir()?;
+        let mut names = Vec::new();
+        if let Ok(readdir) = packages_dir.read_dir() {
+            for entry in readdir {
+                let entry = entry.into_diagnostic()?;
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                if !entry.file_type().into_diagnostic()?.is_dir() {
+                    continue;
+                }
+                if file_name.starts_with('@') {
+                    for scoped in entry.path().read_dir().into_diagnostic()? {
+                        let scoped = scoped.into_diagnostic()?;
+                        names.push(format!(
+                            "{file_name}/{}",
+                            scoped.file_name().to_string_lossy()
+                        ));
+                    }
+                } else {
+                    names.push(file_name);
+                }
+            }
+        }
+        names.sort();
+
+        let mut packages = Vec::with_capacity(names.len());
+        for name in names {
+            let path = self.package_dir(&name)?;
+            let manifest = std::fs::read_to_string(path.join("package.json"))
+                .ok()
+                .and_then(|s| serde_json::from_str::<CorgiManifest>(&s).ok());
+            let mut bins = oro_common::BuildManifest::from_path(path.join("package.json"))
+                .map(|m| m.bin.into_keys().collect::<Vec<_>>())
+                .unwrap_or_default();
+            bins.sort();
+            packages.push(GlobalPackage {
+                version: manifest.and_then(|m| m.version).map(|v| v.to_string()),
+                name,
+                path,
+                bins,
+            });
+        }
+        Ok(packages)
+    }
+
+    /// Warns the user if the global bin directory isn't in their `$PATH`.
+    pub fn check_path(&self) -> Result<()> {
+        let bin_dir = self.bin_dir()?;
+        let in_path = std::env::var_os("PATH")
+            .map(|path| std::env::split_paths(&path).any(|p| p == bin_dir))
+            .unwrap_or(false);
+        if !in_path {
+            tracing::warn!(
+                "{} is not in your PATH. Add it to be able to run globally-installed bins.",
+                bin_dir.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Removes the isolated installation for a global package, including
+    /// its bins. Returns `false` if the package was not installed.
+    pub fn uninstall(&self, name: &str) -> Result<bool> {
+        let root = self.package_root(name)?;
+        if !root.exists() {
+            return Ok(false);
+        }
+        let package_dir = self.package_dir(name)?;
+        if package_dir.join("package.json").exists() {
+            let unlinked = node_maintainer::unlink_package_bins(&package_dir, &self.bin_dir()?)?;
+            for bin in unlinked {
+                tracing::debug!("Removed global bin `{bin}` for {name}.");
+            }
+        }
+        std::fs::remove_dir_all(&root)
+            .into_diagnostic()
+            .wrap_err_with(|| format!("Failed to remove {}", root.display()))?;
+        // Clean up empty scope directories.
+        if let Some(parent) = root.parent() {
+            if parent != self.packages_dir()?
+                && parent
+                    .read_dir()
+                    .map(|mut d| d.next().is_none())
+                    .unwrap_or(false)
+            {
+                std::fs::remove_dir(parent).into_diagnostic()?;
+            }
+        }
+        Ok(true)
+    }
+}
@@ -98,7 +98,7 @@ use std::{
 use async_trait::async_trait;
 use clap::{Args, Command, CommandFactory, FromArgMatches as _, Parser, Subcommand};
 use dialoguer::{theme::ColorfulTheme, Confirm};
-use directories::ProjectDirs;
+use directories::{BaseDirs, ProjectDirs};
 use is_terminal::IsTerminal;
 use kdl::{KdlDocument, KdlNode, KdlValue};
 use miette::{IntoDiagnostic, Result};
@@ -107,23 +107,31 @@ use tracing_appender::non_blocking::WorkerGuard;
 use tracing_indicatif::IndicatifLayer;
 use tracing_subscriber::{
     filter::{Directive, LevelFilter, Targets},
-    fmt,
+    fmt::{self, writer::BoxMakeWriter},
     prelude::*,
     EnvFilter,
 };
 use url::Url;
 
 use commands::OroCommand;
+use credential_args::CredentialStoreKind;
 
 pub use error::OroError;
 
 mod apply_args;
+mod apply_events;
 mod client_args;
 mod commands;
+mod credential_args;
 mod error;
+mod global_install_args;
+mod license_policy;
 mod nassun_args;
+mod registry_args;
 
 const MAX_RETAINED_LOGS: usize = 5;
+/// Name of the directory inside the cache that logs are written to.
+const LOGS_DIR_NAME: &str = "_logs";
 
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
@@ -166,6 +174,23 @@ pub struct Orogene {
     )]
     scoped_registries: Vec<(String, Url)>,
 
+    /// Mirror registry to fall back to when a registry is unavailable, in
+    /// `--registry-mirror https://mirror.com` format for the default
+    /// registry, or `--registry-mirror @scope=https://mirror.com` for a
+    /// scoped registry.
+    ///
+    /// Can be provided multiple times. Mirrors are tried in the order they
+    /// were given whenever the registry before them returns a 5xx response
+    /// or times out.
+    #[arg(
+        help_heading = "Global Options",
+        global = true,
+        alias = "registry-mirrors",
+        long = "registry-mirror",
+        value_parser = parse_registry_mirror
+    )]
+    registry_mirrors: Vec<(Option<String>, Url)>,
+
     /// Credentials to apply to registries when they're accessed. You can
     /// provide credentials for multiple registries at a time, and different
     /// credential fields for a registry.
@@ -182,12 +207,62 @@ pub struct Orogene {
     )]
     auth: Vec<(String, String, String)>,
 
+    /// Where `oro login` saves registry credentials, and where they're
+    /// looked up when they aren't given through `--auth` or oro.kdl.
+    ///
+    /// `encrypted` keeps them in a passphrase-encrypted file (see
+    /// `--credentials-file`). The passphrase is read from the
+    /// `ORO_CREDENTIALS_PASSPHRASE` environment variable, or prompted for.
+    /// `helper` delegates to the command given with `--credential-helper`.
+    #[arg(
+        help_heading = "Global Options",
+        global = true,
+        long,
+        value_enum,
+        default_value_t = CredentialStoreKind::Config
+    )]
+    credential_store: CredentialStoreKind,
+
+    /// Command used to get, store, and erase registry credentials when
+    /// `--credential-store helper` is used.
+    ///
+    /// The command is run with `get`, `store`, or `erase` and the registry
+    /// (in `//host/path/` form) appended to it, and exchanges credentials as
+    /// JSON objects with `token`, `legacy-auth`, or `username` and
+    /// `password` fields.
+    #[arg(help_heading = "Global Options", global = true, long)]
+    credential_helper: Option<String>,
+
+    /// Location of the encrypted credentials file used by
+    /// `--credential-store encrypted`.
+    ///
+    /// Default location varies by platform.
+    #[arg(help_heading = "Global Options", global = true, long)]
+    credentials_file: Option<PathBuf>,
+
     /// Location of disk cache.
     ///
     /// Default location varies by platform.
     #[arg(help_heading = "Global Options", global = true, long)]
     cache: Option<PathBuf>,
 
+    /// Location of a package store shared by isolated installs across
+    /// projects.
+    ///
+    /// When set, isolated installs link into this store instead of keeping
+    /// their own copy of every package in `node_modules/.oro-store`. It
+    /// should be on the same filesystem as your projects. Use `oro store
+    /// prune` to remove packages no project uses anymore.
+    #[arg(help_heading = "Global Options", global = true, long)]
+    store: Option<PathBuf>,
+
+    /// Location where globally-installed packages (`oro add -g`) and their
+    /// bins are kept.
+    ///
+    /// Default location varies by platform.
+    #[arg(help_heading = "Global Options", global = true, long)]
+    global_prefix: Option<PathBuf>,
+
     /// File to read configuration values from.
     ///
     /// When specified, global configuration loading is disabled and
@@ -195,6 +270,20 @@ pub struct Orogene {
     #[arg(help_heading = "Global Options", global = true, long)]
     config: Option<PathBuf>,
 
+    /// Don't read registry and credential settings from `.npmrc` files.
+    ///
+    /// By default, `registry`, `@scope:registry`, and auth settings are read
+    /// from the project's `.npmrc` and from `~/.npmrc` (or
+    /// `$NPM_CONFIG_USERCONFIG`). Settings from `oro.kdl` always take
+    /// precedence over `.npmrc` files at the same level.
+    #[arg(
+        help_heading = "Global Options",
+        global = true,
+        long = "no-npmrc",
+        action = clap::ArgAction::SetFalse,
+    )]
+    npmrc: bool,
+
     /// Log output level/directive.
     ///
     /// Supports plain loglevels (off, error, warn, info, debug, trace) as
@@ -365,6 +454,7 @@ impl Orogene {
                     .with(
                         tracing_subscriber::fmt::layer()
                             .without_time()
+                            .with_writer(self.log_writer())
                             .with_target(false)
                             .with_filter(filter),
                     )
@@ -405,6 +495,7 @@ impl Orogene {
                     .with(
                         tracing_subscriber::fmt::layer()
                             .without_time()
+                            .with_writer(self.log_writer())
                             .with_target(false)
                             .with_filter(filter),
                     )
@@ -425,32 +516,25 @@ impl Orogene {
         }
     }
 
-    fn build_config(&self) -> Result<OroConfig> {
-        let dirs = ProjectDirs::from("", "", "orogene");
-        let cwd = std::env::current_dir().into_diagnostic()?;
-        let root = if let Some(root) = pkg_root(&cwd) {
-            root
+    /// Plain (non-progress-bar) log output normally goes to stdout, but
+    /// `--json` reserves stdout for machine-readable output.
+    fn log_writer(&self) -> BoxMakeWriter {
+        if self.json {
+            BoxMakeWriter::new(std::io::stderr)
         } else {
-            &cwd
-        };
-
-        let mut cfg_builder = OroConfigOptions::new()
-            .set_default("root", &root.to_string_lossy())?
-            .env(true);
-        if let Some(cache) = dirs.as_ref().map(|d| d.cache_dir().to_owned()) {
-            cfg_builder = cfg_builder.set_default("cache", &cache.to_string_lossy())?;
+            BoxMakeWriter::new(std::io::stdout)
         }
+    }
 
-        let cfg = if let Some(file) = &self.config {
-            cfg_builder.global_config_file(Some(file.clone())).load()?
+    fn build_config(&self) -> Result<OroConfig> {
+        let options = config_options(&self.root, self.config.as_deref(), self.npmrc)?;
+        if matches!(self.subcommand, OroCmd::Config(_)) {
+            // `oro config` needs to work even when the config is invalid, so
+            // it can be used to fix it.
+            options.load()
         } else {
-            cfg_builder
-                .global_config_file(dirs.map(|d| d.config_dir().to_owned().join("oro.kdl")))
-                .pkg_root(Some(self.root.clone()))
-                .load()?
-        };
-
-        Ok(cfg)
+            options.validate_against(Self::command()).load()
+        }
     }
 
     fn current_command() -> Command {
@@ -497,14 +581,25 @@ impl Orogene {
         }
 
         // Then, we find the subcommand starting from our toplevel Command.
-        let mut command = command.clone();
-        let mut subcmd = &mut command;
-        subcmd.layered_args(args, config)?;
-        while let Some(name) = subcmd_path.pop_front() {
-            subcmd = subcmd
-                .find_subcommand_mut(name)
-                .expect("This should definitely exist?");
-            subcmd.layered_args(args, config)?;
+        let mut commands = vec![command.clone()];
+        for name in &subcmd_path {
+            let subcmd = commands
+                .last()
+                .expect("starts with the toplevel command")
+                .find_subcommand(name)
+                .expect("This should definitely exist?")
+                .clone();
+            commands.push(subcmd);
+        }
+
+        // Options from per-subcommand blocks, like `apply { ... }`, take
+        // precedence over toplevel ones, and more deeply nested blocks take
+        // precedence over their parents. Whichever gets layered first wins.
+        let subcmd_path = subcmd_path.into_iter().collect::<Vec<_>>();
+        for depth in (0..=subcmd_path.len()).rev() {
+            for command in &commands {
+                command.layered_section_args(args, config, &subcmd_path[..depth])?;
+            }
         }
         Ok(())
     }
@@ -697,7 +792,7 @@ impl Orogene {
             .cache
             .clone()
             .or_else(|| config.get::<String>("cache").ok().map(PathBuf::from))
-            .map(|c| c.join("_logs").join(log_file_name()));
+            .map(|c| c.join(LOGS_DIR_NAME).join(log_file_name()));
         let _logging_guard = oro.setup_logging(log_file.as_deref())?;
         oro.first_time_setup()?;
         let _telemetry_guard = oro.setup_telemetry(log_file.clone())?;
@@ -744,6 +839,46 @@ impl Orogene {
     }
 }
 
+/// Sets up the configuration sources Orogene loads its options from. `root`
+/// and `config` are the `--root` and `--config` options.
+pub(crate) fn config_options(
+    root: &Path,
+    config: Option<&Path>,
+    npmrc: bool,
+) -> Result<OroConfigOptions> {
+    let dirs = ProjectDirs::from("", "", "orogene");
+    let cwd = std::env::current_dir().into_diagnostic()?;
+    let default_root = if let Some(root) = pkg_root(&cwd) {
+        root
+    } else {
+        &cwd
+    };
+
+    let mut cfg_builder = OroConfigOptions::new()
+        .set_default("root", &default_root.to_string_lossy())?
+        .env(true)
+        .npmrc(npmrc);
+    if let Some(cache) = dirs.as_ref().map(|d| d.cache_dir().to_owned()) {
+        cfg_builder = cfg_builder.set_default("cache", &cache.to_string_lossy())?;
+    }
+    if let Some(prefix) = dirs.as_ref().map(|d| d.data_dir().join("global")) {
+        cfg_builder = cfg_builder.set_default("global-prefix", &prefix.to_string_lossy())?;
+    }
+
+    Ok(if let Some(file) = config {
+        cfg_builder.global_config_file(Some(file.to_owned()))
+    } else {
+        cfg_builder
+            .global_config_file(dirs.map(|d| d.config_dir().to_owned().join("oro.kdl")))
+            .user_npmrc_file(
+                std::env::var_os("NPM_CONFIG_USERCONFIG")
+                    .map(PathBuf::from)
+                    .or_else(|| BaseDirs::new().map(|d| d.home_dir().join(".npmrc"))),
+            )
+            .pkg_root(Some(root.to_owned()))
+    })
+}
+
 fn pkg_root(start_dir: &Path) -> Option<&Path> {
     for path in start_dir.ancestors() {
         let node_modules = path.join("node_modules");
@@ -772,7 +907,11 @@ fn log_file_name() -> PathBuf {
 
 fn clean_old_logs(logs_dir: &Path) -> Result<()> {
     if let Ok(readdir) = logs_dir.read_dir() {
-        let mut logs = readdir.filter_map(|e| e.ok()).collect::<Vec<_>>();
+        // Skip over directories, like the one script logs go in.
+        let mut logs = readdir
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .collect::<Vec<_>>();
         logs.sort_by_key(|e| e.file_name());
         while logs.len() >= MAX_RETAINED_LOGS {
             let log = logs.remove(0);
@@ -811,6 +950,17 @@ where
     Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
 }
 
+fn parse_registry_mirror(
+    s: &str,
+) -> Result<(Option<String>, Url), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    if s.starts_with('@') {
+        let (scope, mirror) = parse_key_value::<String, Url>(s)?;
+        Ok((Some(scope), mirror))
+    } else {
+        Ok((None, s.parse()?))
+    }
+}
+
 fn parse_nested_key_value<T, U, V>(
     s: &str,
 ) -> Result<(T, U, V), Box<dyn std::error::Error + Send + Sync + 'static>>
@@ -845,18 +995,58 @@ pub enum OroCmd {
 
     Apply(commands::apply::ApplyCmd),
 
+    Audit(commands::audit::AuditCmd),
+
+    Config(commands::config::ConfigCmd),
+
+    Dedupe(commands::dedupe::DedupeCmd),
+
+    Deprecate(commands::deprecate::DeprecateCmd),
+
+    Diff(commands::diff::DiffCmd),
+
+    DistTag(commands::dist_tag::DistTagCmd),
+
+    Licenses(commands::licenses::LicensesCmd),
+
+    Link(commands::link::LinkCmd),
+
     Login(commands::login::LoginCmd),
 
     Logout(commands::logout::LogoutCmd),
 
+    Logs(commands::logs::LogsCmd),
+
+    Ls(commands::ls::LsCmd),
+
+    Owner(commands::owner::OwnerCmd),
+
+    Patch(commands::patch::PatchCmd),
+
+    PatchCommit(commands::patch_commit::PatchCommitCmd),
+
     Ping(commands::ping::PingCmd),
 
     Reapply(commands::reapply::ReapplyCmd),
 
+    Rebuild(commands::rebuild::RebuildCmd),
+
     Remove(commands::remove::RemoveCmd),
 
+    Sbom(commands::sbom::SbomCmd),
+
+    Search(commands::search::SearchCmd),
+
+    Store(commands::store::StoreCmd),
+
+    Token(commands::token::TokenCmd),
+
+    Unlink(commands::unlink::UnlinkCmd),
+
     View(commands::view::ViewCmd),
 
+    Whoami(commands::whoami::WhoamiCmd),
+
     #[clap(hide = true)]
     HelpMarkdown(HelpMarkdownCmd),
 }
@@ -868,12 +1058,32 @@ impl OroCommand for Orogene {
         match self.subcommand {
             OroCmd::Add(cmd) => cmd.execute().await,
             OroCmd::Apply(cmd) => cmd.execute().await,
+            OroCmd::Audit(cmd) => cmd.execute().await,
+            OroCmd::Config(cmd) => cmd.execute().await,
+            OroCmd::Dedupe(cmd) => cmd.execute().await,
+            OroCmd::Deprecate(cmd) => cmd.execute().await,
+            OroCmd::Diff(cmd) => cmd.execute().await,
+            OroCmd::DistTag(cmd) => cmd.execute().await,
+            OroCmd::Licenses(cmd) => cmd.execute().await,
+            OroCmd::Link(cmd) => cmd.execute().await,
             OroCmd::Login(cmd) => cmd.execute().await,
             OroCmd::Logout(cmd) => cmd.execute().await,
+            OroCmd::Logs(cmd) => cmd.execute().await,
+            OroCmd::Ls(cmd) => cmd.execute().await,
+            OroCmd::Owner(cmd) => cmd.execute().await,
+            OroCmd::Patch(cmd) => cmd.execute().await,
+            OroCmd::PatchCommit(cmd) => cmd.execute().await,
             OroCmd::Ping(cmd) => cmd.execute().await,
             OroCmd::Reapply(cmd) => cmd.execute().await,
+            OroCmd::Rebuild(cmd) => cmd.execute().await,
             OroCmd::Remove(cmd) => cmd.execute().await,
+            OroCmd::Sbom(cmd) => cmd.execute().await,
+            OroCmd::Search(cmd) => cmd.execute().await,
+            OroCmd::Store(cmd) => cmd.execute().await,
+            OroCmd::Token(cmd) => cmd.execute().await,
+            OroCmd::Unlink(cmd) => cmd.execute().await,
             OroCmd::View(cmd) => cmd.execute().await,
+            OroCmd::Whoami(cmd) => cmd.execute().await,
             OroCmd::HelpMarkdown(cmd) => cmd.execute().await,
         }
     }
@@ -0,0 +1,316 @@
+use clap::Args;
+use miette::Result;
+use node_maintainer::{LicenseExpr, Sbom, SbomComponent};
+use node_semver::{Range, Version};
+
+use crate::error::OroError;
+
+/// License policy options, shared by `oro licenses` and `oro apply`. In
+/// `oro.kdl`, these go in a `license-policy` block inside `options`, with a
+/// node per rule kind:
+///
+/// ```kdl
+/// options {
+///     license-policy {
+///         allow "MIT" "ISC"
+///         deny "GPL-3.0-only"
+///         exception "some-pkg@^1.0.0"
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Args)]
+pub struct LicensePolicyArgs {
+    /// License policy rule, as `allow=<SPDX ID>`, `deny=<SPDX ID>`, or
+    /// `exception=<name>[@<semver range>]`.
+    ///
+    /// Can be provided multiple times. When any licenses are allowed, every
+    /// package must have a license expression that can be satisfied using
+    /// only allowed licenses. Packages offering a choice of licenses (`MIT
+    /// OR GPL-3.0-only`) are accepted as long as one of the choices isn't
+    /// denied. Exceptions are exempt from the policy entirely.
+    #[arg(long, value_name = "RULE", value_parser = LicenseRule::parse)]
+    pub license_policy: Vec<LicenseRule>,
+}
+
+/// A single `--license-policy` rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LicenseRule {
+    Allow(String),
+    Deny(String),
+    Exception(String),
+}
+
+impl LicenseRule {
+    fn parse(rule: &str) -> Result<Self, String> {
+        match rule.split_once('=') {
+            Some(("allow", license)) => Ok(Self::Allow(license.into())),
+            Some(("deny", license)) => Ok(Self::Deny(license.into())),
+            Some(("exception", package)) => Ok(Self::Exception(package.into())),
+            _ => {
+                Err("expected `allow=<license>`, `deny=<license>`, or `exception=<package>`".into())
+            }
+        }
+    }
+}
+
+/// A compiled license policy.
+#[derive(Debug, Default)]
+pub struct LicensePolicy {
+    allowed: Vec<String>,
+    denied: Vec<String>,
+    exceptions: Vec<(String, Option<Range>)>,
+}
+
+/// A package that doesn't satisfy a [`LicensePolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseViolation {
+    pub package: String,
+    pub path: String,
+    pub license: Option<String>,
+    pub reason: String,
+}
+
+impl LicensePolicy {
+    pub fn from_args(args: &LicensePolicyArgs) -> Result<Self> {
+        let mut policy = Self::default();
+        for rule in &args.license_policy {
+            match rule {
+                LicenseRule::Allow(license) => policy.allowed.push(license.clone()),
+                LicenseRule::Deny(license) => policy.denied.push(license.clone()),
+                LicenseRule::Exception(exception) => {
+                    policy.exceptions.push(parse_exception(exception)?)
+                }
+            }
+        }
+        Ok(policy)
+    }
+
+    /// Whether this policy has any allowed or denied licenses to enforce.
+    pub fn is_empty(&self) -> bool {
+        self.allowed.is_empty() && self.denied.is_empty()
+    }
+
+    /// Checks every package in `sbom` against this policy, returning the
+    /// ones that don't satisfy it.
+    pub fn check(&self, sbom: &Sbom) -> Vec<LicenseViolation> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        sbom.components
+            .iter()
+            .filter(|component| !self.is_exception(component))
+            .filter_map(|component| {
+                let reason = self.check_license(component.license.as_deref())?;
+                Some(LicenseViolation {
+                    package: match &component.version {
+                        Some(version) => format!("{}@{version}", component.name),
+                        None => component.name.clone(),
+                    },
+                    path: component.bom_ref.clone(),
+                    license: component.license.clone(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+
+    /// Like [`LicensePolicy::check`], but returns an error describing every
+    /// violation, if there were any.
+    pub fn enforce(&self, sbom: &Sbom) -> Result<()> {
+        fail_on_violations(self.check(sbom))
+    }
+
+    fn is_exception(&self, component: &SbomComponent) -> bool {
+        self.exceptions.iter().any(|(name, range)| {
+            name == &component.name
+                && match range {
+                    None => true,
+                    Some(range) => component
+                        .version
+                        .as_deref()
+                        .and_then(|version| Version::parse(version).ok())
+                        .map(|version| range.satisfies(&version))
+                        .unwrap_or(false),
+                }
+        })
+    }
+
+    /// Returns why `license` violates this policy, or `None` if it doesn't.
+    fn check_license(&self, license: Option<&str>) -> Option<String> {
+        let Some(license) = license else {
+            return (!self.allowed.is_empty()).then(|| "no license declared".into());
+        };
+        let Some(expr) = LicenseExpr::parse(license) else {
+            return (!self.allowed.is_empty())
+                .then(|| format!("`{license}` is not a valid SPDX license expression"));
+        };
+        if self.satisfies(&expr) {
+            return None;
+        }
+        let mut denied = Vec::new();
+        expr.collect_ids(&mut denied);
+        denied.retain(|id| matches_any(&self.denied, id));
+        Some(if denied.is_empty() {
+            format!("`{license}` is not an allowed license")
+        } else {
+            format!("`{license}` uses denied license {}", denied.join(", "))
+        })
+    }
+
+    fn satisfies(&self, expr: &LicenseExpr) -> bool {
+        match expr {
+            LicenseExpr::License(id) => {
+                !matches_any(&self.denied, id)
+                    && (self.allowed.is_empty() || matches_any(&self.allowed, id))
+            }
+            LicenseExpr::And(a, b) => self.satisfies(a) && self.satisfies(b),
+            LicenseExpr::Or(a, b) => self.satisfies(a) || self.satisfies(b),
+        }
+    }
+}
+
+/// Parses a `name` or `name@<semver range>` license exception.
+fn parse_exception(exception: &str) -> Result<(String, Option<Range>), OroError> {
+    // Skip a leading `@`, so scoped names don't get split up.
+    let (scope, rest) = match exception.strip_prefix('@') {
+        Some(rest) => ("@", rest),
+        None => ("", exception),
+    };
+    match rest.rsplit_once('@') {
+        Some((name, range)) => Range::parse(range)
+            .map(|range| (format!("{scope}{name}"), Some(range)))
+            .map_err(|_| OroError::InvalidLicenseException(exception.into())),
+        None => Ok((exception.into(), None)),
+    }
+}
+
+/// Turns a list of license policy violations into a single error, if there
+/// were any.
+pub fn fail_on_violations(violations: Vec<LicenseViolation>) -> Result<()> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(OroError::LicensePolicyFailed {
+            count: violations.len(),
+            violations: violations.into_iter().map(Into::into).collect(),
+        }
+        .into())
+    }
+}
+
+impl From<LicenseViolation> for OroError {
+    fn from(violation: LicenseViolation) -> Self {
+        OroError::LicenseViolation {
+            package: violation.package,
+            path: violation.path,
+            reason: violation.reason,
+        }
+    }
+}
+
+/// Whether license `id` (possibly with a `WITH` exception or `+` suffix) is
+/// in `list`. Entries in `list` match either the full ID, or the license
+/// without its exception or `+`.
+fn matches_any(list: &[String], id: &str) -> bool {
+    let base = id
+        .split_once(" WITH ")
+        .map(|(base, _)| base)
+        .unwrap_or(id)
+        .trim_end_matches('+');
+    list.iter()
+        .any(|entry| entry.eq_ignore_ascii_case(id) || entry.eq_ignore_ascii_case(base))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowed: &[&str], denied: &[&str], exceptions: &[&str]) -> LicensePolicy {
+        let rules = |list: &[&str], kind: &str| {
+            list.iter()
+                .map(|s| LicenseRule::parse(&format!("{kind}={s}")).unwrap())
+                .collect::<Vec<_>>()
+        };
+        LicensePolicy::from_args(&LicensePolicyArgs {
+            license_policy: [
+                rules(allowed, "allow"),
+                rules(denied, "deny"),
+                rules(exceptions, "exception"),
+            ]
+            .concat(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(
+            LicenseRule::parse("allow=MIT"),
+            Ok(LicenseRule::Allow("MIT".into()))
+        );
+        assert_eq!(
+            LicenseRule::parse("exception=@scope/a@^1"),
+            Ok(LicenseRule::Exception("@scope/a@^1".into()))
+        );
+        assert!(LicenseRule::parse("MIT").is_err());
+        assert!(LicenseRule::parse("permit=MIT").is_err());
+    }
+
+    #[test]
+    fn checks_licenses() {
+        let allow = policy(&["MIT", "Apache-2.0", "GPL-2.0-only"], &[], &[]);
+        assert_eq!(allow.check_license(Some("MIT")), None);
+        assert_eq!(allow.check_license(Some("mit")), None);
+        assert_eq!(allow.check_license(Some("(MIT OR GPL-3.0-only)")), None);
+        assert_eq!(
+            allow.check_license(Some("GPL-2.0-only WITH Classpath-exception-2.0")),
+            None
+        );
+        assert_eq!(
+            allow.check_license(Some("MIT AND ISC")),
+            Some("`MIT AND ISC` is not an allowed license".into())
+        );
+        assert_eq!(
+            allow.check_license(None),
+            Some("no license declared".into())
+        );
+        assert_eq!(
+            allow.check_license(Some("SEE LICENSE IN LICENSE")),
+            Some("`SEE LICENSE IN LICENSE` is not a valid SPDX license expression".into())
+        );
+        assert_eq!(
+            allow.check_license(Some("UNLICENSED")),
+            Some("`UNLICENSED` is not a valid SPDX license expression".into())
+        );
+
+        let deny = policy(&[], &["GPL-3.0-only"], &[]);
+        assert_eq!(deny.check_license(Some("ISC")), None);
+        assert_eq!(deny.check_license(None), None);
+        assert_eq!(deny.check_license(Some("MIT OR GPL-3.0-only")), None);
+        assert_eq!(
+            deny.check_license(Some("MIT AND GPL-3.0-only")),
+            Some("`MIT AND GPL-3.0-only` uses denied license GPL-3.0-only".into())
+        );
+    }
+
+    #[test]
+    fn exceptions() {
+        let component = |name: &str, version: &str| SbomComponent {
+            bom_ref: format!("node_modules/{name}"),
+            name: name.into(),
+            version: Some(version.into()),
+            purl: None,
+            download_url: None,
+            integrity: None,
+            license: Some("GPL-3.0-only".into()),
+            author: None,
+            repository: None,
+            dependencies: Vec::new(),
+        };
+        let policy = policy(&["MIT"], &[], &["a", "@scope/b@^1"]);
+        assert!(policy.is_exception(&component("a", "1.0.0")));
+        assert!(policy.is_exception(&component("@scope/b", "1.2.0")));
+        assert!(!policy.is_exception(&component("@scope/b", "2.0.0")));
+        assert!(!policy.is_exception(&component("c", "1.0.0")));
+    }
+}
@@ -1,12 +1,14 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
+use chrono::{DateTime, NaiveDate, Utc};
 use clap::Args;
 use miette::Result;
 use nassun::{Nassun, NassunOpts};
 use oro_client::OroClientBuilder;
 use url::Url;
 
-use crate::{apply_args::ApplyArgs, client_args::ClientArgs};
+use crate::{apply_args::ApplyArgs, client_args::ClientArgs, credential_args::CredentialArgs};
 
 #[derive(Clone, Debug, Args)]
 pub struct NassunArgs {
@@ -14,12 +16,31 @@ pub struct NassunArgs {
     #[arg(long, default_value = "latest")]
     pub default_tag: String,
 
+    /// Only resolve to package versions published at or before this date,
+    /// as if resolving at that point in the past.
+    ///
+    /// Accepts an RFC 3339 timestamp (`2023-04-01T12:00:00Z`) or a plain
+    /// date (`2023-04-01`, meaning midnight UTC).
+    #[arg(long, value_parser = parse_before)]
+    pub before: Option<DateTime<Utc>>,
+
+    /// Skip package versions published more recently than this, falling
+    /// back to older versions that satisfy the requested spec.
+    ///
+    /// Accepts a number followed by a unit: `s`, `m`, `h`, `d`, or `w`
+    /// (for example, `3d`). Plain numbers are treated as minutes.
+    #[arg(long, value_parser = parse_release_age)]
+    pub minimum_release_age: Option<Duration>,
+
     #[arg(from_global)]
     pub registry: Url,
 
     #[arg(from_global)]
     pub scoped_registries: Vec<(String, Url)>,
 
+    #[arg(from_global)]
+    pub registry_mirrors: Vec<(Option<String>, Url)>,
+
     #[arg(from_global)]
     pub root: PathBuf,
 
@@ -40,14 +61,20 @@ pub struct NassunArgs {
 
     #[arg(from_global)]
     pub auth: Vec<(String, String, String)>,
+
+    #[command(flatten)]
+    pub credentials: CredentialArgs,
 }
 
 impl NassunArgs {
     pub fn from_apply_args(apply_args: &ApplyArgs) -> Self {
         Self {
             default_tag: apply_args.default_tag.clone(),
+            before: apply_args.before,
+            minimum_release_age: apply_args.minimum_release_age,
             registry: apply_args.registry.clone(),
             scoped_registries: apply_args.scoped_registries.clone(),
+            registry_mirrors: apply_args.registry_mirrors.clone(),
             root: apply_args.root.clone(),
             cache: apply_args.cache.clone(),
             proxy: apply_args.proxy,
@@ -55,12 +82,17 @@ impl NassunArgs {
             no_proxy_domain: apply_args.no_proxy_domain.clone(),
             retries: apply_args.retries,
             auth: apply_args.auth.clone(),
+            credentials: apply_args.credentials.clone(),
         }
     }
 
     pub fn to_nassun(&self) -> Result<Nassun> {
         let client_args: ClientArgs = ((*self).clone()).into();
-        let client_builder: OroClientBuilder = client_args.try_into()?;
+        let registries = std::iter::once(&self.registry)
+            .chain(self.scoped_registries.iter().map(|(_, registry)| registry))
+            .chain(self.registry_mirrors.iter().map(|(_, mirror)| mirror));
+        let client_builder: OroClientBuilder =
+            client_args.with_stored_credentials(registries).try_into()?;
         let mut nassun_opts = NassunOpts::new()
             .registry(self.registry.clone())
             .base_dir(self.root.clone())
@@ -69,9 +101,90 @@ impl NassunArgs {
         for (scope, registry) in &self.scoped_registries {
             nassun_opts = nassun_opts.scope_registry(scope.clone(), registry.clone());
         }
+        for (scope, mirror) in &self.registry_mirrors {
+            nassun_opts = if let Some(scope) = scope {
+                nassun_opts.scope_mirror(scope, mirror.clone())
+            } else {
+                nassun_opts.mirror(mirror.clone())
+            };
+        }
         if let Some(cache) = &self.cache {
             nassun_opts = nassun_opts.cache(cache.clone());
         }
+        if let Some(before) = self.before {
+            nassun_opts = nassun_opts.before(before);
+        }
+        if let Some(age) = self.minimum_release_age {
+            nassun_opts = nassun_opts.minimum_release_age(age);
+        }
         Ok(nassun_opts.build())
     }
 }
+
+pub(crate) fn parse_before(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(s) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc())
+        .ok_or_else(|| {
+            format!("invalid date `{s}`: expected an RFC 3339 timestamp or a YYYY-MM-DD date")
+        })
+}
+
+pub(crate) fn parse_release_age(s: &str) -> Result<Duration, String> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (amount, unit) = s.split_at(split);
+    let invalid = || format!("invalid duration `{s}`: expected something like `3d`");
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "" | "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => {
+            return Err(format!(
+                "invalid duration unit `{unit}`: expected one of `s`, `m`, `h`, `d`, or `w`"
+            ))
+        }
+    };
+    amount
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_release_cutoffs() {
+        assert_eq!(
+            parse_before("2023-04-01").unwrap().to_rfc3339(),
+            "2023-04-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_before("2023-04-01T12:00:00-02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2023-04-01T14:00:00+00:00"
+        );
+        assert!(parse_before("last tuesday").is_err());
+
+        assert_eq!(
+            parse_release_age("90").unwrap(),
+            Duration::from_secs(90 * 60)
+        );
+        assert_eq!(
+            parse_release_age("3d").unwrap(),
+            Duration::from_secs(3 * 24 * 60 * 60)
+        );
+        assert!(parse_release_age("3y").is_err());
+        assert!(parse_release_age("d").is_err());
+        assert!(parse_release_age(&format!("{}w", u64::MAX / 60)).is_err());
+    }
+}
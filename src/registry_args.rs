@@ -0,0 +1,54 @@
+use clap::Args;
+use miette::Result;
+use oro_client::{OroClient, OroClientBuilder};
+use oro_package_spec::{PackageSpec, VersionSpec};
+use url::Url;
+
+use crate::client_args::ClientArgs;
+use crate::OroError;
+
+/// Arguments for commands that manage accounts and packages directly on a
+/// registry.
+#[derive(Debug, Args)]
+pub struct RegistryArgs {
+    #[arg(from_global)]
+    pub registry: Url,
+
+    #[arg(from_global)]
+    pub scoped_registries: Vec<(String, Url)>,
+
+    #[command(flatten)]
+    pub client_args: ClientArgs,
+}
+
+impl RegistryArgs {
+    /// Builds a client for the registry `package_name` is published to, or
+    /// for the default registry if no package is given.
+    pub fn client(self, package_name: Option<&str>) -> Result<OroClient> {
+        let registry = package_name
+            .and_then(|name| name.strip_prefix('@'))
+            .and_then(|name| name.split_once('/'))
+            .and_then(|(scope, _)| {
+                self.scoped_registries
+                    .iter()
+                    .find(|(s, _)| s.strip_prefix('@').unwrap_or(s) == scope)
+                    .map(|(_, registry)| registry.clone())
+            })
+            .unwrap_or(self.registry);
+        let builder: OroClientBuilder = self
+            .client_args
+            .with_stored_credentials([&registry])
+            .try_into()?;
+        Ok(builder.registry(registry).build())
+    }
+}
+
+/// Parses a `<name>[@<version>]` specifier for a registry package.
+pub fn parse_registry_spec(spec: &str) -> Result<(String, Option<VersionSpec>)> {
+    match spec.parse()? {
+        PackageSpec::Npm {
+            name, requested, ..
+        } => Ok((name, requested)),
+        _ => Err(OroError::NotARegistryPackage(spec.into()).into()),
+    }
+}
@@ -0,0 +1,106 @@
+use serde_json::{json, Value};
+use wiremock::MockServer;
+
+mod common;
+use common::{format_output, mock_package, oro};
+
+#[async_std::test]
+async fn apply_json_is_ndjson_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "a",
+        json!({ "dependencies": { "b": "^1" } }),
+        &[],
+    )
+    .await;
+    mock_package(&mock_server, "b", json!({}), &[]).await;
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(
+        project.join("package.json"),
+        json!({
+            "name": "project",
+            "dependencies": { "a": "^1" },
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let root_arg = project.to_string_lossy();
+    let output = oro(
+        dir.path(),
+        &project,
+        &mock_server,
+        &["apply", "--json", "--root", &root_arg],
+    );
+    assert!(output.status.success(), "{}", format_output(&output));
+
+    // Every line of stdout has to be a JSON event, with nothing else mixed
+    // in.
+    let events = std::str::from_utf8(&output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<Value>(line).unwrap_or_else(|err| {
+                panic!("{line:?} is not JSON ({err})\n{}", format_output(&output))
+            })
+        })
+        .collect::<Vec<_>>();
+    for event in &events {
+        assert_eq!(event["schema"], 1, "{event}");
+        assert!(event["event"].is_string(), "{event}");
+    }
+
+    let names = events
+        .iter()
+        .map(|event| event["event"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names.first(), Some(&"start"), "{names:?}");
+    assert_eq!(names.last(), Some(&"summary"), "{names:?}");
+    assert_eq!(
+        names.iter().filter(|name| **name == "summary").count(),
+        1,
+        "{names:?}"
+    );
+
+    // Phases run one after the other, in a fixed order, and each package
+    // event happens during its own phase.
+    let mut phases = Vec::new();
+    let mut current = None;
+    for event in &events {
+        match event["event"].as_str().unwrap() {
+            "phase_start" => {
+                assert_eq!(current, None, "phases overlap: {names:?}");
+                current = event["phase"].as_str();
+                phases.push(current.unwrap());
+            }
+            "phase_end" => {
+                assert_eq!(current, event["phase"].as_str(), "{names:?}");
+                current = None;
+            }
+            "resolved" => assert_eq!(current, Some("resolve"), "{names:?}"),
+            "extracted" => assert_eq!(current, Some("extract"), "{names:?}"),
+            _ => {}
+        }
+    }
+    assert_eq!(current, None, "unfinished phase: {names:?}");
+    assert_eq!(
+        phases,
+        vec!["resolve", "prune", "extract", "scripts", "lockfile"]
+    );
+
+    let mut resolved = events
+        .iter()
+        .filter(|event| event["event"] == "resolved")
+        .map(|event| event["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    resolved.sort_unstable();
+    assert_eq!(resolved, vec!["a", "b"]);
+    let summary = events.last().unwrap();
+    // The project itself counts as a package too.
+    assert_eq!(summary["packages"], 3);
+    assert_eq!(summary["extracted"], 2);
+    assert_eq!(summary["changes"]["added"], 2);
+}
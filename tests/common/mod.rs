@@ -0,0 +1,91 @@
+//! Helpers shared by the tests that run the `oro` binary against a mock
+//! registry.
+
+// Each test binary only uses some of these.
+#![allow(dead_code)]
+
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use serde_json::{json, Value};
+use ssri::Integrity;
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+pub static BIN: &str = env!("CARGO_BIN_EXE_oro");
+
+/// Runs `oro` in `cwd`, against `mock_server`, with everything it would
+/// otherwise keep in the user's home directory kept in `dir` instead.
+pub fn oro(dir: &Path, cwd: &Path, mock_server: &MockServer, args: &[&str]) -> Output {
+    Command::new(BIN)
+        .current_dir(cwd)
+        .args(args)
+        .arg("--registry")
+        .arg(mock_server.uri())
+        .arg("--global-prefix")
+        .arg(dir.join("global"))
+        .arg("--cache")
+        .arg(dir.join("cache"))
+        .arg("--config")
+        .arg(dir.join("oro.kdl"))
+        .arg("--no-npmrc")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("Failed to execute process")
+}
+
+pub fn format_output(output: &Output) -> String {
+    let stdout = std::str::from_utf8(&output.stdout).unwrap();
+    let stderr = std::str::from_utf8(&output.stderr).unwrap();
+    format!("stderr:\n{stderr}\nstdout:\n{stdout}")
+}
+
+/// Serves version 1.0.0 of `name`, with `manifest` as its package.json,
+/// along with any other `files`.
+pub async fn mock_package(
+    mock_server: &MockServer,
+    name: &str,
+    mut manifest: Value,
+    files: &[(&str, &str)],
+) {
+    manifest["name"] = json!(name);
+    manifest["version"] = json!("1.0.0");
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+        Vec::new(),
+        flate2::Compression::default(),
+    ));
+    let package_json = manifest.to_string();
+    for (path, contents) in
+        std::iter::once(("package.json", package_json.as_str())).chain(files.iter().copied())
+    {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("package/{path}"), contents.as_bytes())
+            .unwrap();
+    }
+    let tarball = builder.into_inner().unwrap().finish().unwrap();
+    let tarball_path = format!("/{name}/-/{name}-1.0.0.tgz");
+    manifest["dist"] = json!({
+        "tarball": format!("{}{tarball_path}", mock_server.uri()),
+        "integrity": Integrity::from(&tarball).to_string(),
+    });
+    Mock::given(method("GET"))
+        .and(path(tarball_path))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(tarball))
+        .mount(mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path(format!("/{name}")))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "versions": { "1.0.0": manifest },
+            "dist-tags": { "latest": "1.0.0" },
+        })))
+        .mount(mock_server)
+        .await;
+}
@@ -0,0 +1,54 @@
+use serde_json::{json, Value};
+use wiremock::MockServer;
+
+mod common;
+use common::{format_output, mock_package, oro};
+
+#[async_std::test]
+async fn fail_on_deprecated_still_emits_events() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "hello",
+        json!({ "deprecated": "use goodbye" }),
+        &[],
+    )
+    .await;
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(
+        project.join("package.json"),
+        json!({
+            "name": "project",
+            "dependencies": { "hello": "^1" },
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let root_arg = project.to_string_lossy();
+    let output = oro(
+        dir.path(),
+        &project,
+        &mock_server,
+        &[
+            "apply",
+            "--fail-on-deprecated",
+            "--json",
+            "--root",
+            &root_arg,
+        ],
+    );
+    assert!(!output.status.success(), "{}", format_output(&output));
+    let deprecated = std::str::from_utf8(&output.stdout)
+        .unwrap()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|event| event["event"] == "deprecated")
+        .collect::<Vec<_>>();
+    assert_eq!(deprecated.len(), 1, "{}", format_output(&output));
+    assert_eq!(deprecated[0]["name"], "hello");
+    assert_eq!(deprecated[0]["reason"], "use goodbye");
+    assert!(!project.join("node_modules").join("hello").exists());
+}
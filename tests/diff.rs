@@ -0,0 +1,61 @@
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+static BIN: &str = env!("CARGO_BIN_EXE_oro");
+
+const OLD: &str = r#"
+lockfile-version 1
+root {
+    dependencies {
+        a "^1.0.0"
+    }
+}
+pkg "a" {
+    version "1.0.0"
+    resolved "a/-/a-1.0.0.tgz"
+    integrity "sha512-aaa"
+}
+"#;
+
+const NEW: &str = r#"
+lockfile-version 1
+root {
+    dependencies {
+        a "^1.0.0"
+    }
+}
+pkg "a" {
+    version "1.1.0"
+    resolved "a/-/a-1.1.0.tgz"
+    integrity "sha512-bbb"
+}
+"#;
+
+#[test]
+fn relative_lockfile_paths_are_relative_to_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join("package.json"), "{}").unwrap();
+    std::fs::write(project.join("old.kdl"), OLD).unwrap();
+    std::fs::write(project.join("new.kdl"), NEW).unwrap();
+
+    let output = Command::new(BIN)
+        .current_dir(dir.path())
+        .args(["diff", "old.kdl", "new.kdl", "--json", "--root"])
+        .arg(&project)
+        .arg("--config")
+        .arg(dir.path().join("oro.kdl"))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .expect("Failed to execute process");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let diff: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(diff["upgraded"][0]["to"]["version"], "1.1.0");
+}
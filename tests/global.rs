@@ -0,0 +1,78 @@
+use serde_json::{json, Value};
+use wiremock::MockServer;
+
+mod common;
+use common::{format_output, mock_package, oro};
+
+#[async_std::test]
+async fn global_add_ls_remove() {
+    let dir = tempfile::tempdir().unwrap();
+    let prefix = dir.path().join("global");
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "hello",
+        json!({ "bin": { "hello": "cli.js" } }),
+        &[("cli.js", "#!/usr/bin/env node\n")],
+    )
+    .await;
+
+    let output = oro(
+        dir.path(),
+        dir.path(),
+        &mock_server,
+        &["add", "--global", "hello@^1"],
+    );
+    assert!(output.status.success(), "{}", format_output(&output));
+    let bin = prefix.join("bin").join("hello");
+    assert!(bin.symlink_metadata().is_ok());
+    assert!(prefix
+        .join("packages")
+        .join("hello")
+        .join("node_modules")
+        .join("hello")
+        .join("cli.js")
+        .exists());
+
+    let output = oro(
+        dir.path(),
+        dir.path(),
+        &mock_server,
+        &["ls", "--global", "--json"],
+    );
+    assert!(output.status.success(), "{}", format_output(&output));
+    let listed: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        listed,
+        json!([{
+            "name": "hello",
+            "version": "1.0.0",
+            "path": prefix
+                .join("packages")
+                .join("hello")
+                .join("node_modules")
+                .join("hello"),
+            "bins": ["hello"],
+        }])
+    );
+
+    let output = oro(
+        dir.path(),
+        dir.path(),
+        &mock_server,
+        &["remove", "--global", "hello"],
+    );
+    assert!(output.status.success(), "{}", format_output(&output));
+    assert!(bin.symlink_metadata().is_err());
+    assert!(!prefix.join("packages").join("hello").exists());
+    let output = oro(
+        dir.path(),
+        dir.path(),
+        &mock_server,
+        &["ls", "--global", "--json"],
+    );
+    assert_eq!(
+        serde_json::from_slice::<Value>(&output.stdout).unwrap(),
+        json!([])
+    );
+}
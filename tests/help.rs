@@ -12,6 +12,46 @@ fn apply_markdown() {
     insta::assert_snapshot!("apply", sub_md("apply"));
 }
 
+#[test]
+fn audit_markdown() {
+    insta::assert_snapshot!("audit", sub_md("audit"));
+}
+
+#[test]
+fn config_markdown() {
+    insta::assert_snapshot!("config", sub_md("config"));
+}
+
+#[test]
+fn dedupe_markdown() {
+    insta::assert_snapshot!("dedupe", sub_md("dedupe"));
+}
+
+#[test]
+fn deprecate_markdown() {
+    insta::assert_snapshot!("deprecate", sub_md("deprecate"));
+}
+
+#[test]
+fn diff_markdown() {
+    insta::assert_snapshot!("diff", sub_md("diff"));
+}
+
+#[test]
+fn dist_tag_markdown() {
+    insta::assert_snapshot!("dist-tag", sub_md("dist-tag"));
+}
+
+#[test]
+fn licenses_markdown() {
+    insta::assert_snapshot!("licenses", sub_md("licenses"));
+}
+
+#[test]
+fn link_markdown() {
+    insta::assert_snapshot!("link", sub_md("link"));
+}
+
 #[test]
 fn login_markdown() {
     insta::assert_snapshot!("login", sub_md("login"));
@@ -22,6 +62,31 @@ fn logout_markdown() {
     insta::assert_snapshot!("logout", sub_md("logout"));
 }
 
+#[test]
+fn logs_markdown() {
+    insta::assert_snapshot!("logs", sub_md("logs"));
+}
+
+#[test]
+fn ls_markdown() {
+    insta::assert_snapshot!("ls", sub_md("ls"));
+}
+
+#[test]
+fn owner_markdown() {
+    insta::assert_snapshot!("owner", sub_md("owner"));
+}
+
+#[test]
+fn patch_markdown() {
+    insta::assert_snapshot!("patch", sub_md("patch"));
+}
+
+#[test]
+fn patch_commit_markdown() {
+    insta::assert_snapshot!("patch-commit", sub_md("patch-commit"));
+}
+
 #[test]
 fn ping_markdown() {
     insta::assert_snapshot!("ping", sub_md("ping"));
@@ -32,16 +97,51 @@ fn reapply_markdown() {
     insta::assert_snapshot!("reapply", sub_md("reapply"));
 }
 
+#[test]
+fn rebuild_markdown() {
+    insta::assert_snapshot!("rebuild", sub_md("rebuild"));
+}
+
 #[test]
 fn remove_markdown() {
     insta::assert_snapshot!("remove", sub_md("remove"));
 }
 
+#[test]
+fn sbom_markdown() {
+    insta::assert_snapshot!("sbom", sub_md("sbom"));
+}
+
+#[test]
+fn search_markdown() {
+    insta::assert_snapshot!("search", sub_md("search"));
+}
+
+#[test]
+fn store_markdown() {
+    insta::assert_snapshot!("store", sub_md("store"));
+}
+
+#[test]
+fn token_markdown() {
+    insta::assert_snapshot!("token", sub_md("token"));
+}
+
+#[test]
+fn unlink_markdown() {
+    insta::assert_snapshot!("unlink", sub_md("unlink"));
+}
+
 #[test]
 fn view_markdown() {
     insta::assert_snapshot!("view", sub_md("view"));
 }
 
+#[test]
+fn whoami_markdown() {
+    insta::assert_snapshot!("whoami", sub_md("whoami"));
+}
+
 fn sub_md(subcmd: &str) -> String {
     let output = Command::new(BIN)
         .arg("help-markdown")
@@ -0,0 +1,78 @@
+use serde_json::json;
+use wiremock::MockServer;
+
+mod common;
+use common::{format_output, mock_package, oro};
+
+#[async_std::test]
+async fn apply_enforces_license_policy_block() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "hello",
+        json!({ "license": "GPL-3.0-only" }),
+        &[("index.js", "module.exports = 'hello';\n")],
+    )
+    .await;
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(
+        project.join("package.json"),
+        json!({
+            "name": "project",
+            "dependencies": { "hello": "^1" },
+        })
+        .to_string(),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("oro.kdl"),
+        r#"
+        options {
+            license-policy {
+                allow "MIT" "ISC"
+                deny "GPL-3.0-only"
+            }
+        }
+        "#,
+    )
+    .unwrap();
+
+    let root_arg = project.to_string_lossy();
+    let output = oro(
+        dir.path(),
+        &project,
+        &mock_server,
+        &["apply", "--root", &root_arg],
+    );
+    assert!(!output.status.success(), "{}", format_output(&output));
+    assert!(
+        format_output(&output).contains("uses denied license"),
+        "{}",
+        format_output(&output)
+    );
+    assert!(!project.join("node_modules").join("hello").exists());
+
+    // Exceptions come from the same block.
+    std::fs::write(
+        dir.path().join("oro.kdl"),
+        r#"
+        options {
+            license-policy {
+                deny "GPL-3.0-only"
+                exception "hello@^1"
+            }
+        }
+        "#,
+    )
+    .unwrap();
+    let output = oro(
+        dir.path(),
+        &project,
+        &mock_server,
+        &["apply", "--root", &root_arg],
+    );
+    assert!(output.status.success(), "{}", format_output(&output));
+    assert!(project.join("node_modules").join("hello").exists());
+}
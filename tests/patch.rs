@@ -0,0 +1,64 @@
+use serde_json::{json, Value};
+use wiremock::MockServer;
+
+mod common;
+use common::{format_output, mock_package, oro};
+
+const PATCH: &str = "diff --git a/index.js b/index.js
+--- a/index.js
++++ b/index.js
+@@ -1 +1,2 @@
+ module.exports = 'hello';
++module.exports.patched = true;
+";
+
+#[async_std::test]
+async fn patch_applies_existing_patch() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    let edit_dir = dir.path().join("edit");
+    let mock_server = MockServer::start().await;
+    mock_package(
+        &mock_server,
+        "hello",
+        json!({}),
+        &[("index.js", "module.exports = 'hello';\n")],
+    )
+    .await;
+    std::fs::create_dir_all(project.join("patches")).unwrap();
+    std::fs::write(project.join("patches").join("hello@1.0.0.patch"), PATCH).unwrap();
+    std::fs::write(
+        project.join("package.json"),
+        json!({
+            "name": "project",
+            "dependencies": { "hello": "^1" },
+            "patchedDependencies": { "hello@1.0.0": "patches/hello@1.0.0.patch" },
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let edit_dir_arg = edit_dir.to_string_lossy();
+    let output = oro(
+        dir.path(),
+        &project,
+        &mock_server,
+        &[
+            "patch",
+            "hello",
+            "--edit-dir",
+            &edit_dir_arg,
+            "--json",
+            "--root",
+            &project.to_string_lossy(),
+        ],
+    );
+    assert!(output.status.success(), "{}", format_output(&output));
+    let reported: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(reported["patch"], "patches/hello@1.0.0.patch");
+    // The new patch picks up where the old one left off.
+    assert_eq!(
+        std::fs::read_to_string(edit_dir.join("index.js")).unwrap(),
+        "module.exports = 'hello';\nmodule.exports.patched = true;\n"
+    );
+}